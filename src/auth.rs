@@ -0,0 +1,34 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cookies::Cookie;
+use crate::crypt;
+use crate::keyring::Credentials;
+use crate::utils::write_atomic;
+
+/// What a provider is logged in with, moved between machines by `ranobe
+/// auth`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+	pub provider: String,
+	/// The cookies holding the session.
+	pub cookies: Vec<Cookie>,
+	/// The credentials to log in again with once the session expires.
+	pub credentials: Option<Credentials>,
+}
+
+/// Writes `accounts` to `path`, sealed with `passphrase` as they let anyone
+/// holding them read as the user.
+pub fn export<P: AsRef<Path>>(path: P, passphrase: &str, accounts: &[Account]) -> Result<()> {
+	let accounts = serde_json::to_string(accounts)?;
+	write_atomic(path, crypt::seal_with(passphrase, accounts.as_bytes())?)
+}
+
+/// Reads the accounts `export` wrote to `path`.
+pub fn import<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Vec<Account>> {
+	let accounts = crypt::open_with(passphrase, &fs::read(path)?)?;
+	serde_json::from_slice(&accounts).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
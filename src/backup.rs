@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::export;
+use crate::store::ChapterStore;
+use crate::utils::{cache_dir, data_dir, write_atomic};
+
+/// Prefix of the names of files of the data directory in a backup.
+const DATA: &str = "data";
+/// Prefix of the name of the configuration in a backup.
+const CONFIG: &str = "config";
+
+/// The files of the library as they were at one point in time, by their
+/// name in the backup.
+///
+/// Contents are kept once in `objects` under their hash, so a backup only
+/// adds the files that changed since the previous ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Snapshot {
+	files: BTreeMap<String, String>,
+}
+
+/// What a backup did.
+#[derive(Debug, Clone)]
+pub struct Backup {
+	/// Name of the backup, when it was taken.
+	pub name: String,
+	/// Files the backup holds.
+	pub files: usize,
+	/// Files that changed since the previous backup.
+	pub added: usize,
+}
+
+fn hash(contents: &[u8]) -> String {
+	Sha256::digest(contents)
+		.iter()
+		.map(|byte| format!("{:02x}", byte))
+		.collect()
+}
+
+/// Adds the files under `dir` to `files`, named after their path relative
+/// to `root` under `prefix`, skipping the paths of `skipped`.
+fn walk(
+	root: &Path,
+	dir: &Path,
+	prefix: &str,
+	skipped: &[PathBuf],
+	files: &mut Vec<(String, PathBuf)>,
+) -> Result<()> {
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+		Err(err) => return Err(err),
+	};
+
+	for entry in entries {
+		let path = entry?.path();
+		if skipped.contains(&path) {
+			continue;
+		}
+		if path.is_dir() {
+			walk(root, &path, prefix, skipped, files)?;
+			continue;
+		}
+		// Left over from interrupted writes
+		let extension = path.extension().and_then(|extension| extension.to_str());
+		if matches!(extension, Some("tmp" | "part")) {
+			continue;
+		}
+
+		let name = path.strip_prefix(root).map_err(Error::other)?;
+		let name = name
+			.components()
+			.map(|component| component.as_os_str().to_string_lossy())
+			.collect::<Vec<_>>()
+			.join("/");
+		files.push((format!("{}/{}", prefix, name), path));
+	}
+
+	Ok(())
+}
+
+/// Resolves `path` so paths reached differently compare equal.
+fn canonical(path: PathBuf) -> PathBuf {
+	path.canonicalize().unwrap_or(path)
+}
+
+/// Returns the files a backup holds by their name in it: the library,
+/// the index of the downloaded chapters, the export manifest and the
+/// configuration.
+///
+/// Downloaded chapters and exports are left out, they can be downloaded
+/// or exported again, and so is `destination` if it is in the library.
+fn tracked(destination: &Path) -> Result<Vec<(String, PathBuf)>> {
+	let data = canonical(data_dir());
+	let exports = canonical(export::default_dir());
+	let skipped = [
+		canonical(data_dir().join("libraries")),
+		canonical(ChapterStore::default_root().join("blobs")),
+		exports.clone(),
+		canonical(destination.to_path_buf()),
+	];
+
+	let mut files = vec![];
+	walk(&data, &data, DATA, &skipped, &mut files)?;
+	walk(&data, &exports, DATA, &[], &mut files)?;
+	files.retain(|(name, path)| !path.starts_with(&exports) || name.ends_with("/.manifest.json"));
+
+	let config = Config::default_path();
+	if config.exists() {
+		let name = config.file_name().unwrap().to_string_lossy();
+		files.push((format!("{}/{}", CONFIG, name), config.clone()));
+	}
+
+	files.sort();
+	Ok(files)
+}
+
+/// Returns where the file named `name` in a backup belongs.
+fn path_of(name: &str) -> Option<PathBuf> {
+	let (prefix, name) = name.split_once('/')?;
+	let root = match prefix {
+		DATA => data_dir(),
+		CONFIG => Config::default_path().parent()?.to_path_buf(),
+		_ => return None,
+	};
+	// Names come from the backup, which must not write outside the roots
+	if name.split('/').any(|part| part.is_empty() || part == "..") {
+		return None;
+	}
+
+	Some(name.split('/').fold(root, |path, part| path.join(part)))
+}
+
+/// Returns the names of the backups in `dir`, oldest first.
+fn names(dir: &Path) -> Result<Vec<String>> {
+	let mut names = match fs::read_dir(dir.join("snapshots")) {
+		Ok(entries) => entries
+			.map(|entry| entry.map(|entry| entry.path()))
+			.collect::<Result<Vec<_>>>()?,
+		Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+		Err(err) => return Err(err),
+	}
+	.into_iter()
+	.filter(|path| {
+		path.extension()
+			.is_some_and(|extension| extension == "json")
+	})
+	.filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+	.collect::<Vec<_>>();
+	names.sort();
+	Ok(names)
+}
+
+fn load(dir: &Path, name: &str) -> Result<Snapshot> {
+	let snapshot = fs::read_to_string(dir.join("snapshots").join(format!("{}.json", name)))?;
+	Ok(serde_json::from_str(&snapshot)?)
+}
+
+/// Backs the library up into `dir`, unpacked from `destination`, skipping
+/// the backup if nothing changed since the last one.
+fn backup_dir(dir: &Path, destination: &Path) -> Result<Option<Backup>> {
+	let previous = match names(dir)?.last() {
+		Some(name) => load(dir, name)?,
+		None => Snapshot::default(),
+	};
+
+	let objects = dir.join("objects");
+	fs::create_dir_all(&objects)?;
+	let mut snapshot = Snapshot::default();
+	let mut added = 0;
+	for (name, path) in tracked(destination)? {
+		let contents = fs::read(&path)?;
+		let hash = hash(&contents);
+		let object = objects.join(&hash);
+		if !object.exists() {
+			write_atomic(&object, &contents)?;
+		}
+		if previous.files.get(&name) != Some(&hash) {
+			added += 1;
+		}
+		snapshot.files.insert(name, hash);
+	}
+
+	if snapshot == previous {
+		return Ok(None);
+	}
+
+	let name = Local::now().format("%Y-%m-%dT%H-%M-%S").to_string();
+	let snapshots = dir.join("snapshots");
+	fs::create_dir_all(&snapshots)?;
+	write_atomic(
+		snapshots.join(format!("{}.json", name)),
+		serde_json::to_string(&snapshot)?,
+	)?;
+
+	Ok(Some(Backup {
+		name,
+		files: snapshot.files.len(),
+		added,
+	}))
+}
+
+/// Writes back the files of the backup `name` of `dir`, the latest one if
+/// `None`, and returns its name with how many files it wrote back.
+///
+/// Files the backup does not hold are left as they are.
+fn restore_dir(dir: &Path, name: Option<&str>) -> Result<(String, usize)> {
+	let names = names(dir)?;
+	let name = match name {
+		Some(name) if names.iter().any(|known| known == name) => name.to_string(),
+		Some(name) => {
+			return Err(Error::new(
+				ErrorKind::NotFound,
+				format!("there is no backup {}", name),
+			))
+		}
+		None => names
+			.last()
+			.cloned()
+			.ok_or_else(|| Error::new(ErrorKind::NotFound, "there are no backups"))?,
+	};
+
+	let snapshot = load(dir, &name)?;
+	for (file, hash) in &snapshot.files {
+		let path = path_of(file).ok_or_else(|| {
+			Error::new(
+				ErrorKind::InvalidData,
+				format!("{} does not belong to a library", file),
+			)
+		})?;
+		let contents = fs::read(dir.join("objects").join(hash))?;
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		write_atomic(&path, contents)?;
+	}
+
+	Ok((name, snapshot.files.len()))
+}
+
+/// Runs `f` on the backups of `path`, a directory or a `.tar` archive,
+/// which is unpacked for `f` and packed again if `write` is set.
+fn with_backups<T>(path: &Path, write: bool, f: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+	if path.extension().is_none_or(|extension| extension != "tar") {
+		return f(path);
+	}
+
+	let dir = cache_dir().join(format!("backup-{}", std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir)?;
+
+	let tar = |args: &[&std::ffi::OsStr]| -> Result<()> {
+		let status = match Command::new("tar").args(args).status() {
+			Ok(status) => status,
+			Err(err) if err.kind() == ErrorKind::NotFound => {
+				return Err(Error::new(
+					ErrorKind::NotFound,
+					"tar is needed for backups in an archive",
+				))
+			}
+			Err(err) => return Err(err),
+		};
+		if !status.success() {
+			return Err(Error::other("tar failed"));
+		}
+		Ok(())
+	};
+
+	let result = (|| {
+		if path.exists() {
+			tar(&[
+				"-xf".as_ref(),
+				path.as_os_str(),
+				"-C".as_ref(),
+				dir.as_os_str(),
+			])?;
+		}
+		let result = f(&dir)?;
+		if write {
+			// Packed next to the archive first, so a failure keeps the old one
+			let partial = path.with_extension("tar.part");
+			tar(&[
+				"-cf".as_ref(),
+				partial.as_os_str(),
+				"-C".as_ref(),
+				dir.as_os_str(),
+				".".as_ref(),
+			])?;
+			fs::rename(partial, path)?;
+		}
+		Ok(result)
+	})();
+
+	let _ = fs::remove_dir_all(&dir);
+	result
+}
+
+/// Backs the library and the configuration up into `path`, a directory or
+/// a `.tar` archive, and returns the backup unless nothing changed since
+/// the last one.
+pub fn backup(path: &Path) -> Result<Option<Backup>> {
+	with_backups(path, true, |dir| backup_dir(dir, path))
+}
+
+/// Restores the backup `name` of `path`, the latest one if `None`, and
+/// returns its name with how many files it wrote back.
+pub fn restore(path: &Path, name: Option<&str>) -> Result<(String, usize)> {
+	with_backups(path, false, |dir| restore_dir(dir, name))
+}
+
+/// Returns the names of the backups of `path`, oldest first.
+pub fn list(path: &Path) -> Result<Vec<String>> {
+	if !path.exists() {
+		return Ok(vec![]);
+	}
+	with_backups(path, false, names)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_backups_inside_the_library() {
+		assert_eq!(
+			path_of("data/chapters/index.json"),
+			Some(data_dir().join("chapters").join("index.json"))
+		);
+		assert_eq!(path_of("config/config.toml"), Some(Config::default_path()));
+		assert_eq!(path_of("data/../../etc/passwd"), None);
+		assert_eq!(path_of("data//library.json"), None);
+		assert_eq!(path_of("elsewhere/library.json"), None);
+	}
+}
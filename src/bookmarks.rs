@@ -0,0 +1,162 @@
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::chapter::ChapterNumber;
+use crate::crypt;
+use crate::providers::{canonical_url, Chapter, Listed};
+use crate::utils::data_dir;
+
+/// Most characters of a paragraph kept to recognize a bookmark by.
+const EXCERPT_LENGTH: usize = 80;
+
+/// A paragraph of a chapter saved to come back to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+	pub novel: String,
+	pub title: String,
+	pub url: Url,
+	/// Index of the paragraph in the chapter.
+	pub paragraph: usize,
+	/// Start of the paragraph.
+	pub excerpt: String,
+}
+
+impl Bookmark {
+	pub fn chapter(&self) -> Chapter {
+		Chapter::new(self.title.clone(), self.url.clone())
+	}
+}
+
+/// Paragraphs saved in the chapters read.
+///
+/// Like the history, they are encrypted with the library as they tell what
+/// is read.
+pub struct Bookmarks {
+	path: PathBuf,
+	bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("bookmarks.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let bookmarks = match crypt::read_to_string(&path) {
+			Ok(bookmarks) => serde_json::from_str(&bookmarks)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, bookmarks })
+	}
+
+	/// Saves the paragraph at `paragraph` of `chapter` of `novel`, which
+	/// reads `text`.
+	///
+	/// Returns whether it was not saved already.
+	pub fn add(
+		&mut self,
+		novel: &str,
+		chapter: &Chapter,
+		paragraph: usize,
+		text: &str,
+	) -> Result<bool> {
+		let url = canonical_url(&chapter.url);
+		if self
+			.bookmarks
+			.iter()
+			.any(|bookmark| bookmark.url == url && bookmark.paragraph == paragraph)
+		{
+			return Ok(false);
+		}
+
+		let mut excerpt = text.chars().take(EXCERPT_LENGTH).collect::<String>();
+		if excerpt.len() < text.len() {
+			excerpt.push('…');
+		}
+		self.bookmarks.push(Bookmark {
+			novel: novel.to_string(),
+			title: chapter.title.clone(),
+			url,
+			paragraph,
+			excerpt,
+		});
+
+		crypt::write(&self.path, serde_json::to_string(&self.bookmarks)?)?;
+		Ok(true)
+	}
+
+	/// Returns the bookmarks of `novel` in reading order, by chapter number
+	/// then by paragraph.
+	///
+	/// Chapters without a number come last.
+	pub fn of(&self, novel: &str) -> Vec<&Bookmark> {
+		let mut bookmarks = self
+			.bookmarks
+			.iter()
+			.filter(|bookmark| bookmark.novel == novel)
+			.collect::<Vec<_>>();
+		bookmarks.sort_by_key(|bookmark| {
+			let number = ChapterNumber::parse(&bookmark.title);
+			(
+				number.is_none(),
+				number,
+				bookmark.url.as_str(),
+				bookmark.paragraph,
+			)
+		});
+		bookmarks
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn lists_bookmarks_in_reading_order() {
+		let dir = TestDir::new("bookmarks");
+		let path = dir.join("bookmarks.json");
+		let chapter = |title: &str, n: u32| {
+			Chapter::new(
+				title.to_string(),
+				Url::parse(&format!("https://example.com/novel/{}", n)).unwrap(),
+			)
+		};
+
+		let mut bookmarks = Bookmarks::open(&path).unwrap();
+		bookmarks
+			.add("Novel", &chapter("Chapter 10", 10), 3, "Later.")
+			.unwrap();
+		bookmarks
+			.add("Novel", &chapter("Prologue", 0), 1, "Before.")
+			.unwrap();
+		bookmarks
+			.add("Novel", &chapter("Chapter 2", 2), 7, "Then.")
+			.unwrap();
+		bookmarks
+			.add("Novel", &chapter("Chapter 2", 2), 1, "First.")
+			.unwrap();
+		bookmarks
+			.add("Other", &chapter("Chapter 1", 1), 0, "Elsewhere.")
+			.unwrap();
+		assert!(!bookmarks
+			.add("Novel", &chapter("Chapter 2", 2), 1, "First.")
+			.unwrap());
+
+		let bookmarks = Bookmarks::open(&path).unwrap();
+		let order = bookmarks
+			.of("Novel")
+			.iter()
+			.map(|bookmark| bookmark.excerpt.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(order, vec!["First.", "Then.", "Later.", "Before."]);
+	}
+}
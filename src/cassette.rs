@@ -0,0 +1,227 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use surf::middleware::{Middleware, Next};
+use surf::utils::async_trait;
+use surf::{Client, Request, Response, StatusCode};
+
+use crate::utils::write_atomic;
+
+static CASSETTE: OnceCell<Cassette> = OnceCell::new();
+
+/// A request and the response it got.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+	pub method: String,
+	pub url: String,
+	pub status: u16,
+	pub headers: Vec<(String, String)>,
+	/// The body if it is text, which most pages are.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub body: Option<String>,
+	/// The body otherwise, like the one of a cover.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub bytes: Option<Vec<u8>>,
+}
+
+impl Interaction {
+	fn body(&self) -> Vec<u8> {
+		match (&self.body, &self.bytes) {
+			(Some(body), _) => body.as_bytes().to_vec(),
+			(None, Some(bytes)) => bytes.clone(),
+			(None, None) => vec![],
+		}
+	}
+}
+
+/// The interactions of a cassette file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Tape {
+	interactions: Vec<Interaction>,
+	/// Which interactions were replayed already.
+	#[serde(skip)]
+	played: Vec<bool>,
+}
+
+impl Tape {
+	/// Returns the next interaction of `method` on `url` to replay.
+	///
+	/// The same request is answered in the order it was recorded, then
+	/// with its last answer again.
+	fn play(&mut self, method: &str, url: &str) -> Option<&Interaction> {
+		self.played.resize(self.interactions.len(), false);
+
+		let matches = self
+			.interactions
+			.iter()
+			.enumerate()
+			.filter(|(_, interaction)| interaction.method == method && interaction.url == url)
+			.map(|(i, _)| i)
+			.collect::<Vec<_>>();
+		let i = matches
+			.iter()
+			.copied()
+			.find(|&i| !self.played[i])
+			.or_else(|| matches.last().copied())?;
+
+		self.played[i] = true;
+		Some(&self.interactions[i])
+	}
+}
+
+/// Records every request of a session to a file, or answers them from one,
+/// so providers can be developed and bugs reproduced without the network.
+#[derive(Debug, Clone)]
+pub struct Cassette {
+	path: PathBuf,
+	replay: bool,
+	tape: Arc<Mutex<Tape>>,
+}
+
+impl Cassette {
+	fn open(path: &Path, replay: bool) -> Result<Self> {
+		let tape = match fs::read_to_string(path) {
+			Ok(tape) if replay => serde_json::from_str(&tape)?,
+			Err(err) if replay || err.kind() != ErrorKind::NotFound => return Err(err),
+			// Recording starts a new cassette
+			_ => Tape::default(),
+		};
+
+		Ok(Self {
+			path: path.to_path_buf(),
+			replay,
+			tape: Arc::new(Mutex::new(tape)),
+		})
+	}
+
+	async fn record(&self, req: Request, client: Client, next: Next<'_>) -> surf::Result<Response> {
+		let method = req.method().to_string();
+		let url = req.url().to_string();
+		let mut res = next.run(req, client).await?;
+
+		let bytes = res.body_bytes().await?;
+		let headers = res
+			.iter()
+			.flat_map(|(name, values)| {
+				values
+					.iter()
+					.map(|value| (name.to_string(), value.to_string()))
+					.collect::<Vec<_>>()
+			})
+			.collect();
+		let (body, binary) = match String::from_utf8(bytes.clone()) {
+			Ok(body) => (Some(body), None),
+			Err(_) => (None, Some(bytes.clone())),
+		};
+		res.set_body(bytes);
+
+		let mut tape = self.tape.lock().unwrap();
+		tape.interactions.push(Interaction {
+			method,
+			url,
+			status: res.status().into(),
+			headers,
+			body,
+			bytes: binary,
+		});
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		write_atomic(&self.path, serde_json::to_string_pretty(&*tape)?)?;
+
+		Ok(res)
+	}
+
+	fn replay(&self, req: &Request) -> surf::Result<Response> {
+		let mut tape = self.tape.lock().unwrap();
+		let interaction = match tape.play(req.method().as_ref(), req.url().as_str()) {
+			Some(interaction) => interaction,
+			None => {
+				return Err(surf::Error::from_str(
+					StatusCode::NotFound,
+					format!(
+						"{} {} was not recorded in {}",
+						req.method(),
+						req.url(),
+						self.path.display()
+					),
+				))
+			}
+		};
+
+		let status = StatusCode::try_from(interaction.status)
+			.map_err(|err| surf::Error::from_str(StatusCode::BadGateway, err.to_string()))?;
+		let mut res = surf::http::Response::new(status);
+		for (name, value) in &interaction.headers {
+			res.append_header(name.as_str(), value.as_str());
+		}
+		res.set_body(interaction.body());
+
+		Ok(res.into())
+	}
+}
+
+#[async_trait]
+impl Middleware for Cassette {
+	async fn handle(&self, req: Request, client: Client, next: Next<'_>) -> surf::Result<Response> {
+		if self.replay {
+			self.replay(&req)
+		} else {
+			self.record(req, client, next).await
+		}
+	}
+}
+
+/// Records every request of the session to the cassette at `path`.
+pub fn record<P: AsRef<Path>>(path: P) -> Result<()> {
+	let _ = CASSETTE.set(Cassette::open(path.as_ref(), false)?);
+	Ok(())
+}
+
+/// Answers every request of the session from the cassette at `path`, failing
+/// the ones it did not record.
+pub fn replay<P: AsRef<Path>>(path: P) -> Result<()> {
+	let _ = CASSETTE.set(Cassette::open(path.as_ref(), true)?);
+	Ok(())
+}
+
+/// Returns the cassette set by `record` or `replay`.
+pub fn current() -> Option<&'static Cassette> {
+	CASSETTE.get()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn replays_in_recorded_order() {
+		let interaction = |url: &str, body: &str| Interaction {
+			method: "GET".to_string(),
+			url: url.to_string(),
+			status: 200,
+			headers: vec![],
+			body: Some(body.to_string()),
+			bytes: None,
+		};
+		let mut tape = Tape {
+			interactions: vec![
+				interaction("https://a.test/", "first"),
+				interaction("https://b.test/", "other"),
+				interaction("https://a.test/", "second"),
+			],
+			played: vec![],
+		};
+
+		let mut play = |url| tape.play("GET", url).map(|interaction| interaction.body());
+		assert_eq!(play("https://a.test/"), Some(b"first".to_vec()));
+		assert_eq!(play("https://a.test/"), Some(b"second".to_vec()));
+		assert_eq!(play("https://a.test/"), Some(b"second".to_vec()));
+		assert_eq!(play("https://c.test/"), None);
+	}
+}
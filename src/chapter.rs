@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::providers::{Chapter, Volume};
+
+lazy_static! {
+	static ref NUMBER_RE: Regex = Regex::new(
+		r#"(?i)\b(?:chapter|chap|ch|episode|ep|c)\.?\s*(\d+)(?:\.(\d+))?(?:\s*(?:-|part|pt\.?)\s*(\d+))?"#
+	)
+	.unwrap();
+	static ref BARE_NUMBER_RE: Regex =
+		Regex::new(r#"(\d+)(?:\.(\d+))?(?:\s*(?:-|part|pt\.?)\s*(\d+))?"#).unwrap();
+}
+
+/// A chapter number parsed from a chapter title.
+///
+/// `27.5` parses into a decimal of 5 and `27-2` or `27 part 2` into a part of
+/// 2, numbers compare by number, then decimal, then part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChapterNumber {
+	pub number: u32,
+	pub decimal: Option<u32>,
+	pub part: Option<u32>,
+}
+
+impl ChapterNumber {
+	/// Parses the chapter number out of `title`.
+	///
+	/// Numbers following a chapter keyword win over bare numbers, so
+	/// `Volume 3 Chapter 27` parses as 27.
+	pub fn parse(title: &str) -> Option<Self> {
+		let captures = NUMBER_RE
+			.captures(title)
+			.or_else(|| BARE_NUMBER_RE.captures(title))?;
+
+		let number = |i| captures.get(i).and_then(|m| m.as_str().parse().ok());
+
+		Some(Self {
+			number: number(1)?,
+			decimal: number(2),
+			part: number(3),
+		})
+	}
+}
+
+impl fmt::Display for ChapterNumber {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.number)?;
+		if let Some(decimal) = self.decimal {
+			write!(f, ".{}", decimal)?;
+		}
+		if let Some(part) = self.part {
+			write!(f, "-{}", part)?;
+		}
+		Ok(())
+	}
+}
+
+/// Finds the chapter of `chapters` matching the progress `progress`, which
+/// is the title of the last chapter read on another source.
+///
+/// Chapters are matched by number rather than by url, if the exact number
+/// is missing the closest chapter before it is returned.
+pub fn reconcile<'a>(progress: &str, chapters: &'a [Chapter]) -> Option<&'a Chapter> {
+	let progress = ChapterNumber::parse(progress)?;
+
+	chapters
+		.iter()
+		.filter_map(|chapter| Some((ChapterNumber::parse(&chapter.title)?, chapter)))
+		.filter(|(number, _)| *number <= progress)
+		.max_by_key(|(number, _)| *number)
+		.map(|(_, chapter)| chapter)
+}
+
+/// Groups `chapters` under the `volumes` listing them, in the order of the
+/// volumes.
+///
+/// Volumes without any of `chapters` are left out, chapters no volume
+/// lists are grouped last without a title.
+pub fn by_volume(volumes: &[Volume], chapters: &[Chapter]) -> Vec<(Option<String>, Vec<Chapter>)> {
+	let mut groups = vec![];
+	let mut grouped = vec![false; chapters.len()];
+	for volume in volumes {
+		let urls = volume.chapters.iter().collect::<HashSet<_>>();
+		let mut members = vec![];
+		for (i, chapter) in chapters.iter().enumerate() {
+			if !grouped[i] && urls.contains(&chapter.url) {
+				grouped[i] = true;
+				members.push(chapter.clone());
+			}
+		}
+		if !members.is_empty() {
+			groups.push((Some(volume.title.clone()), members));
+		}
+	}
+
+	let rest = chapters
+		.iter()
+		.zip(grouped)
+		.filter(|(_, grouped)| !grouped)
+		.map(|(chapter, _)| chapter.clone())
+		.collect::<Vec<_>>();
+	if !rest.is_empty() {
+		groups.push((None, rest));
+	}
+
+	groups
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Listed;
+
+	fn number(number: u32, decimal: Option<u32>, part: Option<u32>) -> Option<ChapterNumber> {
+		Some(ChapterNumber {
+			number,
+			decimal,
+			part,
+		})
+	}
+
+	#[test]
+	fn parses_chapter_numbers() {
+		assert_eq!(ChapterNumber::parse("Chapter 27"), number(27, None, None));
+		assert_eq!(
+			ChapterNumber::parse("Vol. 3 Ch. 27.5"),
+			number(27, Some(5), None)
+		);
+		assert_eq!(
+			ChapterNumber::parse("Chapter 27-2"),
+			number(27, None, Some(2))
+		);
+		assert_eq!(
+			ChapterNumber::parse("c27 part 2: Return"),
+			number(27, None, Some(2))
+		);
+		assert_eq!(ChapterNumber::parse("27 - The End"), number(27, None, None));
+		assert_eq!(ChapterNumber::parse("Prologue"), None);
+	}
+
+	#[test]
+	fn reconciles_by_number() {
+		let chapters = ["Chapter 26", "Chapter 27", "Chapter 27.5", "Chapter 28"]
+			.iter()
+			.map(|title| {
+				Chapter::new(
+					title.to_string(),
+					surf::Url::parse("https://example.com").unwrap(),
+				)
+			})
+			.collect::<Vec<_>>();
+
+		let found = |progress| reconcile(progress, &chapters).map(|c| c.title.as_str());
+		assert_eq!(found("Ch. 27.5 - Interlude"), Some("Chapter 27.5"));
+		assert_eq!(found("Chapter 27-2"), Some("Chapter 27"));
+		assert_eq!(found("Chapter 1"), None);
+	}
+
+	#[test]
+	fn groups_by_volume() {
+		let url = |n| surf::Url::parse(&format!("https://example.com/{}", n)).unwrap();
+		let chapters = (1..=4)
+			.map(|n| Chapter::new(format!("Chapter {}", n), url(n)))
+			.collect::<Vec<_>>();
+		let volumes = [
+			Volume {
+				title: "Volume 1".to_string(),
+				chapters: vec![url(1), url(2)],
+			},
+			Volume {
+				title: "Volume 2".to_string(),
+				chapters: vec![url(5)],
+			},
+			Volume {
+				title: "Volume 3".to_string(),
+				chapters: vec![url(3)],
+			},
+		];
+
+		let groups = by_volume(&volumes, &chapters)
+			.into_iter()
+			.map(|(title, chapters)| (title, chapters.len()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			groups,
+			vec![
+				(Some("Volume 1".to_string()), 2),
+				(Some("Volume 3".to_string()), 1),
+				(None, 1),
+			]
+		);
+		let groups = by_volume(&[], &chapters);
+		assert_eq!(groups.len(), 1);
+		assert!(groups[0].0.is_none());
+	}
+}
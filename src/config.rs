@@ -0,0 +1,258 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+use crate::hooks::Hooks;
+use crate::remind::parse_time;
+
+/// Width chapters are wrapped at when nothing else is configured.
+pub const DEFAULT_WRAP: u16 = 80;
+
+/// When the night style is used if no schedule is configured.
+const DEFAULT_NIGHT: (&str, &str) = ("20:00", "07:00");
+
+/// Replaces every occurrence of `from` with `to` in chapter text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replacement {
+	pub from: String,
+	pub to: String,
+}
+
+/// How chapters are rendered.
+///
+/// Every field is optional so the settings of a novel only override what
+/// they set and fall back to the global configuration otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	/// Width to wrap chapters at.
+	pub wrap: Option<u16>,
+	/// Glow style, either a builtin style name or a path to a json style.
+	pub style: Option<String>,
+	/// Glow style used at night instead of `style`.
+	pub night_style: Option<String>,
+	/// When the night starts and ends, as `HH:MM`.
+	pub night_start: Option<String>,
+	pub night_end: Option<String>,
+	/// Replacements applied to the chapter text before it is rendered.
+	pub replacements: Vec<Replacement>,
+	/// Offer to show the top comments after a chapter, off by default as
+	/// it costs an extra request.
+	pub comments: Option<bool>,
+	/// Hyphenate words at the end of the lines of `ranobe split`, by rough
+	/// English syllable rules, off by default.
+	pub hyphenate: Option<bool>,
+}
+
+impl Settings {
+	/// Returns these settings with `overrides` applied on top.
+	///
+	/// Replacements of both are kept, the overriding ones run last.
+	pub fn merge(&self, overrides: &Settings) -> Settings {
+		Settings {
+			wrap: overrides.wrap.or(self.wrap),
+			style: overrides.style.clone().or_else(|| self.style.clone()),
+			night_style: overrides
+				.night_style
+				.clone()
+				.or_else(|| self.night_style.clone()),
+			night_start: overrides
+				.night_start
+				.clone()
+				.or_else(|| self.night_start.clone()),
+			night_end: overrides
+				.night_end
+				.clone()
+				.or_else(|| self.night_end.clone()),
+			replacements: self
+				.replacements
+				.iter()
+				.chain(&overrides.replacements)
+				.cloned()
+				.collect(),
+			comments: overrides.comments.or(self.comments),
+			hyphenate: overrides.hyphenate.or(self.hyphenate),
+		}
+	}
+
+	pub fn comments(&self) -> bool {
+		self.comments.unwrap_or(false)
+	}
+
+	pub fn hyphenate(&self) -> bool {
+		self.hyphenate.unwrap_or(false)
+	}
+
+	pub fn wrap(&self) -> u16 {
+		self.wrap.unwrap_or(DEFAULT_WRAP)
+	}
+
+	/// Returns the style to render with at `now`, the night style during
+	/// the night if one is set.
+	///
+	/// A night that ends before it starts, like `20:00` to `07:00`, spans
+	/// midnight.
+	pub fn style_at(&self, now: NaiveTime) -> Option<&str> {
+		let night = self.night_style.as_deref();
+		let start = parse_time(self.night_start.as_deref().unwrap_or(DEFAULT_NIGHT.0));
+		let end = parse_time(self.night_end.as_deref().unwrap_or(DEFAULT_NIGHT.1));
+
+		let is_night = match (start, end) {
+			(Some(start), Some(end)) if start <= end => start <= now && now < end,
+			(Some(start), Some(end)) => now >= start || now < end,
+			_ => false,
+		};
+
+		match night {
+			Some(night) if is_night => Some(night),
+			_ => self.style.as_deref(),
+		}
+	}
+
+	/// Applies the replacements to `text`, borrowing it when there are none.
+	pub fn replace<'a>(&self, text: &'a str) -> Cow<'a, str> {
+		if self.replacements.is_empty() {
+			return Cow::Borrowed(text);
+		}
+
+		Cow::Owned(
+			self.replacements
+				.iter()
+				.fold(text.to_string(), |text, replacement| {
+					text.replace(&replacement.from, &replacement.to)
+				}),
+		)
+	}
+}
+
+/// How the lists are moved through, in the `[navigation]` table, for those
+/// who find holding or chording keys hard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Navigation {
+	/// Items Left, Right, 'h' and 'l' move by, a whole page if unset.
+	pub page_jump: Option<usize>,
+	/// Whether moving past the last item goes back to the first and the
+	/// other way around, on by default.
+	pub wrap: Option<bool>,
+	/// Milliseconds a key moving through the list is ignored for after it
+	/// was pressed, so a key held down or pressed twice by a shaking hand
+	/// moves once rather than running through the list.
+	pub repeat_delay: Option<u64>,
+}
+
+impl Navigation {
+	pub fn wrap(&self) -> bool {
+		self.wrap.unwrap_or(true)
+	}
+
+	pub fn repeat_delay(&self) -> Duration {
+		Duration::from_millis(self.repeat_delay.unwrap_or(0))
+	}
+}
+
+/// The global configuration in `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	pub reading: Settings,
+	/// Ask before destructive actions like deleting a novel, on by default.
+	pub confirm: Option<bool>,
+	/// Print plain line-by-line lists and prompts for screen readers, also
+	/// turned on by setting `RANOBE_PLAIN`.
+	pub plain: Option<bool>,
+	/// File holding the passphrase of an encrypted library, so it is not
+	/// asked for on every run.
+	pub key_file: Option<PathBuf>,
+	/// Library used unless `--library` picks another, the default library
+	/// if unset.
+	pub library: Option<String>,
+	/// Read adult novels of providers that keep them apart, like
+	/// novel18.syosetu.com, off by default.
+	pub r18: Option<bool>,
+	/// Domains to try when the ones of a provider stop working, by
+	/// provider, like `readlightnovel = ["readlightnovel.example"]`.
+	pub mirrors: HashMap<String, Vec<String>>,
+	/// Directory of epub, txt and md files read with `--provider local`,
+	/// `books` next to the configuration if unset.
+	pub local_dir: Option<PathBuf>,
+	/// Shell commands run after downloads.
+	pub hooks: Hooks,
+	pub navigation: Navigation,
+}
+
+impl Config {
+	pub fn default_path() -> PathBuf {
+		dirs::config_dir()
+			.unwrap_or_else(std::env::temp_dir)
+			.join("ranobe")
+			.join("config.toml")
+	}
+
+	pub fn confirm(&self) -> bool {
+		self.confirm.unwrap_or(true)
+	}
+
+	pub fn plain(&self) -> bool {
+		std::env::var_os("RANOBE_PLAIN").is_some() || self.plain.unwrap_or(false)
+	}
+
+	pub fn r18(&self) -> bool {
+		self.r18.unwrap_or(false)
+	}
+
+	/// Returns the passphrase of an encrypted library from `RANOBE_PASSPHRASE`
+	/// or the key file, if either is set.
+	pub fn passphrase(&self) -> Result<Option<String>> {
+		if let Ok(passphrase) = std::env::var("RANOBE_PASSPHRASE") {
+			return Ok(Some(passphrase));
+		}
+
+		match &self.key_file {
+			Some(path) => Ok(Some(fs::read_to_string(path)?.trim_end().to_string())),
+			None => Ok(None),
+		}
+	}
+
+	/// Loads the configuration at `path`, a missing file is an empty
+	/// configuration.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+		match fs::read_to_string(path) {
+			Ok(config) => {
+				toml::from_str(&config).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+			}
+			Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+			Err(err) => Err(err),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn switches_to_the_night_style() {
+		let at = |time| parse_time(time).unwrap();
+		let mut settings = Settings {
+			style: Some("light".to_string()),
+			night_style: Some("dark".to_string()),
+			..Settings::default()
+		};
+
+		assert_eq!(settings.style_at(at("12:00")), Some("light"));
+		assert_eq!(settings.style_at(at("23:30")), Some("dark"));
+		assert_eq!(settings.style_at(at("06:59")), Some("dark"));
+
+		settings.night_start = Some("13:00".to_string());
+		settings.night_end = Some("14:00".to_string());
+		assert_eq!(settings.style_at(at("13:30")), Some("dark"));
+		assert_eq!(settings.style_at(at("23:30")), Some("light"));
+	}
+}
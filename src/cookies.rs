@@ -0,0 +1,259 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use surf::middleware::{Middleware, Next};
+use surf::utils::async_trait;
+use surf::{Client, Request, Response};
+
+use crate::crypt;
+use crate::utils::data_dir;
+
+lazy_static! {
+	/// The jar of the library in use, opened by the first request.
+	static ref JAR: Mutex<Option<Jar>> = Mutex::new(None);
+}
+
+/// A cookie as a site set it, only what is needed to send it back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+	/// Host the cookie is sent to, along with its subdomains.
+	domain: String,
+	name: String,
+	value: String,
+}
+
+impl Cookie {
+	/// Parses the `Set-Cookie` header `header` of a response of `host`.
+	///
+	/// Returns the cookie and whether the site asked to delete it, with a
+	/// `Max-Age` of zero or an empty value.
+	fn parse(host: &str, header: &str) -> Option<(Self, bool)> {
+		let mut attributes = header.split(';');
+		let (name, value) = attributes.next()?.split_once('=')?;
+
+		let mut cookie = Self {
+			domain: host.to_string(),
+			name: name.trim().to_string(),
+			value: value.trim().trim_matches('"').to_string(),
+		};
+		let mut expired = cookie.value.is_empty();
+		for attribute in attributes {
+			let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+			match key.trim().to_lowercase().as_str() {
+				"domain" => cookie.domain = value.trim().trim_start_matches('.').to_lowercase(),
+				"max-age" => expired |= value.trim().parse::<i64>().is_ok_and(|age| age <= 0),
+				_ => {}
+			}
+		}
+
+		if cookie.name.is_empty() {
+			return None;
+		}
+		Some((cookie, expired))
+	}
+
+	fn matches(&self, host: &str) -> bool {
+		host == self.domain || host.ends_with(&format!(".{}", self.domain))
+	}
+
+	/// Whether the cookie belongs to the site of `host`, set by it or one of
+	/// its subdomains.
+	fn belongs_to(&self, host: &str) -> bool {
+		self.matches(host) || self.domain.ends_with(&format!(".{}", host))
+	}
+}
+
+/// The cookies sites set, kept across runs so logins last.
+pub struct Jar {
+	path: PathBuf,
+	cookies: Vec<Cookie>,
+}
+
+impl Jar {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("cookies.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let cookies = match crypt::read_to_string(&path) {
+			Ok(cookies) => serde_json::from_str(&cookies)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, cookies })
+	}
+
+	/// Returns the `Cookie` header of a request to `host`.
+	fn header(&self, host: &str) -> Option<String> {
+		let cookies = self
+			.cookies
+			.iter()
+			.filter(|cookie| cookie.matches(host))
+			.map(|cookie| format!("{}={}", cookie.name, cookie.value))
+			.collect::<Vec<_>>();
+		(!cookies.is_empty()).then(|| cookies.join("; "))
+	}
+
+	/// Keeps the cookies `headers` of a response of `host` set and returns
+	/// whether any changed.
+	fn set<'a>(&mut self, host: &str, headers: impl Iterator<Item = &'a str>) -> bool {
+		let mut changed = false;
+		for (cookie, expired) in headers.filter_map(|header| Cookie::parse(host, header)) {
+			let existing = self
+				.cookies
+				.iter()
+				.position(|c| c.domain == cookie.domain && c.name == cookie.name);
+			match (existing, expired) {
+				(Some(i), true) => {
+					self.cookies.remove(i);
+				}
+				(Some(i), false) if self.cookies[i] != cookie => self.cookies[i] = cookie,
+				(None, false) => self.cookies.push(cookie),
+				_ => continue,
+			}
+			changed = true;
+		}
+		changed
+	}
+
+	/// Forgets the cookies of the sites of `hosts`, like the session of an
+	/// account.
+	pub fn clear(&mut self, hosts: &[&str]) -> Result<()> {
+		self.cookies
+			.retain(|cookie| !hosts.iter().any(|host| cookie.belongs_to(host)));
+		self.save()
+	}
+
+	/// Returns the cookies of the sites of `hosts`.
+	fn of(&self, hosts: &[&str]) -> Vec<Cookie> {
+		self.cookies
+			.iter()
+			.filter(|cookie| hosts.iter().any(|host| cookie.belongs_to(host)))
+			.cloned()
+			.collect()
+	}
+
+	/// Adds `cookies`, replacing the ones of the same name.
+	fn extend(&mut self, cookies: Vec<Cookie>) -> Result<()> {
+		for cookie in cookies {
+			self.cookies
+				.retain(|c| c.domain != cookie.domain || c.name != cookie.name);
+			self.cookies.push(cookie);
+		}
+		self.save()
+	}
+
+	fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.cookies)?)
+	}
+}
+
+/// Runs `f` on the jar of the library in use, opening it first if needed.
+fn with_jar<T>(f: impl FnOnce(&mut Jar) -> Result<T>) -> Result<T> {
+	let mut jar = JAR.lock().unwrap();
+	let jar = match jar.as_mut() {
+		Some(jar) => jar,
+		None => jar.insert(Jar::open(Jar::default_path())?),
+	};
+	f(jar)
+}
+
+/// Sends the cookies of the jar with every request and keeps the ones the
+/// responses set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cookies;
+
+#[async_trait]
+impl Middleware for Cookies {
+	async fn handle(
+		&self,
+		mut req: Request,
+		client: Client,
+		next: Next<'_>,
+	) -> surf::Result<Response> {
+		let host = req.url().host_str().unwrap_or_default().to_lowercase();
+		if let Some(header) = with_jar(|jar| Ok(jar.header(&host)))? {
+			req.insert_header("cookie", header);
+		}
+
+		let res = next.run(req, client).await?;
+
+		if let Some(headers) = res.header("set-cookie") {
+			with_jar(|jar| {
+				if jar.set(&host, headers.iter().map(|header| header.as_str())) {
+					jar.save()?;
+				}
+				Ok(())
+			})?;
+		}
+
+		Ok(res)
+	}
+}
+
+/// Forgets the cookies of the sites of `hosts`.
+pub fn clear(hosts: &[&str]) -> Result<()> {
+	with_jar(|jar| jar.clear(hosts))
+}
+
+/// Returns the cookies of the sites of `hosts`, to move them to another
+/// machine.
+pub fn export(hosts: &[&str]) -> Result<Vec<Cookie>> {
+	with_jar(|jar| Ok(jar.of(hosts)))
+}
+
+/// Adds cookies exported on another machine to the jar.
+pub fn import(cookies: Vec<Cookie>) -> Result<()> {
+	with_jar(|jar| jar.extend(cookies))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_cookies_per_domain() {
+		let mut jar = Jar {
+			path: PathBuf::new(),
+			cookies: vec![],
+		};
+
+		assert!(jar.set(
+			"archiveofourown.org",
+			[
+				"_session=abc; path=/; HttpOnly",
+				"remember=1; Domain=.archiveofourown.org; Max-Age=3600",
+			]
+			.into_iter()
+		));
+		assert_eq!(
+			jar.header("archiveofourown.org").as_deref(),
+			Some("_session=abc; remember=1")
+		);
+		assert_eq!(
+			jar.header("www.archiveofourown.org").as_deref(),
+			Some("_session=abc; remember=1")
+		);
+		assert_eq!(jar.header("example.com"), None);
+		assert_eq!(jar.of(&["archiveofourown.org"]).len(), 2);
+		assert!(jar.of(&["example.com"]).is_empty());
+
+		assert!(!jar.set("archiveofourown.org", ["_session=abc"].into_iter()));
+		assert!(jar.set("archiveofourown.org", ["_session=; Max-Age=0"].into_iter()));
+		assert_eq!(
+			jar.header("archiveofourown.org").as_deref(),
+			Some("remember=1")
+		);
+	}
+}
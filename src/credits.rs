@@ -0,0 +1,91 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use crate::providers;
+use crate::text::paragraphs;
+
+/// Longest paragraph taken for a credits line, longer ones are story text
+/// that happens to start like one.
+const MAX_CREDITS_LENGTH: usize = 200;
+
+lazy_static! {
+	/// Matches the credits of a translated chapter, like `Translator: Someone`
+	/// or `**TL:** Someone | **ED:** Someone else`.
+	static ref TRANSLATION_RE: Regex = Regex::new(
+		r#"(?i)^[*_\s]*(?:translat(?:ors?|ed by|ion group)|tls?|editors?|edited by|eds?|proofreaders?|prs?|group)[*_\s]*[:：]"#
+	)
+	.unwrap();
+}
+
+/// Returns the rule the credits of the chapters of `provider` are matched
+/// with.
+///
+/// Sites of original works have no translators to credit, so nothing is
+/// matched there rather than lines of the story that look like credits.
+fn rule(provider: Option<&str>) -> Option<&'static Regex> {
+	match provider {
+		Some("royalroad" | "scribblehub" | "syosetu" | "kakuyomu" | "ao3") => None,
+		_ => Some(&TRANSLATION_RE),
+	}
+}
+
+/// Returns the credits lines of the chapter at `url`, as the translators
+/// or groups wrote them.
+pub fn extract(url: &Url, text: &str) -> Vec<String> {
+	let rule = match rule(providers::from_url(url)) {
+		Some(rule) => rule,
+		None => return vec![],
+	};
+
+	paragraphs(text)
+		.map(str::trim)
+		.filter(|paragraph| paragraph.len() <= MAX_CREDITS_LENGTH && rule.is_match(paragraph))
+		.map(str::to_string)
+		.collect()
+}
+
+/// Returns the credits lines of `chapters`, each once and in the order they
+/// first appear, for the colophon of an export.
+pub fn colophon<'a>(chapters: impl IntoIterator<Item = (&'a Url, &'a str)>) -> Vec<String> {
+	let mut credits: Vec<String> = vec![];
+	for (url, text) in chapters {
+		for line in extract(url, text) {
+			if !credits.contains(&line) {
+				credits.push(line);
+			}
+		}
+	}
+	credits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn extracts_credits_per_provider() {
+		let text = "**Translator:** Someone\n\nEditor: Someone else\n\nThe group: it was late.\n\nTL note: a note rather than credits.";
+		let translated = Url::parse("https://www.readlightnovel.me/novel/chapter-1").unwrap();
+		assert_eq!(
+			extract(&translated, text),
+			vec!["**Translator:** Someone", "Editor: Someone else"]
+		);
+
+		let original = Url::parse("https://www.royalroad.com/fiction/1/chapter/2").unwrap();
+		assert!(extract(&original, text).is_empty());
+
+		let next = Url::parse("https://www.readlightnovel.me/novel/chapter-2").unwrap();
+		assert_eq!(
+			colophon([
+				(&translated, text),
+				(&next, "Editor: Someone else\n\nPR: A third")
+			]),
+			vec![
+				"**Translator:** Someone",
+				"Editor: Someone else",
+				"PR: A third"
+			]
+		);
+	}
+}
@@ -0,0 +1,374 @@
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::XChaCha20Poly1305;
+use lazy_static::lazy_static;
+use memmap2::Mmap;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::cookies::Jar;
+use crate::freshness::Freshness;
+use crate::history::History;
+use crate::library::Library;
+use crate::playlists::Playlists;
+use crate::remind::Reminders;
+use crate::searches::Searches;
+use crate::session::Session;
+use crate::store::ChapterStore;
+use crate::tocs::Tocs;
+use crate::trash::Trash;
+use crate::unlocks::Unlocks;
+use crate::utils::{data_dir, write_atomic};
+
+/// Starts every encrypted file, so files written before encryption was
+/// turned on can still be told apart and read.
+const MAGIC: &[u8] = b"ranobe-sealed-1\n";
+
+const NONCE_LEN: usize = 24;
+
+const SALT_LEN: usize = 16;
+
+/// Encrypted files are readable by the passphrase alone, this only tells a
+/// wrong passphrase apart from a damaged file.
+const CHECK: &[u8] = b"ranobe";
+
+/// Loads the key of the library, asking for the passphrase if needed.
+type Unlock = Box<dyn Fn() -> Result<Key> + Send>;
+
+lazy_static! {
+	static ref KEY: RwLock<Option<Key>> = RwLock::new(None);
+	/// Loads the key the first time it is needed.
+	static ref UNLOCK: Mutex<Option<Unlock>> = Mutex::new(None);
+}
+
+/// A key derived from the passphrase of the library.
+#[derive(Clone)]
+pub struct Key([u8; 32]);
+
+/// What `key.json` keeps to derive the key again.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+	salt: Vec<u8>,
+	/// `CHECK` sealed with the key.
+	check: Vec<u8>,
+}
+
+impl Key {
+	/// Derives the key of `passphrase` with argon2.
+	pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+		let mut key = [0; 32];
+		Argon2::default()
+			.hash_password_into(passphrase.as_bytes(), salt, &mut key)
+			.map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
+		Ok(Self(key))
+	}
+
+	fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+		let mut nonce = [0; NONCE_LEN];
+		rand::thread_rng().fill_bytes(&mut nonce);
+
+		let ciphertext = XChaCha20Poly1305::new(&self.0.into())
+			.encrypt(&nonce.into(), plaintext)
+			.map_err(|_| Error::other("failed to encrypt"))?;
+
+		Ok([MAGIC, &nonce, &ciphertext].concat())
+	}
+
+	fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+		let sealed = sealed
+			.strip_prefix(MAGIC)
+			.filter(|sealed| sealed.len() >= NONCE_LEN)
+			.ok_or_else(|| Error::new(ErrorKind::InvalidData, "not an encrypted file"))?;
+		let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+		XChaCha20Poly1305::new(&self.0.into())
+			.decrypt(nonce.into(), ciphertext)
+			.map_err(|_| Error::new(ErrorKind::InvalidData, "failed to decrypt"))
+	}
+}
+
+/// Returns where the salt of the passphrase is kept, the library is
+/// encrypted when it exists.
+pub fn key_path() -> PathBuf {
+	data_dir().join("key.json")
+}
+
+pub fn is_enabled() -> bool {
+	key_path().exists()
+}
+
+/// Starts encrypting with a key derived from `passphrase` and returns it.
+pub fn create(passphrase: &str) -> Result<Key> {
+	let mut salt = vec![0; SALT_LEN];
+	rand::thread_rng().fill_bytes(&mut salt);
+	let key = Key::derive(passphrase, &salt)?;
+
+	let key_file = KeyFile {
+		check: key.seal(CHECK)?,
+		salt,
+	};
+	fs::create_dir_all(data_dir())?;
+	write_atomic(key_path(), serde_json::to_string(&key_file)?)?;
+
+	Ok(key)
+}
+
+/// Derives the key of the library from `passphrase`, failing with
+/// `PermissionDenied` if it is the wrong one.
+pub fn load(passphrase: &str) -> Result<Key> {
+	let key_file: KeyFile = serde_json::from_str(&fs::read_to_string(key_path())?)?;
+	let key = Key::derive(passphrase, &key_file.salt)?;
+
+	match key.open(&key_file.check) {
+		Ok(check) if check == CHECK => Ok(key),
+		_ => Err(Error::new(ErrorKind::PermissionDenied, "wrong passphrase")),
+	}
+}
+
+/// Sets the key files are encrypted with from now on, `None` writes them
+/// in plain text.
+pub fn set_key(key: Option<Key>) {
+	*UNLOCK.lock().unwrap() = None;
+	*KEY.write().unwrap() = key;
+}
+
+/// Sets how the key is loaded the first time an encrypted file is read or
+/// any file is written, so commands touching none of them do not ask for
+/// the passphrase.
+pub fn set_unlock<F: Fn() -> Result<Key> + Send + 'static>(unlock: F) {
+	*UNLOCK.lock().unwrap() = Some(Box::new(unlock));
+}
+
+/// Returns the key set by `set_key`, loading it with the one set by
+/// `set_unlock` on the first call.
+///
+/// A key that fails to load is tried again on the next call, so files are
+/// never written in plain text meanwhile.
+pub fn key() -> Result<Option<Key>> {
+	let mut unlock = UNLOCK.lock().unwrap();
+	if let Some(load) = unlock.as_ref() {
+		*KEY.write().unwrap() = Some(load()?);
+		*unlock = None;
+	}
+	Ok(KEY.read().unwrap().clone())
+}
+
+/// Opens `contents` with `key` if they are encrypted.
+fn open(contents: Vec<u8>, key: Option<&Key>) -> Result<Vec<u8>> {
+	open_lazily(contents, || Ok(key.cloned()))
+}
+
+/// Opens `contents` like `open`, only getting the key from `key` if they
+/// are encrypted.
+fn open_lazily<F: FnOnce() -> Result<Option<Key>>>(contents: Vec<u8>, key: F) -> Result<Vec<u8>> {
+	if !contents.starts_with(MAGIC) {
+		return Ok(contents);
+	}
+
+	match key()? {
+		Some(key) => key.open(&contents),
+		None => Err(Error::new(
+			ErrorKind::PermissionDenied,
+			"the library is encrypted, set RANOBE_PASSPHRASE or key_file in the configuration",
+		)),
+	}
+}
+
+/// Reads `path`, decrypting it if it is encrypted.
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
+	let contents = open_lazily(fs::read(path)?, key)?;
+	String::from_utf8(contents).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+/// Files from this size on are mapped rather than read by `read_text`.
+const MAP_SIZE: u64 = 1024 * 1024;
+
+/// Text read by `read_text`, either mapped from its file or read into memory.
+pub enum Text {
+	Mapped(Mmap),
+	Read(String),
+}
+
+impl Text {
+	pub fn into_string(self) -> String {
+		match self {
+			Text::Mapped(map) => String::from_utf8_lossy(&map).into_owned(),
+			Text::Read(text) => text,
+		}
+	}
+}
+
+impl Deref for Text {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		match self {
+			// Checked to be UTF-8 when mapped
+			Text::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+			Text::Read(text) => text,
+		}
+	}
+}
+
+/// Reads `path` like `read_to_string`, mapping it instead when it is large
+/// and not encrypted, so a whole volume opens without being copied into
+/// memory first.
+///
+/// Only files replaced by renaming them, like the blobs of the store, are
+/// safe to map, as writing to a mapped file changes the text under it.
+pub fn read_text<P: AsRef<Path>>(path: P) -> Result<Text> {
+	let file = File::open(&path)?;
+	if file.metadata()?.len() < MAP_SIZE {
+		return read_to_string(path).map(Text::Read);
+	}
+
+	// Blobs are replaced through a rename, never written in place
+	let map = unsafe { Mmap::map(&file)? };
+	if map.starts_with(MAGIC) {
+		return read_to_string(path).map(Text::Read);
+	}
+
+	std::str::from_utf8(&map).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+	Ok(Text::Mapped(map))
+}
+
+/// Writes `contents` to `path` atomically, encrypted if a key is set.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+	match key()? {
+		Some(key) => write_atomic(path, key.seal(contents.as_ref())?),
+		None => write_atomic(path, contents),
+	}
+}
+
+/// Seals `contents` with a key derived from `passphrase` and a new salt,
+/// kept in front so they open on any machine with the passphrase alone.
+pub fn seal_with(passphrase: &str, contents: &[u8]) -> Result<Vec<u8>> {
+	let mut salt = [0; SALT_LEN];
+	rand::thread_rng().fill_bytes(&mut salt);
+	let key = Key::derive(passphrase, &salt)?;
+
+	Ok([&salt[..], &key.seal(contents)?].concat())
+}
+
+/// Opens what `seal_with` sealed, failing with `PermissionDenied` if
+/// `passphrase` is the wrong one.
+pub fn open_with(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>> {
+	if sealed.len() < SALT_LEN || !sealed[SALT_LEN..].starts_with(MAGIC) {
+		return Err(Error::new(ErrorKind::InvalidData, "not an encrypted file"));
+	}
+	let (salt, sealed) = sealed.split_at(SALT_LEN);
+
+	Key::derive(passphrase, salt)?
+		.open(sealed)
+		.map_err(|_| Error::new(ErrorKind::PermissionDenied, "wrong passphrase"))
+}
+
+/// Returns every file holding the reading list or chapters, the files
+/// encryption covers.
+pub fn covered_files() -> Result<Vec<PathBuf>> {
+	let store = ChapterStore::default_root();
+	let mut files = vec![
+		Library::default_path(),
+		History::default_path(),
+		Trash::default_path(),
+		Session::default_path(),
+		Reminders::default_path(),
+		Searches::default_path(),
+		Playlists::default_path(),
+		Freshness::default_path(),
+		Tocs::default_path(),
+		Unlocks::default_path(),
+		Jar::default_path(),
+		store.join("index.json"),
+		store.join("journal.json"),
+	];
+
+	match fs::read_dir(store.join("blobs")) {
+		Ok(blobs) => {
+			for blob in blobs {
+				files.push(blob?.path());
+			}
+		}
+		Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+		Err(_) => {}
+	}
+
+	files.retain(|file| file.exists());
+	Ok(files)
+}
+
+/// Rewrites `files` encrypted with `to`, or in plain text, after reading
+/// them with `from`.
+pub fn convert(files: &[PathBuf], from: Option<&Key>, to: Option<&Key>) -> Result<()> {
+	for file in files {
+		let contents = open(fs::read(file)?, from)?;
+		match to {
+			Some(key) => write_atomic(file, key.seal(&contents)?)?,
+			None => write_atomic(file, contents)?,
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn seals_and_opens() {
+		let key = Key::derive("passphrase", b"salt of the test").unwrap();
+		let other = Key::derive("other", b"salt of the test").unwrap();
+
+		let sealed = key.seal(b"library").unwrap();
+		assert!(!sealed.windows(7).any(|window| window == b"library"));
+		assert_eq!(open(sealed.clone(), Some(&key)).unwrap(), b"library");
+		assert!(open(sealed.clone(), Some(&other)).is_err());
+		assert!(open(sealed, None).is_err());
+
+		// Files written before encryption was turned on stay readable
+		assert_eq!(open(b"plain".to_vec(), Some(&key)).unwrap(), b"plain");
+	}
+
+	#[test]
+	fn maps_large_files() {
+		let dir = TestDir::new("crypt");
+		let path = dir.join("volume.txt");
+		let volume = "Chapter.\n".repeat(MAP_SIZE as usize / 8);
+
+		fs::write(&path, &volume).unwrap();
+		let text = read_text(&path).unwrap();
+		assert!(matches!(text, Text::Mapped(_)));
+		assert_eq!(&*text, volume);
+
+		fs::write(&path, "Chapter.").unwrap();
+		assert!(matches!(read_text(&path).unwrap(), Text::Read(_)));
+
+		fs::write(&path, [&volume.as_bytes()[1..], &[0xff]].concat()).unwrap();
+		assert_eq!(
+			read_text(&path).err().unwrap().kind(),
+			ErrorKind::InvalidData
+		);
+	}
+
+	#[test]
+	fn seals_with_a_passphrase() {
+		let sealed = seal_with("passphrase", b"cookies").unwrap();
+		assert_eq!(open_with("passphrase", &sealed).unwrap(), b"cookies");
+		assert_eq!(
+			open_with("other", &sealed).unwrap_err().kind(),
+			ErrorKind::PermissionDenied
+		);
+		assert_eq!(
+			open_with("passphrase", b"cookies").unwrap_err().kind(),
+			ErrorKind::InvalidData
+		);
+	}
+}
@@ -0,0 +1,157 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+	static ref RELATIVE_RE: Regex = Regex::new(
+		r#"(?i)^(\d+|an?|one)\s*(seconds?|secs?|minutes?|mins?|hours?|hrs?|days?|weeks?|months?|years?)\s+ago$"#
+	)
+	.unwrap();
+	static ref CJK_RELATIVE_RE: Regex =
+		Regex::new(r#"^(\d+)\s*(秒|分钟|分|時間|小时|日|天|週間|周|ヶ月|か月|个月|年)前$"#).unwrap();
+	static ref CJK_DATE_RE: Regex =
+		Regex::new(r#"^(?:(\d{4})\s*年)?\s*(\d{1,2})\s*月\s*(\d{1,2})\s*日"#).unwrap();
+}
+
+/// Formats of dates that carry their year.
+const FORMATS: [&str; 8] = [
+	"%Y-%m-%d",
+	"%Y/%m/%d",
+	"%B %d, %Y",
+	"%b %d, %Y",
+	"%d %B %Y",
+	"%d %b %Y",
+	"%d.%m.%Y",
+	"%b %d %Y",
+];
+
+/// Formats of dates without a year, like `Jan 5`.
+const YEARLESS_FORMATS: [&str; 4] = ["%b %d", "%B %d", "%d %b", "%d %B"];
+
+/// Parses the update time of a listing, like `2 hours ago`, `Jan 5`,
+/// `2023-01-05` or `3日前`, relative to `now`.
+///
+/// Dates without a year are placed in the last year they could have been,
+/// dates without a time of day at midnight.
+pub fn parse(text: &str, now: DateTime<Local>) -> Option<SystemTime> {
+	let text = text.trim();
+
+	let relative = match text.to_lowercase().as_str() {
+		"just now" | "now" | "today" | "今日" | "今天" => Some(Duration::zero()),
+		"yesterday" | "昨日" | "昨天" => Some(Duration::days(1)),
+		_ => RELATIVE_RE
+			.captures(text)
+			.and_then(|captures| {
+				let amount = match &captures[1].to_lowercase()[..] {
+					"a" | "an" | "one" => 1,
+					amount => amount.parse().ok()?,
+				};
+				duration(amount, &captures[2].to_lowercase())
+			})
+			.or_else(|| {
+				let captures = CJK_RELATIVE_RE.captures(text)?;
+				duration(captures[1].parse().ok()?, &captures[2])
+			}),
+	};
+	if let Some(ago) = relative {
+		return Some((now - ago).into());
+	}
+
+	let date = FORMATS
+		.iter()
+		.find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+		.or_else(|| {
+			let captures = CJK_DATE_RE.captures(text)?;
+			let month = captures[2].parse().ok()?;
+			let day = captures[3].parse().ok()?;
+			match captures.get(1) {
+				Some(year) => NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, month, day),
+				None => yearless(now, |year| NaiveDate::from_ymd_opt(year, month, day)),
+			}
+		})
+		.or_else(|| {
+			yearless(now, |year| {
+				YEARLESS_FORMATS.iter().find_map(|format| {
+					NaiveDate::parse_from_str(
+						&format!("{} {}", text, year),
+						&format!("{} %Y", format),
+					)
+					.ok()
+				})
+			})
+		})?;
+
+	Local
+		.from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+		.earliest()
+		.map(SystemTime::from)
+}
+
+/// Resolves a date without a year to the latest one not after `now`.
+fn yearless<F>(now: DateTime<Local>, in_year: F) -> Option<NaiveDate>
+where
+	F: Fn(i32) -> Option<NaiveDate>,
+{
+	match in_year(now.year()) {
+		Some(date) if date <= now.date_naive() => Some(date),
+		// Listings only show dates without a year for the past
+		_ => in_year(now.year() - 1),
+	}
+}
+
+/// Formats how long ago `time` was, like `5m`, `3h` or `2d`.
+pub fn age(time: SystemTime, now: SystemTime) -> String {
+	let secs = now.duration_since(time).map_or(0, |age| age.as_secs());
+
+	match secs {
+		0..=59 => "now".to_string(),
+		60..=3599 => format!("{}m", secs / 60),
+		3600..=86399 => format!("{}h", secs / 3600),
+		86400..=604799 => format!("{}d", secs / 86400),
+		604800..=2591999 => format!("{}w", secs / 604800),
+		2592000..=31535999 => format!("{}mo", secs / 2592000),
+		_ => format!("{}y", secs / 31536000),
+	}
+}
+
+fn duration(amount: i64, unit: &str) -> Option<Duration> {
+	let duration = match unit.trim_end_matches('s') {
+		"second" | "sec" | "秒" => Duration::seconds(amount),
+		"minute" | "min" | "分" | "分钟" => Duration::minutes(amount),
+		"hour" | "hr" | "時間" | "小时" => Duration::hours(amount),
+		"day" | "日" | "天" => Duration::days(amount),
+		"week" | "週間" | "周" => Duration::weeks(amount),
+		"month" | "ヶ月" | "か月" | "个月" => Duration::days(30 * amount),
+		"year" | "年" => Duration::days(365 * amount),
+		_ => return None,
+	};
+	Some(duration)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_listing_dates() {
+		let now = Local.with_ymd_and_hms(2023, 2, 10, 12, 0, 0).unwrap();
+		let at = |y, m, d, h| SystemTime::from(Local.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap());
+
+		assert_eq!(parse("2 hours ago", now), Some(at(2023, 2, 10, 10)));
+		assert_eq!(parse("an hour ago", now), Some(at(2023, 2, 10, 11)));
+		assert_eq!(parse("3日前", now), Some(at(2023, 2, 7, 12)));
+		assert_eq!(parse("Yesterday", now), Some(at(2023, 2, 9, 12)));
+		assert_eq!(parse("Jan 5", now), Some(at(2023, 1, 5, 0)));
+		assert_eq!(parse("Dec 24", now), Some(at(2022, 12, 24, 0)));
+		assert_eq!(parse("March 3, 2021", now), Some(at(2021, 3, 3, 0)));
+		assert_eq!(parse("2021-03-03", now), Some(at(2021, 3, 3, 0)));
+		assert_eq!(parse("2021年3月3日", now), Some(at(2021, 3, 3, 0)));
+		assert_eq!(parse("Chapter 5", now), None);
+
+		let now = SystemTime::from(now);
+		assert_eq!(age(at(2023, 2, 10, 10), now), "2h");
+		assert_eq!(age(at(2023, 1, 20, 12), now), "3w");
+	}
+}
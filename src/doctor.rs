@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::RanobeError;
+use crate::providers::registry::Provider;
+use crate::utils::{data_dir, write_atomic};
+
+/// How a provider, or one of its probes, fares against its fixture, from
+/// the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+	/// Matches about as much as its fixture.
+	Ok,
+	/// Has no fixture to compare with yet.
+	New,
+	/// Matches far more or far less than its fixture, like a site that
+	/// started changing its layout.
+	Drifting,
+	/// Could not be checked, like a site that is down.
+	Failed,
+	/// Matches nothing anymore.
+	Broken,
+}
+
+impl fmt::Display for Health {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let health = match self {
+			Health::Ok => "ok",
+			Health::New => "new",
+			Health::Drifting => "drifting",
+			Health::Failed => "failed",
+			Health::Broken => "broken",
+		};
+		f.write_str(health)
+	}
+}
+
+/// Judges a probe matching `found` times against its fixture, `expected`.
+///
+/// Counts off by more than half of the fixture either way are drifting,
+/// as listings grow and shrink a little from one day to the next.
+pub fn judge(expected: Option<usize>, found: usize) -> Health {
+	let expected = match expected {
+		Some(expected) => expected,
+		None => return Health::New,
+	};
+
+	if found == 0 && expected > 0 {
+		Health::Broken
+	} else if found * 2 < expected || found > expected * 2 {
+		Health::Drifting
+	} else {
+		Health::Ok
+	}
+}
+
+/// A probe of a provider against its fixture.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+	pub probe: String,
+	pub expected: Option<usize>,
+	pub found: usize,
+	pub health: Health,
+}
+
+/// How a provider fares, as the worst of its probes.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+	pub provider: String,
+	pub health: Health,
+	pub checks: Vec<Check>,
+	/// Why the provider could not be probed.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+impl Report {
+	/// Returns the counts found, to compare the next checks with.
+	pub fn counts(&self) -> BTreeMap<String, usize> {
+		self.checks
+			.iter()
+			.map(|check| (check.probe.clone(), check.found))
+			.collect()
+	}
+}
+
+/// Probes `provider` and judges the counts against `fixtures`, its counts
+/// as of when it last worked.
+///
+/// Pages that can not be parsed anymore break the provider, while other
+/// errors only fail the check.
+pub async fn check(provider: Provider, fixtures: BTreeMap<String, usize>) -> Report {
+	let name = provider.name().to_string();
+
+	let counts = match provider.probe().await {
+		Ok(counts) => counts,
+		Err(err) => {
+			let health = match err {
+				RanobeError::Parse(_) => Health::Broken,
+				_ => Health::Failed,
+			};
+			return Report {
+				provider: name,
+				health,
+				checks: vec![],
+				error: Some(err.to_string()),
+			};
+		}
+	};
+
+	let checks = counts
+		.into_iter()
+		.map(|(probe, found)| {
+			let expected = fixtures.get(probe).copied();
+			Check {
+				probe: probe.to_string(),
+				expected,
+				found,
+				health: judge(expected, found),
+			}
+		})
+		.collect::<Vec<_>>();
+
+	Report {
+		provider: name,
+		health: checks
+			.iter()
+			.map(|check| check.health)
+			.max()
+			.unwrap_or(Health::Ok),
+		checks,
+		error: None,
+	}
+}
+
+/// Counts the probes of every provider matched when they last worked, by
+/// provider.
+///
+/// They are kept as plain JSON, unlike the library, as they hold nothing
+/// personal and maintainers commit them next to the providers for CI.
+pub struct Fixtures {
+	path: PathBuf,
+	fixtures: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl Fixtures {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("doctor.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let fixtures = match fs::read_to_string(&path) {
+			Ok(fixtures) => serde_json::from_str(&fixtures)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, fixtures })
+	}
+
+	pub fn get(&self, provider: &str) -> Option<&BTreeMap<String, usize>> {
+		self.fixtures.get(provider)
+	}
+
+	/// Compares the next checks of `provider` with `counts`.
+	pub fn set(&mut self, provider: &str, counts: BTreeMap<String, usize>) {
+		self.fixtures.insert(provider.to_string(), counts);
+	}
+
+	pub fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		write_atomic(&self.path, serde_json::to_string_pretty(&self.fixtures)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn judges_counts_against_fixtures() {
+		assert_eq!(judge(None, 20), Health::New);
+		assert_eq!(judge(Some(20), 20), Health::Ok);
+		assert_eq!(judge(Some(20), 12), Health::Ok);
+		assert_eq!(judge(Some(20), 9), Health::Drifting);
+		assert_eq!(judge(Some(20), 41), Health::Drifting);
+		assert_eq!(judge(Some(20), 0), Health::Broken);
+		assert_eq!(judge(Some(0), 0), Health::Ok);
+		assert!(Health::Broken > Health::Drifting);
+	}
+}
@@ -0,0 +1,118 @@
+use std::io;
+use std::time::Duration;
+
+use surf::{StatusCode, Url};
+use thiserror::Error;
+
+/// What went wrong scraping or reading a novel, so callers can tell failures
+/// apart without going through their messages.
+#[derive(Debug, Error)]
+pub enum RanobeError {
+	/// The site could not be reached, or answered with an error.
+	#[error("{0}")]
+	Network(surf::Error),
+	/// A page or file is not shaped like it should be, like a site that
+	/// changed its layout.
+	#[error("{0}")]
+	Parse(String),
+	/// The provider does not offer what was asked of it.
+	#[error("{0}")]
+	Unsupported(String),
+	/// What was asked for does not exist, or was taken down.
+	#[error("{0}")]
+	NotFound(String),
+	/// The site refused the request because too many were sent, with how
+	/// long it asked to wait, if it did.
+	#[error("{url} refused the request, too many were sent")]
+	RateLimited {
+		url: Url,
+		retry_after: Option<Duration>,
+	},
+	/// The site only serves it to an account, or one that paid for it.
+	#[error("{0}")]
+	Unauthorized(String),
+	/// An argument or input that can not be used as it is.
+	#[error("{0}")]
+	Invalid(String),
+	/// A program ranobe runs, like glow or tar, failed.
+	#[error("{0}")]
+	Tool(String),
+	#[error(transparent)]
+	Io(#[from] io::Error),
+}
+
+impl RanobeError {
+	/// Returns the error of a request that failed with `status`.
+	pub fn network(status: StatusCode, message: impl Into<String>) -> Self {
+		Self::Network(surf::Error::from_str(status, message.into()))
+	}
+
+	/// Returns the status of the response the error stands for, like the
+	/// status it would have been served with by a site.
+	pub fn status(&self) -> StatusCode {
+		match self {
+			Self::Network(err) => err.status(),
+			Self::Parse(_) => StatusCode::UnprocessableEntity,
+			Self::Unsupported(_) => StatusCode::NotImplemented,
+			Self::NotFound(_) => StatusCode::NotFound,
+			Self::RateLimited { .. } => StatusCode::TooManyRequests,
+			Self::Unauthorized(_) => StatusCode::Unauthorized,
+			Self::Invalid(_) => StatusCode::BadRequest,
+			Self::Tool(_) | Self::Io(_) => StatusCode::InternalServerError,
+		}
+	}
+}
+
+impl From<surf::Error> for RanobeError {
+	fn from(err: surf::Error) -> Self {
+		// Middleware like the cassette can only fail with a surf error
+		let err = match err.downcast::<RanobeError>() {
+			Ok(err) => return err,
+			Err(err) => err,
+		};
+
+		match err.status() {
+			StatusCode::NotFound | StatusCode::Gone | StatusCode::UnavailableForLegalReasons => {
+				Self::NotFound(err.to_string())
+			}
+			StatusCode::Unauthorized | StatusCode::Forbidden | StatusCode::PaymentRequired => {
+				Self::Unauthorized(err.to_string())
+			}
+			_ => Self::Network(err),
+		}
+	}
+}
+
+impl From<serde_json::Error> for RanobeError {
+	fn from(err: serde_json::Error) -> Self {
+		Self::Parse(err.to_string())
+	}
+}
+
+impl From<surf::http::url::ParseError> for RanobeError {
+	fn from(err: surf::http::url::ParseError) -> Self {
+		Self::Parse(err.to_string())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_kind_through_surf() {
+		let err = RanobeError::from(surf::Error::from_str(StatusCode::Gone, "gone"));
+		assert!(matches!(err, RanobeError::NotFound(message) if message == "gone"));
+
+		let err = RanobeError::from(surf::Error::from_str(StatusCode::BadGateway, "down"));
+		assert_eq!(err.status(), StatusCode::BadGateway);
+
+		let url = Url::parse("https://example.com").unwrap();
+		let limited = RanobeError::RateLimited {
+			url,
+			retry_after: None,
+		};
+		let err = RanobeError::from(surf::Error::from(limited));
+		assert!(matches!(err, RanobeError::RateLimited { .. }));
+	}
+}
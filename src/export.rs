@@ -0,0 +1,464 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::chapter::ChapterNumber;
+use crate::credits;
+use crate::providers::Chapter;
+use crate::store::{hash, ChapterStore};
+use crate::utils::{data_dir, write_atomic};
+
+/// Title of the section keeping the credits of the translators, last in
+/// an export.
+const COLOPHON: &str = "Colophon";
+
+lazy_static! {
+	static ref STRONG_RE: Regex = Regex::new(r#"\*\*(.+?)\*\*"#).unwrap();
+	static ref EMPHASIS_RE: Regex = Regex::new(r#"\b_(.+?)_\b"#).unwrap();
+}
+
+/// Format a whole novel is exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// A Markdown file per chapter in a directory named after the novel.
+	Markdown,
+	/// A single epub named after the novel.
+	Epub,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+		match format.to_lowercase().as_str() {
+			"markdown" | "md" => Ok(Self::Markdown),
+			"epub" => Ok(Self::Epub),
+			_ => Err(format!(
+				"unknown format {}, expected markdown or epub",
+				format
+			)),
+		}
+	}
+}
+
+impl Format {
+	/// Returns where `novel` is exported to under `dir`.
+	pub fn path<P: AsRef<Path>>(&self, novel: &str, dir: P) -> PathBuf {
+		match self {
+			Self::Markdown => dir.as_ref().join(file_name(novel)),
+			Self::Epub => dir.as_ref().join(format!("{}.epub", file_name(novel))),
+		}
+	}
+}
+
+/// What exporting a chapter would do to the file it is exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+	Added,
+	Changed,
+	Unchanged,
+}
+
+/// A chapter file an export would write.
+#[derive(Debug, Clone)]
+pub struct Change {
+	pub path: PathBuf,
+	pub title: String,
+	pub kind: ChangeKind,
+	contents: String,
+}
+
+/// Returns the directory exports are written to by default.
+pub fn default_dir() -> PathBuf {
+	data_dir().join("exports")
+}
+
+/// Turns `name` into something usable as a file name.
+pub fn file_name(name: &str) -> String {
+	name.chars()
+		.map(|c| match c {
+			'/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+			c => c,
+		})
+		.collect::<String>()
+		.trim()
+		.to_string()
+}
+
+/// Plans exporting the stored chapters of `novel` as Markdown files under
+/// `dir/<novel>`, without writing anything.
+///
+/// Chapters are ordered by their chapter number, followed by a colophon
+/// keeping the credits of the translators if the chapters have any. Files
+/// that exist with different contents, for example because they were edited
+/// by hand, are reported as changed.
+pub fn plan_markdown<P: AsRef<Path>>(
+	store: &ChapterStore,
+	novel: &str,
+	dir: P,
+) -> Result<Vec<Change>> {
+	let dir = dir.as_ref().join(file_name(novel));
+
+	let mut chapters = store
+		.entries()
+		.filter(|(_, entry)| entry.novel == novel)
+		.map(|(url, entry)| (ChapterNumber::parse(&entry.title), url, entry))
+		.collect::<Vec<_>>();
+	chapters.sort_by(|a, b| (a.0, &a.2.title).cmp(&(b.0, &b.2.title)));
+
+	let mut texts = vec![];
+	for (_, url, entry) in chapters {
+		if let Some(text) = store.get(url)? {
+			texts.push((url, entry.title.clone(), text));
+		}
+	}
+
+	let credits = credits::colophon(texts.iter().map(|(url, _, text)| (*url, text.as_str())));
+	let mut files = texts
+		.into_iter()
+		.map(|(_, title, text)| (title, text.trim().to_string()))
+		.collect::<Vec<_>>();
+	if !credits.is_empty() {
+		files.push((COLOPHON.to_string(), credits.join("\n\n")));
+	}
+
+	let mut changes = vec![];
+	for (title, text) in files {
+		let path = dir.join(format!("{}.md", file_name(&title)));
+		let contents = format!("# {}\n\n{}\n", title, text);
+		let kind = match fs::read_to_string(&path) {
+			Ok(existing) if existing == contents => ChangeKind::Unchanged,
+			Ok(_) => ChangeKind::Changed,
+			Err(_) => ChangeKind::Added,
+		};
+
+		changes.push(Change {
+			path,
+			title,
+			kind,
+			contents,
+		});
+	}
+
+	Ok(changes)
+}
+
+/// Writes every added or changed file of `changes`.
+pub fn apply(changes: &[Change]) -> Result<()> {
+	for change in changes {
+		if change.kind == ChangeKind::Unchanged {
+			continue;
+		}
+
+		if let Some(parent) = change.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		write_atomic(&change.path, &change.contents)?;
+	}
+
+	Ok(())
+}
+
+/// Returns a digest of the stored chapters of `novel`, which changes
+/// whenever a chapter is added, renamed or its text changes.
+pub fn digest(store: &ChapterStore, novel: &str) -> String {
+	digest_chapters(store, &store.chapters(novel))
+}
+
+/// Returns a digest of the stored `chapters` like `digest`.
+pub fn digest_chapters(store: &ChapterStore, chapters: &[Chapter]) -> String {
+	let chapters = chapters
+		.iter()
+		.filter_map(|chapter| {
+			let entry = store.entry(&chapter.url)?;
+			Some(format!("{}\t{}", entry.title, entry.hash))
+		})
+		.collect::<Vec<_>>();
+
+	hash(&chapters.join("\n"))
+}
+
+/// Remembers the digest of the chapters every export was made from, so
+/// exports of novels that did not change can be skipped.
+///
+/// It is kept next to the exports in `.manifest.json`.
+pub struct Manifest {
+	path: PathBuf,
+	exports: BTreeMap<PathBuf, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ManifestData {
+	exports: BTreeMap<PathBuf, String>,
+}
+
+impl Manifest {
+	pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+		let path = dir.as_ref().join(".manifest.json");
+
+		let data: ManifestData = match fs::read_to_string(&path) {
+			Ok(data) => serde_json::from_str(&data)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => ManifestData::default(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self {
+			path,
+			exports: data.exports,
+		})
+	}
+
+	/// Whether `export` exists and was made from chapters with `digest`.
+	pub fn is_current(&self, export: &Path, digest: &str) -> bool {
+		export.exists()
+			&& self
+				.exports
+				.get(export)
+				.is_some_and(|known| known == digest)
+	}
+
+	/// Forgets the exports that do not exist anymore and returns how many.
+	pub fn prune(&mut self) -> usize {
+		let before = self.exports.len();
+		self.exports.retain(|export, _| export.exists());
+		before - self.exports.len()
+	}
+
+	/// Records that `export` was made from chapters with `digest`.
+	pub fn set(&mut self, export: PathBuf, digest: String) {
+		self.exports.insert(export, digest);
+	}
+
+	pub fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let data = ManifestData {
+			exports: self.exports.clone(),
+		};
+		write_atomic(&self.path, serde_json::to_string(&data)?)
+	}
+}
+
+/// Escapes `text` for use in xml.
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+/// Turns the Markdown of a chapter into the body of an xhtml document.
+fn to_xhtml(text: &str) -> String {
+	text.split("\n\n")
+		.map(str::trim)
+		.filter(|paragraph| !paragraph.is_empty())
+		.map(|paragraph| {
+			let paragraph = escape(paragraph.trim_start_matches("> "));
+			let paragraph = STRONG_RE.replace_all(&paragraph, "<strong>$1</strong>");
+			let paragraph = EMPHASIS_RE.replace_all(&paragraph, "<em>$1</em>");
+			format!("<p>{}</p>", paragraph.replace('\n', "<br/>"))
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Returns where the volume `volume` of `novel` is exported to as an epub
+/// under `dir`.
+pub fn volume_path<P: AsRef<Path>>(novel: &str, volume: &str, dir: P) -> PathBuf {
+	dir.as_ref()
+		.join(format!("{} - {}.epub", file_name(novel), file_name(volume)))
+}
+
+/// Writes the stored chapters of `novel` as an epub to `path`, ordered by
+/// their chapter number.
+pub fn write_epub<P: AsRef<Path>>(store: &ChapterStore, novel: &str, path: P) -> Result<()> {
+	write_chapters(store, novel, &store.chapters(novel), path)
+}
+
+/// Writes the stored `chapters` as an epub titled `book` to `path`, in the
+/// order they are given and followed by a colophon with the credits of the
+/// translators if they have any.
+pub fn write_chapters<P: AsRef<Path>>(
+	store: &ChapterStore,
+	book: &str,
+	chapters: &[Chapter],
+	path: P,
+) -> Result<()> {
+	let path = path.as_ref();
+	let mut texts = vec![];
+	for chapter in chapters {
+		if let Some(text) = store.get(&chapter.url)? {
+			texts.push((&chapter.url, chapter.title.clone(), text));
+		}
+	}
+
+	let credits = credits::colophon(texts.iter().map(|(url, _, text)| (*url, text.as_str())));
+	let mut texts = texts
+		.into_iter()
+		.map(|(_, title, text)| (title, text))
+		.collect::<Vec<_>>();
+	if !credits.is_empty() {
+		texts.push((COLOPHON.to_string(), credits.join("\n\n")));
+	}
+
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	// Written next to the export first, so a failed export keeps the old one
+	let partial = path.with_extension("epub.part");
+	let mut zip = ZipWriter::new(File::create(&partial)?);
+	let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+	let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+	// The mimetype has to come first and uncompressed
+	zip.start_file("mimetype", stored)?;
+	zip.write_all(b"application/epub+zip")?;
+
+	zip.start_file("META-INF/container.xml", deflated)?;
+	zip.write_all(
+		br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#,
+	)?;
+
+	let mut items = vec![];
+	let mut spine = vec![];
+	let mut toc = vec![];
+	for (i, (title, text)) in texts.iter().enumerate() {
+		let id = format!("chapter{}", i + 1);
+		let href = format!("{}.xhtml", id);
+
+		zip.start_file(format!("OEBPS/{}", href), deflated)?;
+		write!(
+			zip,
+			r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>{title}</title></head>
+<body>
+<h1>{title}</h1>
+{body}
+</body>
+</html>
+"#,
+			title = escape(title),
+			body = to_xhtml(text)
+		)?;
+
+		items.push(format!(
+			r#"<item id="{}" href="{}" media-type="application/xhtml+xml"/>"#,
+			id, href
+		));
+		spine.push(format!(r#"<itemref idref="{}"/>"#, id));
+		toc.push(format!(
+			r#"<li><a href="{}">{}</a></li>"#,
+			href,
+			escape(title)
+		));
+	}
+
+	zip.start_file("OEBPS/nav.xhtml", deflated)?;
+	write!(
+		zip,
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+<nav epub:type="toc"><ol>
+{toc}
+</ol></nav>
+</body>
+</html>
+"#,
+		title = escape(book),
+		toc = toc.join("\n")
+	)?;
+
+	zip.start_file("OEBPS/content.opf", deflated)?;
+	write!(
+		zip,
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="id">urn:ranobe:{id}</dc:identifier>
+<dc:title>{title}</dc:title>
+<dc:language>en</dc:language>
+<meta property="dcterms:modified">{modified}</meta>
+</metadata>
+<manifest>
+<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+{items}
+</manifest>
+<spine>
+{spine}
+</spine>
+</package>
+"#,
+		id = hash(book),
+		title = escape(book),
+		modified = Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+		items = items.join("\n"),
+		spine = spine.join("\n")
+	)?;
+
+	zip.finish().map_err(Error::other)?;
+	fs::rename(partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::import::read_epub;
+	use crate::utils::TestDir;
+	use surf::Url;
+
+	#[test]
+	fn exports_epubs_that_import_back() {
+		let root = TestDir::new("export");
+		let mut store = ChapterStore::open(root.join("chapters")).unwrap();
+		for (n, text) in [
+			(2, "Two & _more_.\n\nTL: Someone"),
+			(1, "One.\n\nStill **one**."),
+		] {
+			let url = Url::parse(&format!("https://example.com/novel/chapter-{}", n)).unwrap();
+			store
+				.put(&url, "Novel", &format!("Chapter {}", n), text)
+				.unwrap();
+		}
+
+		let path = Format::Epub.path("Novel", &root);
+		write_epub(&store, "Novel", &path).unwrap();
+		let imported = read_epub(&path).unwrap();
+
+		assert_eq!(imported.title, "Novel");
+		let chapters = imported
+			.chapters
+			.iter()
+			.map(|(title, _, text)| (title.as_str(), text.as_str()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			chapters,
+			vec![
+				("Chapter 1", "Chapter 1\n\nOne.\n\nStill one."),
+				("Chapter 2", "Chapter 2\n\nTwo & more.\n\nTL: Someone"),
+				("Colophon", "Colophon\n\nTL: Someone"),
+			]
+		);
+	}
+}
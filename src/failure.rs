@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use surf::Url;
+
+use crate::error::RanobeError;
+use crate::utils::{cache_dir, write_atomic};
+
+/// Pages larger than this are cut off before they are saved.
+const MAX_DUMP_SIZE: usize = 512 * 1024;
+
+/// How many pages are kept, older ones are deleted first.
+const MAX_DUMPS: usize = 20;
+
+/// Returns the directory pages that failed to scrape are saved in.
+pub fn default_dir() -> PathBuf {
+	cache_dir().join("failures")
+}
+
+/// Saves `html`, the page at `url` that `selector` failed on, into `dir`
+/// and returns the path it was saved at.
+///
+/// The page is capped at `MAX_DUMP_SIZE` and only the `MAX_DUMPS` latest
+/// pages are kept.
+pub fn save<P: AsRef<Path>>(
+	dir: P,
+	provider: &str,
+	url: &Url,
+	selector: &str,
+	html: &str,
+) -> Result<PathBuf> {
+	let dir = dir.as_ref();
+	fs::create_dir_all(dir)?;
+
+	let mut end = html.len().min(MAX_DUMP_SIZE);
+	while !html.is_char_boundary(end) {
+		end -= 1;
+	}
+
+	let millis = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |since| since.as_millis());
+	let path = dir.join(format!("{}-{}.html", millis, provider));
+	let contents = format!(
+		"<!-- provider: {}\n     url: {}\n     selector: {} -->\n{}",
+		provider,
+		url,
+		selector,
+		&html[..end]
+	);
+	write_atomic(&path, contents)?;
+
+	rotate(dir)?;
+
+	Ok(path)
+}
+
+/// Deletes the oldest pages beyond `MAX_DUMPS`.
+fn rotate(dir: &Path) -> Result<()> {
+	let mut dumps = fs::read_dir(dir)?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<Result<Vec<_>>>()?;
+	dumps.retain(|path| {
+		path.extension()
+			.is_some_and(|extension| extension == "html")
+	});
+	// File names start with the time they were saved at
+	dumps.sort();
+
+	let excess = dumps.len().saturating_sub(MAX_DUMPS);
+	for dump in &dumps[..excess] {
+		fs::remove_file(dump)?;
+	}
+
+	Ok(())
+}
+
+/// Returns the error for `selector` failing on the page at `url`, saving
+/// the page so the provider can be fixed without asking for the url again.
+pub fn selector_failed(provider: &str, url: &Url, selector: &str, html: &str) -> RanobeError {
+	let saved = match save(default_dir(), provider, url, selector, html) {
+		Ok(path) => format!("the page was saved to {}", path.display()),
+		Err(err) => format!("saving the page failed: {}", err),
+	};
+
+	RanobeError::Parse(format!(
+		"{} could not find the {} on {}, {}.",
+		provider, selector, url, saved
+	))
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::crypt;
+use crate::utils::data_dir;
+
+/// The headers of a page that change along with it, as answered to a HEAD
+/// request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+	pub etag: Option<String>,
+	pub last_modified: Option<String>,
+}
+
+impl Validators {
+	/// Whether the page is known to be unchanged since it had `previous`.
+	///
+	/// The ETag decides when both have one, pages without validators are
+	/// never known to be unchanged.
+	pub fn unchanged_since(&self, previous: &Validators) -> bool {
+		if let (Some(etag), Some(previous)) = (&self.etag, &previous.etag) {
+			return etag == previous;
+		}
+		matches!(
+			(&self.last_modified, &previous.last_modified),
+			(Some(last_modified), Some(previous)) if last_modified == previous
+		)
+	}
+}
+
+/// The validators of the pages last checked for new chapters, by url.
+pub struct Freshness {
+	path: PathBuf,
+	pages: HashMap<String, Validators>,
+}
+
+impl Freshness {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("freshness.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let pages = match crypt::read_to_string(&path) {
+			Ok(pages) => serde_json::from_str(&pages)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, pages })
+	}
+
+	pub fn get(&self, url: &Url) -> Option<&Validators> {
+		self.pages.get(url.as_str())
+	}
+
+	pub fn set(&mut self, url: &Url, validators: Validators) -> Result<()> {
+		self.pages.insert(url.to_string(), validators);
+
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.pages)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compares_validators() {
+		let etag = |etag: &str, last_modified: &str| Validators {
+			etag: Some(etag.to_string()),
+			last_modified: Some(last_modified.to_string()),
+		};
+
+		assert!(etag("\"a\"", "Mon").unchanged_since(&etag("\"a\"", "Mon")));
+		// A new ETag wins over an unchanged date
+		assert!(!etag("\"b\"", "Mon").unchanged_since(&etag("\"a\"", "Mon")));
+
+		let dated = Validators {
+			last_modified: Some("Mon".to_string()),
+			..Validators::default()
+		};
+		assert!(dated.unchanged_since(&etag("\"a\"", "Mon")));
+		assert!(!Validators::default().unchanged_since(&Validators::default()));
+	}
+}
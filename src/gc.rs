@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::store::ChapterStore;
+use crate::trash::Trash;
+
+/// Temporary files younger than this might still be written to.
+const TEMPORARY_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// A file nothing refers to anymore.
+#[derive(Debug, Clone)]
+pub struct Garbage {
+	pub path: PathBuf,
+	pub size: u64,
+	/// Why the file is garbage, like `unreferenced blob`.
+	pub reason: &'static str,
+}
+
+/// Returns the files left over from interrupted writes in `dir`, which
+/// end with `extension`.
+fn leftovers(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+	let files = match fs::read_dir(dir) {
+		Ok(files) => files,
+		Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+		Err(err) => return Err(err),
+	};
+
+	let mut paths = vec![];
+	for file in files {
+		let file = file?;
+		let old = file
+			.metadata()?
+			.modified()?
+			.elapsed()
+			.is_ok_and(|age| age > TEMPORARY_AGE);
+		let name = file.file_name();
+		if old && name.to_string_lossy().ends_with(extension) {
+			paths.push(file.path());
+		}
+	}
+
+	Ok(paths)
+}
+
+/// Finds the blobs of `store` no chapter of the index or of `trash` refers
+/// to, the temporary files interrupted writes left in `dirs` and the
+/// partial exports in `export_dir`.
+pub fn find(
+	store: &ChapterStore,
+	trash: &Trash,
+	dirs: &[&Path],
+	export_dir: &Path,
+) -> Result<Vec<Garbage>> {
+	let trashed = trash
+		.novels()
+		.iter()
+		.flat_map(|trashed| trashed.chapters.iter())
+		.map(|(_, entry)| entry.hash.as_str())
+		.collect::<HashSet<_>>();
+
+	let mut found = vec![];
+	for path in store.unreferenced_blobs(&trashed)? {
+		found.push((path, "unreferenced blob"));
+	}
+	for dir in dirs {
+		for path in leftovers(dir, ".tmp")? {
+			found.push((path, "interrupted write"));
+		}
+	}
+	for path in leftovers(export_dir, ".part")? {
+		found.push((path, "interrupted export"));
+	}
+
+	found
+		.into_iter()
+		.map(|(path, reason)| {
+			Ok(Garbage {
+				size: fs::metadata(&path)?.len(),
+				path,
+				reason,
+			})
+		})
+		.collect()
+}
+
+/// Removes every file of `garbage` and returns the space reclaimed.
+pub fn remove(garbage: &[Garbage]) -> Result<u64> {
+	let mut reclaimed = 0;
+	for file in garbage {
+		match fs::remove_file(&file.path) {
+			Ok(()) => reclaimed += file.size,
+			Err(err) if err.kind() == ErrorKind::NotFound => {}
+			Err(err) => return Err(err),
+		}
+	}
+
+	Ok(reclaimed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+	use surf::Url;
+
+	#[test]
+	fn keeps_referenced_and_trashed_blobs() {
+		let root = TestDir::new("gc");
+		let mut store = ChapterStore::open(root.join("chapters")).unwrap();
+		let mut trash = Trash::open(root.join("trash.json")).unwrap();
+
+		let url = |n| Url::parse(&format!("https://example.com/novel/chapter-{}", n)).unwrap();
+		store.put(&url(1), "Kept", "Chapter 1", "kept").unwrap();
+		store
+			.put(&url(2), "Trashed", "Chapter 1", "trashed")
+			.unwrap();
+		store.put(&url(3), "Gone", "Chapter 1", "gone").unwrap();
+		let trashed = store.remove_novel("Trashed").unwrap();
+		let novel = crate::library::Novel {
+			title: "Trashed".to_string(),
+			provider: "local".to_string(),
+			progress: None,
+			progress_url: None,
+			metadata: None,
+			settings: Default::default(),
+			rating: None,
+			unavailable: false,
+			volumes: vec![],
+		};
+		trash.push(novel, trashed).unwrap();
+		store.remove_novel("Gone").unwrap();
+
+		let garbage = find(&store, &trash, &[store.root()], &root).unwrap();
+
+		assert_eq!(garbage.len(), 1);
+		assert_eq!(garbage[0].reason, "unreferenced blob");
+		assert_eq!(remove(&garbage).unwrap(), 4);
+		assert_eq!(store.get(&url(1)).unwrap().as_deref(), Some("kept"));
+	}
+}
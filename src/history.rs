@@ -0,0 +1,74 @@
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::crypt;
+use crate::providers::{canonical_url, Chapter, Listed};
+use crate::utils::data_dir;
+
+/// How many novels the history remembers.
+const HISTORY_SIZE: usize = 50;
+
+/// The last chapter read of a novel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Read {
+	pub novel: String,
+	pub title: String,
+	pub url: Url,
+	pub read_at: SystemTime,
+}
+
+impl Read {
+	pub fn chapter(&self) -> Chapter {
+		Chapter::new(self.title.clone(), self.url.clone())
+	}
+}
+
+/// Novels read recently, most recent first.
+pub struct History {
+	path: PathBuf,
+	reads: Vec<Read>,
+}
+
+impl History {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("history.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let reads = match crypt::read_to_string(&path) {
+			Ok(reads) => serde_json::from_str(&reads)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, reads })
+	}
+
+	/// Returns the `n` most recently read novels.
+	pub fn recent(&self, n: usize) -> &[Read] {
+		&self.reads[..n.min(self.reads.len())]
+	}
+
+	/// Records `chapter` as the last read chapter of `novel`.
+	pub fn record(&mut self, novel: &str, chapter: &Chapter) -> Result<()> {
+		self.reads.retain(|read| read.novel != novel);
+		self.reads.insert(
+			0,
+			Read {
+				novel: novel.to_string(),
+				title: chapter.title.clone(),
+				url: canonical_url(&chapter.url),
+				read_at: SystemTime::now(),
+			},
+		);
+		self.reads.truncate(HISTORY_SIZE);
+
+		crypt::write(&self.path, serde_json::to_string(&self.reads)?)
+	}
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::Path;
+use std::process::Command;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::export;
+use crate::library::Library;
+use crate::providers::{Chapter, Metadata};
+use crate::store::{hash, ChapterStore};
+use crate::utils::cache_dir;
+
+/// Shell commands run after downloads, set in the `[hooks]` table of the
+/// configuration, to convert or sync what was downloaded.
+///
+/// They run with `sh -c` and get the novel in `RANOBE_NOVEL`, its provider
+/// in `RANOBE_PROVIDER` and a copy of what was downloaded in `RANOBE_FILE`,
+/// which is removed once they exit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+	/// Run after a chapter is downloaded, with the chapter as Markdown in
+	/// `RANOBE_FILE`, its title in `RANOBE_CHAPTER` and its url in
+	/// `RANOBE_URL`.
+	pub on_chapter_downloaded: Option<String>,
+	/// Run once every chapter of a novel that is no longer updated is
+	/// downloaded, with the novel as an epub in `RANOBE_FILE` and its number
+	/// of chapters in `RANOBE_CHAPTERS`.
+	pub on_novel_completed: Option<String>,
+}
+
+lazy_static! {
+	/// Hooks set in the configuration.
+	static ref HOOKS: RwLock<Hooks> = RwLock::new(Hooks::default());
+}
+
+/// Runs the hooks of the configuration from now on.
+pub fn set(hooks: Hooks) {
+	*HOOKS.write().unwrap() = hooks;
+}
+
+/// Whether `downloaded` chapters are every chapter of the novel of
+/// `metadata`, as far as the provider said when it was last refreshed.
+pub fn is_complete(metadata: &Metadata, downloaded: usize) -> bool {
+	let finished = metadata
+		.status
+		.as_deref()
+		.is_some_and(|status| status.to_lowercase().contains("complete"));
+
+	match metadata.chapters {
+		Some(chapters) => finished && chapters > 0 && downloaded >= chapters,
+		None => false,
+	}
+}
+
+/// Runs the hook `name` with `file` in the environment, warning rather than
+/// failing, as the download itself went fine.
+fn run(name: &str, command: &str, file: &Path, vars: &[(&str, &str)]) {
+	let status = Command::new("sh")
+		.arg("-c")
+		.arg(command)
+		.env("RANOBE_FILE", file)
+		.envs(vars.iter().copied())
+		.status();
+
+	match status {
+		Ok(status) if status.success() => {}
+		Ok(status) => eprintln!("The {} hook failed with {}.", name, status),
+		Err(err) => eprintln!("The {} hook could not run: {}", name, err),
+	}
+}
+
+/// Runs the hook `hook` on a file named `name` in the cache, written by
+/// `write` and removed once it exits.
+fn with_file(
+	hook: &str,
+	command: &str,
+	name: &str,
+	write: impl FnOnce(&Path) -> Result<()>,
+	vars: &[(&str, &str)],
+) -> Result<()> {
+	let dir = cache_dir().join("hooks");
+	fs::create_dir_all(&dir)?;
+	let file = dir.join(name);
+
+	write(&file)?;
+	run(hook, command, &file, vars);
+	match fs::remove_file(&file) {
+		Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+		_ => Ok(()),
+	}
+}
+
+/// Stores `text`, the chapter `chapter` of `novel` downloaded from
+/// `provider`, and runs the hooks the download triggers.
+pub fn put(
+	store: &mut ChapterStore,
+	provider: &str,
+	novel: &str,
+	chapter: &Chapter,
+	text: &str,
+) -> Result<()> {
+	let before = store.chapters(novel).len();
+	store.put(&chapter.url, novel, &chapter.title, text)?;
+
+	let hooks = HOOKS.read().unwrap().clone();
+	if let Some(command) = &hooks.on_chapter_downloaded {
+		with_file(
+			"on_chapter_downloaded",
+			command,
+			&format!("{}.md", hash(text)),
+			|file| fs::write(file, text),
+			&[
+				("RANOBE_NOVEL", novel),
+				("RANOBE_PROVIDER", provider),
+				("RANOBE_CHAPTER", &chapter.title),
+				("RANOBE_URL", chapter.url.as_str()),
+			],
+		)?;
+	}
+
+	let command = match &hooks.on_novel_completed {
+		Some(command) => command,
+		None => return Ok(()),
+	};
+	let library = Library::open(Library::default_path())?;
+	let metadata = match library.get(novel).and_then(|novel| novel.metadata.as_ref()) {
+		Some(metadata) => metadata,
+		None => return Ok(()),
+	};
+	// Only the download completing the novel runs it, not later ones
+	let after = store.chapters(novel).len();
+	if is_complete(metadata, before) || !is_complete(metadata, after) {
+		return Ok(());
+	}
+
+	with_file(
+		"on_novel_completed",
+		command,
+		&format!("{}.epub", export::file_name(novel)),
+		|file| export::write_epub(store, novel, file),
+		&[
+			("RANOBE_NOVEL", novel),
+			("RANOBE_PROVIDER", provider),
+			("RANOBE_CHAPTERS", &after.to_string()),
+		],
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn completes_finished_novels() {
+		let metadata = |status: &str, chapters| Metadata {
+			status: Some(status.to_string()),
+			chapters,
+			..Metadata::default()
+		};
+
+		assert!(is_complete(&metadata("Completed", Some(3)), 3));
+		assert!(is_complete(&metadata("complete", Some(3)), 4));
+		assert!(!is_complete(&metadata("Completed", Some(3)), 2));
+		assert!(!is_complete(&metadata("Ongoing", Some(3)), 3));
+		assert!(!is_complete(&metadata("Completed", None), 3));
+	}
+}
@@ -1,25 +1,299 @@
 use lazy_static::lazy_static;
+use regex::Regex;
 use std::time::Duration;
-use surf::Url;
 use surf::{Client, Config};
+use surf::{StatusCode, Url};
 
 use once_cell::sync::OnceCell;
 
+use crate::cassette;
+use crate::cookies::Cookies;
+use crate::error::RanobeError;
+use crate::freshness::Validators;
+use crate::mirrors::{self, Mirrors};
+
 lazy_static! {
 	static ref USER_AGENT: &'static str =
 		"Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
 	pub static ref CLIENT: OnceCell<Client> = OnceCell::new();
+	/// Titles of the pages sites show in place of a novel they took down.
+	static ref TAKEDOWN_RE: Regex = Regex::new(
+		r#"(?i)<title>[^<]*(?:dmca|taken down|has been removed|no longer available)[^<]*</title>"#
+	)
+	.unwrap();
+	/// Pages registrars show on a domain that lapsed or is for sale.
+	static ref PARKED_RE: Regex = Regex::new(
+		r#"(?i)<title>[^<]*(?:(?:domain|\.\w+) (?:is )?for sale|parked (?:domain|free)|buy this domain)[^<]*</title>|sedoparking\.com|parkingcrew\.net|bodis\.com"#
+	)
+	.unwrap();
+	/// Titles of the pages sites show in place of a page while throttling.
+	static ref RATE_LIMIT_RE: Regex = Regex::new(
+		r#"(?i)<title>[^<]*(?:too many requests|rate limit(?:ed)?|slow down)[^<]*</title>"#
+	)
+	.unwrap();
+}
+
+pub fn client_init() -> Result<Client, RanobeError> {
+	Ok(session_init()?.with(surf::middleware::Redirect::default()))
 }
 
-pub fn client_init() -> Result<Client, surf::Error> {
-	Ok(<Config as TryInto<Client>>::try_into(
+/// Returns a client keeping the cookies of the jar like `client_init`, but
+/// that does not follow redirects.
+///
+/// Logins go through it, as sites set the session cookie on the redirect
+/// answering the login form, which redirected requests never see.
+pub fn session_init() -> Result<Client, RanobeError> {
+	let client = <Config as TryInto<Client>>::try_into(
 		Config::new()
 			.set_timeout(Some(Duration::from_secs(30)))
 			.add_header("user-agent", *USER_AGENT)?,
-	)?
-	.with(surf::middleware::Redirect::default()))
+	)
+	.map_err(surf::Error::from)?;
+
+	Ok(with_cassette(client.with(Cookies)))
+}
+
+/// Records or replays the requests of `client` if a cassette is set.
+///
+/// The cassette has to come before any middleware sending requests
+/// itself, like redirects, requests they send themselves skip the
+/// middleware of the client.
+fn with_cassette(client: Client) -> Client {
+	match cassette::current() {
+		Some(cassette) => client.with(cassette.clone()),
+		None => client,
+	}
+}
+
+/// Returns where `url` redirects to, or `url` itself if it does not.
+///
+/// Only the first redirect is followed.
+pub async fn resolve_redirect(url: Url) -> Result<Url, RanobeError> {
+	let client: Client = Config::new()
+		.set_timeout(Some(Duration::from_secs(30)))
+		.add_header("user-agent", *USER_AGENT)?
+		.try_into()
+		.map_err(surf::Error::from)?;
+	let client = with_cassette(client);
+
+	let response = client.get(url.clone()).await?;
+	match response.header("location") {
+		Some(location) => Ok(url.join(location.as_str())?),
+		None => Ok(url),
+	}
+}
+
+/// Returns the status of a response that means the page was removed,
+/// treating takedown pages served as successes like the statuses they
+/// stand for.
+fn removed(status: StatusCode, body: &str) -> Option<StatusCode> {
+	match status {
+		StatusCode::NotFound | StatusCode::Gone | StatusCode::UnavailableForLegalReasons => {
+			Some(status)
+		}
+		_ if TAKEDOWN_RE.is_match(body) => Some(StatusCode::UnavailableForLegalReasons),
+		_ => None,
+	}
+}
+
+/// Returns the error of the page at `url` removed with `status`, like a
+/// novel taken down after a DMCA notice.
+fn removed_error(url: &Url, status: StatusCode) -> RanobeError {
+	RanobeError::NotFound(format!(
+		"{} was removed ({})",
+		url,
+		status.canonical_reason()
+	))
+}
+
+/// Returns the error of a response refusing the request because too many
+/// were sent, treating throttling pages served as successes the same way.
+///
+/// Only a `Retry-After` given in seconds is understood.
+fn rate_limited(res: &surf::Response, url: &Url, body: &str) -> Option<RanobeError> {
+	if res.status() != StatusCode::TooManyRequests && !RATE_LIMIT_RE.is_match(body) {
+		return None;
+	}
+
+	let retry_after = res
+		.header("retry-after")
+		.and_then(|retry_after| retry_after.as_str().trim().parse().ok())
+		.map(Duration::from_secs);
+	Some(RanobeError::RateLimited {
+		url: url.clone(),
+		retry_after,
+	})
+}
+
+/// Fetches the page at `url`, failing if it was removed or the site
+/// throttles the requests.
+pub async fn fetch_url(client: &Client, url: Url) -> Result<String, RanobeError> {
+	let mut res = client.get(url.clone()).await?;
+	let body = res.body_string().await?;
+
+	if let Some(err) = rate_limited(&res, &url, &body) {
+		return Err(err);
+	}
+	match removed(res.status(), &body) {
+		Some(status) => Err(removed_error(&url, status)),
+		None => Ok(body),
+	}
+}
+
+/// Fetches the validators of the page at `url` with a HEAD request, so
+/// whether it changed is known without downloading it.
+pub async fn fetch_validators(client: &Client, url: Url) -> Result<Validators, RanobeError> {
+	let res = client.head(url.clone()).await?;
+	if !res.status().is_success() {
+		return Err(RanobeError::network(
+			res.status(),
+			format!("{} failed ({})", url, res.status().canonical_reason()),
+		));
+	}
+
+	let header = |name| res.header(name).map(|value| value.as_str().to_string());
+	Ok(Validators {
+		etag: header("etag"),
+		last_modified: header("last-modified"),
+	})
+}
+
+/// Returns whether `body` is the page of a parked domain rather than the
+/// site that used to be served on it.
+fn is_parked(body: &str) -> bool {
+	PARKED_RE.is_match(body)
+}
+
+/// Fetches the page at `url` from the first mirror of `provider` serving
+/// it, starting with the one that worked last, and remembers that mirror.
+///
+/// Mirrors that cannot be reached, answer with a server error or show a
+/// parked domain are skipped. `mirrors` are the domains of the provider,
+/// subdomains like `www` are kept when `url` is moved to another one.
+/// Returns the url the page was fetched from along with it.
+pub async fn fetch_mirrored(
+	client: &Client,
+	provider: &str,
+	mirrors: &[&str],
+	url: Url,
+) -> Result<(Url, String), RanobeError> {
+	let host = url.host_str().unwrap_or_default().to_string();
+	let subdomain = match mirrors.iter().find_map(|mirror| {
+		host.strip_suffix(mirror)
+			.filter(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+	}) {
+		Some(subdomain) => subdomain.to_string(),
+		None => return Ok((url.clone(), fetch_url(client, url).await?)),
+	};
+
+	let mut working = Mirrors::open(Mirrors::default_path())?;
+	let mut last_err = None;
+	for mirror in mirrors::candidates(provider, mirrors, working.working(provider)) {
+		let mut url = url.clone();
+		url.set_host(Some(&format!("{}{}", subdomain, mirror)))?;
+
+		let mut res = match client.get(url.clone()).await {
+			Ok(res) => res,
+			Err(err) => {
+				last_err = Some(err.into());
+				continue;
+			}
+		};
+		let body = res.body_string().await?;
+
+		// Another mirror is most likely served by the same machines
+		if let Some(err) = rate_limited(&res, &url, &body) {
+			return Err(err);
+		}
+		if res.status().is_server_error() {
+			last_err = Some(RanobeError::network(
+				res.status(),
+				format!("{} failed ({})", url, res.status().canonical_reason()),
+			));
+			continue;
+		}
+		if is_parked(&body) {
+			last_err = Some(RanobeError::network(
+				StatusCode::BadGateway,
+				format!("{} is a parked domain", mirror),
+			));
+			continue;
+		}
+		if let Some(status) = removed(res.status(), &body) {
+			return Err(removed_error(&url, status));
+		}
+
+		// Most requests reach the mirror that worked last, which is already
+		// written down
+		if working.working(provider) != Some(mirror.as_str()) {
+			working.set_working(provider, &mirror)?;
+		}
+		return Ok((url, body));
+	}
+
+	Err(last_err.unwrap_or_else(|| {
+		RanobeError::network(
+			StatusCode::BadGateway,
+			format!("No mirror of {} serves {}", provider, url),
+		)
+	}))
 }
 
-pub async fn fetch_url(client: &Client, url: Url) -> Result<String, surf::Error> {
-	client.get(url).recv_string().await
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_removed_pages() {
+		assert_eq!(removed(StatusCode::Ok, "<title>Chapter 1</title>"), None);
+		assert_eq!(
+			removed(StatusCode::NotFound, ""),
+			Some(StatusCode::NotFound)
+		);
+		assert_eq!(
+			removed(StatusCode::Ok, "<title>DMCA Notice - Site</title>"),
+			Some(StatusCode::UnavailableForLegalReasons)
+		);
+		// Footers link to the DMCA policy of most sites
+		assert_eq!(
+			removed(StatusCode::Ok, "<title>Chapter 1</title><a>DMCA</a>"),
+			None
+		);
+	}
+
+	#[test]
+	fn detects_parked_domains() {
+		assert!(is_parked("<title>example.com is for sale</title>"));
+		assert!(is_parked(
+			r#"<script src="https://www.sedoparking.com/js.js"></script>"#
+		));
+		assert!(!is_parked("<title>Chapter 1 - Parked Car</title>"));
+	}
+
+	#[test]
+	fn detects_rate_limits() {
+		let url = Url::parse("https://example.com/novel").unwrap();
+		let ok = surf::Response::from(surf::http::Response::new(StatusCode::Ok));
+		assert!(rate_limited(&ok, &url, "<title>Chapter 1</title>").is_none());
+
+		let err = rate_limited(&ok, &url, "<title>429 Too Many Requests</title>").unwrap();
+		assert!(matches!(
+			err,
+			RanobeError::RateLimited {
+				retry_after: None,
+				..
+			}
+		));
+
+		let mut res = surf::http::Response::new(StatusCode::TooManyRequests);
+		res.insert_header("retry-after", "30");
+		let err = rate_limited(&res.into(), &url, "").unwrap();
+		assert!(matches!(
+			err,
+			RanobeError::RateLimited {
+				retry_after: Some(retry_after),
+				..
+			} if retry_after == Duration::from_secs(30)
+		));
+	}
 }
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Instant;
+
+use async_std::task;
+use surf::Url;
+
+use crate::error::RanobeError;
+use crate::providers::{canonical_url, Listed, Metadata, RanobeScraper};
+
+/// Metadata fetched in the background, or why it could not be.
+type Fetched = (Url, Result<Metadata, String>);
+
+/// Lazily fetches metadata for listing items.
+///
+/// Listings only carry a title and an url, so metadata is fetched on demand
+/// for the item the user is looking at and cached by canonical url for later
+/// lookups, either right away with `get` or in the background with `fetch`.
+pub struct Hydrator<'a, S: RanobeScraper> {
+	provider: &'a S,
+	cache: HashMap<Url, Metadata>,
+	failed: HashMap<Url, String>,
+	/// Urls being fetched in the background.
+	pending: HashSet<Url>,
+	/// When the next background fetch may start.
+	next_start: Instant,
+	sender: Sender<Fetched>,
+	receiver: Receiver<Fetched>,
+}
+
+impl<'a, S: RanobeScraper + Sync> Hydrator<'a, S> {
+	pub fn new(provider: &'a S) -> Self {
+		let (sender, receiver) = channel();
+
+		Self {
+			provider,
+			cache: HashMap::new(),
+			failed: HashMap::new(),
+			pending: HashSet::new(),
+			next_start: Instant::now(),
+			sender,
+			receiver,
+		}
+	}
+
+	/// Returns the cached metadata of the novel of `ranobe`, a novel or one
+	/// of its chapters, without fetching it.
+	pub fn cached<E: Listed>(&self, ranobe: &E) -> Option<&Metadata> {
+		self.cache.get(&canonical_url(ranobe.url()))
+	}
+
+	/// Returns why fetching the metadata of the novel of `ranobe` in the
+	/// background failed, if it did.
+	pub fn failure<E: Listed>(&self, ranobe: &E) -> Option<&str> {
+		self.failed
+			.get(&canonical_url(ranobe.url()))
+			.map(String::as_str)
+	}
+
+	/// Returns the metadata of the novel of `ranobe`, fetching it on the
+	/// first call.
+	pub async fn get<E: Listed>(&mut self, ranobe: &E) -> Result<&Metadata, RanobeError> {
+		let url = canonical_url(ranobe.url());
+		if !self.cache.contains_key(&url) {
+			let metadata = self.provider.get_metadata(ranobe.url().clone()).await?;
+			self.cache.insert(url.clone(), metadata);
+		}
+
+		Ok(&self.cache[&url])
+	}
+
+	/// Caches the metadata fetched in the background since the last call and
+	/// returns whether there was any.
+	pub fn receive(&mut self) -> bool {
+		let mut received = false;
+		while let Ok((url, fetched)) = self.receiver.try_recv() {
+			self.pending.remove(&url);
+			match fetched {
+				Ok(metadata) => {
+					self.cache.insert(url, metadata);
+				}
+				Err(err) => {
+					self.failed.insert(url, err);
+				}
+			}
+			received = true;
+		}
+		received
+	}
+}
+
+impl<S: RanobeScraper + Clone + Send + Sync + 'static> Hydrator<'_, S> {
+	/// Returns the future fetching the metadata of the novel of `ranobe` in
+	/// the background, to be run by the caller, unless it is cached, being
+	/// fetched or failed to be.
+	///
+	/// Fetches start `request_interval` apart, the result is cached by the
+	/// next `receive` after the future completes.
+	pub fn fetch<E: Listed>(
+		&mut self,
+		ranobe: &E,
+	) -> Option<impl Future<Output = Result<(), RanobeError>> + Send + 'static> {
+		let url = canonical_url(ranobe.url());
+		if self.cache.contains_key(&url)
+			|| self.failed.contains_key(&url)
+			|| !self.pending.insert(url.clone())
+		{
+			return None;
+		}
+
+		let now = Instant::now();
+		let start = self.next_start.max(now);
+		self.next_start = start + self.provider.request_interval();
+
+		let provider = self.provider.clone();
+		let sender = self.sender.clone();
+		let listed = ranobe.url().clone();
+		Some(async move {
+			task::sleep(start - now).await;
+			let fetched = provider.get_metadata(listed).await;
+			let _ = sender.send((url, fetched.as_ref().map_err(ToString::to_string).cloned()));
+			fetched.map(|_| ())
+		})
+	}
+}
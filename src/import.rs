@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+use zip::ZipArchive;
+
+lazy_static! {
+	static ref HEADING_RE: Regex = Regex::new(
+		r#"(?im)^[ \t]*(?:#+[ \t]*)?(?:(?:chapter|ch\.)\s*\d+|prologue|epilogue|interlude|side story|第.+?[章話]).*$"#
+	)
+	.unwrap();
+	static ref ROOTFILE_RE: Regex = Regex::new(r#"<rootfile[^>]+full-path="([^"]+)""#).unwrap();
+	static ref OPF_TITLE_RE: Regex = Regex::new(r#"<dc:title[^>]*>([\S\s]+?)</dc:title>"#).unwrap();
+	static ref ITEM_RE: Regex = Regex::new(r#"<item\s[^>]*>"#).unwrap();
+	static ref ITEMREF_RE: Regex = Regex::new(r#"<itemref[^>]+idref="([^"]+)""#).unwrap();
+	static ref ATTR_RE: Regex = Regex::new(r#"([\w-]+)="([^"]*)""#).unwrap();
+	static ref BODY_RE: Regex = Regex::new(r#"(?i)<body[^>]*>([\S\s]*)</body>"#).unwrap();
+	static ref XHTML_HEADING_RE: Regex =
+		Regex::new(r#"(?i)<h[1-3][^>]*>([\S\s]+?)</h[1-3]>"#).unwrap();
+	static ref BLOCK_END_RE: Regex = Regex::new(r#"(?i)</(p|div|h[1-6]|li)>|<br\s*/?>"#).unwrap();
+	static ref TAG_RE: Regex = Regex::new(r#"<[^>]+>"#).unwrap();
+}
+
+/// Name of the provider imported novels are pinned to.
+pub const LOCAL_PROVIDER: &str = "local";
+
+/// A novel read from a local file.
+#[derive(Debug, Clone)]
+pub struct Imported {
+	pub title: String,
+	/// Chapters as `(title, url, text)`, the url points into the file.
+	pub chapters: Vec<(String, Url, String)>,
+}
+
+/// Splits plain text or Markdown into chapters on lines that look like
+/// chapter headings, text before the first heading becomes its own chapter.
+pub fn split_txt(text: &str) -> Vec<(String, String)> {
+	let headings = HEADING_RE.find_iter(text).collect::<Vec<_>>();
+
+	let mut chapters = vec![];
+	let first = headings
+		.first()
+		.map_or(text.len(), |heading| heading.start());
+	let before = &text[..first];
+	if !before.trim().is_empty() {
+		chapters.push((String::new(), before.trim().to_string()));
+	}
+
+	for (i, heading) in headings.iter().enumerate() {
+		let end = headings.get(i + 1).map_or(text.len(), |next| next.start());
+		chapters.push((
+			heading
+				.as_str()
+				.trim()
+				.trim_start_matches('#')
+				.trim()
+				.to_string(),
+			text[heading.end()..end].trim().to_string(),
+		));
+	}
+
+	chapters
+}
+
+/// Turns xhtml into plain text with one paragraph per line.
+fn xhtml_to_text(xhtml: &str) -> String {
+	let body = BODY_RE
+		.captures(xhtml)
+		.map_or(xhtml, |body| body.get(1).unwrap().as_str());
+	let text = BLOCK_END_RE.replace_all(body, "\n");
+	let text = TAG_RE.replace_all(&text, "");
+
+	decode_entities(&text)
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty())
+		.collect::<Vec<_>>()
+		.join("\n\n")
+}
+
+fn decode_entities(text: &str) -> String {
+	text.replace("&nbsp;", " ")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&amp;", "&")
+}
+
+fn read_entry<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String> {
+	let mut entry = archive
+		.by_name(name)
+		.map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+	let mut contents = String::new();
+	entry.read_to_string(&mut contents)?;
+
+	Ok(contents)
+}
+
+/// Reads the chapters of an epub in reading order.
+pub fn read_epub(path: &Path) -> Result<Imported> {
+	let mut archive = ZipArchive::new(File::open(path)?)
+		.map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+	let url = file_url(path)?;
+
+	let container = read_entry(&mut archive, "META-INF/container.xml")?;
+	let opf_path = ROOTFILE_RE
+		.captures(&container)
+		.map(|rootfile| rootfile.get(1).unwrap().as_str().to_string())
+		.ok_or_else(|| Error::new(ErrorKind::InvalidData, "epub without a rootfile"))?;
+	let opf = read_entry(&mut archive, &opf_path)?;
+	let base = opf_path.rsplit_once('/').map_or("", |(base, _)| base);
+
+	let title = OPF_TITLE_RE
+		.captures(&opf)
+		.map(|title| decode_entities(title.get(1).unwrap().as_str().trim()))
+		.unwrap_or_else(|| file_stem(path));
+
+	// Manifest ids to the path of their file inside the archive
+	let manifest = ITEM_RE
+		.find_iter(&opf)
+		.filter_map(|item| {
+			let attrs = ATTR_RE
+				.captures_iter(item.as_str())
+				.map(|attr| (attr.get(1).unwrap().as_str(), attr.get(2).unwrap().as_str()))
+				.collect::<HashMap<_, _>>();
+			let href = match base {
+				"" => attrs.get("href")?.to_string(),
+				base => format!("{}/{}", base, attrs.get("href")?),
+			};
+			Some((attrs.get("id")?.to_string(), href))
+		})
+		.collect::<HashMap<_, _>>();
+
+	let mut chapters = vec![];
+	for itemref in ITEMREF_RE.captures_iter(&opf) {
+		let href = match manifest.get(itemref.get(1).unwrap().as_str()) {
+			Some(href) => href,
+			None => continue,
+		};
+
+		let xhtml = read_entry(&mut archive, href)?;
+		let text = xhtml_to_text(&xhtml);
+		if text.is_empty() {
+			continue;
+		}
+
+		let chapter_title = XHTML_HEADING_RE
+			.captures(&xhtml)
+			.map(|heading| {
+				decode_entities(&TAG_RE.replace_all(heading.get(1).unwrap().as_str(), ""))
+			})
+			.map(|heading| heading.trim().to_string())
+			.unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1));
+
+		let mut chapter_url = url.clone();
+		chapter_url.set_fragment(Some(href));
+		chapters.push((chapter_title, chapter_url, text));
+	}
+
+	Ok(Imported { title, chapters })
+}
+
+/// Reads a plain text or Markdown file, splitting it into chapters on their
+/// headings.
+pub fn read_txt(path: &Path) -> Result<Imported> {
+	let url = file_url(path)?;
+	let title = file_stem(path);
+
+	let chapters = split_txt(&fs::read_to_string(path)?)
+		.into_iter()
+		.enumerate()
+		.map(|(i, (chapter_title, text))| {
+			let chapter_title = if chapter_title.is_empty() {
+				title.clone()
+			} else {
+				chapter_title
+			};
+			let mut chapter_url = url.clone();
+			chapter_url.set_fragment(Some(&i.to_string()));
+			(chapter_title, chapter_url, text)
+		})
+		.collect();
+
+	Ok(Imported { title, chapters })
+}
+
+/// Reads an epub, txt or md file, or every such file of a directory.
+pub fn read_path(path: &Path) -> Result<Vec<Imported>> {
+	if path.is_dir() {
+		let mut paths = fs::read_dir(path)?
+			.map(|entry| entry.map(|entry| entry.path()))
+			.collect::<Result<Vec<_>>>()?;
+		paths.sort();
+
+		let mut novels = vec![];
+		for path in paths.iter().filter(|path| is_importable(path)) {
+			novels.extend(read_path(path)?);
+		}
+		return Ok(novels);
+	}
+
+	match extension(path).as_str() {
+		"epub" => Ok(vec![read_epub(path)?]),
+		"txt" | "md" => Ok(vec![read_txt(path)?]),
+		_ => Err(Error::new(
+			ErrorKind::InvalidInput,
+			format!("{} is not an epub, txt or md file", path.display()),
+		)),
+	}
+}
+
+pub fn is_importable(path: &Path) -> bool {
+	matches!(extension(path).as_str(), "epub" | "txt" | "md")
+}
+
+fn extension(path: &Path) -> String {
+	path.extension()
+		.map(|extension| extension.to_string_lossy().to_lowercase())
+		.unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> String {
+	path.file_stem()
+		.map(|stem| stem.to_string_lossy().to_string())
+		.unwrap_or_default()
+}
+
+fn file_url(path: &Path) -> Result<Url> {
+	Url::from_file_path(fs::canonicalize(path)?)
+		.map_err(|_| Error::new(ErrorKind::InvalidInput, "path cannot be turned into an url"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splits_txt_on_headings() {
+		let chapters = split_txt("Foreword\n\nChapter 1: Start\nOne.\n\nChapter 2\nTwo.\n");
+
+		assert_eq!(
+			chapters,
+			vec![
+				("".to_string(), "Foreword".to_string()),
+				("Chapter 1: Start".to_string(), "One.".to_string()),
+				("Chapter 2".to_string(), "Two.".to_string()),
+			]
+		);
+		assert_eq!(
+			split_txt("# Chapter 1\n\nOne.\n")[0].0,
+			"Chapter 1".to_string()
+		);
+	}
+}
@@ -1 +1,3 @@
+pub mod profile;
 pub mod select;
+pub mod tasks;
@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+/// Records how long each phase of a run took.
+///
+/// Phases are only reported when `--profile-startup` is passed, recording
+/// them is cheap enough to always do it.
+pub struct Profile {
+	enabled: bool,
+	start: Instant,
+	last: Instant,
+	phases: Vec<(&'static str, Duration)>,
+}
+
+impl Profile {
+	pub fn new(start: Instant) -> Self {
+		Self {
+			enabled: false,
+			start,
+			last: start,
+			phases: vec![],
+		}
+	}
+
+	pub fn enable(&mut self, enabled: bool) {
+		self.enabled = enabled;
+	}
+
+	/// Ends the current phase under the name `phase`.
+	pub fn mark(&mut self, phase: &'static str) {
+		let now = Instant::now();
+		self.phases.push((phase, now - self.last));
+		self.last = now;
+	}
+
+	/// Prints the recorded phases to stderr.
+	pub fn report(&self) {
+		if !self.enabled {
+			return;
+		}
+
+		for (phase, duration) in &self.phases {
+			eprintln!("{:>24}: {:>8.2}ms", phase, duration.as_secs_f64() * 1000.0);
+		}
+		eprintln!(
+			"{:>24}: {:>8.2}ms",
+			"total",
+			self.start.elapsed().as_secs_f64() * 1000.0
+		);
+	}
+}
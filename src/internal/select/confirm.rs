@@ -0,0 +1,103 @@
+use std::io;
+
+use console::{Key, Term};
+
+use crate::internal::select::theme::{SimpleTheme, TermThemeRenderer, Theme};
+use ranobe::utils::plain;
+
+/// A yes/no prompt answered with a single key.
+pub struct Confirm<'a> {
+	prompt: String,
+	default: bool,
+	theme: &'a dyn Theme,
+}
+
+impl Default for Confirm<'static> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Confirm<'static> {
+	/// Creates the prompt with the default theme.
+	pub fn new() -> Self {
+		Self::with_theme(&SimpleTheme)
+	}
+}
+
+impl<'a> Confirm<'a> {
+	/// Same as `new` but with a specific theme.
+	pub fn with_theme(theme: &'a dyn Theme) -> Self {
+		Self {
+			prompt: "".into(),
+			default: false,
+			theme,
+		}
+	}
+
+	/// Sets the question asked.
+	pub fn with_prompt<S: Into<String>>(&mut self, prompt: S) -> &mut Self {
+		self.prompt = prompt.into();
+		self
+	}
+
+	/// Sets the answer of 'Enter'.
+	///
+	/// The default is no.
+	pub fn default(&mut self, val: bool) -> &mut Self {
+		self.default = val;
+		self
+	}
+
+	/// Enables user interaction and returns the answer.
+	///
+	/// 'y' and 'n' answer right away, 'Enter' picks the default and 'Esc' or
+	/// 'q' answer no.
+	#[inline]
+	pub fn interact(&self) -> io::Result<bool> {
+		self.interact_on(&Term::stderr())
+	}
+
+	/// Like `interact` but allows a specific terminal to be set.
+	pub fn interact_on(&self, term: &Term) -> io::Result<bool> {
+		if plain() {
+			return self.interact_plain(term);
+		}
+
+		let mut render = TermThemeRenderer::new(term, self.theme);
+
+		render.confirm_prompt(&self.prompt, self.default)?;
+		term.hide_cursor()?;
+
+		let answer = loop {
+			match term.read_key()? {
+				Key::Char('y' | 'Y') => break true,
+				Key::Char('n' | 'N' | 'q') | Key::Escape => break false,
+				Key::Enter => break self.default,
+				_ => {}
+			}
+		};
+
+		term.clear_line()?;
+		render.clear()?;
+		render.confirm_prompt_selection(&self.prompt, answer)?;
+		term.show_cursor()?;
+
+		Ok(answer)
+	}
+
+	/// Asks line by line without redrawing, for screen readers.
+	fn interact_plain(&self, term: &Term) -> io::Result<bool> {
+		let choices = if self.default { "Y/n" } else { "y/N" };
+		term.write_line(&format!("{} {}", self.prompt, choices))?;
+
+		let answer = match term.read_line()?.trim().to_lowercase().as_str() {
+			"y" | "yes" => true,
+			"" => self.default,
+			_ => false,
+		};
+		term.write_line(if answer { "yes" } else { "no" })?;
+
+		Ok(answer)
+	}
+}
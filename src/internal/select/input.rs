@@ -0,0 +1,56 @@
+use std::io;
+use std::time::Duration;
+
+/// Waits up to `timeout` for a key to be pressed, without reading it, and
+/// returns whether one was.
+///
+/// The terminal only hands keys over one by one in raw mode, which `Term`
+/// enters just for reading a key, so it is entered here for the wait too.
+#[cfg(unix)]
+pub fn wait_for_key(timeout: Duration) -> io::Result<bool> {
+	use std::fs::File;
+	use std::os::unix::io::AsRawFd;
+
+	// Keys are read from the terminal even when stdin is redirected
+	let tty;
+	let fd = if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+		libc::STDIN_FILENO
+	} else {
+		tty = File::open("/dev/tty")?;
+		tty.as_raw_fd()
+	};
+
+	let mut termios = std::mem::MaybeUninit::uninit();
+	if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let original = unsafe { termios.assume_init() };
+	let mut raw = original;
+	unsafe { libc::cfmakeraw(&mut raw) };
+	raw.c_oflag = original.c_oflag;
+	if unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &raw) } != 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let mut pollfd = libc::pollfd {
+		fd,
+		events: libc::POLLIN,
+		revents: 0,
+	};
+	let ready = unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis() as libc::c_int) };
+	let err = io::Error::last_os_error();
+	unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &original) };
+
+	match ready {
+		-1 if err.kind() == io::ErrorKind::Interrupted => Ok(false),
+		-1 => Err(err),
+		_ => Ok(pollfd.revents & libc::POLLIN != 0),
+	}
+}
+
+/// Says a key was pressed right away, leaving terminals other than unix
+/// ones to block in `Term::read_key`.
+#[cfg(not(unix))]
+pub fn wait_for_key(_timeout: Duration) -> io::Result<bool> {
+	Ok(true)
+}
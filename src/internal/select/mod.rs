@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+pub mod confirm;
+mod input;
 mod paging;
 pub mod select;
 pub mod theme;
@@ -12,6 +12,9 @@ pub struct Paging<'a> {
 	pub capacity: usize,
 	pub active: bool,
 	pub max_capacity: Option<usize>,
+	/// Whether turning past the last page goes back to the first and the
+	/// other way around.
+	pub wrap: bool,
 	term: &'a Term,
 	current_term_size: (u16, u16),
 	items_len: usize,
@@ -37,6 +40,7 @@ impl<'a> Paging<'a> {
 			current_term_size: term_size,
 			items_len,
 			max_capacity,
+			wrap: true,
 			// Set transition initially to true to trigger prompt rendering for inactive paging on start
 			activity_transition: true,
 		}
@@ -75,15 +79,16 @@ impl<'a> Paging<'a> {
 		Ok(())
 	}
 
-	/// Renders a prompt when the following conditions are met:
+	/// Renders a prompt, with the current page, the page count and the item
+	/// count when paging is active, when the following conditions are met:
 	/// * Paging is active
 	/// * Transition of the paging activity happened (active -> inactive / inactive -> active)
 	pub fn render_prompt<F>(&mut self, mut render_prompt: F) -> io::Result<()>
 	where
-		F: FnMut(Option<(usize, usize)>) -> io::Result<()>,
+		F: FnMut(Option<(usize, usize, usize)>) -> io::Result<()>,
 	{
 		if self.active {
-			let paging_info = Some((self.current_page + 1, self.pages));
+			let paging_info = Some((self.current_page + 1, self.pages, self.items_len));
 			render_prompt(paging_info)?;
 		} else if self.activity_transition {
 			render_prompt(None)?;
@@ -94,25 +99,57 @@ impl<'a> Paging<'a> {
 		Ok(())
 	}
 
-	/// Navigates to the next page
-	pub fn next_page(&mut self) -> usize {
+	/// Navigates to the next page, `None` past the last page without `wrap`
+	pub fn next_page(&mut self) -> Option<usize> {
 		if self.current_page == self.pages - 1 {
+			if !self.wrap {
+				return None;
+			}
 			self.current_page = 0;
 		} else {
 			self.current_page += 1;
 		}
 
+		Some(self.current_page * self.capacity)
+	}
+
+	/// Navigates to the first page
+	pub fn first_page(&mut self) -> usize {
+		self.current_page = 0;
+
+		0
+	}
+
+	/// Navigates to the last page
+	pub fn last_page(&mut self) -> usize {
+		self.current_page = self.pages.saturating_sub(1);
+
 		self.current_page * self.capacity
 	}
 
-	/// Navigates to the previous page
-	pub fn previous_page(&mut self) -> usize {
+	/// Navigates to `page`, counted from one, if it exists
+	pub fn go_to_page(&mut self, page: usize) -> Option<usize> {
+		if page == 0 || page > self.pages {
+			return None;
+		}
+
+		self.current_page = page - 1;
+
+		Some(self.current_page * self.capacity)
+	}
+
+	/// Navigates to the previous page, `None` before the first page without
+	/// `wrap`
+	pub fn previous_page(&mut self) -> Option<usize> {
 		if self.current_page == 0 {
+			if !self.wrap {
+				return None;
+			}
 			self.current_page = self.pages - 1;
 		} else {
 			self.current_page -= 1;
 		}
 
-		self.current_page * self.capacity
+		Some(self.current_page * self.capacity)
 	}
 }
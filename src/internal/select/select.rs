@@ -1,19 +1,309 @@
+use crate::internal::select::input::wait_for_key;
 use crate::internal::select::paging::Paging;
 use crate::internal::select::theme::{SimpleTheme, TermThemeRenderer, Theme};
-use console::{Key, Term};
+use console::{measure_text_width, Key, Term};
+use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
-use std::{io, ops::Rem};
+use std::io;
+use std::time::{Duration, Instant};
 
-use ranobe::providers::Ranobe;
+use ranobe::providers::{Chapter, Novel};
+use ranobe::searches::Searches;
+use ranobe::utils::{navigation, plain};
+
+/// Items listed per page in plain mode without a maximum length.
+const PLAIN_PAGE_SIZE: usize = 10;
+
+/// Renders the preview lines of the highlighted item.
+pub type Preview<'a, T> = &'a mut dyn FnMut(&T) -> Vec<String>;
+
+/// Returns the extra columns of an item, like its chapter count.
+pub type Columns<'a, T> = &'a mut dyn FnMut(&T) -> Vec<String>;
+
+/// How often updates are checked for while waiting for a key.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Widest a column is rendered, longer values are truncated.
+const MAX_COLUMN_WIDTH: usize = 20;
+
+/// Action of the actions menu listing works by the same author, offered
+/// along with the actions of the caller when `by_author` is set.
+const BY_AUTHOR: &str = "Other works by the author";
+
+/// Returns the values an item can be filtered by.
+type Facets<'a, T> = &'a mut dyn FnMut(&T) -> Vec<String>;
+
+/// Returns the other names an item is matched under, like the original
+/// title of a novel.
+pub type Aliases<'a, T> = &'a mut dyn FnMut(&T) -> Vec<String>;
+
+/// An item that can be matched and rendered by the fuzzy selector.
+pub trait Item: Clone {
+	/// The text the item is matched against and rendered as.
+	fn text(&self) -> &str;
+	/// Whether the item was read, for lists that track it.
+	fn read(&self) -> Option<bool> {
+		None
+	}
+}
+
+impl Item for Novel {
+	fn text(&self) -> &str {
+		&self.title
+	}
+}
+
+impl Item for Chapter {
+	fn text(&self) -> &str {
+		&self.title
+	}
+}
+
+impl Item for String {
+	fn text(&self) -> &str {
+		self
+	}
+}
+
+/// An item marked as read or not, like a chapter of a novel in the library.
+///
+/// Read items are dimmed and unread ones highlighted, 'u' jumps to the
+/// first unread one.
+#[derive(Debug, Clone)]
+pub struct Marked<T> {
+	pub item: T,
+	pub read: bool,
+}
+
+impl<T: Item> Item for Marked<T> {
+	fn text(&self) -> &str {
+		self.item.text()
+	}
+
+	fn read(&self) -> Option<bool> {
+		Some(self.read)
+	}
+}
+
+/// An item of a list grouped under headers, like the chapters of a novel
+/// under its volumes.
+///
+/// Selecting a header is left to the caller, which usually collapses or
+/// expands its group and lists the items again with `Grouped::list`.
+#[derive(Debug, Clone)]
+pub enum Grouped<T> {
+	/// Header of the `group`th group.
+	Header {
+		group: usize,
+		text: String,
+	},
+	Item(T),
+}
+
+impl<T: Item> Item for Grouped<T> {
+	fn text(&self) -> &str {
+		match self {
+			Self::Header { text, .. } => text,
+			Self::Item(item) => item.text(),
+		}
+	}
+
+	fn read(&self) -> Option<bool> {
+		match self {
+			Self::Header { .. } => None,
+			Self::Item(item) => item.read(),
+		}
+	}
+}
+
+impl<T: Item> Grouped<T> {
+	/// Lists the items of `groups` under their headers, leaving out the items
+	/// of the groups `collapsed` marks.
+	///
+	/// Groups without a title are listed without a header and cannot be
+	/// collapsed.
+	pub fn list(groups: &[(Option<String>, Vec<T>)], collapsed: &[bool]) -> Vec<Self> {
+		let mut list = vec![];
+		for (group, (title, items)) in groups.iter().enumerate() {
+			let collapsed = collapsed.get(group).copied().unwrap_or(false);
+			if let Some(title) = title {
+				list.push(Self::Header {
+					group,
+					text: Self::header(title, items.len(), collapsed),
+				});
+			}
+			if !collapsed || title.is_none() {
+				list.extend(items.iter().cloned().map(Self::Item));
+			}
+		}
+		list
+	}
+
+	/// Returns the text of the header of a group titled `title`.
+	pub fn header(title: &str, len: usize, collapsed: bool) -> String {
+		format!(
+			"[{}] {} ({})",
+			if collapsed { '+' } else { '-' },
+			title,
+			len
+		)
+	}
+}
+
+/// A page of the source of a list to load instead of the one listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+	Next,
+	Previous,
+}
+
+/// What the user was looking at when an interaction ended, so a refreshed
+/// or extended list can pick up where they left off.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+	pub search_term: String,
+	/// Text of the highlighted item, so it is found again in a new list.
+	pub selected: Option<String>,
+	/// The active facet filter.
+	pub filter: Option<String>,
+	/// Whether the interaction ended because a refresh was asked for.
+	pub refresh: bool,
+	/// Whether the interaction ended because the other works by the author
+	/// of the highlighted item were asked for.
+	pub by_author: bool,
+	/// Which page the interaction ended to load, if another page was asked
+	/// for.
+	pub turn_page: Option<Turn>,
+	/// Whether the interaction ended because the items past the last one
+	/// were asked for.
+	pub load_more: bool,
+	/// Index of the action picked from the actions menu of the selected
+	/// item, if the item was picked through it.
+	pub action: Option<usize>,
+	/// The active value of every refinement, by the key toggling it.
+	pub refinements: Vec<(char, String)>,
+}
+
+/// A filter of the list toggled with its own key, like the genre of a
+/// novel, which narrows down the list along with the other filters.
+struct Refinement<'a, T> {
+	key: char,
+	name: String,
+	facets: Facets<'a, T>,
+	/// Values of every item, computed on first use.
+	item_values: Option<Vec<Vec<String>>>,
+	/// Every value, numbers sorted by their value.
+	values: Vec<String>,
+	active: Option<usize>,
+}
+
+impl<T> Refinement<'_, T> {
+	/// Computes the values of `items` unless they already are.
+	fn load(&mut self, items: &[T]) {
+		if self.item_values.is_some() {
+			return;
+		}
+
+		let all = items
+			.iter()
+			.map(|item| (self.facets)(item))
+			.collect::<Vec<_>>();
+		let mut values = all.iter().flatten().cloned().collect::<Vec<_>>();
+		values.sort_by_cached_key(|value| {
+			let number = value
+				.chars()
+				.take_while(char::is_ascii_digit)
+				.collect::<String>();
+			(number.parse::<u64>().ok(), value.clone())
+		});
+		values.dedup();
+
+		self.values = values;
+		self.item_values = Some(all);
+	}
+
+	/// Computes the values again if they were, as the items they come from
+	/// changed, keeping the active value.
+	fn reload(&mut self, items: &[T]) {
+		if self.item_values.is_none() {
+			return;
+		}
+
+		let active = self.active().map(str::to_string);
+		self.item_values = None;
+		self.load(items);
+		self.active = active.and_then(|active| self.values.iter().position(|v| *v == active));
+	}
+
+	/// Activates the next value, none after the last one.
+	fn cycle(&mut self) {
+		self.active = match self.active {
+			None if !self.values.is_empty() => Some(0),
+			Some(active) if active + 1 < self.values.len() => Some(active + 1),
+			_ => None,
+		};
+	}
+
+	fn active(&self) -> Option<&str> {
+		self.active.map(|active| self.values[active].as_str())
+	}
+
+	/// Whether the `i`th item has the active value, any item does without
+	/// one.
+	fn keeps(&self, i: usize) -> bool {
+		match (self.active(), &self.item_values) {
+			(Some(active), Some(values)) => values[i].iter().any(|value| value == active),
+			_ => true,
+		}
+	}
+}
+
+/// Returns how well the best of `text` and its `aliases` matches
+/// `search_term`, `None` if none of them does.
+fn score(
+	matcher: &SkimMatcherV2,
+	text: &str,
+	aliases: Option<&Vec<String>>,
+	search_term: &str,
+) -> Option<i64> {
+	std::iter::once(text)
+		.chain(aliases.into_iter().flatten().map(String::as_str))
+		.filter_map(|text| matcher.fuzzy_match(text, search_term))
+		.max()
+}
+
+/// Returns the position `by` items away from `sel` in a list of `len`
+/// items, going around its ends with `wrap` and stopping at them otherwise.
+fn step(sel: usize, by: isize, len: usize, wrap: bool) -> usize {
+	let to = sel as isize + by;
+	if wrap {
+		to.rem_euclid(len as isize) as usize
+	} else {
+		to.clamp(0, len as isize - 1) as usize
+	}
+}
+
+/// Returns the position among `shown`, indices of `items` in the order they
+/// are listed, of the earliest unread item.
+///
+/// Items keep the order they were given in, like chapters, while the list
+/// is ordered by how well they match.
+fn first_unread<T: Item>(items: &[T], shown: impl Iterator<Item = usize>) -> Option<usize> {
+	shown
+		.enumerate()
+		.filter(|(_, i)| items[*i].read() == Some(false))
+		.min_by_key(|(_, i)| *i)
+		.map(|(n, _)| n)
+}
 
 enum InputMode {
 	Normal,
 	Editing,
 }
 
-pub struct FuzzySelect<'a> {
+pub struct FuzzySelect<'a, T: Item> {
 	default: Option<usize>,
-	items: Vec<Ranobe>,
+	items: Vec<T>,
 	prompt: String,
 	report: bool,
 	clear: bool,
@@ -21,25 +311,55 @@ pub struct FuzzySelect<'a> {
 	max_length: Option<usize>,
 	theme: &'a dyn Theme,
 	input_mode: &'a InputMode,
+	/// Renders extra lines about the highlighted item below the list.
+	preview: Option<Preview<'a, T>>,
+	/// Renders extra columns next to every item.
+	columns: Option<Columns<'a, T>>,
+	/// Lets the list be filtered by the facets of its items.
+	facets: Option<Facets<'a, T>>,
+	/// Other names the items are matched under.
+	aliases: Option<Aliases<'a, T>>,
+	/// Renders a status bar below the list.
+	status: Option<&'a mut dyn FnMut() -> Option<String>>,
+	/// Checked while waiting for a key, whether the list is to be redrawn.
+	updates: Option<&'a mut dyn FnMut() -> bool>,
+	/// Context the search queries are remembered under.
+	history: Option<String>,
+	/// Restored at the start of an interaction and updated when it ends.
+	state: Option<&'a mut State>,
+	/// Called whenever the state changes during an interaction.
+	on_state_change: Option<&'a mut dyn FnMut(&State)>,
+	/// Whether 'r' ends the interaction to refresh the list.
+	refreshable: bool,
+	/// Whether 'a' ends the interaction to list works by the same author.
+	by_author: bool,
+	/// Whether '[' and ']' end the interaction to load another page.
+	pageable: bool,
+	/// Whether moving past the last item ends the interaction to load more.
+	extendable: bool,
+	/// Actions 'a' offers for the highlighted item.
+	actions: Vec<String>,
+	/// Filters toggled with their own keys.
+	refinements: Vec<Refinement<'a, T>>,
 	/// Search string that a fuzzy search with start with.
 	/// Defaults to an empty string.
 	initial_text: String,
 }
 
-impl Default for FuzzySelect<'static> {
+impl<T: Item> Default for FuzzySelect<'static, T> {
 	fn default() -> Self {
 		Self::new()
 	}
 }
 
-impl FuzzySelect<'static> {
+impl<T: Item> FuzzySelect<'static, T> {
 	/// Creates the prompt with a specific text.
 	pub fn new() -> Self {
 		Self::with_theme(&SimpleTheme)
 	}
 }
 
-impl FuzzySelect<'_> {
+impl<T: Item> FuzzySelect<'_, T> {
 	/// Sets the clear behavior of the menu.
 	///
 	/// The default is to clear the menu.
@@ -55,19 +375,28 @@ impl FuzzySelect<'_> {
 	}
 
 	/// Add a single item to the fuzzy selector.
-	pub fn item(&mut self, item: Ranobe) -> &mut Self {
+	pub fn item(&mut self, item: T) -> &mut Self {
 		self.items.push(item);
 		self
 	}
 
 	/// Adds multiple items to the fuzzy selector.
-	pub fn items(&mut self, items: &[Ranobe]) -> &mut Self {
+	pub fn items(&mut self, items: &[T]) -> &mut Self {
 		for item in items {
 			self.items.push(item.clone());
 		}
 		self
 	}
 
+	/// Remembers the search queries under `context`.
+	///
+	/// Up and Down in Editing Mode cycle through the recent queries of the
+	/// context, the query is recorded when an item is selected.
+	pub fn with_history<S: Into<String>>(&mut self, context: S) -> &mut Self {
+		self.history = Some(context.into());
+		self
+	}
+
 	/// Sets the search text that a fuzzy search starts with.
 	pub fn with_initial_text<S: Into<String>>(&mut self, initial_text: S) -> &mut Self {
 		self.initial_text = initial_text.into();
@@ -116,7 +445,10 @@ impl FuzzySelect<'_> {
 	/// The user interect with the selector with vim-like binding
 	///
 	/// In Normal Mode, the user can move arround dusing 'k' or 'j' for up and down
-	/// along side with arrow keys
+	/// along side with arrow keys, '1' to '9' pick the numbered items of the page, 'g' and 'G' jump to the first and last page,
+	/// ':' followed by a number and 'Enter' jumps to that page and 'u' jumps to the first unread item,
+	/// Home and End do as 'g' and 'G' without Shift,
+	/// the keys of refinements cycle through their values
 	///
 	/// In Editing Mode, the user type the fuzzy search and see new result
 	///
@@ -131,27 +463,283 @@ impl FuzzySelect<'_> {
 	/// Like `interact` but allows a specific terminal to be set.
 	#[inline]
 	pub fn interact_on(&mut self, term: &Term) -> io::Result<Option<usize>> {
+		if plain() {
+			return self.interact_plain(term);
+		}
 		self._interact_on(term)
 	}
 
+	/// Lets the user pick one of the actions for the item `text`, the works
+	/// by its author being the one past the actions of the caller.
+	fn pick_action(&self, term: &Term, text: &str) -> io::Result<Option<usize>> {
+		let mut actions = self.actions.clone();
+		if self.by_author {
+			actions.push(BY_AUTHOR.to_string());
+		}
+
+		FuzzySelect::with_theme(self.theme)
+			.with_prompt(format!("{}:", text))
+			.report(false)
+			.max_length(actions.len() + 2)
+			.default(0)
+			.items(&actions)
+			.interact_on(term)
+	}
+
+	/// Lists the items line by line without redrawing, for screen readers.
+	///
+	/// A number picks the item, other text searches, 'n' and 'p' turn the
+	/// page and 'q' or an empty line leaves.
+	fn interact_plain(&mut self, term: &Term) -> io::Result<Option<usize>> {
+		let matcher = SkimMatcherV2::default();
+		let item_aliases = self.item_aliases();
+		let capacity = self
+			.max_length
+			.map_or(PLAIN_PAGE_SIZE, |max_length| max_length - 2)
+			.max(1);
+
+		let mut search_term = self.initial_text.to_owned();
+		let mut page = 0;
+
+		// Pick up on the page of the item highlighted last
+		let mut restore = None;
+		if let Some(state) = self.state.as_mut() {
+			state.refresh = false;
+			state.by_author = false;
+			state.turn_page = None;
+			state.load_more = false;
+			state.action = None;
+			restore = state.selected.take();
+		}
+
+		loop {
+			let mut filtered_list = self
+				.items
+				.iter()
+				.enumerate()
+				.filter_map(|(i, item)| {
+					let score = score(&matcher, item.text(), item_aliases.get(i), &search_term)?;
+					Some((i, score))
+				})
+				.collect::<Vec<_>>();
+			filtered_list.sort_by(|(_, s1), (_, s2)| s2.cmp(s1));
+
+			if let Some(text) = restore.take() {
+				if let Some(n) = filtered_list
+					.iter()
+					.position(|&(i, _)| self.items[i].text() == text)
+				{
+					page = n / capacity;
+				}
+			}
+
+			let pages = filtered_list.len().div_ceil(capacity).max(1);
+			page = page.min(pages - 1);
+
+			term.write_line(&format!(
+				"{} {} items, page {} of {}.",
+				self.prompt,
+				filtered_list.len(),
+				page + 1,
+				pages
+			))?;
+			for (n, &(i, _)) in filtered_list
+				.iter()
+				.enumerate()
+				.skip(page * capacity)
+				.take(capacity)
+			{
+				let item = &self.items[i];
+				let columns = self
+					.columns
+					.as_mut()
+					.map_or(vec![], |columns| columns(item));
+				let mut columns = columns
+					.into_iter()
+					.filter(|column| !column.is_empty())
+					.collect::<Vec<_>>();
+				match item.read() {
+					Some(true) => columns.push("read".to_string()),
+					Some(false) => columns.push("unread".to_string()),
+					None => {}
+				}
+
+				if columns.is_empty() {
+					term.write_line(&format!("{}. {}", n + 1, item.text()))?;
+				} else {
+					term.write_line(&format!(
+						"{}. {}, {}",
+						n + 1,
+						item.text(),
+						columns.join(", ")
+					))?;
+				}
+			}
+			if self.actions.is_empty() {
+				term.write_line(
+					"Number to choose, text to search, n or p to turn the page, u for the first unread, q to leave:",
+				)?;
+			} else {
+				term.write_line(
+					"Number to choose, a and a number for its actions, text to search, n or p to turn the page, u for the first unread, q to leave:",
+				)?;
+			}
+
+			let input = term.read_line()?;
+			match input.trim() {
+				"" | "q" => return Ok(None),
+				"n" if self.extendable && page + 1 == pages => {
+					if let Some(state) = self.state.as_mut() {
+						state.selected = filtered_list
+							.last()
+							.map(|&(i, _)| self.items[i].text().to_string());
+						state.load_more = true;
+					}
+					return Ok(None);
+				}
+				"n" => page += 1,
+				"p" => page = page.saturating_sub(1),
+				"u" => match first_unread(&self.items, filtered_list.iter().map(|&(i, _)| i)) {
+					Some(n) => page = n / capacity,
+					None => term.write_line("Everything is read.")?,
+				},
+				input
+					if !self.actions.is_empty()
+						&& input
+							.strip_prefix("a ")
+							.is_some_and(|n| n.parse::<usize>().is_ok()) =>
+				{
+					let n = input[2..].parse::<usize>().unwrap();
+					let i = match n.checked_sub(1).and_then(|n| filtered_list.get(n)) {
+						Some(&(i, _)) => i,
+						None => {
+							term.write_line("No item has that number.")?;
+							continue;
+						}
+					};
+					match self.pick_action(term, self.items[i].text())? {
+						Some(action) if action == self.actions.len() => {
+							if let Some(state) = self.state.as_mut() {
+								state.selected = Some(self.items[i].text().to_string());
+								state.by_author = true;
+							}
+							return Ok(None);
+						}
+						Some(action) => {
+							if let Some(state) = self.state.as_mut() {
+								state.selected = Some(self.items[i].text().to_string());
+								state.action = Some(action);
+							}
+							return Ok(Some(i));
+						}
+						None => {}
+					}
+				}
+				input => match input.parse::<usize>() {
+					Ok(n) if (1..=filtered_list.len()).contains(&n) => {
+						if let Some(context) = &self.history {
+							Searches::open(Searches::default_path())?
+								.record(context, &search_term)?;
+						}
+						return Ok(Some(filtered_list[n - 1].0));
+					}
+					Ok(_) => term.write_line("No item has that number.")?,
+					Err(_) => {
+						search_term = input.to_string();
+						page = 0;
+					}
+				},
+			}
+		}
+	}
+
 	/// Like `interact` but allows a specific terminal to be set.
 	fn _interact_on(&mut self, term: &Term) -> io::Result<Option<usize>> {
 		// Place cursor at the end of the search term
 		let mut position = self.initial_text.len();
 		let mut search_term = self.initial_text.to_owned();
 
+		let navigation = navigation();
+		let wrap = navigation.wrap();
+		let repeat_delay = navigation.repeat_delay();
+		let page_jump = navigation
+			.page_jump
+			.filter(|jump| *jump > 0)
+			.map(|jump| jump as isize);
+		// Last key that moved through the list and when it was pressed
+		let mut last_move: Option<(Key, Instant)> = None;
+
 		let mut paging = Paging::new(term, self.items.len(), self.max_length);
+		paging.wrap = wrap;
 		let mut render = TermThemeRenderer::new(term, self.theme);
 		let mut sel = self.default;
 
-		let mut size_vec = Vec::new();
-		for item in self.items.iter().as_slice() {
-			let size = &item.title.len();
-			size_vec.push(*size);
+		// Facets of every item, the sorted facet values and the active one
+		let mut item_facets: Option<Vec<Vec<String>>> = None;
+		let mut filters: Vec<String> = vec![];
+		let mut filter: Option<usize> = None;
+
+		// Page number typed after `:`
+		let mut page_input: Option<String> = None;
+
+		// Recent queries, the recalled one and the query typed before recalling
+		let queries = match &self.history {
+			Some(context) => Searches::open(Searches::default_path())?
+				.queries(context)
+				.to_vec(),
+			None => vec![],
+		};
+		let mut recalled: Option<usize> = None;
+		let mut draft = String::new();
+
+		macro_rules! load_facets {
+			() => {
+				if item_facets.is_none() {
+					let facets = self.facets.as_mut().unwrap();
+					let all = self.items.iter().map(facets).collect::<Vec<_>>();
+					filters = all.iter().flatten().cloned().collect();
+					filters.sort();
+					filters.dedup();
+					item_facets = Some(all);
+				}
+			};
+		}
+
+		// Pick up where the last interaction on the list ended
+		let mut restore = None;
+		if let Some(state) = self.state.as_mut() {
+			state.refresh = false;
+			state.by_author = false;
+			state.turn_page = None;
+			state.load_more = false;
+			state.action = None;
+			search_term = state.search_term.clone();
+			position = search_term.len();
+			restore = state.selected.clone();
+
+			let restore_filter = state.filter.clone();
+			if restore_filter.is_some() && self.facets.is_some() {
+				load_facets!();
+				filter = filters
+					.iter()
+					.position(|f| Some(f) == restore_filter.as_ref());
+			}
+
+			for (key, value) in &state.refinements {
+				if let Some(refinement) = self
+					.refinements
+					.iter_mut()
+					.find(|refinement| refinement.key == *key)
+				{
+					refinement.load(&self.items);
+					refinement.active = refinement.values.iter().position(|v| v == value);
+				}
+			}
 		}
 
 		// Fuzzy matcher
-		let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
+		let matcher = SkimMatcherV2::default();
+		let item_aliases = self.item_aliases();
 
 		term.hide_cursor()?;
 
@@ -159,10 +747,7 @@ impl FuzzySelect<'_> {
 			($filtered_list:expr) => {
 				sel = match sel {
 					None => Some($filtered_list.len() - 1),
-					Some(sel) => Some(
-						((sel as i64 - 1 + $filtered_list.len() as i64)
-							% ($filtered_list.len() as i64)) as usize,
-					),
+					Some(sel) => Some(step(sel, -1, $filtered_list.len(), wrap)),
 				};
 			};
 		}
@@ -171,12 +756,119 @@ impl FuzzySelect<'_> {
 			($filtered_list:expr) => {
 				sel = match sel {
 					None => Some(0),
-					Some(sel) => Some((sel as u64 + 1).rem($filtered_list.len() as u64) as usize),
+					Some(sel) => Some(step(sel, 1, $filtered_list.len(), wrap)),
 				};
 			};
 		}
 
+		// Moves by `page_jump` items when it is set, by a whole page otherwise
+		macro_rules! jump {
+			($filtered_list:expr, $by:expr, $turn:ident) => {
+				sel = match $by {
+					Some(by) if !$filtered_list.is_empty() => {
+						Some(step(sel.unwrap_or(0), by, $filtered_list.len(), wrap))
+					}
+					_ => paging.$turn().or(sel),
+				}
+			};
+		}
+
 		loop {
+			// Maps all items to a tuple of item and its match score.
+			let mut filtered_list = self
+				.items
+				.iter()
+				.enumerate()
+				.filter(|(i, _)| match (filter, &item_facets) {
+					(Some(filter), Some(facets)) => facets[*i].contains(&filters[filter]),
+					_ => true,
+				})
+				.filter(|(i, _)| {
+					self.refinements
+						.iter()
+						.all(|refinement| refinement.keeps(*i))
+				})
+				.map(|(i, item)| {
+					let score = score(&matcher, item.text(), item_aliases.get(i), &search_term);
+					(item, score)
+				})
+				.filter_map(|(item, score)| score.map(|s| (item, s)))
+				.collect::<Vec<_>>();
+
+			// Renders all matching items, from best match to worst.
+			filtered_list.sort_unstable_by(|(_, s1), (_, s2)| s2.cmp(s1));
+
+			if let Some(text) = restore.take() {
+				if let Some(i) = filtered_list
+					.iter()
+					.position(|(item, _)| item.text() == text)
+				{
+					sel = Some(i);
+					paging.update(i)?;
+				}
+			}
+
+			macro_rules! save_state {
+				() => {
+					if let Some(state) = self.state.as_mut() {
+						state.search_term = search_term.clone();
+						state.selected = sel
+							.and_then(|sel| filtered_list.get(sel))
+							.map(|(item, _)| item.text().to_string());
+						state.filter = filter.map(|filter| filters[filter].clone());
+						state.refinements = self
+							.refinements
+							.iter()
+							.filter_map(|refinement| {
+								Some((refinement.key, refinement.active()?.to_string()))
+							})
+							.collect();
+					}
+				};
+			}
+
+			macro_rules! select_item {
+				($sel:expr) => {{
+					save_state!();
+
+					if self.clear {
+						render.clear()?;
+					}
+
+					if self.report {
+						render.input_prompt_selection(
+							self.prompt.as_str(),
+							filtered_list[$sel].0.text(),
+						)?;
+					}
+
+					if let Some(context) = &self.history {
+						Searches::open(Searches::default_path())?.record(context, &search_term)?;
+					}
+
+					let sel_string = filtered_list[$sel].0.text();
+					let sel_string_pos_in_items = self
+						.items
+						.iter()
+						.position(|item| item.text().eq(sel_string));
+
+					term.show_cursor()?;
+					return Ok(sel_string_pos_in_items);
+				}};
+			}
+
+			if self.on_state_change.is_some() {
+				let last = self.state.as_deref().cloned();
+				save_state!();
+				if let (Some(state), Some(callback)) =
+					(self.state.as_deref(), self.on_state_change.as_mut())
+				{
+					if last.as_ref() != Some(state) {
+						callback(state);
+					}
+				}
+			}
+
 			render.clear()?;
 
 			paging.render_prompt(|paging_info| {
@@ -188,37 +880,157 @@ impl FuzzySelect<'_> {
 				)
 			})?;
 
-			// Maps all items to a tuple of item and its match score.
-			let mut filtered_list = self
-				.items
-				.iter()
-				.map(|item| (item, matcher.fuzzy_match(&item.title, &search_term)))
-				.filter_map(|(item, score)| score.map(|s| (item, s)))
-				.collect::<Vec<_>>();
-
-			// Renders all matching items, from best match to worst.
-			filtered_list.sort_unstable_by(|(_, s1), (_, s2)| s2.cmp(s1));
-
-			for (idx, (item, _)) in filtered_list
+			let page = filtered_list
 				.iter()
 				.enumerate()
 				.skip(paging.current_page * paging.capacity)
 				.take(paging.capacity)
-			{
-				render.fuzzy_select_prompt_item(
-					&item.title,
+				.map(|(idx, (item, _))| {
+					let columns = self
+						.columns
+						.as_mut()
+						.map_or(vec![], |columns| columns(item));
+					(idx, item, columns)
+				})
+				.collect::<Vec<_>>();
+
+			// Align the columns across the items of the page
+			let mut widths = vec![];
+			for (_, _, columns) in &page {
+				widths.resize(widths.len().max(columns.len()), 0);
+				for (width, column) in widths.iter_mut().zip(columns) {
+					*width = (*width).max(measure_text_width(column).min(MAX_COLUMN_WIDTH));
+				}
+			}
+
+			let first = paging.current_page * paging.capacity;
+			// Narrow terminals truncate items, the highlighted one is shown in full
+			let mut detail = None;
+			for (idx, item, columns) in page {
+				// The first nine items of the page can be picked with 1-9
+				let quick = Some(idx - first + 1).filter(|quick| *quick <= 9);
+				let truncated = render.fuzzy_select_prompt_item(
+					item.text(),
+					quick,
+					&columns,
+					&widths,
 					Some(idx) == sel,
+					item.read(),
 					self.highlight_matches,
 					&matcher,
 					&search_term,
 				)?;
+				if truncated && Some(idx) == sel {
+					detail = Some(item.text());
+				}
+			}
+
+			if let Some(text) = detail {
+				render.detail(text)?;
+			}
+
+			if self.facets.is_some() {
+				render.filter_bar(&filters, filter.map(|filter| filters[filter].as_str()))?;
+			}
+
+			if !self.refinements.is_empty() {
+				let refinements = self
+					.refinements
+					.iter()
+					.map(|refinement| {
+						(
+							refinement.key,
+							refinement.name.as_str(),
+							refinement.active(),
+						)
+					})
+					.collect::<Vec<_>>();
+				render.refinements(&refinements)?;
+			}
+
+			if let Some(preview) = self.preview.as_mut() {
+				if let Some((item, _)) = sel.and_then(|sel| filtered_list.get(sel)) {
+					render.preview(&preview(item))?;
+				}
+			}
+
+			if let Some(status) = self.status.as_mut().and_then(|status| status()) {
+				render.status(&status)?;
+			}
+
+			if let Some(input) = &page_input {
+				render.page_prompt(input)?;
 			}
 
 			term.flush()?;
 
-			match (term.read_key()?, sel) {
+			// Redrawn as it is when an update comes in before a key
+			let mut updated = false;
+			if let Some(updates) = self.updates.as_mut() {
+				while !wait_for_key(UPDATE_INTERVAL)? {
+					if updates() {
+						updated = true;
+						break;
+					}
+				}
+			}
+			if updated {
+				if item_facets.is_some() {
+					let active = filter.map(|filter| filters[filter].clone());
+					item_facets = None;
+					load_facets!();
+					filter = filters.iter().position(|f| Some(f) == active.as_ref());
+				}
+				for refinement in &mut self.refinements {
+					refinement.reload(&self.items);
+				}
+				render.clear_preserve_prompt()?;
+				continue;
+			}
+
+			let mut key = term.read_key()?;
+			let moves = matches!(
+				key,
+				Key::ArrowUp
+					| Key::ArrowDown
+					| Key::ArrowLeft
+					| Key::ArrowRight
+					| Key::Tab | Key::BackTab
+			) || (matches!(self.input_mode, InputMode::Normal)
+				&& matches!(key, Key::Char('j' | 'k' | 'h' | 'l')));
+			if moves && page_input.is_none() {
+				// A key held down moves at most once every `repeat_delay`
+				if last_move
+					.as_ref()
+					.is_some_and(|(last, at)| *last == key && at.elapsed() < repeat_delay)
+				{
+					key = Key::Unknown;
+				} else {
+					last_move = Some((key.clone(), Instant::now()));
+				}
+			}
+
+			match (key, sel) {
+				(key, _) if page_input.is_some() => match key {
+					Key::Char(chr) if chr.is_ascii_digit() => {
+						page_input.as_mut().unwrap().push(chr)
+					}
+					Key::Backspace => {
+						page_input.as_mut().unwrap().pop();
+					}
+					Key::Enter => {
+						let page = page_input.take().and_then(|input| input.parse().ok());
+						if let Some(first) = page.and_then(|page| paging.go_to_page(page)) {
+							sel = Some(first.min(filtered_list.len().saturating_sub(1)));
+						}
+					}
+					Key::Escape => page_input = None,
+					_ => {}
+				},
 				(Key::Escape, _) => match self.input_mode {
 					InputMode::Normal => {
+						save_state!();
+
 						if self.clear {
 							render.clear()?;
 							term.flush()?;
@@ -229,9 +1041,141 @@ impl FuzzySelect<'_> {
 					}
 					InputMode::Editing => self.input_mode = &InputMode::Normal,
 				},
+				(Key::Char('r'), _)
+					if matches!(self.input_mode, InputMode::Normal) && self.refreshable =>
+				{
+					save_state!();
+					if let Some(state) = self.state.as_mut() {
+						state.refresh = true;
+					}
+
+					if self.clear {
+						render.clear()?;
+						term.flush()?;
+					}
+					term.show_cursor()?;
+
+					return Ok(None);
+				}
+				(Key::Char(chr @ ('[' | ']')), _)
+					if matches!(self.input_mode, InputMode::Normal) && self.pageable =>
+				{
+					save_state!();
+					if let Some(state) = self.state.as_mut() {
+						state.turn_page = Some(if chr == ']' {
+							Turn::Next
+						} else {
+							Turn::Previous
+						});
+					}
+
+					if self.clear {
+						render.clear()?;
+						term.flush()?;
+					}
+					term.show_cursor()?;
+
+					return Ok(None);
+				}
+				(key @ (Key::ArrowDown | Key::Tab | Key::Char('j')), Some(sel))
+					if self.extendable
+						&& sel + 1 == filtered_list.len()
+						&& (key != Key::Char('j')
+							|| matches!(self.input_mode, InputMode::Normal)) =>
+				{
+					save_state!();
+					if let Some(state) = self.state.as_mut() {
+						state.load_more = true;
+					}
+
+					if self.clear {
+						render.clear()?;
+						term.flush()?;
+					}
+					term.show_cursor()?;
+
+					return Ok(None);
+				}
+				(Key::Char('a'), Some(sel))
+					if matches!(self.input_mode, InputMode::Normal)
+						&& !self.actions.is_empty()
+						&& sel < filtered_list.len() =>
+				{
+					render.clear()?;
+					let text = filtered_list[sel].0.text().to_string();
+					let action = self.pick_action(term, &text)?;
+					term.hide_cursor()?;
+
+					match action {
+						Some(action) if action == self.actions.len() => {
+							save_state!();
+							if let Some(state) = self.state.as_mut() {
+								state.by_author = true;
+							}
+
+							if self.clear {
+								render.clear()?;
+								term.flush()?;
+							}
+							term.show_cursor()?;
+
+							return Ok(None);
+						}
+						Some(action) => {
+							if let Some(state) = self.state.as_mut() {
+								state.action = Some(action);
+							}
+							select_item!(sel)
+						}
+						// Back to the list
+						None => {}
+					}
+				}
+				(Key::Char('a'), _)
+					if matches!(self.input_mode, InputMode::Normal)
+						&& self.by_author && sel.is_some() =>
+				{
+					save_state!();
+					if let Some(state) = self.state.as_mut() {
+						state.by_author = true;
+					}
+
+					if self.clear {
+						render.clear()?;
+						term.flush()?;
+					}
+					term.show_cursor()?;
+
+					return Ok(None);
+				}
 				(Key::Char('i'), _) if matches!(self.input_mode, InputMode::Normal) => {
 					self.input_mode = &InputMode::Editing
 				}
+				(Key::ArrowUp, _)
+					if matches!(self.input_mode, InputMode::Editing) && self.history.is_some() =>
+				{
+					let older = recalled.map_or(0, |recalled| recalled + 1);
+					if let Some(query) = queries.get(older) {
+						if recalled.is_none() {
+							draft = search_term.clone();
+						}
+						recalled = Some(older);
+						search_term = query.clone();
+						position = search_term.len();
+						sel = Some(0);
+					}
+				}
+				(Key::ArrowDown, _)
+					if matches!(self.input_mode, InputMode::Editing) && recalled.is_some() =>
+				{
+					recalled = recalled.and_then(|recalled| recalled.checked_sub(1));
+					search_term = match recalled {
+						Some(newer) => queries[newer].clone(),
+						None => std::mem::take(&mut draft),
+					};
+					position = search_term.len();
+					sel = Some(0);
+				}
 				(Key::ArrowUp | Key::BackTab, _) if !filtered_list.is_empty() => {
 					next_item!(filtered_list);
 					term.flush()?;
@@ -254,47 +1198,102 @@ impl FuzzySelect<'_> {
 					prev_item!(filtered_list);
 					term.flush()?;
 				}
-				(Key::ArrowLeft, _) if paging.active => sel = Some(paging.previous_page()),
+				(Key::Char('f'), _)
+					if matches!(self.input_mode, InputMode::Normal) && self.facets.is_some() =>
+				{
+					load_facets!();
+
+					filter = match filter {
+						None if !filters.is_empty() => Some(0),
+						Some(filter) if filter + 1 < filters.len() => Some(filter + 1),
+						_ => None,
+					};
+					sel = Some(0);
+				}
+				(Key::Char(chr), _)
+					if matches!(self.input_mode, InputMode::Normal)
+						&& self
+							.refinements
+							.iter()
+							.any(|refinement| refinement.key == chr) =>
+				{
+					let refinement = self
+						.refinements
+						.iter_mut()
+						.find(|refinement| refinement.key == chr)
+						.unwrap();
+					refinement.load(&self.items);
+					refinement.cycle();
+					sel = Some(0);
+				}
+				(key @ (Key::Char('g') | Key::Home), _)
+					if (key == Key::Home || matches!(self.input_mode, InputMode::Normal))
+						&& paging.active =>
+				{
+					sel = Some(paging.first_page())
+				}
+				(key @ (Key::Char('G') | Key::End), _)
+					if (key == Key::End || matches!(self.input_mode, InputMode::Normal))
+						&& paging.active =>
+				{
+					sel = Some(
+						paging
+							.last_page()
+							.min(filtered_list.len().saturating_sub(1)),
+					)
+				}
+				(Key::Char(':'), _)
+					if matches!(self.input_mode, InputMode::Normal) && paging.active =>
+				{
+					page_input = Some(String::new())
+				}
+				(Key::Char('u'), _) if matches!(self.input_mode, InputMode::Normal) => {
+					let shown = filtered_list.iter().map(|(item, _)| {
+						self.items
+							.iter()
+							.position(|other| std::ptr::eq(other, *item))
+							.unwrap()
+					});
+					if let Some(n) = first_unread(&self.items, shown) {
+						sel = Some(n);
+					}
+				}
+				(Key::ArrowLeft, _) if paging.active => {
+					jump!(filtered_list, page_jump.map(|jump| -jump), previous_page)
+				}
 				(Key::Char('h'), _)
 					if matches!(self.input_mode, InputMode::Normal) && paging.active =>
 				{
-					sel = Some(paging.previous_page())
+					jump!(filtered_list, page_jump.map(|jump| -jump), previous_page)
 				}
-				(Key::ArrowRight, _) if paging.active => sel = Some(paging.next_page()),
+				(Key::ArrowRight, _) if paging.active => jump!(filtered_list, page_jump, next_page),
 				(Key::Char('l'), _)
 					if matches!(self.input_mode, InputMode::Normal) && paging.active =>
 				{
-					sel = Some(paging.next_page())
+					jump!(filtered_list, page_jump, next_page)
 				}
 
 				(Key::Enter, Some(sel)) => match self.input_mode {
 					InputMode::Editing => self.input_mode = &InputMode::Normal,
-					InputMode::Normal if !filtered_list.is_empty() => {
-						if self.clear {
-							render.clear()?;
-						}
-
-						if self.report {
-							render.input_prompt_selection(
-								self.prompt.as_str(),
-								filtered_list[sel].0.title.as_str(),
-							)?;
-						}
-
-						let sel_string = &filtered_list[sel].0.title;
-						let sel_string_pos_in_items =
-							self.items.iter().position(|item| item.title.eq(sel_string));
-
-						term.show_cursor()?;
-						return Ok(sel_string_pos_in_items);
-					}
+					InputMode::Normal if !filtered_list.is_empty() => select_item!(sel),
 					_ => {}
 				},
+				(Key::Char(chr @ '1'..='9'), _) if matches!(self.input_mode, InputMode::Normal) => {
+					let sel = paging.current_page * paging.capacity
+						+ chr.to_digit(10).unwrap() as usize
+						- 1;
+					if sel < filtered_list.len()
+						&& sel < (paging.current_page + 1) * paging.capacity
+					{
+						select_item!(sel);
+					}
+				}
 				(Key::Backspace, _)
 					if matches!(self.input_mode, InputMode::Editing) && position > 0 =>
 				{
 					position -= 1;
 					search_term.remove(position);
+					recalled = None;
 					term.flush()?;
 				}
 				(Key::Char(chr), _)
@@ -302,6 +1301,7 @@ impl FuzzySelect<'_> {
 				{
 					search_term.insert(position, chr);
 					position += 1;
+					recalled = None;
 					term.flush()?;
 					sel = Some(0);
 				}
@@ -314,12 +1314,12 @@ impl FuzzySelect<'_> {
 				None => paging.update(0)?,
 			}
 
-			render.clear_preserve_prompt(&size_vec)?;
+			render.clear_preserve_prompt()?;
 		}
 	}
 }
 
-impl<'a> FuzzySelect<'a> {
+impl<'a, T: Item> FuzzySelect<'a, T> {
 	/// Same as `new` but with a specific theme.
 	pub fn with_theme(theme: &'a dyn Theme) -> Self {
 		Self {
@@ -332,7 +1332,176 @@ impl<'a> FuzzySelect<'a> {
 			max_length: None,
 			theme,
 			input_mode: &InputMode::Normal,
+			preview: None,
+			columns: None,
+			facets: None,
+			aliases: None,
+			status: None,
+			updates: None,
+			history: None,
+			state: None,
+			on_state_change: None,
+			refreshable: false,
+			by_author: false,
+			pageable: false,
+			extendable: false,
+			actions: vec![],
+			refinements: vec![],
 			initial_text: "".into(),
 		}
 	}
+
+	/// Sets a preview for the highlighted item.
+	///
+	/// The returned lines are rendered below the list and are truncated to
+	/// the width of the terminal.
+	pub fn with_preview(&mut self, preview: Preview<'a, T>) -> &mut Self {
+		self.preview = Some(preview);
+		self
+	}
+
+	/// Renders extra columns next to every item, aligned across the page.
+	///
+	/// The item text is truncated to leave room for the columns, columns
+	/// wider than `MAX_COLUMN_WIDTH` are truncated too.
+	pub fn with_columns(&mut self, columns: Columns<'a, T>) -> &mut Self {
+		self.columns = Some(columns);
+		self
+	}
+
+	/// Lets the list be filtered by the facets of its items, e.g. the
+	/// language a novel was written in.
+	///
+	/// `f` cycles through the facets of all items in Normal Mode. Facets are
+	/// only computed once the filter is first used, so `facets` may be slow.
+	pub fn with_facets(&mut self, facets: Facets<'a, T>) -> &mut Self {
+		self.facets = Some(facets);
+		self
+	}
+
+	/// Matches the items against the other names `aliases` returns for them
+	/// too, like the original or romanized title of a novel.
+	///
+	/// Items are rendered under their own text whichever name matched.
+	pub fn with_aliases(&mut self, aliases: Aliases<'a, T>) -> &mut Self {
+		self.aliases = Some(aliases);
+		self
+	}
+
+	/// Returns the aliases of every item, none without `with_aliases`.
+	fn item_aliases(&mut self) -> Vec<Vec<String>> {
+		match self.aliases.as_mut() {
+			Some(aliases) => self.items.iter().map(aliases).collect(),
+			None => vec![],
+		}
+	}
+
+	/// Lets `key` in Normal Mode cycle through the values `facets` returns
+	/// for the items, e.g. their genres, keeping the items with the active
+	/// value.
+	///
+	/// Refinements narrow down the list along with each other and the facet
+	/// filter. Like facets, values are only computed once `key` is first
+	/// pressed.
+	pub fn with_refinement<S: Into<String>>(
+		&mut self,
+		key: char,
+		name: S,
+		facets: Facets<'a, T>,
+	) -> &mut Self {
+		self.refinements.push(Refinement {
+			key,
+			name: name.into(),
+			facets,
+			item_values: None,
+			values: vec![],
+			active: None,
+		});
+		self
+	}
+
+	/// Keeps the query, the highlighted item and the filter in `state`.
+	///
+	/// They are restored when the interaction starts, so a refreshed or
+	/// extended list keeps the place of the user, and updated when it ends.
+	pub fn with_state(&mut self, state: &'a mut State) -> &mut Self {
+		self.state = Some(state);
+		self
+	}
+
+	/// Calls `callback` whenever the query, the highlighted item or the
+	/// filter changes, with the state kept by `with_state`.
+	pub fn on_state_change(&mut self, callback: &'a mut dyn FnMut(&State)) -> &mut Self {
+		self.on_state_change = Some(callback);
+		self
+	}
+
+	/// Lets 'r' in Normal Mode end the interaction with `State::refresh`
+	/// set, for the caller to reload the list.
+	///
+	/// The default is to not be refreshable.
+	pub fn refreshable(&mut self, val: bool) -> &mut Self {
+		self.refreshable = val;
+		self
+	}
+
+	/// Lets 'a' in Normal Mode end the interaction with `State::by_author`
+	/// set, for the caller to list other works by the author of the
+	/// highlighted item, which `State::selected` holds.
+	///
+	/// The default is to not offer it.
+	pub fn by_author(&mut self, val: bool) -> &mut Self {
+		self.by_author = val;
+		self
+	}
+
+	/// Lets ']' and '[' in Normal Mode end the interaction with
+	/// `State::turn_page` set, for the caller to load the next or previous
+	/// page of wherever the list comes from.
+	///
+	/// The default is to not offer it.
+	pub fn pageable(&mut self, val: bool) -> &mut Self {
+		self.pageable = val;
+		self
+	}
+
+	/// Lets moving down past the last item, or to the page after the last
+	/// one in plain mode, end the interaction with `State::load_more` set,
+	/// for the caller to add the items that come after.
+	///
+	/// The default is to wrap around to the first item.
+	pub fn extendable(&mut self, val: bool) -> &mut Self {
+		self.extendable = val;
+		self
+	}
+
+	/// Lets 'a' in Normal Mode open a menu of `actions` for the highlighted
+	/// item, picking one selects the item with `State::action` set to its
+	/// index.
+	///
+	/// Works by the same author are offered in the menu rather than on 'a'
+	/// when `by_author` is set too. The default is to have no menu.
+	pub fn with_actions<S: ToString>(&mut self, actions: &[S]) -> &mut Self {
+		self.actions = actions.iter().map(ToString::to_string).collect();
+		self
+	}
+
+	/// Sets a status bar rendered below the list.
+	///
+	/// It is refreshed every time the list is redrawn and hidden while
+	/// `status` returns `None`.
+	pub fn with_status(&mut self, status: &'a mut dyn FnMut() -> Option<String>) -> &mut Self {
+		self.status = Some(status);
+		self
+	}
+
+	/// Redraws the list whenever `updates` returns true, checked every
+	/// `UPDATE_INTERVAL` while waiting for a key, e.g. once a background
+	/// task fetched what the preview, facets and refinements show.
+	///
+	/// Facets and refinements are computed again on every update.
+	pub fn with_updates(&mut self, updates: &'a mut dyn FnMut() -> bool) -> &mut Self {
+		self.updates = Some(updates);
+		self
+	}
 }
@@ -1,7 +1,9 @@
 //! Customizes the rendering of the elements.
 use std::{fmt, io};
 
-use console::{measure_text_width, style, Style, StyledObject, Term};
+use console::{
+	measure_text_width, pad_str, style, truncate_str, Alignment, Style, StyledObject, Term,
+};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
 /// Implements a theme for dialoguer.
@@ -32,6 +34,28 @@ pub trait Theme {
 		}
 	}
 
+	/// Formats a yes/no prompt.
+	#[inline]
+	fn format_confirm_prompt(
+		&self,
+		f: &mut dyn fmt::Write,
+		prompt: &str,
+		default: bool,
+	) -> fmt::Result {
+		write!(f, "{} [{}] ", prompt, if default { "Y/n" } else { "y/N" })
+	}
+
+	/// Formats a yes/no prompt after it was answered.
+	#[inline]
+	fn format_confirm_prompt_selection(
+		&self,
+		f: &mut dyn fmt::Write,
+		prompt: &str,
+		sel: bool,
+	) -> fmt::Result {
+		write!(f, "{} {}", prompt, if sel { "yes" } else { "no" })
+	}
+
 	/// Formats an input prompt after selection.
 	#[inline]
 	fn format_input_prompt_selection(
@@ -43,17 +67,23 @@ pub trait Theme {
 		write!(f, "{}: {}", prompt, sel)
 	}
 
-	/// Formats a fuzzy select prompt item.
+	/// Formats a fuzzy select prompt item, `read` tells read items apart
+	/// from unread ones in lists that track them.
+	#[allow(clippy::too_many_arguments)]
 	fn format_fuzzy_select_prompt_item(
 		&self,
 		f: &mut dyn fmt::Write,
 		text: &str,
 		active: bool,
+		read: Option<bool>,
 		highlight_matches: bool,
 		matcher: &SkimMatcherV2,
 		search_term: &str,
 	) -> fmt::Result {
 		write!(f, "{} ", if active { ">" } else { " " })?;
+		if read == Some(true) {
+			write!(f, "(read) ")?;
+		}
 
 		if highlight_matches {
 			if let Some((_score, indices)) = matcher.fuzzy_indices(text, &search_term) {
@@ -72,6 +102,93 @@ pub trait Theme {
 		write!(f, "{}", text)
 	}
 
+	/// Formats the quick-select number in front of an item, if it has one.
+	#[inline]
+	fn format_item_index(&self, f: &mut dyn fmt::Write, index: Option<usize>) -> fmt::Result {
+		match index {
+			Some(index) => write!(f, "{} ", index),
+			None => write!(f, "  "),
+		}
+	}
+
+	/// Formats the extra columns of an item, already aligned to their width.
+	#[inline]
+	fn format_item_columns(
+		&self,
+		f: &mut dyn fmt::Write,
+		columns: &[String],
+		_active: bool,
+	) -> fmt::Result {
+		for column in columns {
+			write!(f, "  {}", column)?;
+		}
+		Ok(())
+	}
+
+	/// Formats the paging indicator in front of the prompt.
+	#[inline]
+	fn format_paging_info(
+		&self,
+		f: &mut dyn fmt::Write,
+		page: usize,
+		pages: usize,
+		items: usize,
+	) -> fmt::Result {
+		write!(f, " [Page {}/{} — {} items] ", page, pages, items)
+	}
+
+	/// Formats a line of the preview of the highlighted item.
+	#[inline]
+	fn format_preview_line(&self, f: &mut dyn fmt::Write, line: &str) -> fmt::Result {
+		write!(f, "  {}", line)
+	}
+
+	/// Formats a line of the full text of a truncated item.
+	#[inline]
+	fn format_detail_line(&self, f: &mut dyn fmt::Write, line: &str) -> fmt::Result {
+		write!(f, "  {}", line)
+	}
+
+	/// Formats the status bar.
+	#[inline]
+	fn format_status(&self, f: &mut dyn fmt::Write, status: &str) -> fmt::Result {
+		write!(f, "-- {}", status)
+	}
+
+	/// Formats the refinements of the list, each with the key toggling it
+	/// and its active value in brackets.
+	#[inline]
+	fn format_refinements(
+		&self,
+		f: &mut dyn fmt::Write,
+		refinements: &[(char, &str, Option<&str>)],
+	) -> fmt::Result {
+		write!(f, "refine:")?;
+		for (key, name, active) in refinements {
+			write!(f, " {} {} [{}]", key, name, active.unwrap_or("all"))?;
+		}
+		Ok(())
+	}
+
+	/// Formats the filter bar, with the active filter in brackets.
+	#[inline]
+	fn format_filter_bar(
+		&self,
+		f: &mut dyn fmt::Write,
+		filters: &[String],
+		active: Option<&str>,
+	) -> fmt::Result {
+		write!(f, "filter:")?;
+		for filter in std::iter::once("all").chain(filters.iter().map(String::as_str)) {
+			if active.unwrap_or("all") == filter {
+				write!(f, " [{}]", filter)?;
+			} else {
+				write!(f, " {}", filter)?;
+			}
+		}
+		Ok(())
+	}
+
 	/// Formats a fuzzy select prompt.
 	fn format_fuzzy_select_prompt(
 		&self,
@@ -143,6 +260,10 @@ pub struct ColorfulTheme {
 	pub fuzzy_cursor_style: Style,
 	// Formats the highlighting if matched characters
 	pub fuzzy_match_highlight_style: Style,
+	/// The style for items already read
+	pub read_item_style: Style,
+	/// The style for items not read yet
+	pub unread_item_style: Style,
 	/// Show the selections from certain prompts inline
 	pub inline_selections: bool,
 }
@@ -170,6 +291,8 @@ impl Default for ColorfulTheme {
 			unpicked_item_prefix: style(" ".to_string()).for_stderr(),
 			fuzzy_cursor_style: Style::new().for_stderr().black().on_white(),
 			fuzzy_match_highlight_style: Style::new().for_stderr().bold(),
+			read_item_style: Style::new().for_stderr().black().bright(),
+			unread_item_style: Style::new().for_stderr().white().bright(),
 			inline_selections: true,
 		}
 	}
@@ -227,6 +350,55 @@ impl Theme for ColorfulTheme {
 		}
 	}
 
+	/// Formats a yes/no prompt.
+	fn format_confirm_prompt(
+		&self,
+		f: &mut dyn fmt::Write,
+		prompt: &str,
+		default: bool,
+	) -> fmt::Result {
+		if !prompt.is_empty() {
+			write!(
+				f,
+				"{} {} ",
+				&self.prompt_prefix,
+				self.prompt_style.apply_to(prompt)
+			)?;
+		}
+
+		write!(
+			f,
+			"{} {} ",
+			self.hint_style
+				.apply_to(if default { "(Y/n)" } else { "(y/N)" }),
+			&self.prompt_suffix
+		)
+	}
+
+	/// Formats a yes/no prompt after it was answered.
+	fn format_confirm_prompt_selection(
+		&self,
+		f: &mut dyn fmt::Write,
+		prompt: &str,
+		sel: bool,
+	) -> fmt::Result {
+		if !prompt.is_empty() {
+			write!(
+				f,
+				"{} {} ",
+				&self.success_prefix,
+				self.prompt_style.apply_to(prompt)
+			)?;
+		}
+
+		write!(
+			f,
+			"{} {}",
+			&self.success_suffix,
+			self.values_style.apply_to(if sel { "yes" } else { "no" })
+		)
+	}
+
 	/// Formats an input prompt after selection.
 	fn format_input_prompt_selection(
 		&self,
@@ -257,6 +429,7 @@ impl Theme for ColorfulTheme {
 		f: &mut dyn fmt::Write,
 		text: &str,
 		active: bool,
+		read: Option<bool>,
 		highlight_matches: bool,
 		matcher: &SkimMatcherV2,
 		search_term: &str,
@@ -271,6 +444,13 @@ impl Theme for ColorfulTheme {
 			}
 		)?;
 
+		// Read items are dimmed and unread ones stand out
+		let item_style = match read {
+			Some(true) => self.read_item_style.clone(),
+			Some(false) => self.unread_item_style.clone(),
+			None => Style::new().for_stderr(),
+		};
+
 		if highlight_matches {
 			if let Some((_score, indices)) = matcher.fuzzy_indices(text, &search_term) {
 				for (idx, c) in text.chars().into_iter().enumerate() {
@@ -289,7 +469,7 @@ impl Theme for ColorfulTheme {
 						if active {
 							write!(f, "{}", self.active_item_style.apply_to(c))?;
 						} else {
-							write!(f, "{}", c)?;
+							write!(f, "{}", item_style.apply_to(c))?;
 						}
 					}
 				}
@@ -298,7 +478,104 @@ impl Theme for ColorfulTheme {
 			}
 		}
 
-		write!(f, "{}", text)
+		write!(f, "{}", item_style.apply_to(text))
+	}
+
+	/// Formats the quick-select number in front of an item, if it has one.
+	fn format_item_index(&self, f: &mut dyn fmt::Write, index: Option<usize>) -> fmt::Result {
+		match index {
+			Some(index) => write!(f, "{} ", self.hint_style.apply_to(index)),
+			None => write!(f, "  "),
+		}
+	}
+
+	/// Formats the extra columns of an item, dimmed unless it is active.
+	fn format_item_columns(
+		&self,
+		f: &mut dyn fmt::Write,
+		columns: &[String],
+		active: bool,
+	) -> fmt::Result {
+		for column in columns {
+			if active {
+				write!(f, "  {}", self.active_item_style.apply_to(column))?;
+			} else {
+				write!(f, "  {}", self.hint_style.apply_to(column))?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Formats the paging indicator in front of the prompt.
+	fn format_paging_info(
+		&self,
+		f: &mut dyn fmt::Write,
+		page: usize,
+		pages: usize,
+		items: usize,
+	) -> fmt::Result {
+		write!(
+			f,
+			"{} ",
+			self.hint_style
+				.apply_to(format!(" [Page {}/{} — {} items]", page, pages, items))
+		)
+	}
+
+	/// Formats a line of the preview of the highlighted item.
+	fn format_preview_line(&self, f: &mut dyn fmt::Write, line: &str) -> fmt::Result {
+		write!(f, "  {}", self.hint_style.apply_to(line))
+	}
+
+	/// Formats a line of the full text of a truncated item.
+	fn format_detail_line(&self, f: &mut dyn fmt::Write, line: &str) -> fmt::Result {
+		write!(f, "  {}", self.values_style.apply_to(line))
+	}
+
+	/// Formats the status bar.
+	fn format_status(&self, f: &mut dyn fmt::Write, status: &str) -> fmt::Result {
+		write!(
+			f,
+			"{} {}",
+			&self.success_suffix,
+			self.defaults_style.apply_to(status)
+		)
+	}
+
+	/// Formats the refinements of the list, with their active values
+	/// highlighted.
+	fn format_refinements(
+		&self,
+		f: &mut dyn fmt::Write,
+		refinements: &[(char, &str, Option<&str>)],
+	) -> fmt::Result {
+		write!(f, "{}", self.hint_style.apply_to("refine:"))?;
+		for (key, name, active) in refinements {
+			write!(f, " {} {} ", self.hint_style.apply_to(key), name)?;
+			match active {
+				Some(active) => write!(f, "{}", self.active_item_style.apply_to(active))?,
+				None => write!(f, "{}", self.inactive_item_style.apply_to("all"))?,
+			}
+		}
+		Ok(())
+	}
+
+	/// Formats the filter bar, with the active filter highlighted.
+	fn format_filter_bar(
+		&self,
+		f: &mut dyn fmt::Write,
+		filters: &[String],
+		active: Option<&str>,
+	) -> fmt::Result {
+		write!(f, "{}", self.hint_style.apply_to("filter:"))?;
+		for filter in std::iter::once("all").chain(filters.iter().map(String::as_str)) {
+			if active.unwrap_or("all") == filter {
+				write!(f, " {}", self.active_item_style.apply_to(filter))?;
+			} else {
+				write!(f, " {}", self.inactive_item_style.apply_to(filter))?;
+			}
+		}
+		Ok(())
 	}
 
 	/// Formats a fuzzy-selectprompt after selection.
@@ -405,10 +682,6 @@ impl<'a> TermThemeRenderer<'a> {
 		Ok(())
 	}
 
-	fn write_paging_info(buf: &mut dyn fmt::Write, paging_info: (usize, usize)) -> fmt::Result {
-		write!(buf, " [Page {}/{}] ", paging_info.0, paging_info.1)
-	}
-
 	pub fn error(&mut self, err: &str) -> io::Result<()> {
 		self.write_formatted_line(|this, buf| this.theme.format_error(buf, err))
 	}
@@ -418,11 +691,11 @@ impl<'a> TermThemeRenderer<'a> {
 		prompt: &str,
 		search_term: &str,
 		cursor_pos: usize,
-		paging_info: Option<(usize, usize)>,
+		paging_info: Option<(usize, usize, usize)>,
 	) -> io::Result<()> {
 		self.write_formatted_prompt(|this, buf| {
-			if let Some(paging_info) = paging_info {
-				TermThemeRenderer::write_paging_info(buf, paging_info)?;
+			if let Some((page, pages, items)) = paging_info {
+				this.theme.format_paging_info(buf, page, pages, items)?;
 			}
 
 			this.theme
@@ -436,32 +709,141 @@ impl<'a> TermThemeRenderer<'a> {
 		self.write_formatted_str(|this, buf| this.theme.format_input_prompt(buf, prompt, default))
 	}
 
+	pub fn confirm_prompt(&mut self, prompt: &str, default: bool) -> io::Result<usize> {
+		self.write_formatted_str(|this, buf| this.theme.format_confirm_prompt(buf, prompt, default))
+	}
+
+	pub fn confirm_prompt_selection(&mut self, prompt: &str, sel: bool) -> io::Result<()> {
+		self.write_formatted_prompt(|this, buf| {
+			this.theme.format_confirm_prompt_selection(buf, prompt, sel)
+		})
+	}
+
 	pub fn input_prompt_selection(&mut self, prompt: &str, sel: &str) -> io::Result<()> {
 		self.write_formatted_prompt(|this, buf| {
 			this.theme.format_input_prompt_selection(buf, prompt, sel)
 		})
 	}
 
+	/// Renders an item with its extra `columns` right-aligned to `widths`,
+	/// truncating the text so the item stays on a single row.
+	///
+	/// Returns whether the text was truncated.
+	#[allow(clippy::too_many_arguments)]
 	pub fn fuzzy_select_prompt_item(
 		&mut self,
 		text: &str,
+		index: Option<usize>,
+		columns: &[String],
+		widths: &[usize],
 		active: bool,
+		read: Option<bool>,
 		highlight: bool,
 		matcher: &SkimMatcherV2,
 		search_term: &str,
-	) -> io::Result<()> {
+	) -> io::Result<bool> {
+		let columns = widths
+			.iter()
+			.enumerate()
+			.map(|(i, &width)| {
+				let column = columns.get(i).map_or("", String::as_str);
+				pad_str(
+					&truncate_str(column, width, "…"),
+					width,
+					Alignment::Right,
+					None,
+				)
+				.to_string()
+			})
+			.collect::<Vec<_>>();
+		// Two for the number, two for the prefix, two before every column and
+		// one to spare
+		let width = (self.term.size().1 as usize).saturating_sub(
+			5 + columns
+				.iter()
+				.map(|c| measure_text_width(c) + 2)
+				.sum::<usize>(),
+		);
+		let truncated = measure_text_width(text) > width;
+		let text = truncate_str(text, width, "…");
+
 		self.write_formatted_line(|this, buf| {
+			this.theme.format_item_index(buf, index)?;
 			this.theme.format_fuzzy_select_prompt_item(
 				buf,
-				text,
+				&text,
 				active,
+				read,
 				highlight,
 				matcher,
 				search_term,
-			)
+			)?;
+			if !columns.is_empty() {
+				let padding = " ".repeat(width.saturating_sub(measure_text_width(&text)));
+				write!(buf, "{}", padding)?;
+				this.theme.format_item_columns(buf, &columns, active)?;
+			}
+			Ok(())
+		})?;
+
+		Ok(truncated)
+	}
+
+	/// Renders the full text of a truncated item, wrapped by hand so every
+	/// row is counted when clearing.
+	pub fn detail(&mut self, text: &str) -> io::Result<()> {
+		let width = (self.term.size().1 as usize).saturating_sub(3).max(1);
+
+		let mut line = String::new();
+		for chr in text.chars() {
+			if measure_text_width(&line) + measure_text_width(chr.encode_utf8(&mut [0; 4])) > width
+			{
+				let full = std::mem::take(&mut line);
+				self.write_formatted_line(|this, buf| this.theme.format_detail_line(buf, &full))?;
+			}
+			line.push(chr);
+		}
+		if !line.is_empty() {
+			self.write_formatted_line(|this, buf| this.theme.format_detail_line(buf, &line))?;
+		}
+
+		Ok(())
+	}
+
+	pub fn preview(&mut self, lines: &[String]) -> io::Result<()> {
+		// Keep every line on a single row so clearing can rely on the height
+		let width = (self.term.size().1 as usize).saturating_sub(3);
+
+		for line in lines {
+			let line = line.chars().take(width).collect::<String>();
+			self.write_formatted_line(|this, buf| this.theme.format_preview_line(buf, &line))?;
+		}
+
+		Ok(())
+	}
+
+	pub fn status(&mut self, status: &str) -> io::Result<()> {
+		let width = (self.term.size().1 as usize).saturating_sub(3);
+		let status = status.chars().take(width).collect::<String>();
+
+		self.write_formatted_line(|this, buf| this.theme.format_status(buf, &status))
+	}
+
+	pub fn page_prompt(&mut self, input: &str) -> io::Result<()> {
+		self.write_formatted_line(|this, buf| {
+			this.theme.format_input_prompt(buf, "Go to page", None)?;
+			write!(buf, "{}", input)
 		})
 	}
 
+	pub fn refinements(&mut self, refinements: &[(char, &str, Option<&str>)]) -> io::Result<()> {
+		self.write_formatted_line(|this, buf| this.theme.format_refinements(buf, refinements))
+	}
+
+	pub fn filter_bar(&mut self, filters: &[String], active: Option<&str>) -> io::Result<()> {
+		self.write_formatted_line(|this, buf| this.theme.format_filter_bar(buf, filters, active))
+	}
+
 	pub fn clear(&mut self) -> io::Result<()> {
 		self.term
 			.clear_last_lines(self.height + self.prompt_height)?;
@@ -470,18 +852,9 @@ impl<'a> TermThemeRenderer<'a> {
 		Ok(())
 	}
 
-	pub fn clear_preserve_prompt(&mut self, size_vec: &[usize]) -> io::Result<()> {
-		let mut new_height = self.height;
-		let prefix_width = 2;
-		//Check each item size, increment on finding an overflow
-		for size in size_vec {
-			if *size > self.term.size().1 as usize {
-				new_height += (((*size as f64 + prefix_width as f64) / self.term.size().1 as f64)
-					.ceil()) as usize - 1;
-			}
-		}
-
-		self.term.clear_last_lines(new_height)?;
+	pub fn clear_preserve_prompt(&mut self) -> io::Result<()> {
+		// Every line rendered is kept to a single row, so the height is exact
+		self.term.clear_last_lines(self.height)?;
 		self.height = 0;
 		Ok(())
 	}
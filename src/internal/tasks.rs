@@ -0,0 +1,93 @@
+use std::future::Future;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use async_std::task;
+
+use ranobe::error::RanobeError;
+
+enum TaskStatus {
+	Started(String),
+	Finished(String),
+	Failed(String, String),
+}
+
+/// Runs futures behind the interactive ui.
+///
+/// Every task reports when it starts and finishes through a channel, which
+/// the ui drains with `status` to render a status bar without blocking on
+/// any of them.
+pub struct Tasks {
+	sender: Sender<TaskStatus>,
+	receiver: Receiver<TaskStatus>,
+	running: Vec<String>,
+	last: Option<String>,
+}
+
+impl Tasks {
+	pub fn new() -> Self {
+		let (sender, receiver) = channel();
+
+		Self {
+			sender,
+			receiver,
+			running: vec![],
+			last: None,
+		}
+	}
+
+	/// Spawns `future` in the background under `name`.
+	pub fn spawn<S, F>(&self, name: S, future: F)
+	where
+		S: Into<String>,
+		F: Future<Output = Result<(), RanobeError>> + Send + 'static,
+	{
+		let name = name.into();
+		let sender = self.sender.clone();
+		let _ = sender.send(TaskStatus::Started(name.clone()));
+
+		task::spawn(async move {
+			let status = match future.await {
+				Ok(()) => TaskStatus::Finished(name),
+				Err(err) => TaskStatus::Failed(name, err.to_string()),
+			};
+			let _ = sender.send(status);
+		});
+	}
+
+	/// Takes in what the tasks reported since the last call and returns
+	/// whether any of them started or finished meanwhile.
+	pub fn poll(&mut self) -> bool {
+		let mut changed = false;
+		while let Ok(status) = self.receiver.try_recv() {
+			changed = true;
+			match status {
+				TaskStatus::Started(name) => self.running.push(name),
+				TaskStatus::Finished(name) => {
+					self.running.retain(|running| running != &name);
+					self.last = Some(format!("{}: done", name));
+				}
+				TaskStatus::Failed(name, err) => {
+					self.running.retain(|running| running != &name);
+					self.last = Some(format!("{}: {}", name, err));
+				}
+			}
+		}
+		changed
+	}
+
+	/// Returns a one line summary of the running tasks, or of the last
+	/// finished one if nothing is running.
+	pub fn status(&mut self) -> Option<String> {
+		self.poll();
+
+		if self.running.is_empty() {
+			self.last.clone()
+		} else {
+			Some(format!(
+				"[{}] {}",
+				self.running.len(),
+				self.running.join(", ")
+			))
+		}
+	}
+}
@@ -0,0 +1,123 @@
+use std::io::{Error, ErrorKind, Result, Write};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+/// Service the secrets of ranobe are filed under in the keyring.
+const SERVICE: &str = "ranobe";
+
+/// An account on a provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+	pub username: String,
+	pub password: String,
+}
+
+fn missing() -> Error {
+	Error::new(
+		ErrorKind::NotFound,
+		"no keyring found, install secret-tool or use the macOS Keychain",
+	)
+}
+
+/// Stores the credentials of `provider` in the system keyring, through
+/// `secret-tool` or the macOS `security` tool.
+///
+/// The secret is written to the tool rather than passed as an argument,
+/// which every user could read with `ps`.
+pub fn store(provider: &str, credentials: &Credentials) -> Result<()> {
+	let secret = serde_json::to_string(credentials)?;
+
+	if cfg!(target_os = "macos") {
+		// `security` only takes the password as an argument, unless the
+		// command itself is read from stdin in interactive mode. It is
+		// given in hexadecimal so it needs no quoting.
+		let hex = secret
+			.bytes()
+			.map(|byte| format!("{:02x}", byte))
+			.collect::<String>();
+		let command = format!(
+			"add-generic-password -U -s {} -a {} -X {}\n",
+			SERVICE, provider, hex
+		);
+		write_to(Command::new("security").arg("-i"), &command)?;
+
+		// Interactive mode succeeds whether its commands do or not
+		return match get(provider)? {
+			Some(stored) if stored == *credentials => Ok(()),
+			_ => Err(Error::other("security failed")),
+		};
+	}
+
+	write_to(
+		Command::new("secret-tool")
+			.args(["store", "--label"])
+			.arg(format!("ranobe: {}", provider))
+			.args(["service", SERVICE, "account", provider]),
+		&secret,
+	)
+}
+
+/// Runs `command` with `input` written to its stdin.
+fn write_to(command: &mut Command, input: &str) -> Result<()> {
+	let mut child = match command.stdin(Stdio::piped()).stdout(Stdio::null()).spawn() {
+		Ok(child) => child,
+		Err(err) if err.kind() == ErrorKind::NotFound => return Err(missing()),
+		Err(err) => return Err(err),
+	};
+	child.stdin.take().unwrap().write_all(input.as_bytes())?;
+	if !child.wait()?.success() {
+		return Err(Error::other(format!(
+			"{} failed",
+			command.get_program().to_string_lossy()
+		)));
+	}
+	Ok(())
+}
+
+/// Returns the credentials of `provider` in the system keyring, if any.
+pub fn get(provider: &str) -> Result<Option<Credentials>> {
+	let output = if cfg!(target_os = "macos") {
+		Command::new("security")
+			.args(["find-generic-password", "-s", SERVICE, "-a", provider, "-w"])
+			.stderr(Stdio::null())
+			.output()
+	} else {
+		Command::new("secret-tool")
+			.args(["lookup", "service", SERVICE, "account", provider])
+			.stderr(Stdio::null())
+			.output()
+	};
+	let output = match output {
+		Ok(output) => output,
+		Err(err) if err.kind() == ErrorKind::NotFound => return Err(missing()),
+		Err(err) => return Err(err),
+	};
+
+	// Both tools fail when there is no such secret
+	if !output.status.success() || output.stdout.is_empty() {
+		return Ok(None);
+	}
+	Ok(Some(serde_json::from_slice(output.stdout.trim_ascii())?))
+}
+
+/// Removes the credentials of `provider` from the system keyring.
+pub fn delete(provider: &str) -> Result<()> {
+	let status = if cfg!(target_os = "macos") {
+		Command::new("security")
+			.args(["delete-generic-password", "-s", SERVICE, "-a", provider])
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.status()
+	} else {
+		Command::new("secret-tool")
+			.args(["clear", "service", SERVICE, "account", provider])
+			.status()
+	};
+
+	match status {
+		Ok(_) => Ok(()),
+		Err(err) if err.kind() == ErrorKind::NotFound => Err(missing()),
+		Err(err) => Err(err),
+	}
+}
@@ -1,8 +1,44 @@
+pub mod auth;
+pub mod backup;
+pub mod bookmarks;
+pub mod cassette;
+pub mod chapter;
+pub mod config;
+pub mod cookies;
+pub mod credits;
+pub mod crypt;
+pub mod date;
+pub mod doctor;
+pub mod error;
+pub mod export;
+pub mod failure;
+pub mod freshness;
+pub mod gc;
+pub mod history;
+pub mod hooks;
 pub mod http;
+pub mod hydrate;
+pub mod import;
+pub mod keyring;
+pub mod library;
+pub mod mirrors;
+pub mod playlists;
 pub mod providers;
+pub mod rank;
+pub mod remind;
+pub mod searches;
+pub mod session;
+pub mod stats;
+pub mod store;
+pub mod suggest;
+pub mod text;
+pub mod tocs;
+pub mod trash;
+pub mod trending;
+pub mod unlocks;
 pub mod utils;
 
-pub type RanobeResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+pub type RanobeResult<T> = Result<T, error::RanobeError>;
 
 pub fn add(left: usize, right: usize) -> usize {
 	left + right
@@ -0,0 +1,281 @@
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::config::Settings;
+use crate::crypt;
+use crate::providers::{canonical_url, title_key, Chapter, Metadata, Volume};
+use crate::utils::data_dir;
+
+/// A novel in the library.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Novel {
+	pub title: String,
+	/// Name of the provider the novel is read from.
+	pub provider: String,
+	/// Title of the last chapter read.
+	pub progress: Option<String>,
+	pub progress_url: Option<Url>,
+	#[serde(default)]
+	pub metadata: Option<Metadata>,
+	/// Overrides of the global reading settings for this novel.
+	#[serde(default)]
+	pub settings: Settings,
+	/// Own rating of the novel, from 1 to 5.
+	#[serde(default)]
+	pub rating: Option<u8>,
+	/// Whether the provider removed the novel, like after a DMCA notice.
+	#[serde(default)]
+	pub unavailable: bool,
+	/// Volumes the table of contents of the novel is split into.
+	#[serde(default)]
+	pub volumes: Vec<Volume>,
+}
+
+impl Novel {
+	/// Returns the other titles of the novel its metadata lists, like its
+	/// original title or romanization.
+	pub fn aliases(&self) -> Vec<String> {
+		self.metadata
+			.iter()
+			.flat_map(|metadata| {
+				std::iter::once(&metadata.title).chain(&metadata.alternative_titles)
+			})
+			.filter(|alias| **alias != self.title)
+			.cloned()
+			.collect()
+	}
+
+	/// Whether the novel is titled `title`, or known under it too, ignoring
+	/// case, spacing and punctuation.
+	pub fn is_titled(&self, title: &str) -> bool {
+		let key = title_key(title);
+		!key.is_empty()
+			&& std::iter::once(self.title.clone())
+				.chain(self.aliases())
+				.any(|known| title_key(&known) == key)
+	}
+}
+
+/// Every novel that was read at least once, pinned to its provider.
+pub struct Library {
+	path: PathBuf,
+	novels: Vec<Novel>,
+}
+
+impl Library {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("library.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let novels = match crypt::read_to_string(&path) {
+			Ok(novels) => serde_json::from_str(&novels)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, novels })
+	}
+
+	pub fn novels(&self) -> &[Novel] {
+		&self.novels
+	}
+
+	/// Returns the novel titled `title`, or failing that the one known under
+	/// `title` too.
+	pub fn get(&self, title: &str) -> Option<&Novel> {
+		self.position(title).map(|index| &self.novels[index])
+	}
+
+	fn position(&self, title: &str) -> Option<usize> {
+		self.novels
+			.iter()
+			.position(|novel| novel.title == title)
+			.or_else(|| self.novels.iter().position(|novel| novel.is_titled(title)))
+	}
+
+	fn get_mut(&mut self, title: &str) -> Option<&mut Novel> {
+		self.position(title).map(|index| &mut self.novels[index])
+	}
+
+	/// Records `chapter` as the progress of `title`.
+	///
+	/// Novels not in the library yet are added and pinned to `provider`.
+	pub fn read(&mut self, title: &str, provider: &str, chapter: &Chapter) -> Result<()> {
+		let index = self.index_or_add(title, provider);
+
+		let novel = &mut self.novels[index];
+		novel.progress = Some(chapter.title.clone());
+		novel.progress_url = Some(canonical_url(&chapter.url));
+
+		self.save()
+	}
+
+	/// Adds `title` pinned to `provider` without any progress, so it is
+	/// followed before a chapter of it is read, and returns whether it was
+	/// not in the library yet.
+	pub fn follow(&mut self, title: &str, provider: &str) -> Result<bool> {
+		if self.get(title).is_some() {
+			return Ok(false);
+		}
+
+		self.index_or_add(title, provider);
+		self.save()?;
+		Ok(true)
+	}
+
+	/// Returns the index of `title`, adding it pinned to `provider` if it is
+	/// not in the library.
+	///
+	/// A novel read under another of its titles, like from another provider,
+	/// is not added twice.
+	fn index_or_add(&mut self, title: &str, provider: &str) -> usize {
+		if let Some(index) = self.position(title) {
+			return index;
+		}
+
+		self.novels.push(Novel {
+			title: title.to_string(),
+			provider: provider.to_string(),
+			progress: None,
+			progress_url: None,
+			metadata: None,
+			settings: Settings::default(),
+			rating: None,
+			unavailable: false,
+			volumes: vec![],
+		});
+		self.novels.len() - 1
+	}
+
+	/// Pins `title` to `provider`, keeping its progress.
+	///
+	/// The progress url belongs to the old provider, so it is dropped and
+	/// only the title of the last chapter read is kept.
+	pub fn pin(&mut self, title: &str, provider: &str) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.provider = provider.to_string();
+			novel.progress_url = None;
+			novel.unavailable = false;
+		}
+
+		self.save()
+	}
+
+	/// Removes `title` from the library and returns it.
+	pub fn remove(&mut self, title: &str) -> Result<Option<Novel>> {
+		let novel = match self.position(title) {
+			Some(index) => self.novels.remove(index),
+			None => return Ok(None),
+		};
+
+		self.save()?;
+
+		Ok(Some(novel))
+	}
+
+	/// Adds `novel` back to the library, replacing any novel with its title.
+	pub fn insert(&mut self, novel: Novel) -> Result<()> {
+		self.novels.retain(|existing| existing.title != novel.title);
+		self.novels.push(novel);
+
+		self.save()
+	}
+
+	/// Replaces the reading settings of `title`.
+	pub fn set_settings(&mut self, title: &str, settings: Settings) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.settings = settings;
+		}
+
+		self.save()
+	}
+
+	/// Sets the own rating of `title`, `None` clears it.
+	pub fn rate(&mut self, title: &str, rating: Option<u8>) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.rating = rating;
+		}
+
+		self.save()
+	}
+
+	/// Marks `title` as removed by its provider or as available again.
+	pub fn set_unavailable(&mut self, title: &str, unavailable: bool) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.unavailable = unavailable;
+		}
+
+		self.save()
+	}
+
+	/// Replaces the metadata stored for `title`.
+	pub fn set_metadata(&mut self, title: &str, metadata: Metadata) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.metadata = Some(metadata);
+		}
+
+		self.save()
+	}
+
+	/// Replaces the volumes stored for `title`.
+	pub fn set_volumes(&mut self, title: &str, volumes: Vec<Volume>) -> Result<()> {
+		if let Some(novel) = self.get_mut(title) {
+			novel.volumes = volumes;
+		}
+
+		self.save()
+	}
+
+	fn save(&self) -> Result<()> {
+		crypt::write(&self.path, serde_json::to_string(&self.novels)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Listed;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn finds_novels_under_their_other_titles() {
+		let dir = TestDir::new("library");
+		let path = dir.join("library.json");
+		let chapter = |n: u32| {
+			Chapter::new(
+				format!("Chapter {}", n),
+				Url::parse(&format!("https://example.com/mushoku/{}", n)).unwrap(),
+			)
+		};
+
+		let mut library = Library::open(&path).unwrap();
+		library.read("無職転生", "syosetu", &chapter(1)).unwrap();
+		assert!(library.get("Mushoku Tensei").is_none());
+
+		library
+			.set_metadata(
+				"無職転生",
+				Metadata {
+					title: "無職転生".to_string(),
+					alternative_titles: vec!["Mushoku Tensei".to_string()],
+					..Metadata::default()
+				},
+			)
+			.unwrap();
+		assert_eq!(library.get("mushoku tensei!").unwrap().title, "無職転生");
+		assert!(library.get("").is_none());
+
+		// Reading it under another title does not add it twice
+		library
+			.read("Mushoku Tensei", "novelupdates", &chapter(2))
+			.unwrap();
+		assert_eq!(library.novels().len(), 1);
+		assert_eq!(library.novels()[0].progress.as_deref(), Some("Chapter 2"));
+	}
+}
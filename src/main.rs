@@ -1,29 +1,442 @@
 mod internal;
 
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::env;
+use std::future::Future;
+use std::io::{self, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use console::{Key, Term};
 
 use ranobe::{
-	http::{client_init, fetch_url, CLIENT},
-	providers::readlightnovel::ReadLightNovel,
-	providers::RanobeScraper,
-	utils::open_glow,
+	auth::{self, Account},
+	backup,
+	bookmarks::{Bookmark, Bookmarks},
+	cassette,
+	chapter::{self, ChapterNumber},
+	config::{Config, Replacement, Settings, DEFAULT_WRAP},
+	cookies, crypt, date,
+	doctor::{self, Fixtures, Health},
+	error::RanobeError,
+	export::{self, ChangeKind},
+	freshness::Freshness,
+	gc,
+	history::History,
+	hooks,
+	http::{client_init, fetch_url, fetch_validators, CLIENT},
+	hydrate::Hydrator,
+	import::{self, LOCAL_PROVIDER},
+	keyring::{self, Credentials},
+	library::{self, Library},
+	mirrors::{self, Mirrors},
+	playlists::{self, Playlists},
+	providers,
+	providers::{
+		local, registry, Chapter, Comment, Listed, Lock, Metadata, Novel, RanobeScraper, Volume,
+	},
+	rank,
+	remind::{self, Reminders},
+	session::Session,
+	stats::{Goal, Stats},
+	store::{hash, ChapterStore},
+	suggest, text,
+	tocs::Tocs,
+	trash::Trash,
+	trending::{self, Movement, Snapshot},
+	unlocks::{Unlock, Unlocks},
+	utils::{
+		data_dir, libraries, library, notify, open_browser, open_glow, open_pager, plain,
+		read_clipboard, set_library, set_navigation, set_plain, write_clipboard,
+	},
 };
 
-use crate::internal::select::{select::FuzzySelect, theme::ColorfulTheme};
+#[cfg(feature = "syosetu")]
+use ranobe::providers::syosetu;
+
+#[cfg(not(any(
+	feature = "readlightnovel",
+	feature = "royalroad",
+	feature = "scribblehub",
+	feature = "novelupdates",
+	feature = "webnovel",
+	feature = "lightnovelworld",
+	feature = "novelfull",
+	feature = "syosetu",
+	feature = "kakuyomu",
+	feature = "ao3"
+)))]
+compile_error!("ranobe needs at least one provider feature enabled to build the binary.");
+
+use crate::internal::{
+	profile::Profile,
+	select::{
+		confirm::Confirm,
+		select::{Aliases, FuzzySelect, Grouped, Marked, Preview, State, Turn},
+		theme::ColorfulTheme,
+	},
+	tasks::Tasks,
+};
+use async_std::task;
+use chrono::{DateTime, Local, NaiveTime};
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use serde_json::json;
 use surf::{client, Url};
 
 use clap::{Parser, Subcommand};
 
+/// How many recently read novels bare `ranobe` offers to continue.
+const RESUME_SIZE: usize = 5;
+
+/// Most pages of latest updates fetched to fill a list of `--size`.
+const MAX_LATEST_PAGES: usize = 10;
+
+/// Actions 'a' offers for the highlighted entry of the latest updates.
+const LATEST_ACTIONS: [&str; 6] = [
+	"Read",
+	"Download",
+	"Follow",
+	"Stash",
+	"Open in browser",
+	"Info",
+];
+
+/// Playlist chapters stashed from the latest updates are added to.
+const STASH: &str = "Stash";
+
+/// Most chapters downloaded at once from a provider.
+const DOWNLOAD_CONCURRENCY: usize = 4;
+
+/// Chapter counts the latest updates can be refined to novels of at least.
+const CHAPTER_STEPS: [usize; 4] = [50, 100, 500, 1000];
+
 #[derive(Subcommand, Debug)]
 enum RanobeMode {
 	#[command(about = "Search and Read Light Novel with glow.")]
-	Read,
+	Read {
+		/// Title to search for, asked for if not given.
+		#[arg(conflicts_with = "from_clipboard")]
+		query: Option<String>,
+		/// Open the chapter whose url is on the clipboard.
+		#[arg(long)]
+		from_clipboard: bool,
+	},
 	#[command(about = "Get latest update list and Read Light Novel with glow.")]
 	Latest,
 	#[command(about = "Search and Download Light Novel.")]
 	Download,
 	#[command(about = "Seach and Stash Light Novel with glow.")]
 	Stash,
+	#[command(about = "Manage the novels in the library.")]
+	Library {
+		#[command(subcommand)]
+		action: LibraryAction,
+	},
+	#[command(about = "Import epub, txt or md files into the library.")]
+	Import {
+		/// An epub, txt or md file, or a directory of them.
+		path: PathBuf,
+	},
+	#[command(about = "Move a novel of the library to another provider.")]
+	Migrate {
+		/// Title of the novel in the library.
+		novel: String,
+		/// Provider to read the novel from.
+		#[arg(long)]
+		to: String,
+	},
+	#[command(about = "Fetch the metadata of novels in the library again.")]
+	RefreshMetadata {
+		/// Title of the novel in the library.
+		novel: Option<String>,
+		/// Refresh every novel in the library.
+		#[arg(long, conflicts_with = "novel")]
+		all: bool,
+		/// Fetch the novels again even if their pages did not change.
+		#[arg(long)]
+		force: bool,
+	},
+	#[command(about = "Export downloaded chapters as Markdown files.")]
+	Update {
+		/// Only export this novel.
+		novel: Option<String>,
+		/// Directory to export to.
+		#[arg(long)]
+		dir: Option<PathBuf>,
+		/// Overwrite changed files without asking.
+		#[arg(short, long)]
+		yes: bool,
+	},
+	#[command(about = "Export whole novels, skipping the ones unchanged since their last export.")]
+	Export {
+		/// Title of the novel to export.
+		#[arg(required_unless_present = "all")]
+		novel: Option<String>,
+		/// Export every novel with downloaded chapters.
+		#[arg(long, conflicts_with = "novel")]
+		all: bool,
+		/// Format to export in, `markdown` or `epub`.
+		#[arg(long, default_value = "markdown")]
+		format: export::Format,
+		/// Directory to export to.
+		#[arg(long)]
+		dir: Option<PathBuf>,
+		/// Number of novels exported at the same time.
+		#[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+		jobs: u16,
+		/// Export an epub per volume, for novels whose volumes are known.
+		#[arg(long)]
+		by_volume: bool,
+	},
+	#[command(about = "Encrypt the library and downloaded chapters with a passphrase.")]
+	Encrypt {
+		/// Decrypt them again and stop encrypting.
+		#[arg(long)]
+		off: bool,
+	},
+	#[command(about = "Remove files nothing refers to anymore.")]
+	Gc {
+		/// Only list what would be removed.
+		#[arg(long)]
+		dry_run: bool,
+	},
+	#[command(about = "Back up the library and the configuration, or restore a backup.")]
+	Backup {
+		/// Directory, or `.tar` archive, keeping the backups.
+		path: PathBuf,
+		/// Restore the latest backup instead, like after a bad migration.
+		#[arg(long)]
+		restore: bool,
+		/// Name of the backup to restore instead of the latest.
+		#[arg(long, requires = "restore")]
+		at: Option<String>,
+		/// List the backups instead.
+		#[arg(long, conflicts_with = "restore")]
+		list: bool,
+	},
+	#[command(about = "Verify downloaded chapters and re-download corrupted ones.")]
+	Verify {
+		/// Only verify chapters of this novel.
+		novel: Option<String>,
+	},
+	#[command(about = "Show a random novel of the provider's catalogue.")]
+	Random {
+		/// Only pick novels of this genre.
+		#[arg(long)]
+		genre: Option<String>,
+	},
+	#[command(about = "Browse the novels of the provider by the letter their title starts with.")]
+	Index {
+		/// Letter to list the novels of, `#` for titles starting with another
+		/// character, picked from the index if omitted.
+		#[arg(value_parser = parse_letter)]
+		letter: Option<char>,
+	},
+	#[command(about = "Show the series related to a novel in the library.")]
+	Related {
+		/// Title of the novel in the library.
+		novel: String,
+	},
+	#[command(about = "Show the most popular novels across providers.")]
+	Trending,
+	#[command(about = "List the available providers and their aliases.")]
+	Providers,
+	#[command(
+		about = "Check that providers still scrape their sites, against the counts they last worked with."
+	)]
+	Doctor {
+		/// Providers to check, every one if omitted.
+		providers: Vec<String>,
+		/// Keep the counts found as the ones to check against from now on.
+		#[arg(long)]
+		update: bool,
+		/// File of the counts to check against, like one committed next to
+		/// the providers.
+		#[arg(long)]
+		fixtures: Option<PathBuf>,
+		/// Also fail when a provider is drifting.
+		#[arg(long)]
+		strict: bool,
+	},
+	#[command(about = "Log in to a provider, keeping the credentials in the system keyring.")]
+	Login {
+		/// Provider to log in to, the one of `--provider` if omitted.
+		provider: Option<String>,
+		/// Log out instead, forgetting the session and the credentials.
+		#[arg(long)]
+		logout: bool,
+	},
+	#[command(about = "Move the sessions of logged in providers between machines.")]
+	Auth {
+		#[command(subcommand)]
+		action: AuthAction,
+	},
+	#[command(about = "Read chapters of several novels in a set order.")]
+	Playlist {
+		#[command(subcommand)]
+		action: PlaylistAction,
+	},
+	#[command(about = "Remind to continue a novel every day.")]
+	Remind {
+		/// Title of the novel in the library, lists the reminders and the locked
+		/// chapters waited for if omitted.
+		novel: Option<String>,
+		/// Time of day to be reminded at, as `HH:MM`.
+		#[arg(long, value_parser = parse_daily, requires = "novel")]
+		daily: Option<NaiveTime>,
+		/// Stop reminding to continue the novel.
+		#[arg(long, requires = "novel", conflicts_with = "daily")]
+		off: bool,
+		/// Send the due reminders and the chapters that became free as
+		/// desktop notifications, meant to be run periodically, e.g. from
+		/// cron.
+		#[arg(long, conflicts_with = "novel")]
+		notify: bool,
+	},
+	#[command(about = "Copy a paragraph of a chapter with a Markdown citation.")]
+	Quote {
+		/// Url of the chapter, the last read chapter if omitted.
+		url: Option<Url>,
+	},
+	#[command(about = "Bookmark a paragraph of a chapter, jumped to with ' after reading.")]
+	Bookmark {
+		/// Url of the chapter, the last read chapter if omitted.
+		url: Option<Url>,
+	},
+	#[command(about = "Read two chapters side by side, paragraph by paragraph.")]
+	Split {
+		/// Url of the chapter on the left, like a raw chapter.
+		left: Url,
+		/// Url of the chapter on the right, like its translation or the next
+		/// chapter.
+		right: Url,
+	},
+	#[command(about = "Show reading streaks or set a daily reading goal.")]
+	Stats {
+		/// Number of chapters to read every day.
+		#[arg(long)]
+		chapters: Option<u32>,
+		/// Number of minutes to read every day.
+		#[arg(long)]
+		minutes: Option<u32>,
+		/// Stop tracking a daily goal.
+		#[arg(long, conflicts_with_all = ["chapters", "minutes"])]
+		no_goal: bool,
+	},
+}
+
+#[derive(Subcommand, Debug)]
+enum LibraryAction {
+	#[command(about = "Read a downloaded chapter of a novel in the library.")]
+	Open {
+		/// Title of the novel, picked interactively if omitted.
+		novel: Option<String>,
+	},
+	#[command(about = "Override the reading settings for a novel.")]
+	Settings {
+		/// Title of the novel in the library.
+		novel: String,
+		/// Width to wrap the chapters of the novel at.
+		#[arg(long)]
+		wrap: Option<u16>,
+		/// Glow style to render the chapters of the novel with.
+		#[arg(long)]
+		style: Option<String>,
+		/// Offer to show the top comments after each chapter of the novel.
+		#[arg(long)]
+		comments: Option<bool>,
+		/// Replace text in the chapters of the novel, given as `from=to`.
+		#[arg(long = "replace", value_parser = parse_replacement)]
+		replacements: Vec<Replacement>,
+		/// Drop the current overrides before applying the new ones.
+		#[arg(long)]
+		reset: bool,
+	},
+	#[command(about = "List the novels in the library.")]
+	List,
+	#[command(about = "List the named libraries, marking the one in use.")]
+	Libraries,
+	#[command(about = "Rate a novel in the library.")]
+	Rate {
+		/// Title of the novel in the library.
+		novel: String,
+		/// Rating from 1 to 5, clears the rating if omitted.
+		#[arg(value_parser = clap::value_parser!(u8).range(1..=5))]
+		rating: Option<u8>,
+	},
+	#[command(about = "Move a novel and its chapters to the trash.")]
+	Delete {
+		/// Title of the novel, picked interactively if omitted.
+		novel: Option<String>,
+	},
+	#[command(about = "Restore a novel deleted in the last 30 days.")]
+	Restore {
+		/// Title of the novel, picked interactively if omitted.
+		novel: Option<String>,
+	},
+}
+
+#[derive(Subcommand, Debug)]
+enum PlaylistAction {
+	#[command(about = "Add a chapter to a playlist, creating the playlist if needed.")]
+	Add {
+		/// Name of the playlist.
+		playlist: String,
+		/// Url of the chapter.
+		url: Url,
+		/// Title of the novel, fetched from the provider if omitted.
+		#[arg(long)]
+		novel: Option<String>,
+		/// Title of the chapter, the one it was downloaded with if omitted.
+		#[arg(long)]
+		title: Option<String>,
+	},
+	#[command(about = "List the playlists, or the chapters of one.")]
+	List {
+		/// Name of the playlist to list the chapters of.
+		playlist: Option<String>,
+	},
+	#[command(about = "Remove a chapter from a playlist, or the whole playlist.")]
+	Remove {
+		/// Name of the playlist.
+		playlist: String,
+		/// Number of the chapter in the playlist, removes the playlist if
+		/// omitted.
+		#[arg(value_parser = clap::value_parser!(u32).range(1..))]
+		number: Option<u32>,
+	},
+	#[command(about = "Read a playlist from where it was left.")]
+	Play {
+		/// Name of the playlist.
+		playlist: String,
+		/// Number of the chapter to start from instead.
+		#[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+		from: Option<u32>,
+	},
+}
+
+#[derive(Subcommand, Debug)]
+enum AuthAction {
+	#[command(about = "Export the sessions to a file sealed with a passphrase.")]
+	Export {
+		/// File to export to.
+		path: PathBuf,
+		/// Only export the session of this provider.
+		#[arg(long)]
+		provider: Option<String>,
+	},
+	#[command(about = "Import the sessions exported on another machine.")]
+	Import {
+		/// File exported by `ranobe auth export`.
+		path: PathBuf,
+	},
 }
 
 #[derive(Parser, Debug)]
@@ -32,62 +445,3456 @@ struct Args {
 	#[command(subcommand)]
 	mode: Option<RanobeMode>,
 
-	/// Provider for anime or light novel.
-	#[arg(short = 'r', long, default_value = "readlightnovel")]
+	/// Provider for anime or light novel, by name or alias, see `ranobe
+	/// providers`, the first one compiled in by default.
+	#[arg(short = 'r', long, default_value_t = providers::available()[0].to_string())]
 	provider: String,
 
-	/// Size of the list. Please only send in positive number.
-	#[arg(short, long, default_value_t = 80)]
-	wrap: u16,
+	/// Width to wrap chapters at, overrides the configured width.
+	#[arg(short, long)]
+	wrap: Option<u16>,
 
-	/// Size of the list. Please only send in positive number.
+	/// Size of the list, at least as many latest updates are fetched. Please
+	/// only send in positive number.
 	#[arg(short, long, default_value_t = 20)]
 	size: usize,
+
+	/// Show every entry of the latest updates, including the site notices
+	/// providers filter out.
+	#[arg(long)]
+	show_all: bool,
+
+	/// Print how long each phase of the run took.
+	#[arg(long)]
+	profile_startup: bool,
+
+	/// Restore the list left behind by a crash or a closed terminal.
+	#[arg(long)]
+	resume_session: bool,
+
+	/// Named library to use, with its own database and downloaded chapters.
+	#[arg(short, long)]
+	library: Option<String>,
+
+	/// Save every request and response of the session to a cassette file.
+	#[arg(long, value_name = "CASSETTE", conflicts_with = "replay")]
+	record: Option<PathBuf>,
+
+	/// Answer requests from a cassette file instead of the network.
+	#[arg(long, value_name = "CASSETTE")]
+	replay: Option<PathBuf>,
+
+	/// Print lists as JSON, for scripts and other tools.
+	#[arg(long, global = true)]
+	json: bool,
 }
 
 #[async_std::main]
-async fn main() -> Result<(), surf::Error> {
+async fn main() -> ExitCode {
+	match run().await {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(err) => {
+			eprintln!("Error: {}", err);
+			if let Some(hint) = hint(&err) {
+				eprintln!("{}", hint);
+			}
+			ExitCode::FAILURE
+		}
+	}
+}
+
+/// Returns what can be done about `err`, when its message does not say.
+fn hint(err: &RanobeError) -> Option<String> {
+	match err {
+		RanobeError::Network(_) => {
+			Some("Check the connection, or try again later if the site is down.".to_string())
+		}
+		RanobeError::RateLimited {
+			retry_after: Some(wait),
+			..
+		} => Some(format!("Try again in {} seconds.", wait.as_secs())),
+		RanobeError::RateLimited { .. } => Some("Try again in a few minutes.".to_string()),
+		RanobeError::Unsupported(_) => {
+			Some("Pick another provider with --provider, see `ranobe providers`.".to_string())
+		}
+		_ => None,
+	}
+}
+
+async fn run() -> Result<(), RanobeError> {
+	let mut profile = Profile::new(Instant::now());
 	let args = Args::parse();
+	profile.enable(args.profile_startup);
+	profile.mark("parse arguments");
 
-	let mode = match &args.mode {
-		None => &RanobeMode::Read,
-		Some(m) => m,
-	};
+	let config = Config::load(Config::default_path())?;
+	set_plain(config.plain());
+	set_navigation(config.navigation);
+	set_library(args.library.as_deref().or(config.library.as_deref()))?;
+	#[cfg(feature = "syosetu")]
+	syosetu::set_r18(config.r18());
+	mirrors::set_extra(config.mirrors.clone());
+	local::set_dir(config.local_dir.clone());
+	hooks::set(config.hooks.clone());
+	if let Some(cassette) = &args.record {
+		cassette::record(cassette)?;
+	} else if let Some(cassette) = &args.replay {
+		cassette::replay(cassette)?;
+	}
+	if crypt::is_enabled() {
+		let config = config.clone();
+		crypt::set_unlock(move || {
+			let passphrase = passphrase(&config, "Passphrase: ").map_err(io::Error::other)?;
+			crypt::load(&passphrase)
+		});
+	}
+
+	if args.resume_session {
+		return resume_session(&args, &mut profile).await;
+	}
 
-	let _ = match mode {
-		&RanobeMode::Read => {}
-		&RanobeMode::Latest => {}
-		&RanobeMode::Stash => {}
-		&RanobeMode::Download => {}
+	match &args.mode {
+		None => resume(&args, &mut profile).await?,
+		Some(RanobeMode::Read {
+			from_clipboard: true,
+			..
+		}) => read_clipboard_url(&args, &mut profile).await?,
+		Some(RanobeMode::Read { query, .. }) => {
+			search(query.as_deref(), &args, &mut profile).await?
+		}
+		Some(RanobeMode::Latest) => latest(&args, &mut profile, None).await?,
+		Some(RanobeMode::Stash) => latest(&args, &mut profile, None).await?,
+		Some(RanobeMode::Download) => latest(&args, &mut profile, None).await?,
+		Some(RanobeMode::Library { action }) => match action {
+			LibraryAction::Open { novel } => open(novel.as_deref(), &args, &mut profile).await?,
+			LibraryAction::Settings {
+				novel,
+				wrap,
+				style,
+				comments,
+				replacements,
+				reset,
+			} => settings(novel, *wrap, style, *comments, replacements, *reset)?,
+			LibraryAction::List => list(args.json)?,
+			LibraryAction::Libraries => list_libraries(args.json)?,
+			LibraryAction::Rate { novel, rating } => rate(novel, *rating)?,
+			LibraryAction::Delete { novel } => delete(novel.as_deref(), &args)?,
+			LibraryAction::Restore { novel } => restore(novel.as_deref(), &args)?,
+		},
+		Some(RanobeMode::Import { path }) => import(path)?,
+		Some(RanobeMode::Migrate { novel, to }) => migrate(novel, to).await?,
+		Some(RanobeMode::RefreshMetadata { novel, all, force }) => {
+			refresh_metadata(novel.as_deref(), *all, *force).await?
+		}
+		Some(RanobeMode::Update { novel, dir, yes }) => update(novel.as_deref(), dir, *yes)?,
+		Some(RanobeMode::Export {
+			novel,
+			all: _,
+			format,
+			dir,
+			jobs,
+			by_volume,
+		}) => export(novel.as_deref(), *format, dir, *jobs, *by_volume)?,
+		Some(RanobeMode::Encrypt { off }) => encrypt(*off, &config)?,
+		Some(RanobeMode::Gc { dry_run }) => collect_garbage(*dry_run)?,
+		Some(RanobeMode::Backup {
+			path,
+			restore,
+			at,
+			list,
+		}) => back_up(path, *restore, at.as_deref(), *list, args.json)?,
+		Some(RanobeMode::Verify { novel }) => verify(novel.as_deref(), &args).await?,
+		Some(RanobeMode::Index { letter }) => index(*letter, &args, &mut profile).await?,
+		Some(RanobeMode::Random { genre }) => random(genre.as_deref(), &args).await?,
+		Some(RanobeMode::Related { novel }) => related(novel, args.json).await?,
+		Some(RanobeMode::Trending) => trending(&args).await?,
+		Some(RanobeMode::Providers) => list_providers(args.json)?,
+		Some(RanobeMode::Doctor {
+			providers,
+			update,
+			fixtures,
+			strict,
+		}) => diagnose(providers, *update, fixtures.as_deref(), *strict, args.json).await?,
+		Some(RanobeMode::Login { provider, logout }) => {
+			login(provider.as_deref(), *logout, &args).await?
+		}
+		Some(RanobeMode::Auth { action }) => match action {
+			AuthAction::Export { path, provider } => export_auth(path, provider.as_deref())?,
+			AuthAction::Import { path } => import_auth(path)?,
+		},
+		Some(RanobeMode::Playlist { action }) => match action {
+			PlaylistAction::Add {
+				playlist,
+				url,
+				novel,
+				title,
+			} => add_to_playlist(playlist, url, novel.as_deref(), title.as_deref(), &args).await?,
+			PlaylistAction::List { playlist } => list_playlists(playlist.as_deref(), args.json)?,
+			PlaylistAction::Remove { playlist, number } => {
+				remove_from_playlist(playlist, number.map(|number| number as usize))?
+			}
+			PlaylistAction::Play { playlist, from } => {
+				play(
+					playlist,
+					from.map(|from| from as usize),
+					&args,
+					&mut profile,
+				)
+				.await?
+			}
+		},
+		Some(RanobeMode::Remind {
+			novel,
+			daily,
+			off,
+			notify,
+		}) => remind(novel.as_deref(), *daily, *off, *notify, args.json)?,
+		Some(RanobeMode::Quote { url }) => quote(url.as_ref(), &args, &mut profile).await?,
+		Some(RanobeMode::Bookmark { url }) => bookmark(url.as_ref(), &args, &mut profile).await?,
+		Some(RanobeMode::Split { left, right }) => split(left, right, &mut profile).await?,
+		Some(RanobeMode::Stats {
+			chapters,
+			minutes,
+			no_goal,
+		}) => stats(*chapters, *minutes, *no_goal)?,
 	};
 
-	// let selections = vec![
-	// 	"Ice Cream",
-	// 	"Vanilla Cupcake",
-	// 	"Chocolate Muffin",
-	// 	"A Pile of sweet, sweet mustard",
-	// ];
-	//
+	profile.report();
 
-	let provider = ReadLightNovel::new()?;
+	Ok(())
+}
+
+/// Returns the error for `novel` missing from `library`, suggesting the
+/// titles it is close to.
+fn not_in_library(library: &Library, novel: &str) -> RanobeError {
+	let titles = library.novels().iter().map(|entry| entry.title.as_str());
+	RanobeError::NotFound(suggest::did_you_mean(
+		&format!("{} is not in the library.", novel),
+		&suggest::similar(novel, titles),
+	))
+}
+
+/// Returns the genres of the novels in the library, as named by their
+/// providers.
+fn known_genres() -> Result<Vec<String>, RanobeError> {
+	let library = Library::open(Library::default_path())?;
+	let mut genres = library
+		.novels()
+		.iter()
+		.filter_map(|novel| novel.metadata.as_ref())
+		.flat_map(|metadata| metadata.genres.iter().cloned())
+		.collect::<Vec<_>>();
+	genres.sort_unstable();
+	genres.dedup();
+	Ok(genres)
+}
+
+/// Formats the metadata shown next to a novel.
+fn metadata_lines(metadata: &Metadata) -> Vec<String> {
+	let mut lines = vec![];
+	if !metadata.alternative_titles.is_empty() {
+		lines.push(format!(
+			"Also known as: {}",
+			metadata.alternative_titles.join(", ")
+		));
+	}
+	if let Some(author) = &metadata.author {
+		lines.push(format!("Author: {}", author));
+	}
+	if let Some(status) = &metadata.status {
+		lines.push(format!("Status: {}", status));
+	}
+	if let Some(rating) = metadata.rating {
+		match metadata.reviews {
+			Some(reviews) => lines.push(format!("Rating: {:.1}/5 ({} reviews)", rating, reviews)),
+			None => lines.push(format!("Rating: {:.1}/5", rating)),
+		}
+	}
+	if !metadata.genres.is_empty() {
+		lines.push(format!("Genres: {}", metadata.genres.join(", ")));
+	}
+	if let Some(description) = &metadata.description {
+		lines.push(description.clone());
+	}
+	lines
+}
 
-	let body = provider.get_latest().await?;
+/// Lets the user pick a chapter of the latest updates of the provider
+/// passed to `--provider` and reads it, picking up where `session` left
+/// off if given.
+async fn latest(
+	args: &Args,
+	profile: &mut Profile,
+	session: Option<Session>,
+) -> Result<(), RanobeError> {
+	let provider = registry::get(&args.provider)?;
+	profile.mark("init provider");
+	browse_latest(provider, args, profile, session).await
+}
+
+async fn browse_latest<P>(
+	provider: P,
+	args: &Args,
+	profile: &mut Profile,
+	session: Option<Session>,
+) -> Result<(), RanobeError>
+where
+	P: RanobeScraper + Clone + Send + Sync + 'static,
+{
+	// Kept across refreshes, so the query and highlighted novel survive them
+	let (mut body, mut state, mut pages, mut next_page) = match session {
+		Some(session) => (
+			session.list,
+			State {
+				search_term: session.search_term,
+				selected: session.selected,
+				filter: session.filter,
+				refresh: false,
+				by_author: false,
+				turn_page: None,
+				load_more: false,
+				action: None,
+				refinements: vec![],
+			},
+			session.pages,
+			session.next_page,
+		),
+		None => {
+			let (body, next_page) = fetch_latest(&provider, args.size, args.show_all, 0).await?;
+			(body, State::default(), vec![], next_page)
+		}
+	};
+	if pages.is_empty() {
+		pages.push(0);
+	}
+	profile.mark("fetch latest");
 
 	// println!("{:?}", body);
 
+	let tasks = RefCell::new(Tasks::new());
+	// Prefetched chapters are stored here rather than by the task, so only
+	// one store writes the index
+	let (prefetched, prefetches) = channel();
+	if let Some(first) = body.first().cloned() {
+		let provider = provider.clone();
+		tasks
+			.borrow()
+			.spawn(format!("prefetch {}", first.title), async move {
+				let text = provider.get_text(first.url.clone()).await?;
+				let novel = provider.get_metadata(first.url.clone()).await?.title;
+				let _ = prefetched.send((first, novel, text));
+				Ok(())
+			});
+	}
+	let store_prefetched = || -> Result<(), RanobeError> {
+		for (chapter, novel, text) in prefetches.try_iter() {
+			ChapterStore::open(ChapterStore::default_root())?.put(
+				&chapter.url,
+				&novel,
+				&chapter.title,
+				&text,
+			)?;
+		}
+		Ok(())
+	};
+	let goal = Stats::open(Stats::default_path())?.progress(Local::now().date_naive());
+	let mut status = || match (tasks.borrow_mut().status(), &goal) {
+		(Some(tasks), Some(goal)) => Some(format!("{} · {}", tasks, goal)),
+		(tasks, goal) => tasks.or_else(|| goal.clone()),
+	};
+
+	// Metadata is fetched by the tasks, the list is redrawn as it comes in
+	let hydrator = RefCell::new(Hydrator::new(&provider));
+	let mut updates = || {
+		// Best effort, the chapter is fetched again when it is read
+		store_prefetched().ok();
+		let finished = tasks.borrow_mut().poll();
+		hydrator.borrow_mut().receive() || finished
+	};
+	// Only the metadata of the highlighted novel is fetched
+	let mut preview = |ranobe: &Chapter| -> Vec<String> {
+		if let Some(fetch) = hydrator.borrow_mut().fetch(ranobe) {
+			tasks
+				.borrow()
+				.spawn(format!("metadata of {}", ranobe.title), fetch);
+		}
+		metadata_preview(&hydrator.borrow(), ranobe)
+	};
+	let now = SystemTime::now();
+	let mut columns = |ranobe: &Chapter| -> Vec<String> {
+		let chapters = hydrator
+			.borrow()
+			.cached(ranobe)
+			.and_then(|metadata| metadata.chapters)
+			.map_or(String::new(), |chapters| format!("{} ch", chapters));
+		let updated = ranobe
+			.published
+			.map_or(String::new(), |updated| date::age(updated, now));
+		vec![chapters, updated, provider.name().to_string()]
+	};
+	// Facets and refinements only use the metadata fetched for the novels
+	// previewed so far, fetching it for every listed novel would cost a
+	// request each. Novels are left out until theirs is fetched.
+	let metadata = |ranobe: &Chapter| {
+		hydrator
+			.borrow()
+			.cached(ranobe)
+			.cloned()
+			.unwrap_or_default()
+	};
+	let mut facets = |ranobe: &Chapter| -> Vec<String> {
+		let metadata = metadata(ranobe);
+		metadata
+			.origin
+			.into_iter()
+			.chain(metadata.translator)
+			.collect()
+	};
+	let mut genres = |ranobe: &Chapter| -> Vec<String> {
+		metadata(ranobe)
+			.genres
+			.iter()
+			.map(|genre| genre.to_lowercase())
+			.collect()
+	};
+	let mut statuses = |ranobe: &Chapter| -> Vec<String> {
+		metadata(ranobe)
+			.status
+			.map(|status| status.to_lowercase())
+			.into_iter()
+			.collect()
+	};
+	let mut chapter_counts = |ranobe: &Chapter| -> Vec<String> {
+		let chapters = metadata(ranobe).chapters.unwrap_or(0);
+		CHAPTER_STEPS
+			.iter()
+			.filter(|step| chapters >= **step)
+			.map(|step| format!("{}+", step))
+			.collect()
+	};
+	let mut languages = |ranobe: &Chapter| -> Vec<String> {
+		metadata(ranobe)
+			.origin
+			.as_deref()
+			.map(language)
+			.into_iter()
+			.collect()
+	};
+
+	let session_path = Session::default_path();
+	let selection = loop {
+		// Saved as the user browses, so `--resume-session` can restore it
+		let list = body.clone();
+		let browsed = pages.clone();
+		let mut autosave = |state: &State| {
+			let session = Session {
+				mode: "latest".to_string(),
+				list: list.clone(),
+				search_term: state.search_term.clone(),
+				selected: state.selected.clone(),
+				filter: state.filter.clone(),
+				pages: browsed.clone(),
+				next_page,
+				saved_at: SystemTime::now(),
+			};
+			// Best effort, failing to save must not interrupt browsing
+			session.save(&session_path).ok();
+		};
+
+		let prompt = match pages.len() {
+			1 => "Choose chapter of light novel to read:".to_string(),
+			page => format!("Choose chapter of light novel to read (page {}):", page),
+		};
+		let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+			.with_prompt(prompt)
+			.with_history("novel search")
+			.max_length(args.size)
+			.default(0)
+			.items(&body[..])
+			.with_preview(&mut preview)
+			.with_columns(&mut columns)
+			.with_facets(&mut facets)
+			.with_refinement('t', "genre", &mut genres)
+			.with_refinement('s', "status", &mut statuses)
+			.with_refinement('c', "chapters", &mut chapter_counts)
+			.with_refinement('o', "language", &mut languages)
+			.with_status(&mut status)
+			.with_updates(&mut updates)
+			.with_state(&mut state)
+			.on_state_change(&mut autosave)
+			.refreshable(true)
+			.by_author(provider.capabilities().by_author)
+			.pageable(next_page.is_some())
+			.extendable(next_page.is_some())
+			.with_actions(&LATEST_ACTIONS)
+			.interact()?;
+
+		// Actions other than reading keep the list open
+		if let (Some(action @ 1..), Some(i)) = (state.action, selection) {
+			let chapter = &body[i];
+			let metadata = match LATEST_ACTIONS[action] {
+				"Open in browser" => Metadata::default(),
+				_ => {
+					let cached = hydrator.borrow().cached(chapter).cloned();
+					match cached {
+						Some(metadata) => metadata,
+						None => provider.get_metadata(chapter.url.clone()).await?,
+					}
+				}
+			};
+			latest_action(&provider, chapter, &metadata, LATEST_ACTIONS[action]).await?;
+			continue;
+		}
+
+		if state.by_author {
+			let selected = state.selected.clone();
+			if let Some(ranobe) = body
+				.iter()
+				.find(|ranobe| Some(&ranobe.title) == selected.as_ref())
+			{
+				let cached = hydrator.borrow().cached(ranobe).cloned();
+				let metadata = match cached {
+					Some(metadata) => metadata,
+					None => provider.get_metadata(ranobe.url.clone()).await?,
+				};
+				other_works(&provider, &metadata, args).await?;
+			}
+			continue;
+		}
+		if let Some(turn) = state.turn_page {
+			let page = match turn {
+				Turn::Next => next_page,
+				Turn::Previous => pages.len().checked_sub(2).map(|i| pages[i]),
+			};
+			// Nothing happens past the first and last pages
+			let page = match page {
+				Some(page) => page,
+				None => continue,
+			};
+			let (list, after) = fetch_latest(&provider, args.size, args.show_all, page).await?;
+			if list.is_empty() {
+				continue;
+			}
+			match turn {
+				Turn::Next => pages.push(page),
+				Turn::Previous => {
+					pages.pop();
+				}
+			}
+			(body, next_page) = (list, after);
+			profile.mark("turn page");
+			continue;
+		}
+		if state.load_more {
+			let page = match next_page {
+				Some(page) => page,
+				None => continue,
+			};
+			let (list, after) = match fetch_latest(&provider, args.size, args.show_all, page).await
+			{
+				Ok(more) => more,
+				// Sites answer past their last page with an error or nothing
+				Err(RanobeError::NotFound(_)) => (vec![], None),
+				Err(err) => return Err(err),
+			};
+			let before = body.len();
+			for ranobe in list {
+				// Entries move to the next page when novels update meanwhile
+				if !body.iter().any(|seen| seen.url == ranobe.url) {
+					body.push(ranobe);
+				}
+			}
+			// Move on to the first entry loaded, the end of the list was
+			// reached without any
+			match body.get(before) {
+				Some(first) => {
+					state.selected = Some(first.title.clone());
+					next_page = after;
+				}
+				None => next_page = None,
+			}
+			profile.mark("load more");
+			continue;
+		}
+		if !state.refresh {
+			break selection;
+		}
+		// As many as are listed, so the entries loaded stay
+		(body, next_page) = fetch_latest(
+			&provider,
+			body.len().max(args.size),
+			args.show_all,
+			*pages.last().unwrap(),
+		)
+		.await?;
+		profile.mark("refresh latest");
+	};
+	Session::clear(&session_path)?;
+	store_prefetched()?;
+	profile.mark("select chapter");
+
+	let chapter = match selection {
+		Some(i) => &body[i],
+		None => return Ok(()),
+	};
+
+	let novel = hydrator
+		.borrow()
+		.cached(chapter)
+		.map_or(chapter.title.clone(), |metadata| metadata.title.clone());
+
+	read_chapter(&provider, chapter, &novel, args, profile).await
+}
+
+/// Runs the action `action` of `LATEST_ACTIONS` other than reading on
+/// `chapter`, of the novel of `metadata`.
+async fn latest_action<P: RanobeScraper + Sync>(
+	provider: &P,
+	chapter: &Chapter,
+	metadata: &Metadata,
+	action: &str,
+) -> Result<(), RanobeError> {
+	match action {
+		"Download" => {
+			let text = backing_off(|| provider.get_text(chapter.url.clone())).await?;
+			let mut store = ChapterStore::open(ChapterStore::default_root())?;
+			hooks::put(&mut store, provider.name(), &metadata.title, chapter, &text)?;
+			println!("Downloaded {}.", chapter.title);
+		}
+		"Follow" => {
+			let mut library = Library::open(Library::default_path())?;
+			if library.follow(&metadata.title, provider.name())? {
+				println!("Following {}.", metadata.title);
+			} else {
+				println!("{} is already in the library.", metadata.title);
+			}
+		}
+		"Stash" => {
+			Playlists::open(Playlists::default_path())?.push(
+				STASH,
+				playlists::Entry {
+					novel: metadata.title.clone(),
+					provider: provider.name().to_string(),
+					title: chapter.title.clone(),
+					url: chapter.url.clone(),
+				},
+			)?;
+			println!(
+				"Stashed {}, read it with `ranobe playlist play {}`.",
+				chapter.title, STASH
+			);
+		}
+		"Open in browser" => {
+			open_browser(chapter.url.as_str())?;
+		}
+		_ => print_novel(chapter, metadata),
+	}
+
+	Ok(())
+}
+
+/// Returns the language novels published in the country `origin` are
+/// written in.
+fn language(origin: &str) -> String {
+	match origin {
+		"CN" => "Chinese",
+		"KR" => "Korean",
+		"JP" => "Japanese",
+		origin => origin,
+	}
+	.to_string()
+}
+
+/// Lets the user pick one of the other works by the author of the novel
+/// of `metadata` and shows it.
+async fn other_works<P: RanobeScraper + Clone + Send + Sync + 'static>(
+	provider: &P,
+	metadata: &Metadata,
+	args: &Args,
+) -> Result<(), RanobeError> {
+	// Co-authors are joined, their works are looked up by the first one
+	let author = match metadata
+		.author
+		.as_deref()
+		.and_then(|author| author.split(", ").next())
+	{
+		Some(author) => author,
+		None => {
+			println!("The author of {} is unknown.", metadata.title);
+			return Ok(());
+		}
+	};
+
+	let works = provider
+		.get_by_author(author)
+		.await?
+		.into_iter()
+		.filter(|work| work.title != metadata.title)
+		.collect::<Vec<_>>();
+	if works.is_empty() {
+		println!("No other works by {} on {}.", author, provider.name());
+		return Ok(());
+	}
+
+	pick_novel(
+		provider,
+		&works,
+		&format!("Other works by {}:", author),
+		args,
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// Lets the user pick one of `novels`, previewing their metadata, and
+/// shows it.
+///
+/// Returns the picked novel with its metadata.
+async fn pick_novel<P: RanobeScraper + Clone + Send + Sync + 'static>(
+	provider: &P,
+	novels: &[Novel],
+	prompt: &str,
+	args: &Args,
+) -> Result<Option<(Novel, Metadata)>, RanobeError> {
+	// Metadata is fetched by the tasks, the preview is redrawn as it comes in
+	let tasks = RefCell::new(Tasks::new());
+	let hydrator = RefCell::new(Hydrator::new(provider));
+	let mut preview = |ranobe: &Novel| -> Vec<String> {
+		if let Some(fetch) = hydrator.borrow_mut().fetch(ranobe) {
+			tasks
+				.borrow()
+				.spawn(format!("metadata of {}", ranobe.title), fetch);
+		}
+		metadata_preview(&hydrator.borrow(), ranobe)
+	};
+	let mut updates = || {
+		let finished = tasks.borrow_mut().poll();
+		hydrator.borrow_mut().receive() || finished
+	};
+	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+		.with_prompt(prompt)
+		.max_length(args.size)
+		.default(0)
+		.items(novels)
+		.with_preview(&mut preview)
+		.with_updates(&mut updates)
+		.interact()?;
+
+	let novel = match selection {
+		Some(i) => &novels[i],
+		None => return Ok(None),
+	};
+	let cached = hydrator.borrow().cached(novel).cloned();
+	let metadata = match cached {
+		Some(metadata) => metadata,
+		None => provider.get_metadata(novel.url.clone()).await?,
+	};
+	print_novel(novel, &metadata);
+
+	Ok(Some((novel.clone(), metadata)))
+}
+
+/// Returns the preview of `ranobe`, a novel or one of its chapters, with the
+/// metadata `hydrator` fetched so far.
+fn metadata_preview<S: RanobeScraper + Sync, E: Listed>(
+	hydrator: &Hydrator<'_, S>,
+	ranobe: &E,
+) -> Vec<String> {
+	match (hydrator.cached(ranobe), hydrator.failure(ranobe)) {
+		(Some(metadata), _) => metadata_lines(metadata),
+		(None, Some(err)) => vec![format!("Failed to load metadata: {}", err)],
+		(None, None) => vec!["Loading metadata…".to_string()],
+	}
+}
+
+/// Returns the table of contents of `novel`, fetched again only once the
+/// one fetched last expired or the latest updates showed it is missing
+/// chapters.
+async fn chapters_of<P: RanobeScraper + Sync>(
+	provider: &P,
+	novel: &Novel,
+) -> Result<Vec<Chapter>, RanobeError> {
+	let now = SystemTime::now();
+	let mut tocs = Tocs::open(Tocs::default_path())?;
+	if let Some(chapters) = tocs.get(&novel.url, now) {
+		return Ok(chapters.to_vec());
+	}
+
+	let chapters = provider.get_chapters(novel).await?;
+	tocs.set(&novel.url, chapters.clone(), now)?;
+	Ok(chapters)
+}
+
+/// Lets the user pick a chapter of the table of contents of `novel`, titled
+/// `title`, and reads it.
+///
+/// Returns whether a chapter was picked, so callers can go back to where
+/// the novel was picked from otherwise.
+async fn pick_chapter<P: RanobeScraper + Sync>(
+	provider: &P,
+	novel: &Novel,
+	title: &str,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<bool, RanobeError> {
+	let chapters = chapters_of(provider, novel).await?;
+	profile.mark("fetch chapters");
+
+	let now = SystemTime::now();
+	let mut columns = |chapter: &Chapter| -> Vec<String> { vec![lock_label(chapter.lock, now)] };
+	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+		.with_prompt(format!("Chapters of {}:", title))
+		.max_length(args.size)
+		.default(0)
+		.items(&chapters)
+		.with_columns(&mut columns)
+		.interact()?;
+
+	let chapter = match selection {
+		Some(i) => &chapters[i],
+		None => return Ok(false),
+	};
+	match chapter.lock {
+		Some(Lock::Until(at)) if at > now => wait_for_unlock(provider.name(), title, chapter, at)?,
+		_ => read_chapter(provider, chapter, title, args, profile).await?,
+	}
+	Ok(true)
+}
+
+/// Returns how `lock` is shown next to its chapter at `now`, nothing for
+/// chapters free to read.
+fn lock_label(lock: Option<Lock>, now: SystemTime) -> String {
+	match lock {
+		Some(Lock::Paid) => "locked".to_string(),
+		Some(Lock::Until(at)) if at > now => format!(
+			"free on {}, in {}",
+			DateTime::<Local>::from(at).format("%b %-d %H:%M"),
+			date::age(now, at)
+		),
+		_ => String::new(),
+	}
+}
+
+/// Offers to notify once `chapter` of `novel`, locked until `at`, is free
+/// to read.
+fn wait_for_unlock(
+	provider: &str,
+	novel: &str,
+	chapter: &Chapter,
+	at: SystemTime,
+) -> Result<(), RanobeError> {
+	let prompt = format!(
+		"{} is locked until {}, notify once it is free?",
+		chapter.title,
+		DateTime::<Local>::from(at).format("%b %-d %H:%M")
+	);
+	if !Confirm::with_theme(&ColorfulTheme::default())
+		.with_prompt(prompt)
+		.interact()?
+	{
+		return Ok(());
+	}
+
+	Unlocks::open(Unlocks::default_path())?.add(Unlock {
+		novel: novel.to_string(),
+		provider: provider.to_string(),
+		chapter: chapter.clone(),
+		at,
+	})?;
+	println!(
+		"`ranobe remind --notify` will tell once {} is free.",
+		chapter.title
+	);
+	Ok(())
+}
+
+/// Searches for `query`, asking for it if `None`, and lets the user pick one
+/// of the novels the provider finds then one of its chapters to read.
+///
+/// The novels found are ranked best match first, printed with their score
+/// with `--json`. Leaving the chapters goes back to the novels found.
+async fn search(
+	query: Option<&str>,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let provider = registry::get(&args.provider)?;
+	if !provider.capabilities().search {
+		return Err(RanobeError::Unsupported(format!(
+			"{} can not search, browse its latest updates with `ranobe latest` instead.",
+			provider.name()
+		)));
+	}
+
+	let query = match query {
+		Some(query) => query.to_string(),
+		None => {
+			let term = Term::stderr();
+			term.write_str("Search: ")?;
+			term.read_line()?
+		}
+	};
+	if query.trim().is_empty() {
+		return Ok(());
+	}
+
+	let novels = provider.search(&query).await?;
+	profile.mark("search");
+	if novels.is_empty() {
+		return Err(RanobeError::NotFound(format!(
+			"No novels found for {}.",
+			query.trim()
+		)));
+	}
+
+	// Only metadata already in the library, ranking costs no request
+	let library = Library::open(Library::default_path())?;
+	let ranked = rank::rank(query.trim(), novels, |novel| {
+		library
+			.get(&novel.title)
+			.and_then(|novel| novel.metadata.as_ref())
+	});
+	if args.json {
+		let ranked = ranked
+			.iter()
+			.map(|ranked| {
+				json!({
+					"title": ranked.ranobe.title,
+					"url": ranked.ranobe.url,
+					"score": ranked.score,
+				})
+			})
+			.collect::<Vec<_>>();
+		return print_json(&ranked);
+	}
+	let novels = ranked
+		.into_iter()
+		.map(|ranked| ranked.ranobe)
+		.collect::<Vec<_>>();
+
+	loop {
+		let picked = pick_novel(&provider, &novels, "Choose light novel:", args).await?;
+		match picked {
+			Some((novel, metadata)) if provider.capabilities().toc => {
+				if pick_chapter(&provider, &novel, &metadata.title, args, profile).await? {
+					return Ok(());
+				}
+			}
+			_ => return Ok(()),
+		}
+	}
+}
+
+fn parse_letter(letter: &str) -> Result<char, String> {
+	match letter.chars().collect::<Vec<_>>()[..] {
+		[letter] if letter == '#' || letter.is_ascii_alphabetic() => {
+			Ok(letter.to_ascii_uppercase())
+		}
+		_ => Err("expected a letter from A to Z or #".to_string()),
+	}
+}
+
+/// Lets the user pick one of the novels of the provider whose title starts
+/// with `letter`, asking for it if `None`, then one of its chapters to read.
+///
+/// Works when the search of the provider is down or rate limited.
+async fn index(
+	letter: Option<char>,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let provider = registry::get(&args.provider)?;
+	if !provider.capabilities().index {
+		return Err(RanobeError::Unsupported(format!(
+			"{} has no index of its novels.",
+			provider.name()
+		)));
+	}
+
+	let letter = match letter {
+		Some(letter) => letter,
+		None => {
+			let letters = std::iter::once('#')
+				.chain('A'..='Z')
+				.map(String::from)
+				.collect::<Vec<_>>();
+			let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+				.with_prompt("Choose letter:")
+				.max_length(args.size)
+				.default(0)
+				.items(&letters)
+				.interact()?;
+			match selection {
+				Some(i) => letters[i].chars().next().unwrap(),
+				None => return Ok(()),
+			}
+		}
+	};
+
+	let novels = provider.get_by_letter(letter).await?;
+	profile.mark("fetch index");
+	if novels.is_empty() {
+		return Err(RanobeError::NotFound(format!(
+			"No novels under {} on {}.",
+			letter,
+			provider.name()
+		)));
+	}
+
+	let prompt = format!("Novels under {}:", letter);
+	loop {
+		match pick_novel(&provider, &novels, &prompt, args).await? {
+			Some((novel, metadata)) if provider.capabilities().toc => {
+				if pick_chapter(&provider, &novel, &metadata.title, args, profile).await? {
+					return Ok(());
+				}
+			}
+			_ => return Ok(()),
+		}
+	}
+}
+
+/// Prints `ranobe`, a novel or one of its chapters, with the metadata of
+/// the novel.
+fn print_novel<E: Listed>(ranobe: &E, metadata: &Metadata) {
+	println!("{}", ranobe.title());
+	println!("{}\n", ranobe.url());
+	for line in metadata_lines(metadata) {
+		println!("{}", line);
+	}
+}
+
+/// Restores the session left behind by a crash or a closed terminal.
+async fn resume_session(args: &Args, profile: &mut Profile) -> Result<(), RanobeError> {
+	let session = match Session::load(Session::default_path())? {
+		Some(session) => session,
+		None => {
+			return Err(RanobeError::NotFound(
+				"There is no session to resume.".to_string(),
+			))
+		}
+	};
+	profile.mark("load session");
+
+	match session.mode.as_str() {
+		"latest" => latest(args, profile, Some(session)).await,
+		mode => Err(RanobeError::Unsupported(format!(
+			"Sessions of {} cannot be resumed.",
+			mode
+		))),
+	}
+}
+
+/// Fetches at least `size` of the latest updates from `page` of the
+/// provider on, most recently updated first.
+///
+/// Providers that can list `size` entries at once are asked to, the others
+/// are paged through until there are enough. Entries that are not chapter
+/// updates are left out unless `all` is set.
+///
+/// Returns the page after the last one fetched along with the updates, for
+/// providers that can go to any page.
+async fn fetch_latest<P: RanobeScraper + Clone>(
+	provider: &P,
+	size: usize,
+	all: bool,
+	page: u32,
+) -> Result<(Vec<Chapter>, Option<u32>), RanobeError> {
+	if !provider.capabilities().latest {
+		return Err(RanobeError::Unsupported(format!(
+			"{} does not list its latest updates.",
+			provider.name()
+		)));
+	}
+
+	let shown = |ranobe: &Chapter| all || provider.is_update(ranobe);
+	// A copy starts at `page` however far the provider paged
+	let mut provider = provider.clone();
+	provider.set_page_size(size);
+	provider.set_latest_page(page);
+
+	let mut body = provider.get_latest().await?;
+	for _ in 1..MAX_LATEST_PAGES {
+		if body.iter().filter(|ranobe| shown(ranobe)).count() >= size {
+			break;
+		}
+		// The list is usable without the pages after the first
+		let page = match provider.get_latest().await {
+			Ok(page) => page,
+			Err(_) => break,
+		};
+		let before = body.len();
+		for ranobe in page {
+			// Entries move to the next page when novels update meanwhile
+			if !body.iter().any(|seen| seen.url == ranobe.url) {
+				body.push(ranobe);
+			}
+		}
+		if body.len() == before {
+			break;
+		}
+	}
+	// New chapters make the tables of contents of their novels stale
+	Tocs::open(Tocs::default_path())?.invalidate(&body, SystemTime::now())?;
+	body.retain(|ranobe| shown(ranobe));
+	// Entries without an update time keep their order
+	body.sort_by_key(|ranobe| std::cmp::Reverse(ranobe.published));
+	Ok((body, provider.latest_page()))
+}
+
+/// Shows the recently read novels with entries for the other modes.
+async fn resume(args: &Args, profile: &mut Profile) -> Result<(), RanobeError> {
+	let history = History::open(History::default_path())?;
+	let recent = history.recent(RESUME_SIZE);
+	if recent.is_empty() {
+		return latest(args, profile, None).await;
+	}
+
+	let mut items = recent
+		.iter()
+		.map(|read| format!("Continue {}: {}", read.novel, read.title))
+		.collect::<Vec<_>>();
+	items.push("Search".to_string());
+	items.push("Latest updates".to_string());
+
+	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+		.with_prompt("Continue reading:")
+		.max_length(args.size)
+		.default(0)
+		.items(&items)
+		.interact()?;
+	profile.mark("select from history");
+
+	match selection {
+		Some(i) if i < recent.len() => {
+			let read = &recent[i];
+			let name = providers::from_url(&read.url).unwrap_or(&args.provider);
+			let provider = registry::get(name)?;
+			read_chapter(&provider, &read.chapter(), &read.novel, args, profile).await
+		}
+		Some(i) if i == recent.len() => search(None, args, profile).await,
+		Some(_) => latest(args, profile, None).await,
+		None => Ok(()),
+	}
+}
+
+/// Opens the chapter whose url is on the clipboard.
+async fn read_clipboard_url(args: &Args, profile: &mut Profile) -> Result<(), RanobeError> {
+	let clipboard = read_clipboard()?;
+	let url = match Url::parse(&clipboard) {
+		Ok(url) => url,
+		Err(_) => return Err(RanobeError::Invalid(format!("{} is not a url.", clipboard))),
+	};
+	profile.mark("read clipboard");
+
+	let name = match providers::from_url(&url) {
+		Some(name) => name,
+		None => {
+			return Err(RanobeError::NotFound(format!(
+				"No provider serves {}.",
+				url.host_str().unwrap_or_default()
+			)))
+		}
+	};
+
+	// Chapter urls end in a slug like `chapter-536`
+	let slug = url
+		.path_segments()
+		.and_then(|mut segments| segments.next_back())
+		.unwrap_or_default();
+	let mut title = slug.replace('-', " ");
+	if let Some(first) = title.get(..1) {
+		title = first.to_uppercase() + &title[1..];
+	}
+	let chapter = Chapter::new(title, url.clone());
+
+	let provider = registry::get(name)?;
+	let novel = provider.get_metadata(url).await?.title;
+	profile.mark("fetch metadata");
+
+	read_chapter(&provider, &chapter, &novel, args, profile).await
+}
+
+/// Counts a chapter read for `spent` towards the daily goal.
+fn record_reading(spent: Duration) -> Result<(), RanobeError> {
+	let mut stats = Stats::open(Stats::default_path())?;
+	let today = Local::now().date_naive();
+	let goal = stats.goal();
+	let was_met = goal.is_met(&stats.day(today));
+
+	stats.record(today, spent)?;
+
+	let day = stats.day(today);
+	if goal.is_met(&day) {
+		if !was_met {
+			println!("Daily goal reached, {} days in a row.", stats.streak(today));
+		}
+	} else if let Some(chapters) = goal.chapters.filter(|&chapters| chapters > day.chapters) {
+		println!(
+			"{} more chapters to reach today's goal.",
+			chapters - day.chapters
+		);
+	}
+
+	Ok(())
+}
+
+/// Opens `chapter` in glow and records it in the history.
+async fn read_chapter<P: RanobeScraper + Sync>(
+	provider: &P,
+	chapter: &Chapter,
+	novel: &str,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let mut store = ChapterStore::open(ChapterStore::default_root())?;
+	let settings = reading_settings(novel, args)?;
+	let started = Instant::now();
+
+	match store.read(&chapter.url).ok().flatten() {
+		// Imported chapters have nothing to revalidate against
+		Some(cached) if chapter.url.scheme() == "file" => {
+			profile.mark("open cached chapter");
+			open_glow(
+				&settings.replace(&cached),
+				settings.wrap(),
+				settings.style_at(Local::now().time()),
+			)?;
+		}
+		Some(cached) => {
+			// Show the cached copy right away and revalidate it while the user reads
+			let (shown, wrap, style) = (
+				settings.clone(),
+				settings.wrap(),
+				settings.style_at(Local::now().time()).map(str::to_string),
+			);
+			let reader = task::spawn_blocking(move || {
+				open_glow(&shown.replace(&cached), wrap, style.as_deref())
+			});
+			let fresh = provider.get_text(chapter.url.clone()).await;
+			profile.mark("open cached chapter");
+			reader.await?;
+
+			if matches!(fresh, Err(RanobeError::NotFound(_))) {
+				let mut library = Library::open(Library::default_path())?;
+				mark_unavailable(&mut library, novel, provider.name())?;
+			}
+
+			// A broken fetch must not replace a good copy
+			if let Some(fresh) = fresh.ok().filter(|fresh| text::validate(fresh).is_ok()) {
+				if store.entry(&chapter.url).map(|entry| &entry.hash) != Some(&hash(&fresh)) {
+					hooks::put(&mut store, provider.name(), novel, chapter, &fresh)?;
+					eprintln!(
+						"{} was updated upstream, the new version will be shown next time.",
+						chapter.title
+					);
+				}
+			}
+		}
+		None => {
+			let text = match fetch_chapter(provider, chapter, args).await {
+				Ok(Some(text)) => text,
+				Ok(None) => return Ok(()),
+				Err(err @ RanobeError::NotFound(_)) => {
+					let mut library = Library::open(Library::default_path())?;
+					if mark_unavailable(&mut library, novel, provider.name())? {
+						return Ok(());
+					}
+					return Err(err);
+				}
+				Err(err) => return Err(err),
+			};
+			hooks::put(&mut store, provider.name(), novel, chapter, &text)?;
+			profile.mark("fetch chapter");
+			open_glow(
+				&settings.replace(&text),
+				settings.wrap(),
+				settings.style_at(Local::now().time()),
+			)?;
+		}
+	}
+
+	offer_jump(novel, chapter, args).await?;
+
+	History::open(History::default_path())?.record(novel, chapter)?;
+	let mut library = Library::open(Library::default_path())?;
+	library.read(novel, provider.name(), chapter)?;
+	record_reading(started.elapsed())?;
+
+	// Suggest the sequels once the last released chapter is read
+	if let Some(metadata) = library.get(novel).and_then(|novel| novel.metadata.as_ref()) {
+		let finished = metadata.chapters.is_some_and(|chapters| {
+			ChapterNumber::parse(&chapter.title).is_some_and(|n| n.number as usize >= chapters)
+		});
+		if finished {
+			for sequel in metadata
+				.related
+				.iter()
+				.filter(|related| related.is_sequel())
+			{
+				println!("{} continues in {}.", novel, sequel.title);
+			}
+		}
+	}
+
+	if settings.comments()
+		&& chapter.url.scheme() != "file"
+		&& Confirm::with_theme(&ColorfulTheme::default())
+			.with_prompt("Show top comments?")
+			.interact()?
+	{
+		let comments = provider.get_comments(chapter.url.clone()).await?;
+		if comments.is_empty() {
+			println!("No comments on {}.", chapter.title);
+		} else {
+			open_glow(
+				&Comment::to_markdown(&comments),
+				settings.wrap(),
+				settings.style_at(Local::now().time()),
+			)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Seconds a bulk operation backs off for, one after the other, when a
+/// provider throttles it without saying how long to wait.
+const BACKOFFS: [u64; 4] = [30, 60, 120, 300];
+
+/// Runs `fetch` again while the provider throttles it, waiting with a
+/// countdown in between, so a bulk operation resumes instead of failing.
+///
+/// Gives up with the error of the provider after `BACKOFFS.len()` waits.
+async fn backing_off<T, F, Fut>(mut fetch: F) -> Result<T, RanobeError>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, RanobeError>>,
+{
+	let mut backoffs = BACKOFFS.iter();
+	loop {
+		match fetch().await {
+			Err(err @ RanobeError::RateLimited { retry_after, .. }) => {
+				let wait = match (retry_after, backoffs.next()) {
+					(_, None) => return Err(err),
+					(Some(wait), _) => wait,
+					(None, Some(&secs)) => Duration::from_secs(secs),
+				};
+				countdown(&err.to_string(), wait).await?;
+			}
+			result => return result,
+		}
+	}
+}
+
+/// Waits for `wait`, counting the seconds left down on stderr after
+/// `reason`.
+///
+/// Plain mode only says how long the wait is, a line every second would
+/// flood screen readers.
+async fn countdown(reason: &str, wait: Duration) -> std::io::Result<()> {
+	let term = Term::stderr();
+	if plain() || !term.is_term() {
+		term.write_line(&format!(
+			"{}. Resuming in {} seconds.",
+			reason,
+			wait.as_secs()
+		))?;
+		task::sleep(wait).await;
+		return Ok(());
+	}
+
+	for left in (1..=wait.as_secs()).rev() {
+		term.clear_line()?;
+		term.write_str(&format!("{}, resuming in {}s", reason, left))?;
+		task::sleep(Duration::from_secs(1)).await;
+	}
+	term.clear_line()
+}
+
+/// Marks `novel` as removed by `provider` if it is in the library, telling
+/// the user how to keep reading it, and returns whether it is.
+fn mark_unavailable(
+	library: &mut Library,
+	novel: &str,
+	provider: &str,
+) -> Result<bool, RanobeError> {
+	let unavailable = match library.get(novel) {
+		Some(entry) => entry.unavailable,
+		None => return Ok(false),
+	};
+
+	if !unavailable {
+		library.set_unavailable(novel, true)?;
+		println!(
+			"{} is no longer available on {}, its downloaded chapters stay readable.",
+			novel, provider
+		);
+		println!(
+			"Read it from another provider with `ranobe migrate \"{}\" --to <provider>`.",
+			novel
+		);
+	}
+
+	Ok(true)
+}
+
+/// Fetches the text of `chapter`, letting the user retry, try a mirror of
+/// the site, open it in the browser or show it anyway if it does not look
+/// like a chapter.
+///
+/// The mirror tried is remembered as the working one of the provider.
+/// Returns `None` if the chapter should not be opened.
+async fn fetch_chapter<P: RanobeScraper + Sync>(
+	provider: &P,
+	chapter: &Chapter,
+	args: &Args,
+) -> Result<Option<String>, RanobeError> {
+	let mut url = chapter.url.clone();
+	let mut relogged = false;
+	loop {
+		let text = match provider.get_text(url.clone()).await {
+			// The session expired, log in again with the saved credentials
+			Err(err @ RanobeError::Unauthorized(_)) if !relogged => {
+				relogged = true;
+				match keyring::get(provider.name()).ok().flatten() {
+					Some(credentials) => {
+						provider.login(&credentials).await?;
+						continue;
+					}
+					None => return Err(err),
+				}
+			}
+			text => text?,
+		};
+		let problem = match text::validate(&text) {
+			Ok(()) => return Ok(Some(text)),
+			// Local books have no better copy to retry for
+			Err(_) if url.scheme() == "file" => return Ok(Some(text)),
+			Err(problem) => problem,
+		};
+
+		eprintln!("{} did not load properly, {}.", chapter.title, problem);
+		let mut working = Mirrors::open(Mirrors::default_path())?;
+		let mirror = mirrors::next(
+			provider.name(),
+			providers::hosts(provider.name()),
+			working.working(provider.name()),
+			&url,
+		);
+		let mut actions = vec!["Retry".to_string()];
+		if mirror.is_some() {
+			actions.push("Try a mirror".to_string());
+		}
+		actions.extend(["Open in browser".to_string(), "Show anyway".to_string()]);
+		let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+			.with_prompt("What now?")
+			.max_length(args.size)
+			.default(0)
+			.items(&actions)
+			.interact()?;
+
+		match selection.map(|i| actions[i].as_str()) {
+			Some("Retry") => continue,
+			Some("Try a mirror") => {
+				if let Some((mirror, moved)) = mirror {
+					eprintln!("Trying {}.", mirror);
+					working.set_working(provider.name(), &mirror)?;
+					url = moved;
+				}
+				continue;
+			}
+			Some("Open in browser") => {
+				open_browser(url.as_str())?;
+				return Ok(None);
+			}
+			Some(_) => return Ok(Some(text)),
+			None => return Ok(None),
+		}
+	}
+}
+
+/// Returns `title` or lets the user pick one of `titles` if it is `None`,
+/// matching them under their `aliases` too.
+fn pick_title(
+	title: Option<&str>,
+	titles: Vec<String>,
+	prompt: &str,
+	preview: Option<Preview<'_, String>>,
+	aliases: Option<Aliases<'_, String>>,
+	args: &Args,
+) -> std::io::Result<Option<String>> {
+	if let Some(title) = title {
+		return Ok(Some(title.to_string()));
+	}
+
+	let theme = ColorfulTheme::default();
+	let mut select = FuzzySelect::with_theme(&theme);
+	select
+		.with_prompt(prompt)
+		.with_history("library")
+		.max_length(args.size)
+		.default(0)
+		.items(&titles);
+	if let Some(preview) = preview {
+		select.with_preview(preview);
+	}
+	if let Some(aliases) = aliases {
+		select.with_aliases(aliases);
+	}
+	let selection = select.interact()?;
+
+	Ok(selection.map(|i| titles[i].clone()))
+}
+
+async fn open(novel: Option<&str>, args: &Args, profile: &mut Profile) -> Result<(), RanobeError> {
+	let library = Library::open(Library::default_path())?;
+	// Novels can be opened under any of their titles
+	let novel = novel.map(|novel| library.get(novel).map_or(novel, |known| &known.title));
+
+	let titles = library
+		.novels()
+		.iter()
+		.map(|novel| novel.title.clone())
+		.collect();
+	let mut preview = |title: &String| -> Vec<String> {
+		let novel = match library.get(title) {
+			Some(novel) => novel,
+			None => return vec![],
+		};
+
+		let mut lines = vec![];
+		if novel.unavailable {
+			lines.push(format!(
+				"Removed from {}, only downloaded chapters can be read.",
+				novel.provider
+			));
+		}
+		if let Some(rating) = novel.rating {
+			lines.push(format!("Your rating: {}", stars(rating)));
+		}
+		if let Some(progress) = &novel.progress {
+			lines.push(format!("Progress: {}", progress));
+		}
+		if let Some(metadata) = &novel.metadata {
+			lines.extend(metadata_lines(metadata));
+		}
+		lines
+	};
+	let mut aliases = |title: &String| library.get(title).map_or(vec![], library::Novel::aliases);
+	let title = match pick_title(
+		novel,
+		titles,
+		"Choose novel to read:",
+		Some(&mut preview),
+		Some(&mut aliases),
+		args,
+	)? {
+		Some(title) => title,
+		None => return Ok(()),
+	};
+
+	let chapters = ChapterStore::open(ChapterStore::default_root())?.chapters(&title);
+	// Progress from another provider, or kept by title only, matches no url
+	// of the downloaded chapters and is found by its chapter number instead
+	let progress = library.get(&title).and_then(|novel| {
+		novel
+			.progress_url
+			.as_ref()
+			.map(providers::canonical_url)
+			.and_then(|url| chapters.iter().position(|chapter| chapter.url == url))
+			.or_else(|| {
+				let last = chapter::reconcile(novel.progress.as_ref()?, &chapters)?;
+				chapters.iter().position(|chapter| chapter.url == last.url)
+			})
+	});
+	// Chapters up to the last one read count as read
+	let read = chapters
+		.iter()
+		.take(progress.map_or(0, |progress| progress + 1))
+		.map(|chapter| &chapter.url)
+		.collect::<HashSet<_>>();
+	let last_read = progress.map(|progress| &chapters[progress].url);
+
+	let volumes = library
+		.get(&title)
+		.map_or(&[][..], |novel| novel.volumes.as_slice());
+	let groups = chapter::by_volume(volumes, &chapters)
+		.into_iter()
+		.map(|(volume, chapters)| {
+			let items = chapters
+				.into_iter()
+				.map(|chapter| Marked {
+					read: read.contains(&chapter.url),
+					item: chapter,
+				})
+				.collect::<Vec<_>>();
+			(volume, items)
+		})
+		.collect::<Vec<_>>();
+	// Volumes read through start collapsed, unless the last chapter read
+	// closes them
+	let mut collapsed = groups
+		.iter()
+		.map(|(_, items)| {
+			items.iter().all(|chapter| chapter.read)
+				&& !items
+					.iter()
+					.any(|chapter| Some(&chapter.item.url) == last_read)
+		})
+		.collect::<Vec<_>>();
+
+	let mut state = State::default();
+	let mut header = None;
+	loop {
+		let rows = Grouped::list(&groups, &collapsed);
+		let default = rows.iter().position(|row| match row {
+			Grouped::Header { group, .. } => Some(*group) == header,
+			Grouped::Item(chapter) => header.is_none() && Some(&chapter.item.url) == last_read,
+		});
+
+		let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+			.with_prompt("Choose chapter to read:")
+			.with_history("chapter filter")
+			.with_state(&mut state)
+			.max_length(args.size)
+			.default(default.unwrap_or(0))
+			.items(&rows)
+			.interact()?;
+
+		let chapter = match selection.map(|i| &rows[i]) {
+			Some(Grouped::Header { group, .. }) => {
+				let group = *group;
+				collapsed[group] = !collapsed[group];
+				// The header reads differently once toggled
+				if let (Some(title), items) = &groups[group] {
+					state.selected = Some(Grouped::<Marked<Chapter>>::header(
+						title,
+						items.len(),
+						collapsed[group],
+					));
+				}
+				header = Some(group);
+				continue;
+			}
+			Some(Grouped::Item(chapter)) => chapter.item.clone(),
+			None => return Ok(()),
+		};
+
+		// Imported chapters are read from the store, whatever the provider
+		let name = providers::from_url(&chapter.url).unwrap_or(&args.provider);
+		let provider = registry::get(name)?;
+		return read_chapter(&provider, &chapter, &title, args, profile).await;
+	}
+}
+
+fn parse_replacement(replacement: &str) -> Result<Replacement, String> {
+	match replacement.split_once('=') {
+		Some((from, to)) if !from.is_empty() => Ok(Replacement {
+			from: from.to_string(),
+			to: to.to_string(),
+		}),
+		_ => Err("expected `from=to`".to_string()),
+	}
+}
+
+fn settings(
+	novel: &str,
+	wrap: Option<u16>,
+	style: &Option<String>,
+	comments: Option<bool>,
+	replacements: &[Replacement],
+	reset: bool,
+) -> Result<(), RanobeError> {
+	let mut library = Library::open(Library::default_path())?;
+
+	let mut settings = match library.get(novel) {
+		Some(novel) if !reset => novel.settings.clone(),
+		Some(_) => Settings::default(),
+		None => return Err(not_in_library(&library, novel)),
+	};
+
+	settings.wrap = wrap.or(settings.wrap);
+	settings.style = style.clone().or(settings.style);
+	settings.comments = comments.or(settings.comments);
+	settings.replacements.extend_from_slice(replacements);
+
+	let lines = settings_lines(&settings);
+	if lines.is_empty() {
+		println!("{} follows the configuration.", novel);
+	} else {
+		println!("Settings of {}:", novel);
+		for line in lines {
+			println!("{}", line);
+		}
+	}
+	library.set_settings(novel, settings)?;
+
+	Ok(())
+}
+
+/// Formats the reading settings a novel sets itself.
+fn settings_lines(settings: &Settings) -> Vec<String> {
+	let mut lines = vec![];
+	if let Some(wrap) = settings.wrap {
+		lines.push(format!("Wrap: {}", wrap));
+	}
+	if let Some(style) = &settings.style {
+		lines.push(format!("Style: {}", style));
+	}
+	if let Some(night_style) = &settings.night_style {
+		lines.push(format!("Night style: {}", night_style));
+	}
+	if let (Some(start), Some(end)) = (&settings.night_start, &settings.night_end) {
+		lines.push(format!("Night: {} to {}", start, end));
+	}
+	if let Some(comments) = settings.comments {
+		lines.push(format!("Comments: {}", if comments { "on" } else { "off" }));
+	}
+	if let Some(hyphenate) = settings.hyphenate {
+		lines.push(format!(
+			"Hyphenate: {}",
+			if hyphenate { "on" } else { "off" }
+		));
+	}
+	for replacement in &settings.replacements {
+		lines.push(format!(
+			"Replace: {} with {}",
+			replacement.from, replacement.to
+		));
+	}
+	lines
+}
+
+/// Returns the reading settings of `novel`, the global configuration
+/// overridden by the novel, overridden by the command line.
+fn reading_settings(novel: &str, args: &Args) -> std::io::Result<Settings> {
+	let global = Config::load(Config::default_path())?.reading;
+	let library = Library::open(Library::default_path())?;
+
+	let settings = match library.get(novel) {
+		Some(novel) => global.merge(&novel.settings),
+		None => global,
+	};
+
+	Ok(settings.merge(&Settings {
+		wrap: args.wrap,
+		..Settings::default()
+	}))
+}
+
+fn delete(novel: Option<&str>, args: &Args) -> Result<(), RanobeError> {
+	let mut library = Library::open(Library::default_path())?;
+	let novel = novel.map(|novel| library.get(novel).map_or(novel, |known| &known.title));
+
+	let titles = library
+		.novels()
+		.iter()
+		.map(|novel| novel.title.clone())
+		.collect();
+	let mut aliases = |title: &String| library.get(title).map_or(vec![], library::Novel::aliases);
+	let title = match pick_title(
+		novel,
+		titles,
+		"Choose novel to delete:",
+		None,
+		Some(&mut aliases),
+		args,
+	)? {
+		Some(title) => title,
+		None => return Ok(()),
+	};
+	if !confirm(&format!("Move {} to the trash?", title))? {
+		return Ok(());
+	}
+
+	let novel = match library.remove(&title)? {
+		Some(novel) => novel,
+		None => return Err(not_in_library(&library, &title)),
+	};
+	let chapters = ChapterStore::open(ChapterStore::default_root())?.remove_novel(&title)?;
+
+	let count = chapters.len();
+	Trash::open(Trash::default_path())?.push(novel, chapters)?;
+	println!(
+		"Moved {} and {} chapters to the trash, run `ranobe library restore` to undo.",
+		title, count
+	);
+
+	Ok(())
+}
+
+fn restore(novel: Option<&str>, args: &Args) -> Result<(), RanobeError> {
+	let mut trash = Trash::open(Trash::default_path())?;
+
+	let titles = trash
+		.novels()
+		.iter()
+		.map(|trashed| trashed.novel.title.clone())
+		.collect();
+	let title = match pick_title(novel, titles, "Choose novel to restore:", None, None, args)? {
+		Some(title) => title,
+		None => return Ok(()),
+	};
+
+	let trashed = match trash.take(&title)? {
+		Some(trashed) => trashed,
+		None => {
+			let titles = trash
+				.novels()
+				.iter()
+				.map(|trashed| trashed.novel.title.as_str());
+			return Err(RanobeError::NotFound(suggest::did_you_mean(
+				&format!("{} is not in the trash.", title),
+				&suggest::similar(&title, titles),
+			)));
+		}
+	};
+
+	let count = trashed.chapters.len();
+	ChapterStore::open(ChapterStore::default_root())?.restore(trashed.chapters)?;
+	Library::open(Library::default_path())?.insert(trashed.novel)?;
+	println!("Restored {} and {} chapters.", title, count);
+
+	Ok(())
+}
+
+fn import(path: &Path) -> Result<(), RanobeError> {
+	let mut store = ChapterStore::open(ChapterStore::default_root())?;
+	let mut library = Library::open(Library::default_path())?;
+
+	for novel in import::read_path(path)? {
+		for (title, url, text) in &novel.chapters {
+			store.put(url, &novel.title, title, text)?;
+		}
+
+		if library.get(&novel.title).is_none() {
+			library.insert(library::Novel {
+				title: novel.title.clone(),
+				provider: LOCAL_PROVIDER.to_string(),
+				progress: None,
+				progress_url: None,
+				metadata: None,
+				settings: Settings::default(),
+				rating: None,
+				unavailable: false,
+				volumes: vec![],
+			})?;
+		}
+
+		println!(
+			"Imported {} with {} chapters.",
+			novel.title,
+			novel.chapters.len()
+		);
+	}
+
+	Ok(())
+}
+
+/// Moves `novel` to the provider `to`, carrying its progress over to the
+/// chapter of the same number there.
+async fn migrate(novel: &str, to: &str) -> Result<(), RanobeError> {
+	let mut library = Library::open(Library::default_path())?;
+
+	let pinned = match library.get(novel) {
+		Some(pinned) => pinned.clone(),
+		None => return Err(not_in_library(&library, novel)),
+	};
+
+	let to = registry::resolve(to)?;
+	if pinned.provider == to {
+		println!("{} is already read from {}.", pinned.title, to);
+		return Ok(());
+	}
+
+	let provider = registry::get(&to)?;
+	let chapter = match &pinned.progress {
+		Some(progress) => remap_progress(&provider, &pinned, progress).await?,
+		None => None,
+	};
+
+	library.pin(&pinned.title, &to)?;
+	match (chapter, &pinned.progress) {
+		(Some(chapter), _) => {
+			library.read(&pinned.title, &to, &chapter)?;
+			println!(
+				"{} is now read from {}, continuing after {}.",
+				pinned.title, to, chapter.title
+			);
+		}
+		(None, Some(progress)) => println!(
+			"{} is now read from {}, {} was not found there so only its title is kept.",
+			pinned.title, to, progress
+		),
+		(None, None) => println!("{} is now read from {}.", pinned.title, to),
+	}
+
+	Ok(())
+}
+
+/// Returns the chapter of `novel` on `provider` matching `progress`, the
+/// title of the last chapter read elsewhere, by its number.
+///
+/// The novel is searched for under each of its titles, `None` is returned
+/// when it is not found or the provider can not list its chapters.
+async fn remap_progress(
+	provider: &registry::Provider,
+	novel: &library::Novel,
+	progress: &str,
+) -> Result<Option<Chapter>, RanobeError> {
+	let mut found = None;
+	for title in std::iter::once(novel.title.clone()).chain(novel.aliases()) {
+		let results = match provider.search(&title).await {
+			Ok(results) => results,
+			Err(RanobeError::Unsupported(_)) => return Ok(None),
+			Err(err) => return Err(err),
+		};
+		found = results
+			.into_iter()
+			.find(|result| novel.is_titled(&result.title));
+		if found.is_some() {
+			break;
+		}
+	}
+
+	let found = match found {
+		Some(found) => found,
+		None => return Ok(None),
+	};
+	let chapters = match chapters_of(provider, &found).await {
+		Ok(chapters) => chapters,
+		Err(RanobeError::Unsupported(_)) => return Ok(None),
+		Err(err) => return Err(err),
+	};
+
+	Ok(chapter::reconcile(progress, &chapters).cloned())
+}
+
+/// Fetches the metadata and volumes of `novel`, or of every novel with
+/// `all`, again.
+///
+/// Novels whose provider tells when they get new chapters are skipped while
+/// the validators of that page stay the same, unless `force` is set.
+async fn refresh_metadata(novel: Option<&str>, all: bool, force: bool) -> Result<(), RanobeError> {
+	let mut library = Library::open(Library::default_path())?;
+	let mut freshness = Freshness::open(Freshness::default_path())?;
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+	let novels = library
+		.novels()
+		.iter()
+		.filter(|entry| all || novel.is_some_and(|novel| entry.is_titled(novel)))
+		.cloned()
+		.collect::<Vec<_>>();
+	if novels.is_empty() {
+		return Err(match novel {
+			Some(novel) => not_in_library(&library, novel),
+			None => RanobeError::Invalid("Pass the title of a novel or --all.".to_string()),
+		});
+	}
+
+	for (i, novel) in novels.iter().enumerate() {
+		let url = match &novel.progress_url {
+			Some(url) => url.clone(),
+			None => {
+				println!("Skipping {}, it has no chapter read yet.", novel.title);
+				continue;
+			}
+		};
+		let provider = match registry::get(&novel.provider) {
+			Ok(provider) => provider,
+			Err(err) => {
+				println!("Skipping {}: {}", novel.title, err);
+				continue;
+			}
+		};
+
+		if i > 0 {
+			task::sleep(provider.request_interval()).await;
+		}
+
+		// Pages that fail to answer a HEAD request are fetched whole
+		let page = provider.freshness_url(&url);
+		let validators = match &page {
+			Some(page) => backing_off(|| fetch_validators(client, page.clone()))
+				.await
+				.ok(),
+			None => None,
+		};
+		if let (Some(page), Some(validators)) = (&page, &validators) {
+			let unchanged = freshness
+				.get(page)
+				.is_some_and(|previous| validators.unchanged_since(previous));
+			if unchanged && !force && !novel.unavailable {
+				println!("{} is unchanged.", novel.title);
+				continue;
+			}
+		}
+
+		match backing_off(|| provider.get_metadata(url.clone())).await {
+			Ok(metadata) => {
+				library.set_metadata(&novel.title, metadata)?;
+				if let (Some(page), Some(validators)) = (&page, validators) {
+					freshness.set(page, validators)?;
+				}
+				if novel.unavailable {
+					library.set_unavailable(&novel.title, false)?;
+				}
+				match backing_off(|| provider.get_volumes(url.clone())).await {
+					Ok(volumes) => library.set_volumes(&novel.title, volumes)?,
+					Err(err) => {
+						eprintln!("Failed to fetch the volumes of {}: {}", novel.title, err)
+					}
+				}
+				println!("Refreshed {}.", novel.title);
+			}
+			Err(RanobeError::NotFound(_)) => {
+				if novel.unavailable {
+					println!(
+						"{} is still unavailable on {}.",
+						novel.title,
+						provider.name()
+					);
+				}
+				mark_unavailable(&mut library, &novel.title, provider.name())?;
+			}
+			Err(err) => eprintln!("Failed to refresh {}: {}", novel.title, err),
+		}
+	}
+
+	Ok(())
+}
+
+fn update(novel: Option<&str>, dir: &Option<PathBuf>, yes: bool) -> Result<(), RanobeError> {
+	let store = ChapterStore::open(ChapterStore::default_root())?;
+	let dir = dir.clone().unwrap_or_else(export::default_dir);
+
+	let mut novels = store
+		.entries()
+		.map(|(_, entry)| entry.novel.as_str())
+		.filter(|title| novel.is_none_or(|novel| novel == *title))
+		.collect::<Vec<_>>();
+	novels.sort_unstable();
+	novels.dedup();
+
+	let mut changes = vec![];
+	for novel in novels {
+		let plan = export::plan_markdown(&store, novel, &dir)?;
+
+		let count = |kind| plan.iter().filter(|change| change.kind == kind).count();
+		println!(
+			"{}: {} added, {} changed, {} unchanged",
+			novel,
+			count(ChangeKind::Added),
+			count(ChangeKind::Changed),
+			count(ChangeKind::Unchanged)
+		);
+		for change in plan
+			.iter()
+			.filter(|change| change.kind == ChangeKind::Changed)
+		{
+			println!("  changed: {}", change.path.display());
+		}
+
+		changes.extend(plan);
+	}
+
+	// Changed files might hold edits made by hand
+	let overwrites = changes
+		.iter()
+		.any(|change| change.kind == ChangeKind::Changed);
+	if overwrites && !yes && !confirm("Overwrite the changed files?")? {
+		return Ok(());
+	}
+
+	export::apply(&changes)?;
+
+	Ok(())
+}
+
+/// Exports `novel`, or every novel with downloaded chapters, in `format`
+/// with up to `jobs` novels at once.
+///
+/// With `by_volume`, novels whose volumes are known are exported as an epub
+/// per volume.
+fn export(
+	novel: Option<&str>,
+	format: export::Format,
+	dir: &Option<PathBuf>,
+	jobs: u16,
+	by_volume: bool,
+) -> Result<(), RanobeError> {
+	if by_volume && format != export::Format::Epub {
+		return Err(RanobeError::Invalid(
+			"Only epubs can be exported by volume.".to_string(),
+		));
+	}
+
+	let store = ChapterStore::open(ChapterStore::default_root())?;
+	let library = Library::open(Library::default_path())?;
+	let dir = dir.clone().unwrap_or_else(export::default_dir);
+	let manifest = Mutex::new(export::Manifest::open(&dir)?);
+
+	let mut novels = store
+		.entries()
+		.map(|(_, entry)| entry.novel.as_str())
+		.collect::<Vec<_>>();
+	novels.sort_unstable();
+	novels.dedup();
+	let downloaded = novels.clone();
+	novels.retain(|title| novel.is_none_or(|novel| novel == *title));
+	if novels.is_empty() {
+		return Err(RanobeError::NotFound(match novel {
+			Some(novel) => suggest::did_you_mean(
+				&format!("No downloaded chapters of {}.", novel),
+				&suggest::similar(novel, downloaded),
+			),
+			None => "No downloaded chapters.".to_string(),
+		}));
+	}
+
+	let total = novels.len();
+	let next = AtomicUsize::new(0);
+	let done = AtomicUsize::new(0);
+	let failed = AtomicUsize::new(0);
+	std::thread::scope(|scope| {
+		for _ in 0..usize::from(jobs).min(total) {
+			scope.spawn(|| {
+				while let Some(novel) = novels.get(next.fetch_add(1, Ordering::Relaxed)) {
+					let volumes = library
+						.get(novel)
+						.map_or(&[][..], |novel| novel.volumes.as_slice());
+					let result = if by_volume && !volumes.is_empty() {
+						export_volumes(&store, &manifest, novel, volumes, &dir)
+					} else {
+						let path = format.path(novel, &dir);
+						let digest = export::digest(&store, novel);
+						let current = manifest.lock().unwrap().is_current(&path, &digest);
+
+						let result = match format {
+							_ if current => Ok("unchanged"),
+							export::Format::Markdown => export::plan_markdown(&store, novel, &dir)
+								.and_then(|plan| export::apply(&plan))
+								.map(|_| "exported"),
+							export::Format::Epub => {
+								export::write_epub(&store, novel, &path).map(|_| "exported")
+							}
+						};
+						result.inspect(|_| manifest.lock().unwrap().set(path, digest))
+					};
+
+					let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+					match result {
+						Ok(outcome) => println!("[{}/{}] {}: {}", done, total, novel, outcome),
+						Err(err) => {
+							failed.fetch_add(1, Ordering::Relaxed);
+							eprintln!("[{}/{}] {}: {}", done, total, novel, err);
+						}
+					}
+				}
+			});
+		}
+	});
+
+	manifest.into_inner().unwrap().save()?;
+
+	match failed.into_inner() {
+		0 => Ok(()),
+		failed => Err(RanobeError::Io(io::Error::other(format!(
+			"Failed to export {} of {} novels.",
+			failed, total
+		)))),
+	}
+}
+
+/// Exports the chapters of `novel` as an epub per volume into `dir`,
+/// skipping the volumes unchanged since their last export.
+fn export_volumes(
+	store: &ChapterStore,
+	manifest: &Mutex<export::Manifest>,
+	novel: &str,
+	volumes: &[Volume],
+	dir: &Path,
+) -> std::io::Result<&'static str> {
+	let mut outcome = "unchanged";
+	for (volume, chapters) in chapter::by_volume(volumes, &store.chapters(novel)) {
+		let volume = volume.unwrap_or_else(|| "Other chapters".to_string());
+		let path = export::volume_path(novel, &volume, dir);
+		let digest = export::digest_chapters(store, &chapters);
+		if manifest.lock().unwrap().is_current(&path, &digest) {
+			continue;
+		}
+
+		let book = format!("{}: {}", novel, volume);
+		export::write_chapters(store, &book, &chapters, &path)?;
+		manifest.lock().unwrap().set(path, digest);
+		outcome = "exported";
+	}
+
+	Ok(outcome)
+}
+
+/// Returns the passphrase of the library from the configuration, or asks
+/// for it with `prompt`.
+fn passphrase(config: &Config, prompt: &str) -> Result<String, RanobeError> {
+	if let Some(passphrase) = config.passphrase()? {
+		return Ok(passphrase);
+	}
+
+	let term = Term::stderr();
+	term.write_str(prompt)?;
+	Ok(term.read_secure_line()?)
+}
+
+/// Encrypts every file holding the reading list or chapters, or with `off`
+/// decrypts them again.
+fn encrypt(off: bool, config: &Config) -> Result<(), RanobeError> {
+	let files = crypt::covered_files()?;
+
+	match (off, crypt::key()?) {
+		(false, Some(_)) => Err(RanobeError::Invalid(
+			"The library is already encrypted.".to_string(),
+		)),
+		(false, None) => {
+			let passphrase = passphrase(config, "New passphrase: ")?;
+			if config.passphrase()?.is_none()
+				&& passphrase != self::passphrase(config, "Repeat the passphrase: ")?
+			{
+				return Err(RanobeError::Invalid("The passphrases differ.".to_string()));
+			}
+			if passphrase.is_empty() {
+				return Err(RanobeError::Invalid(
+					"The passphrase can not be empty.".to_string(),
+				));
+			}
+
+			// Files are readable either way until every one is converted
+			let key = crypt::create(&passphrase)?;
+			crypt::convert(&files, None, Some(&key))?;
+			println!("Encrypted {} files.", files.len());
+			Ok(())
+		}
+		(true, Some(key)) => {
+			crypt::convert(&files, Some(&key), None)?;
+			std::fs::remove_file(crypt::key_path())?;
+			crypt::set_key(None);
+			println!("Decrypted {} files.", files.len());
+			Ok(())
+		}
+		(true, None) => Err(RanobeError::Invalid(
+			"The library is not encrypted.".to_string(),
+		)),
+	}
+}
+
+/// Removes the blobs no chapter refers to, files left by interrupted writes
+/// and the manifest entries of exports that were deleted.
+fn collect_garbage(dry_run: bool) -> Result<(), RanobeError> {
+	let store = ChapterStore::open(ChapterStore::default_root())?;
+	let trash = Trash::open(Trash::default_path())?;
+	let export_dir = export::default_dir();
+	let mut manifest = export::Manifest::open(&export_dir)?;
+
+	let dirs = [
+		data_dir(),
+		store.root().to_path_buf(),
+		store.root().join("blobs"),
+		export_dir.clone(),
+	];
+	let dirs = dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+	let garbage = gc::find(&store, &trash, &dirs, &export_dir)?;
+	let forgotten = manifest.prune();
+
+	for file in &garbage {
+		println!("{}: {}", file.reason, file.path.display());
+	}
+
+	if dry_run {
+		let size = garbage.iter().map(|file| file.size).sum();
+		println!(
+			"Would remove {} files and reclaim {}.",
+			garbage.len(),
+			format_size(size)
+		);
+		return Ok(());
+	}
+
+	// Expired novels were dropped when the trash was opened
+	trash.purge()?;
+	if forgotten > 0 {
+		manifest.save()?;
+		println!("Forgot {} deleted exports.", forgotten);
+	}
+	let reclaimed = gc::remove(&garbage)?;
+	println!(
+		"Removed {} files and reclaimed {}.",
+		garbage.len(),
+		format_size(reclaimed)
+	);
+
+	Ok(())
+}
+
+/// Formats `bytes` with a binary unit, like `1.5 MiB`.
+fn format_size(bytes: u64) -> String {
+	let units = ["B", "KiB", "MiB", "GiB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < units.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	match unit {
+		0 => format!("{} B", bytes),
+		unit => format!("{:.1} {}", size, units[unit]),
+	}
+}
+
+/// Asks to confirm a destructive action, unless confirming is turned off
+/// in the configuration.
+fn confirm(prompt: &str) -> std::io::Result<bool> {
+	if !Config::load(Config::default_path())?.confirm() {
+		return Ok(true);
+	}
+
+	Confirm::with_theme(&ColorfulTheme::default())
+		.with_prompt(prompt)
+		.interact()
+}
+
+async fn verify(novel: Option<&str>, args: &Args) -> Result<(), RanobeError> {
+	let mut store = ChapterStore::open(ChapterStore::default_root())?;
+
+	let corrupted = store.corrupted(novel);
+	if corrupted.is_empty() {
+		println!("All downloaded chapters are intact.");
+		return Ok(());
+	}
+
+	// Imported chapters have no source to download them from again
+	let (imported, corrupted): (Vec<_>, Vec<_>) = corrupted
+		.into_iter()
+		.partition(|url| url.scheme() == "file");
+	for url in &imported {
+		let entry = store.entry(url).unwrap();
+		println!(
+			"Skipping {} of {}, import it again to repair it",
+			entry.title, entry.novel
+		);
+	}
+
+	// Chapters of a provider are downloaded together
+	let mut by_provider: Vec<(&str, Vec<Url>)> = vec![];
+	for url in corrupted {
+		let name = providers::from_url(&url).unwrap_or(&args.provider);
+		match by_provider
+			.iter_mut()
+			.find(|(provider, _)| *provider == name)
+		{
+			Some((_, urls)) => urls.push(url),
+			None => by_provider.push((name, vec![url])),
+		}
+	}
+
+	// A chapter failing does not keep the others from being repaired
+	let mut failed = 0;
+	for (name, urls) in by_provider {
+		let provider = match registry::get(name) {
+			Ok(provider) => provider,
+			Err(err) => {
+				println!("Failed to re-download {} chapters: {}", urls.len(), err);
+				failed += urls.len();
+				continue;
+			}
+		};
+		println!("Re-downloading {} chapters from {}", urls.len(), name);
+
+		let texts = provider.get_texts(&urls, DOWNLOAD_CONCURRENCY).await;
+		for (url, text) in urls.iter().zip(texts) {
+			let entry = store.entry(url).unwrap().clone();
+			let text = match text {
+				// Throttled chapters are fetched again once the provider lets them
+				Err(RanobeError::RateLimited { .. }) => {
+					backing_off(|| provider.get_text(url.clone())).await
+				}
+				text => text,
+			};
+			match text {
+				Ok(text) => {
+					println!("Re-downloaded {}", entry.title);
+					let chapter = Chapter::new(entry.title.clone(), url.clone());
+					hooks::put(&mut store, name, &entry.novel, &chapter, &text)?;
+				}
+				Err(err) => {
+					println!("Failed to re-download {}: {}", entry.title, err);
+					failed += 1;
+				}
+			}
+		}
+	}
+
+	if failed > 0 {
+		println!(
+			"{} chapters could not be re-downloaded, run verify again later.",
+			failed
+		);
+	}
+
+	Ok(())
+}
+
+fn parse_daily(time: &str) -> Result<NaiveTime, String> {
+	remind::parse_time(time).ok_or_else(|| "expected a time as `HH:MM`".to_string())
+}
+
+fn remind(
+	novel: Option<&str>,
+	daily: Option<NaiveTime>,
+	off: bool,
+	notify_due: bool,
+	json: bool,
+) -> Result<(), RanobeError> {
+	let mut reminders = Reminders::open(Reminders::default_path())?;
+	let library = Library::open(Library::default_path())?;
+
+	if notify_due {
+		for reminder in reminders.take_due(Local::now())? {
+			let body = match library
+				.get(&reminder.novel)
+				.and_then(|n| n.progress.as_ref())
+			{
+				Some(progress) => format!("Continue from {}.", progress),
+				None => "Time to continue reading.".to_string(),
+			};
+			notify(&reminder.novel, &body)?;
+		}
+		let mut unlocks = Unlocks::open(Unlocks::default_path())?;
+		for unlock in unlocks.take_due(SystemTime::now())? {
+			notify(
+				&unlock.novel,
+				&format!("{} is free to read.", unlock.chapter.title),
+			)?;
+		}
+		return Ok(());
+	}
+
+	match (novel, daily) {
+		(Some(novel), _) if off => {
+			if !reminders.remove(novel)? {
+				println!("{} has no reminder.", novel);
+			}
+		}
+		(Some(novel), Some(at)) => {
+			if library.get(novel).is_none() {
+				return Err(not_in_library(&library, novel));
+			}
+			reminders.set(novel, at)?;
+			println!(
+				"Reminding to continue {} every day at {}.",
+				novel,
+				at.format("%H:%M")
+			);
+		}
+		(Some(_), None) => {
+			return Err(RanobeError::Invalid(
+				"Pass --daily <HH:MM> or --off.".to_string(),
+			))
+		}
+		(None, _) if json => {
+			let unlocks = Unlocks::open(Unlocks::default_path())?;
+			print_json(&json!({
+				"reminders": reminders.reminders(),
+				"unlocks": unlocks.unlocks(),
+			}))?;
+		}
+		(None, _) => {
+			for reminder in reminders.reminders() {
+				println!("{}  {}", reminder.at.format("%H:%M"), reminder.novel);
+			}
+			for unlock in Unlocks::open(Unlocks::default_path())?.unlocks() {
+				println!(
+					"{}  {}: {}",
+					DateTime::<Local>::from(unlock.at).format("%b %-d %H:%M"),
+					unlock.novel,
+					unlock.chapter.title
+				);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the error for the missing playlist `name`, suggesting the
+/// playlists it is close to.
+fn no_playlist(playlists: &Playlists, name: &str) -> RanobeError {
+	let names = playlists
+		.playlists()
+		.iter()
+		.map(|playlist| playlist.name.as_str());
+	RanobeError::NotFound(suggest::did_you_mean(
+		&format!("There is no playlist {}.", name),
+		&suggest::similar(name, names),
+	))
+}
+
+/// Returns the title of the chapter at `url`, as it was downloaded or as
+/// its book names it for local chapters.
+fn chapter_title(url: &Url) -> Result<Option<String>, RanobeError> {
+	if let Some(entry) = ChapterStore::open(ChapterStore::default_root())?.entry(url) {
+		return Ok(Some(entry.title.clone()));
+	}
+
+	let path = match url.to_file_path() {
+		Ok(path) if url.scheme() == "file" => path,
+		_ => return Ok(None),
+	};
+	Ok(import::read_path(&path)?
+		.into_iter()
+		.flat_map(|book| book.chapters)
+		.find(|(_, chapter, _)| chapter == url)
+		.map(|(title, _, _)| title))
+}
+
+/// Adds the chapter at `url` to `playlist`.
+///
+/// The chapter is read from the provider serving `url`, the one of
+/// `--provider` for sites no built-in provider serves.
+async fn add_to_playlist(
+	playlist: &str,
+	url: &Url,
+	novel: Option<&str>,
+	title: Option<&str>,
+	args: &Args,
+) -> Result<(), RanobeError> {
+	let name = match url.scheme() {
+		"file" => local::NAME.to_string(),
+		_ => match providers::from_url(url) {
+			Some(name) => name.to_string(),
+			None => registry::resolve(&args.provider)?,
+		},
+	};
+
+	let novel = match novel {
+		Some(novel) => novel.to_string(),
+		None => registry::get(&name)?.get_metadata(url.clone()).await?.title,
+	};
+	let title = match title {
+		Some(title) => title.to_string(),
+		None => match chapter_title(url)? {
+			Some(title) => title,
+			None => url
+				.path_segments()
+				.and_then(|mut segments| segments.next_back())
+				.filter(|segment| !segment.is_empty())
+				.unwrap_or(url.as_str())
+				.to_string(),
+		},
+	};
+
+	let mut playlists = Playlists::open(Playlists::default_path())?;
+	playlists.push(
+		playlist,
+		playlists::Entry {
+			novel: novel.clone(),
+			provider: name,
+			title: title.clone(),
+			url: url.clone(),
+		},
+	)?;
+	println!("Added {} of {} to {}.", title, novel, playlist);
+
+	Ok(())
+}
+
+/// Lists the playlists with how far they were read, or the chapters of
+/// `playlist` marking the next one to read.
+fn list_playlists(playlist: Option<&str>, json: bool) -> Result<(), RanobeError> {
+	let playlists = Playlists::open(Playlists::default_path())?;
+
+	let name = match playlist {
+		Some(name) => name,
+		None if json => return print_json(playlists.playlists()),
+		None => {
+			for playlist in playlists.playlists() {
+				println!(
+					"{}  {}/{}",
+					playlist.name,
+					playlist.position,
+					playlist.entries.len()
+				);
+			}
+			return Ok(());
+		}
+	};
+
+	let playlist = playlists
+		.get(name)
+		.ok_or_else(|| no_playlist(&playlists, name))?;
+	if json {
+		return print_json(playlist);
+	}
+	for (i, entry) in playlist.entries.iter().enumerate() {
+		let marker = if i == playlist.position { ">" } else { " " };
+		println!("{} {}. {}: {}", marker, i + 1, entry.novel, entry.title);
+	}
+
+	Ok(())
+}
+
+/// Removes the `number`th chapter of `playlist`, counted from one, or the
+/// whole playlist without a number.
+fn remove_from_playlist(playlist: &str, number: Option<usize>) -> Result<(), RanobeError> {
+	let mut playlists = Playlists::open(Playlists::default_path())?;
+	if playlists.get(playlist).is_none() {
+		return Err(no_playlist(&playlists, playlist));
+	}
+
+	if !playlists.remove(playlist, number.map(|number| number - 1))? {
+		return Err(RanobeError::NotFound(format!(
+			"{} has no chapter {}.",
+			playlist,
+			number.unwrap_or_default()
+		)));
+	}
+	match number {
+		Some(number) => println!("Removed chapter {} of {}.", number, playlist),
+		None => println!("Removed {}.", playlist),
+	}
+
+	Ok(())
+}
+
+/// Reads `playlist` chapter after chapter from where it was left, or from
+/// its `from`th chapter, saving the position after each one.
+async fn play(
+	playlist: &str,
+	from: Option<usize>,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let mut playlists = Playlists::open(Playlists::default_path())?;
+	if playlists.get(playlist).is_none() {
+		return Err(no_playlist(&playlists, playlist));
+	}
+	if let Some(from) = from {
+		playlists.seek(playlist, from - 1)?;
+	}
+
+	loop {
+		let entry = match playlists
+			.get(playlist)
+			.and_then(|playlist| playlist.current())
+		{
+			Some(entry) => entry.clone(),
+			None => {
+				println!("{} is finished.", playlist);
+				return Ok(());
+			}
+		};
+
+		let provider = registry::get(&entry.provider)?;
+		read_chapter(&provider, &entry.chapter(), &entry.novel, args, profile).await?;
+		playlists.advance(playlist)?;
+
+		let next = match playlists
+			.get(playlist)
+			.and_then(|playlist| playlist.current())
+		{
+			Some(next) => next,
+			None => {
+				println!("{} is finished.", playlist);
+				return Ok(());
+			}
+		};
+		if !Confirm::with_theme(&ColorfulTheme::default())
+			.with_prompt(format!("Continue with {}: {}?", next.novel, next.title))
+			.interact()?
+		{
+			return Ok(());
+		}
+	}
+}
+
+/// Lets the user pick a paragraph of the chapter at `url`, or of the last
+/// read chapter, and copies it with a citation.
+async fn quote(url: Option<&Url>, args: &Args, profile: &mut Profile) -> Result<(), RanobeError> {
+	let provider = registry::get(provider_of(url, args)?)?;
+	quote_from(&provider, url, args, profile).await
+}
+
+/// Returns the name of the provider serving the chapter at `url`, or the
+/// last read chapter if `url` is `None`.
+fn provider_of<'a>(url: Option<&Url>, args: &'a Args) -> std::io::Result<&'a str> {
+	let last = History::open(History::default_path())?
+		.recent(1)
+		.first()
+		.map(|read| read.url.clone());
+
+	Ok(url
+		.or(last.as_ref())
+		.and_then(providers::from_url)
+		.unwrap_or(&args.provider))
+}
+
+/// Returns the novel and the chapter at `url`, or the last read chapter if
+/// `url` is `None`.
+async fn chapter_at<P: RanobeScraper + Sync>(
+	provider: &P,
+	url: Option<&Url>,
+) -> Result<(String, Chapter), RanobeError> {
+	Ok(match url {
+		Some(url) => match ChapterStore::open(ChapterStore::default_root())?.entry(url) {
+			Some(entry) => (
+				entry.novel.clone(),
+				Chapter::new(entry.title.clone(), url.clone()),
+			),
+			None => {
+				let novel = provider.get_metadata(url.clone()).await?.title;
+				let title = url
+					.path_segments()
+					.and_then(|mut segments| segments.next_back())
+					.unwrap_or_default()
+					.to_string();
+				(novel, Chapter::new(title, url.clone()))
+			}
+		},
+		None => match History::open(History::default_path())?.recent(1).first() {
+			Some(read) => (read.novel.clone(), read.chapter()),
+			None => {
+				return Err(RanobeError::NotFound(
+					"Nothing was read yet, pass the url of a chapter.".to_string(),
+				))
+			}
+		},
+	})
+}
+
+async fn quote_from<P: RanobeScraper + Sync>(
+	provider: &P,
+	url: Option<&Url>,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let (novel, chapter) = chapter_at(provider, url).await?;
+
+	let paragraphs = provider.get_paragraphs(chapter.url.clone()).await?;
+	profile.mark("fetch paragraphs");
+
+	let items = paragraphs
+		.iter()
+		.map(|paragraph| paragraph.text.clone())
+		.collect::<Vec<_>>();
+	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+		.with_prompt("Paragraph to quote:")
+		.max_length(args.size)
+		.default(0)
+		.items(&items)
+		.interact()?;
+
+	if let Some(i) = selection {
+		let citation = paragraphs[i].cite(i, &novel, &chapter);
+		println!("{}", citation);
+		write_clipboard(&citation)?;
+	}
+
+	Ok(())
+}
+
+/// Returns the text of the chapter at `url`, downloaded or fetched from
+/// `provider`.
+async fn chapter_text<P: RanobeScraper + Sync>(
+	provider: &P,
+	url: &Url,
+) -> Result<String, RanobeError> {
+	match ChapterStore::open(ChapterStore::default_root())?.get(url)? {
+		Some(text) => Ok(text),
+		None => provider.get_text(url.clone()).await,
+	}
+}
+
+/// Lets the user pick a paragraph of the chapter at `url`, or of the last
+/// read chapter, to jump back to after reading its novel.
+async fn bookmark(
+	url: Option<&Url>,
+	args: &Args,
+	profile: &mut Profile,
+) -> Result<(), RanobeError> {
+	let provider = registry::get(provider_of(url, args)?)?;
+	let (novel, chapter) = chapter_at(&provider, url).await?;
+
+	let text = chapter_text(&provider, &chapter.url).await?;
+	profile.mark("fetch chapter");
+
+	let paragraphs = text::paragraphs(&text)
+		.map(str::to_string)
+		.collect::<Vec<_>>();
 	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-		.with_prompt("Choose chapter of light novel to read:")
+		.with_prompt("Paragraph to bookmark:")
 		.max_length(args.size)
 		.default(0)
-		.items(&body[..])
+		.items(&paragraphs)
+		.interact()?;
+
+	if let Some(i) = selection {
+		let mut bookmarks = Bookmarks::open(Bookmarks::default_path())?;
+		if bookmarks.add(&novel, &chapter, i, &paragraphs[i])? {
+			println!(
+				"Bookmarked paragraph {} of {}, jump to it with ' after reading a chapter of {}.",
+				i + 1,
+				chapter.title,
+				novel
+			);
+		} else {
+			println!(
+				"Paragraph {} of {} is already bookmarked.",
+				i + 1,
+				chapter.title
+			);
+		}
+	}
+
+	Ok(())
+}
+
+/// Offers the bookmarks of `novel` once `chapter` is closed, as glow keeps
+/// its keys to itself: `'` lists them to jump to, any other key goes on.
+///
+/// Nothing is asked for novels without bookmarks.
+async fn offer_jump(novel: &str, chapter: &Chapter, args: &Args) -> Result<(), RanobeError> {
+	let term = Term::stderr();
+	if !term.is_term() {
+		return Ok(());
+	}
+
+	loop {
+		let bookmarks = Bookmarks::open(Bookmarks::default_path())?;
+		let bookmarks = bookmarks.of(novel);
+		if bookmarks.is_empty() {
+			return Ok(());
+		}
+
+		let asked = if plain() {
+			term.write_line("Type ' to jump to a bookmark, Enter to go on:")?;
+			term.read_line()?.trim() == "'"
+		} else {
+			term.write_str("Press ' to jump to a bookmark, any other key to go on.")?;
+			let key = term.read_key()?;
+			term.clear_line()?;
+			key == Key::Char('\'')
+		};
+		if !asked {
+			return Ok(());
+		}
+
+		jump(novel, &bookmarks, chapter, args).await?;
+	}
+}
+
+/// Lets the user pick one of `bookmarks` of `novel`, in reading order from
+/// the first one of `chapter`, and shows its chapter from the bookmarked
+/// paragraph on.
+async fn jump(
+	novel: &str,
+	bookmarks: &[&Bookmark],
+	chapter: &Chapter,
+	args: &Args,
+) -> Result<(), RanobeError> {
+	let items = bookmarks
+		.iter()
+		.map(|bookmark| {
+			format!(
+				"{}, paragraph {}: {}",
+				bookmark.title,
+				bookmark.paragraph + 1,
+				bookmark.excerpt
+			)
+		})
+		.collect::<Vec<_>>();
+	let url = providers::canonical_url(&chapter.url);
+	let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+		.with_prompt("Jump to bookmark:")
+		.max_length(args.size)
+		.default(
+			bookmarks
+				.iter()
+				.position(|bookmark| bookmark.url == url)
+				.unwrap_or(0),
+		)
+		.items(&items)
 		.interact()?;
+	let bookmark = match selection {
+		Some(i) => bookmarks[i],
+		None => return Ok(()),
+	};
+
+	// Imported chapters are read from the store, whatever the provider
+	let provider = registry::get(providers::from_url(&bookmark.url).unwrap_or(&args.provider))?;
+	let text = chapter_text(&provider, &bookmark.url).await?;
+	let from = text::paragraphs(&text)
+		.skip(bookmark.paragraph)
+		.collect::<Vec<_>>()
+		.join("\n\n");
+
+	let settings = reading_settings(novel, args)?;
+	open_glow(
+		&settings.replace(&from),
+		settings.wrap(),
+		settings.style_at(Local::now().time()),
+	)?;
+
+	Ok(())
+}
+
+/// Shows the chapters at `left` and `right` side by side, with paragraphs of
+/// the same index starting on the same row.
+async fn split(left: &Url, right: &Url, profile: &mut Profile) -> Result<(), RanobeError> {
+	let store = ChapterStore::open(ChapterStore::default_root())?;
+
+	let mut texts = vec![];
+	for url in [left, right] {
+		let text = match (store.get(url).ok().flatten(), providers::from_url(url)) {
+			(Some(cached), _) => cached,
+			(None, Some(name)) => {
+				let provider = registry::get(name)?;
+				provider.get_text(url.clone()).await?
+			}
+			(None, None) => {
+				return Err(RanobeError::NotFound(format!(
+					"No provider serves {}.",
+					url.host_str().unwrap_or_default()
+				)))
+			}
+		};
+		texts.push(text);
+	}
+	profile.mark("fetch chapters");
+
+	let width = termsize::get().map_or(DEFAULT_WRAP as usize * 2, |size| size.cols as usize);
+	let hyphenate = Config::load(Config::default_path())?.reading.hyphenate();
+	open_pager(&text::side_by_side(&texts[0], &texts[1], width, hyphenate))?;
+
+	Ok(())
+}
+
+async fn random(genre: Option<&str>, args: &Args) -> Result<(), RanobeError> {
+	let provider = registry::get(&args.provider)?;
+	if !provider.capabilities().catalogue {
+		return Err(RanobeError::Unsupported(format!(
+			"{} has no catalogue to pick from.",
+			provider.name()
+		)));
+	}
+
+	let catalogue = provider.get_catalogue(genre, 1).await?;
+	let ranobe = match catalogue.choose(&mut rand::thread_rng()) {
+		Some(ranobe) => ranobe,
+		None => {
+			return Err(RanobeError::NotFound(match genre {
+				Some(genre) => suggest::did_you_mean(
+					&format!("No novels found for the genre {}.", genre),
+					&suggest::similar(genre, known_genres()?.iter().map(String::as_str)),
+				),
+				None => "No novels found.".to_string(),
+			}))
+		}
+	};
+
+	let metadata = provider.get_metadata(ranobe.url.clone()).await?;
+	print_novel(ranobe, &metadata);
+
+	Ok(())
+}
+
+async fn trending(args: &Args) -> Result<(), RanobeError> {
+	let mut rankings = vec![];
+	for name in providers::available() {
+		let provider = registry::get(name)?;
+		if !provider.capabilities().ranking {
+			continue;
+		}
+		let ranking = provider.get_ranking().await?;
+		if !ranking.is_empty() {
+			rankings.push((name, ranking));
+		}
+	}
+
+	let mut trends = trending::aggregate(&rankings);
+	trends.truncate(args.size);
+
+	let snapshot = Snapshot::open(Snapshot::default_path())?;
+	snapshot.compare(&mut trends);
+	if args.json {
+		print_json(&trends)?;
+	} else {
+		for (rank, trend) in trends.iter().enumerate() {
+			let movement = match trend.movement {
+				Movement::New => "new".to_string(),
+				Movement::Up(n) => format!("▲{}", n),
+				Movement::Down(n) => format!("▼{}", n),
+				Movement::Same => "-".to_string(),
+			};
+			println!(
+				"{:>3} {:>4}  {} ({})",
+				rank + 1,
+				movement,
+				trend.ranobe.title,
+				trend.providers.join(", ")
+			);
+		}
+	}
+
+	Snapshot::save(Snapshot::default_path(), &trends)?;
+
+	Ok(())
+}
+
+/// Checks `providers`, every one if empty, all at once against the counts
+/// in `fixtures` and fails when one of them broke, or drifts with `strict`.
+///
+/// With `update`, keeps the counts found to check against from now on.
+async fn diagnose(
+	providers: &[String],
+	update: bool,
+	fixtures: Option<&Path>,
+	strict: bool,
+	json: bool,
+) -> Result<(), RanobeError> {
+	let path = fixtures
+		.map(Path::to_path_buf)
+		.unwrap_or_else(Fixtures::default_path);
+	let mut fixtures = Fixtures::open(&path)?;
+	let names = if providers.is_empty() {
+		registry::names()
+	} else {
+		providers.to_vec()
+	};
+
+	let mut checks = vec![];
+	for name in names {
+		let provider = registry::get(&name)?;
+		let expected = fixtures.get(provider.name()).cloned().unwrap_or_default();
+		checks.push(task::spawn(doctor::check(provider, expected)));
+	}
+	let mut reports = vec![];
+	for check in checks {
+		reports.push(check.await);
+	}
+
+	if json {
+		print_json(&reports)?;
+	} else {
+		for report in &reports {
+			println!("{:<20} {}", report.provider, report.health);
+			for check in &report.checks {
+				let expected = check
+					.expected
+					.map_or("-".to_string(), |expected| expected.to_string());
+				println!(
+					"  {:<18} {:>5} / {:<5} {}",
+					check.probe, check.found, expected, check.health
+				);
+			}
+			if let Some(err) = &report.error {
+				println!("  {}", err);
+			}
+		}
+	}
+
+	if update {
+		for report in reports.iter().filter(|report| report.error.is_none()) {
+			fixtures.set(&report.provider, report.counts());
+		}
+		fixtures.save()?;
+	}
+
+	let threshold = if strict {
+		Health::Drifting
+	} else {
+		Health::Failed
+	};
+	let failing = reports
+		.iter()
+		.filter(|report| report.health >= threshold)
+		.map(|report| report.provider.as_str())
+		.collect::<Vec<_>>();
+	if !failing.is_empty() {
+		return Err(RanobeError::Parse(format!(
+			"{} failed the check.",
+			failing.join(", ")
+		)));
+	}
+
+	Ok(())
+}
+
+/// Renders a rating from 1 to 5 as stars.
+/// Logs in to `provider`, asking for the credentials, and keeps them in the
+/// system keyring so an expired session is renewed on its own.
+///
+/// With `logout`, forgets the session and the credentials instead.
+async fn login(provider: Option<&str>, logout: bool, args: &Args) -> Result<(), RanobeError> {
+	let provider = registry::get(provider.unwrap_or(&args.provider))?;
+	if !provider.capabilities().login {
+		return Err(RanobeError::Unsupported(format!(
+			"{} has no accounts to log in with.",
+			provider.name()
+		)));
+	}
+
+	if logout {
+		cookies::clear(providers::hosts(provider.name()))?;
+		keyring::delete(provider.name())?;
+		println!("Logged out of {}.", provider.name());
+		return Ok(());
+	}
 
-	let text = match selection {
-		Some(i) => provider.get_text(body[i].url.clone()).await?,
-		None => "".to_string(),
+	let term = Term::stderr();
+	term.write_str("Username: ")?;
+	let username = term.read_line()?;
+	term.write_str("Password: ")?;
+	let password = term.read_secure_line()?;
+	let credentials = Credentials {
+		username: username.trim().to_string(),
+		password,
 	};
 
-	open_glow(text, args.wrap)?;
+	provider.login(&credentials).await?;
+	println!("Logged in to {}.", provider.name());
+
+	// The session works without them, it just can not be renewed
+	if let Err(err) = keyring::store(provider.name(), &credentials) {
+		eprintln!("The credentials were not saved: {}", err);
+	}
+
+	Ok(())
+}
+
+/// Backs the library up into `path`, or restores the backup `at` of it, the
+/// latest one without a name, or lists its backups.
+fn back_up(
+	path: &Path,
+	restore: bool,
+	at: Option<&str>,
+	list: bool,
+	json: bool,
+) -> Result<(), RanobeError> {
+	if list {
+		let names = backup::list(path)?;
+		if json {
+			return print_json(&names);
+		}
+		if names.is_empty() {
+			println!("There are no backups in {}.", path.display());
+		}
+		for name in names {
+			println!("{}", name);
+		}
+		return Ok(());
+	}
+
+	if !restore {
+		match backup::backup(path)? {
+			Some(backup) => println!(
+				"Backed up {} files as {}, {} of them changed since the last backup.",
+				backup.files, backup.name, backup.added
+			),
+			None => println!("Nothing changed since the last backup."),
+		}
+		return Ok(());
+	}
+
+	if let Some(at) = at {
+		let names = backup::list(path)?;
+		if !names.iter().any(|name| name == at) {
+			return Err(RanobeError::NotFound(suggest::did_you_mean(
+				&format!("There is no backup {} in {}.", at, path.display()),
+				&suggest::similar(at, names.iter().map(String::as_str)),
+			)));
+		}
+	}
+	let (name, files) = backup::restore(path, at)?;
+	println!("Restored {} files of the backup {}.", files, name);
+
+	Ok(())
+}
+
+/// Exports the cookies and credentials of the logged in providers, or only
+/// of `provider`, to `path`.
+fn export_auth(path: &Path, provider: Option<&str>) -> Result<(), RanobeError> {
+	let names = match provider {
+		Some(provider) => vec![registry::resolve(provider)?],
+		None => providers::available()
+			.into_iter()
+			.map(String::from)
+			.collect(),
+	};
+
+	let mut accounts = vec![];
+	for name in names {
+		let cookies = cookies::export(providers::hosts(&name))?;
+		let credentials = keyring::get(&name).ok().flatten();
+		if !cookies.is_empty() || credentials.is_some() {
+			accounts.push(Account {
+				provider: name,
+				cookies,
+				credentials,
+			});
+		}
+	}
+	if accounts.is_empty() {
+		return Err(RanobeError::NotFound(match provider {
+			Some(provider) => format!("{} is not logged in.", provider),
+			None => "No provider is logged in.".to_string(),
+		}));
+	}
+
+	let passphrase = auth_passphrase("Passphrase of the export: ")?;
+	if passphrase != auth_passphrase("Repeat the passphrase: ")? {
+		return Err(RanobeError::Invalid("The passphrases differ.".to_string()));
+	}
+	if passphrase.is_empty() {
+		return Err(RanobeError::Invalid(
+			"The passphrase can not be empty.".to_string(),
+		));
+	}
+
+	auth::export(path, &passphrase, &accounts)?;
+	println!(
+		"Exported the sessions of {} to {}.",
+		accounts
+			.iter()
+			.map(|account| account.provider.as_str())
+			.collect::<Vec<_>>()
+			.join(", "),
+		path.display()
+	);
+
+	Ok(())
+}
+
+/// Imports the cookies and credentials exported to `path` on another
+/// machine.
+fn import_auth(path: &Path) -> Result<(), RanobeError> {
+	let accounts = auth::import(path, &auth_passphrase("Passphrase of the export: ")?)?;
+
+	for account in &accounts {
+		cookies::import(account.cookies.clone())?;
+		if let Some(credentials) = &account.credentials {
+			if let Err(err) = keyring::store(&account.provider, credentials) {
+				eprintln!(
+					"The credentials of {} were not saved: {}",
+					account.provider, err
+				);
+			}
+		}
+	}
+	println!(
+		"Imported the sessions of {}.",
+		accounts
+			.iter()
+			.map(|account| account.provider.as_str())
+			.collect::<Vec<_>>()
+			.join(", ")
+	);
+
+	Ok(())
+}
+
+/// Asks for the passphrase sealing exported sessions, which is not the one
+/// of the library.
+fn auth_passphrase(prompt: &str) -> Result<String, RanobeError> {
+	let term = Term::stderr();
+	term.write_str(prompt)?;
+	Ok(term.read_secure_line()?)
+}
+
+/// Lists the providers with their aliases and what they can serve.
+fn list_providers(json: bool) -> Result<(), RanobeError> {
+	if json {
+		let providers = registry::names()
+			.into_iter()
+			.map(|name| {
+				let capabilities = registry::get(&name)
+					.ok()
+					.map(|provider| provider.capabilities().names());
+				json!({
+					"name": name,
+					"aliases": registry::aliases(&name),
+					"capabilities": capabilities,
+				})
+			})
+			.collect::<Vec<_>>();
+		return print_json(&providers);
+	}
+
+	for name in registry::names() {
+		let aliases = registry::aliases(&name);
+		let mut line = if aliases.is_empty() {
+			name.clone()
+		} else {
+			format!("{} ({})", name, aliases.join(", "))
+		};
+		// Providers that fail to load, like broken definitions, are listed
+		// without what they serve
+		if let Ok(provider) = registry::get(&name) {
+			line = format!("{}: {}", line, provider.capabilities().names().join(", "));
+		}
+		println!("{}", line);
+	}
+	Ok(())
+}
+
+fn stars(rating: u8) -> String {
+	let rating = rating.min(5) as usize;
+	format!("{}{}", "★".repeat(rating), "☆".repeat(5 - rating))
+}
+
+/// Prints `value` as JSON, in place of a list with `--json`.
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), RanobeError> {
+	let json = serde_json::to_string_pretty(value)?;
+	// Pipelines like `| head` may stop reading before the end
+	match writeln!(io::stdout().lock(), "{}", json) {
+		Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+		result => Ok(result?),
+	}
+}
+
+fn list(json: bool) -> Result<(), RanobeError> {
+	let library = Library::open(Library::default_path())?;
+	if json {
+		return print_json(library.novels());
+	}
+
+	for novel in library.novels() {
+		let mut line = format!("{} ({})", novel.title, novel.provider);
+		if novel.unavailable {
+			line.push_str(" [unavailable]");
+		}
+		if let Some(progress) = &novel.progress {
+			line.push_str(&format!(" - {}", progress));
+		}
+		if let Some(rating) = novel.rating {
+			line.push_str(&format!(" {}", stars(rating)));
+		}
+		if let Some(rating) = novel.metadata.as_ref().and_then(|m| m.rating) {
+			line.push_str(&format!(" [{:.1}/5]", rating));
+		}
+		println!("{}", line);
+	}
+
+	Ok(())
+}
+
+fn list_libraries(json: bool) -> Result<(), RanobeError> {
+	let current = library();
+	if json {
+		let names = libraries()?;
+		let libraries = iter::once(None)
+			.chain(names.iter().map(|name| Some(name.as_str())))
+			.map(|name| {
+				json!({
+					"name": name,
+					"current": current.as_deref() == name,
+				})
+			})
+			.collect::<Vec<_>>();
+		return print_json(&libraries);
+	}
+	let mark = |name: Option<&str>| if current.as_deref() == name { "*" } else { " " };
+
+	println!("{} (default)", mark(None));
+	for name in libraries()? {
+		println!("{} {}", mark(Some(&name)), name);
+	}
+
+	Ok(())
+}
+
+fn rate(novel: &str, rating: Option<u8>) -> Result<(), RanobeError> {
+	let mut library = Library::open(Library::default_path())?;
+	if library.get(novel).is_none() {
+		return Err(not_in_library(&library, novel));
+	}
+
+	library.rate(novel, rating)?;
+
+	Ok(())
+}
+
+async fn related(novel: &str, json: bool) -> Result<(), RanobeError> {
+	let library = Library::open(Library::default_path())?;
+
+	let entry = match library.get(novel) {
+		Some(entry) => entry,
+		None => return Err(not_in_library(&library, novel)),
+	};
+
+	let metadata = match (&entry.metadata, &entry.progress_url) {
+		(Some(metadata), _) => metadata.clone(),
+		(None, Some(url)) => match registry::get(&entry.provider) {
+			Ok(provider) => provider.get_metadata(url.clone()).await?,
+			// Imported novels have no provider to ask
+			Err(_) => Metadata::default(),
+		},
+		_ => Metadata::default(),
+	};
+
+	if json {
+		return print_json(&metadata.related);
+	}
+	if metadata.related.is_empty() {
+		println!("{} has no known related series.", novel);
+		return Ok(());
+	}
+
+	for related in &metadata.related {
+		let mut line = format!("{}: {}", related.relation, related.title);
+		if library.get(&related.title).is_some() {
+			line.push_str(" (in library)");
+		} else if let Some(url) = &related.url {
+			line.push_str(&format!(" <{}>", url));
+		}
+		println!("{}", line);
+	}
+
+	Ok(())
+}
+
+fn stats(chapters: Option<u32>, minutes: Option<u32>, no_goal: bool) -> Result<(), RanobeError> {
+	let mut stats = Stats::open(Stats::default_path())?;
+
+	if no_goal || chapters.is_some() || minutes.is_some() {
+		let goal = Goal { chapters, minutes };
+		stats.set_goal(goal)?;
+		if let Some(progress) = stats.progress(Local::now().date_naive()) {
+			println!("Daily {}.", progress);
+		}
+		return Ok(());
+	}
+
+	let today = Local::now().date_naive();
+	match stats.progress(today) {
+		Some(progress) => println!("Today: {}", progress),
+		None => println!("No daily goal, set one with --chapters or --minutes."),
+	}
+	let day = stats.day(today);
+	println!(
+		"Read today: {} chapters in {} minutes",
+		day.chapters,
+		day.seconds / 60
+	);
+
+	if stats.goal().is_set() {
+		println!("Streak: {} days", stats.streak(today));
+		println!("Longest streak: {} days", stats.longest_streak());
+		if let Some(rate) = stats.completion_rate(today, 30) {
+			println!("Goal met on {:.0}% of the last 30 days", rate * 100.0);
+		}
+	}
 
 	Ok(())
 }
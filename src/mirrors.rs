@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use surf::Url;
+
+use crate::utils::{data_dir, write_atomic};
+
+lazy_static! {
+	/// Mirrors added in the configuration, by provider.
+	static ref EXTRA: RwLock<HashMap<String, Vec<String>>> = RwLock::new(HashMap::new());
+}
+
+/// Adds the mirrors of the configuration, tried after the built-in ones.
+pub fn set_extra(mirrors: HashMap<String, Vec<String>>) {
+	*EXTRA.write().unwrap() = mirrors;
+}
+
+/// Returns the mirrors of `provider` in the order they are tried: the one
+/// that worked last, then the built-in ones, then the configured ones.
+pub fn candidates(provider: &str, builtin: &[&str], working: Option<&str>) -> Vec<String> {
+	let extra = EXTRA.read().unwrap();
+	let mut candidates = working
+		.into_iter()
+		.chain(builtin.iter().copied())
+		.chain(
+			extra
+				.get(provider)
+				.into_iter()
+				.flatten()
+				.map(String::as_str),
+		)
+		.map(|host| host.trim().to_lowercase())
+		.filter(|host| !host.is_empty())
+		.collect::<Vec<_>>();
+
+	let mut seen = vec![];
+	candidates.retain(|host| {
+		let new = !seen.contains(host);
+		seen.push(host.clone());
+		new
+	});
+	candidates
+}
+
+/// Returns `url` moved to the mirror of `provider` tried after `current`,
+/// or after the one `url` is on, going around to the first one.
+///
+/// Subdomains like `www` are kept. Returns `None` if `url` is on none of
+/// the mirrors or there is no other one, along with the mirror otherwise.
+pub fn next(
+	provider: &str,
+	builtin: &[&str],
+	current: Option<&str>,
+	url: &Url,
+) -> Option<(String, Url)> {
+	let candidates = candidates(provider, builtin, None);
+	let host = url.host_str()?;
+	let (subdomain, on) = candidates.iter().find_map(|mirror| {
+		let subdomain = host.strip_suffix(mirror.as_str())?;
+		(subdomain.is_empty() || subdomain.ends_with('.')).then_some((subdomain, mirror))
+	})?;
+	if candidates.len() < 2 {
+		return None;
+	}
+
+	let current = current.unwrap_or(on);
+	let position = candidates.iter().position(|mirror| mirror == current);
+	let mirror = candidates[position.map_or(0, |position| position + 1) % candidates.len()].clone();
+	let mut url = url.clone();
+	url.set_host(Some(&format!("{}{}", subdomain, mirror)))
+		.ok()?;
+	Some((mirror, url))
+}
+
+/// The mirror each provider was last reached on, for providers whose site
+/// keeps moving to new domains.
+///
+/// Like the statistics, they are kept in plain text as they say nothing
+/// about what is read.
+pub struct Mirrors {
+	path: PathBuf,
+	working: HashMap<String, String>,
+}
+
+impl Mirrors {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("mirrors.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let working = match fs::read_to_string(&path) {
+			Ok(working) => serde_json::from_str(&working)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, working })
+	}
+
+	/// Returns the mirror `provider` was last reached on.
+	pub fn working(&self, provider: &str) -> Option<&str> {
+		self.working.get(provider).map(String::as_str)
+	}
+
+	/// Remembers that `provider` was reached on `host`.
+	pub fn set_working(&mut self, provider: &str, host: &str) -> Result<()> {
+		self.working.insert(provider.to_string(), host.to_string());
+
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		write_atomic(&self.path, serde_json::to_string(&self.working)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn tries_the_working_mirror_first() {
+		let builtin = ["a.com", "b.com"];
+		assert_eq!(candidates("test", &builtin, None), vec!["a.com", "b.com"]);
+		assert_eq!(
+			candidates("test", &builtin, Some("b.com")),
+			vec!["b.com", "a.com"]
+		);
+
+		set_extra(HashMap::from([(
+			"test".to_string(),
+			vec!["C.com".to_string(), "a.com".to_string()],
+		)]));
+		assert_eq!(
+			candidates("test", &builtin, Some("c.com")),
+			vec!["c.com", "a.com", "b.com"]
+		);
+		assert_eq!(candidates("other", &builtin, None), vec!["a.com", "b.com"]);
+	}
+
+	#[test]
+	fn moves_to_the_next_mirror() {
+		let builtin = ["a.com", "b.com", "c.com"];
+		let url = Url::parse("https://www.a.com/novel/chapter-1").unwrap();
+		let moved =
+			|current| next("cycle", &builtin, current, &url).map(|(_, url)| url.to_string());
+
+		assert_eq!(
+			moved(None).as_deref(),
+			Some("https://www.b.com/novel/chapter-1")
+		);
+		assert_eq!(
+			moved(Some("b.com")).as_deref(),
+			Some("https://www.c.com/novel/chapter-1")
+		);
+		assert_eq!(
+			moved(Some("c.com")).as_deref(),
+			Some("https://www.a.com/novel/chapter-1")
+		);
+		assert_eq!(next("cycle", &builtin[..1], None, &url), None);
+		let elsewhere = Url::parse("https://d.com/novel").unwrap();
+		assert_eq!(next("cycle", &builtin, None, &elsewhere), None);
+	}
+}
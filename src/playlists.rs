@@ -0,0 +1,184 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::crypt;
+use crate::providers::{Chapter, Listed};
+use crate::utils::data_dir;
+
+/// A chapter of a playlist with the novel it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+	pub novel: String,
+	/// Provider the chapter is read from.
+	pub provider: String,
+	pub title: String,
+	pub url: Url,
+}
+
+impl Entry {
+	pub fn chapter(&self) -> Chapter {
+		Chapter::new(self.title.clone(), self.url.clone())
+	}
+}
+
+/// Chapters of several novels read in a set order, like side stories
+/// between the chapters of the main story.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+	pub name: String,
+	pub entries: Vec<Entry>,
+	/// Index of the next entry to read, the length of `entries` once every
+	/// entry is read.
+	pub position: usize,
+}
+
+impl Playlist {
+	/// Returns the next entry to read.
+	pub fn current(&self) -> Option<&Entry> {
+		self.entries.get(self.position)
+	}
+
+	/// Removes the entry at `index`, keeping the position on the same
+	/// entry.
+	pub fn remove(&mut self, index: usize) -> Option<Entry> {
+		if index >= self.entries.len() {
+			return None;
+		}
+		if index < self.position {
+			self.position -= 1;
+		}
+		Some(self.entries.remove(index))
+	}
+}
+
+/// The playlists of the library, in the order they were created.
+pub struct Playlists {
+	path: PathBuf,
+	playlists: Vec<Playlist>,
+}
+
+impl Playlists {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("playlists.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let playlists = match crypt::read_to_string(&path) {
+			Ok(playlists) => serde_json::from_str(&playlists)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, playlists })
+	}
+
+	pub fn playlists(&self) -> &[Playlist] {
+		&self.playlists
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Playlist> {
+		self.playlists.iter().find(|playlist| playlist.name == name)
+	}
+
+	/// Appends `entry` to the playlist `name`, creating it if needed.
+	pub fn push(&mut self, name: &str, entry: Entry) -> Result<()> {
+		match self
+			.playlists
+			.iter_mut()
+			.find(|playlist| playlist.name == name)
+		{
+			Some(playlist) => playlist.entries.push(entry),
+			None => self.playlists.push(Playlist {
+				name: name.to_string(),
+				entries: vec![entry],
+				position: 0,
+			}),
+		}
+
+		self.save()
+	}
+
+	/// Removes the entry at `index` of the playlist `name`, or the whole
+	/// playlist without an index, and returns whether there was one.
+	pub fn remove(&mut self, name: &str, index: Option<usize>) -> Result<bool> {
+		let removed = match index {
+			Some(index) => self
+				.playlists
+				.iter_mut()
+				.find(|playlist| playlist.name == name)
+				.and_then(|playlist| playlist.remove(index))
+				.is_some(),
+			None => {
+				let before = self.playlists.len();
+				self.playlists.retain(|playlist| playlist.name != name);
+				self.playlists.len() != before
+			}
+		};
+
+		if removed {
+			self.save()?;
+		}
+		Ok(removed)
+	}
+
+	/// Moves the playlist `name` to its entry at `position`, past the last
+	/// one at most.
+	pub fn seek(&mut self, name: &str, position: usize) -> Result<()> {
+		if let Some(playlist) = self
+			.playlists
+			.iter_mut()
+			.find(|playlist| playlist.name == name)
+		{
+			playlist.position = position.min(playlist.entries.len());
+			self.save()?;
+		}
+		Ok(())
+	}
+
+	/// Marks the current entry of the playlist `name` as read.
+	pub fn advance(&mut self, name: &str) -> Result<()> {
+		let position = self.get(name).map_or(0, |playlist| playlist.position + 1);
+		self.seek(name, position)
+	}
+
+	fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.playlists)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_position_on_removal() {
+		let entry = |title: &str| Entry {
+			novel: "Novel".to_string(),
+			provider: "royalroad".to_string(),
+			title: title.to_string(),
+			url: Url::parse(&format!("https://example.com/{}", title)).unwrap(),
+		};
+		let mut playlist = Playlist {
+			name: "Side stories".to_string(),
+			entries: vec![entry("a"), entry("b"), entry("c")],
+			position: 1,
+		};
+
+		assert_eq!(playlist.current().unwrap().title, "b");
+		assert_eq!(playlist.remove(0).unwrap().title, "a");
+		assert_eq!(playlist.current().unwrap().title, "b");
+		assert_eq!(playlist.remove(1).unwrap().title, "c");
+		assert_eq!(playlist.current().unwrap().title, "b");
+		assert!(playlist.remove(5).is_none());
+	}
+}
@@ -0,0 +1,501 @@
+use crate::{
+	date,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, session_init, CLIENT},
+	keyring::Credentials,
+};
+use chrono::Local;
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper,
+};
+
+pub const NAME: &str = "ao3";
+
+/// Hosts the provider serves works from.
+pub const HOSTS: [&str; 2] = ["archiveofourown.org", "ao3.org"];
+
+const BASE_URL: &str = "https://archiveofourown.org";
+
+lazy_static! {
+	static ref WORK_ITEM_RE: Regex =
+		Regex::new(r#"<li id="work_\d+" class="work blurb[^"]*"[^>]*>"#).unwrap();
+	static ref WORK_LINK_RE: Regex =
+		Regex::new(r#"<h4 class="heading">\s*<a href="(/works/\d+)"[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref LATEST_CHAPTER_RE: Regex =
+		Regex::new(r#"<dd class="chapters">\s*<a href="(/works/\d+/chapters/\d+)"[^>]*>(\d+)</a>"#)
+			.unwrap();
+	static ref ITEM_DATE_RE: Regex =
+		Regex::new(r#"<p class="datetime">([^<]+)</p>"#).unwrap();
+	static ref CHAPTER_ROW_RE: Regex = Regex::new(
+		r#"<li>\s*<a href="(/works/\d+/chapters/\d+)"[^>]*>([\S\s]+?)</a>\s*(?:<span class="datetime">\(([^)]+)\)</span>)?"#
+	)
+	.unwrap();
+	static ref FULL_CHAPTER_RE: Regex =
+		Regex::new(r#"<div class="chapter" id="chapter-\d+">"#).unwrap();
+	static ref FULL_TITLE_RE: Regex = Regex::new(
+		r#"<h3 class="title">\s*<a href="(/works/\d+/chapters/\d+)"[^>]*>([\S\s]+?)</a>([\S\s]*?)</h3>"#
+	)
+	.unwrap();
+	static ref CONTENT_RE: Regex = Regex::new(
+		r#"<div class="userstuff(?: module)?"[^>]*>([\S\s]+?)</div>\s*(?:<!--|<div class="(?:chapter|end)|<div id="(?:work_endnotes|children|feedback))"#
+	)
+	.unwrap();
+	static ref HEADING_RE: Regex =
+		Regex::new(r#"<h3 class="landmark heading"[^>]*>[\S\s]*?</h3>"#).unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+	static ref WORK_TITLE_RE: Regex =
+		Regex::new(r#"<h2 class="title heading">([\S\s]+?)</h2>"#).unwrap();
+	static ref AUTHOR_RE: Regex = Regex::new(r#"<a rel="author"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref TAGS_RE: Regex =
+		Regex::new(r#"<dd class="(?:fandom|relationship|character|freeform) tags">([\S\s]+?)</dd>"#)
+			.unwrap();
+	static ref TAG_LINK_RE: Regex = Regex::new(r#"<a class="tag"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref SUMMARY_RE: Regex = Regex::new(
+		r#"<div class="summary module"[^>]*>[\S\s]*?<blockquote class="userstuff">([\S\s]+?)</blockquote>"#
+	)
+	.unwrap();
+	static ref STATUS_RE: Regex =
+		Regex::new(r#"<dt class="status">(Completed|Updated):</dt>\s*<dd class="status">([^<]+)</dd>"#)
+			.unwrap();
+	static ref PUBLISHED_RE: Regex =
+		Regex::new(r#"<dd class="published">([^<]+)</dd>"#).unwrap();
+	static ref CHAPTERS_RE: Regex =
+		Regex::new(r#"<dd class="chapters">(?:<a[^>]*>)?(\d+)"#).unwrap();
+	static ref KUDOS_RE: Regex =
+		Regex::new(r#"<dd class="kudos">(?:<a[^>]*>)?([\d,]+)"#).unwrap();
+	static ref TOKEN_RE: Regex =
+		Regex::new(r#"<input[^>]*name="authenticity_token"[^>]*value="([^"]+)""#).unwrap();
+	/// Works restricted to members redirect to the login page.
+	static ref RESTRICTED_RE: Regex =
+		Regex::new(r#"(?i)only available to registered users|please log in"#).unwrap();
+}
+
+/// Returns the url of the work `url` belongs to.
+///
+/// Chapter urls look like `https://archiveofourown.org/works/<id>/chapters/<id>`.
+fn work_url(url: &Url) -> Result<Url, RanobeError> {
+	let mut segments = url.path_segments().into_iter().flatten();
+	match (segments.next(), segments.next()) {
+		(Some("works"), Some(id)) if !id.is_empty() => {
+			Ok(Url::parse(&format!("{}/works/{}", BASE_URL, id))?)
+		}
+		_ => Err(RanobeError::Invalid(format!(
+			"{} is not a work of {}",
+			url, NAME
+		))),
+	}
+}
+
+/// Adds the confirmation works rated explicit or without a rating ask for.
+fn with_adult_view(url: &Url) -> Url {
+	let mut url = url.clone();
+	url.query_pairs_mut().append_pair("view_adult", "true");
+	url
+}
+
+/// Parses the text of the chapter in `html`, leaving out the notes of the
+/// author.
+pub(crate) fn chapter_text(html: &str) -> Option<String> {
+	let content = CONTENT_RE.captures(html)?.get(1).unwrap().as_str();
+	let content = HEADING_RE.replace(content, "");
+
+	let text = PARAGRAPH_RE
+		.captures_iter(&content)
+		.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+		.filter(|paragraph| !paragraph.is_empty())
+		.collect::<Vec<_>>()
+		.join("\n\n");
+
+	// Some works are written without paragraphs
+	let text = if text.is_empty() {
+		to_markdown(&content)
+	} else {
+		text
+	};
+
+	(!text.trim().is_empty()).then_some(text)
+}
+
+/// Parses the works of a search or tag listing, with the last chapter of
+/// the ones that have several, so the latest updates open the newest
+/// chapter.
+fn works(url: &Url, body: &str) -> Result<Vec<(Novel, Option<Chapter>)>, RanobeError> {
+	let mut ranobe_list = vec![];
+	let starts = WORK_ITEM_RE
+		.find_iter(body)
+		.map(|item| item.start())
+		.collect::<Vec<_>>();
+	for (i, &start) in starts.iter().enumerate() {
+		let end = starts.get(i + 1).copied().unwrap_or(body.len());
+		let item = &body[start..end];
+
+		let work = match WORK_LINK_RE.captures(item) {
+			Some(work) => work,
+			None => continue,
+		};
+		let work = Novel {
+			title: strip_tags(work.get(2).unwrap().as_str()),
+			url: url.join(work.get(1).unwrap().as_str())?,
+			updated: ITEM_DATE_RE
+				.captures(item)
+				.and_then(|updated| date::parse(updated.get(1).unwrap().as_str(), Local::now())),
+		};
+
+		let chapter = match LATEST_CHAPTER_RE.captures(item) {
+			Some(chapter) => Some(Chapter {
+				title: format!(
+					"{}: Chapter {}",
+					work.title,
+					chapter.get(2).unwrap().as_str()
+				),
+				url: url.join(chapter.get(1).unwrap().as_str())?,
+				novel: Some(work.url.clone()),
+				index: None,
+				published: work.updated,
+				lock: None,
+			}),
+			None => None,
+		};
+		ranobe_list.push((work, chapter));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct Ao3 {
+	page: u32,
+}
+
+impl Ao3 {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+
+	/// Searches works for `query`, only keeping the ones tagged with every
+	/// tag of `tags`.
+	pub async fn search_works(
+		&self,
+		query: &str,
+		tags: &[&str],
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut url = Url::parse(&format!("{}/works/search", BASE_URL))?;
+		url.query_pairs_mut()
+			.append_pair("work_search[query]", query)
+			.append_pair("work_search[other_tag_names]", &tags.join(","))
+			.append_pair("work_search[sort_column]", "kudos_count")
+			.append_pair("page", &page.to_string());
+		let body = fetch_url(client, url.clone()).await?;
+
+		Ok(works(&url, &body)?
+			.into_iter()
+			.map(|(work, _)| work)
+			.collect())
+	}
+
+	/// Fetches every chapter of the work `url` belongs to with its text, in
+	/// a single request through the entire work view.
+	pub async fn get_full_work(&self, url: Url) -> Result<Vec<(Chapter, String)>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let work = work_url(&url)?;
+		let mut full = with_adult_view(&work);
+		full.query_pairs_mut().append_pair("view_full_work", "true");
+		let body = fetch_url(client, full.clone()).await?;
+
+		let starts = FULL_CHAPTER_RE
+			.find_iter(&body)
+			.map(|chapter| chapter.start())
+			.collect::<Vec<_>>();
+
+		// Works of a single chapter are not split into chapters
+		if starts.is_empty() {
+			let title = match WORK_TITLE_RE.captures(&body) {
+				Some(title) => strip_tags(title.get(1).unwrap().as_str()),
+				None => return Err(selector_failed(NAME, &full, "work title", &body)),
+			};
+			let text = match chapter_text(&body) {
+				Some(text) => text,
+				None => return Err(selector_failed(NAME, &full, "work text", &body)),
+			};
+			let chapter = Chapter {
+				novel: Some(work.clone()),
+				index: Some(0),
+				..Chapter::new(title, work)
+			};
+			return Ok(vec![(chapter, highlight_quotes(&text))]);
+		}
+
+		let mut chapters = vec![];
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let html = &body[start..end];
+
+			let (title, text) = match (FULL_TITLE_RE.captures(html), chapter_text(html)) {
+				(Some(title), Some(text)) => (title, text),
+				_ => return Err(selector_failed(NAME, &full, "chapter", &body)),
+			};
+			let chapter = Chapter {
+				title: format!(
+					"{}{}",
+					strip_tags(title.get(2).unwrap().as_str()),
+					strip_tags(title.get(3).unwrap().as_str())
+				),
+				url: full.join(title.get(1).unwrap().as_str())?,
+				novel: Some(work.clone()),
+				index: Some(i),
+				published: None,
+				lock: None,
+			};
+			chapters.push((chapter, highlight_quotes(&text)));
+		}
+
+		Ok(chapters)
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for Ao3 {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			search: true,
+			toc: true,
+			login: true,
+			catalogue: true,
+			ranking: true,
+			by_author: true,
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut url = Url::parse(&format!("{}/works/search", BASE_URL))?;
+		url.query_pairs_mut()
+			.append_pair("work_search[query]", "")
+			.append_pair("work_search[sort_column]", "revised_at")
+			.append_pair("page", &self.page.to_string());
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		let ranobe_list = works(&url, &body)?
+			.into_iter()
+			.map(|(work, chapter)| {
+				chapter.unwrap_or_else(|| Chapter {
+					novel: Some(work.url.clone()),
+					index: Some(0),
+					published: work.updated,
+					..Chapter::new(work.title, work.url)
+				})
+			})
+			.collect::<Vec<_>>();
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, with_adult_view(&url)).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		let text = match chapter_text(&body) {
+			Some(text) => text,
+			None if RESTRICTED_RE.is_match(&body) => {
+				return Err(RanobeError::Unauthorized(format!(
+					"{} is only shown to members, log in with `ranobe login`.",
+					url
+				)))
+			}
+			None => return Err(selector_failed(NAME, &url, "chapter text", &body)),
+		};
+
+		Ok(highlight_quotes(&text))
+	}
+	/// Sends the login form with the token it was served with.
+	async fn login(&self, credentials: &Credentials) -> Result<(), RanobeError> {
+		let client = session_init()?;
+
+		let url = Url::parse(&format!("{}/users/login", BASE_URL))?;
+		let body = fetch_url(&client, url.clone()).await?;
+		let token = match TOKEN_RE.captures(&body) {
+			Some(token) => token.get(1).unwrap().as_str().to_string(),
+			None => return Err(selector_failed(NAME, &url, "authenticity token", &body)),
+		};
+
+		let form = [
+			("authenticity_token", token.as_str()),
+			("user[login]", credentials.username.as_str()),
+			("user[password]", credentials.password.as_str()),
+			("user[remember_me]", "1"),
+			("commit", "Log in"),
+		];
+		let res = client.post(url).body(surf::Body::from_form(&form)?).await?;
+
+		// Logging in redirects away from the form, failing shows it again
+		let logged_in = res.status().is_redirection()
+			&& res
+				.header("location")
+				.is_some_and(|location| !location.as_str().contains("/users/login"));
+		if !logged_in {
+			return Err(RanobeError::Unauthorized(
+				"AO3 refused the username or password.".to_string(),
+			));
+		}
+
+		Ok(())
+	}
+	/// Lists the most liked works tagged with `genre`, several tags can be
+	/// given separated by commas.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let tags = genre
+			.map(|genre| genre.split(',').map(str::trim).collect::<Vec<_>>())
+			.unwrap_or_default();
+
+		self.search_works("", &tags, page).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		self.search_works("", &[], 1).await
+	}
+	async fn search(&self, query: &str) -> Result<Vec<Novel>, RanobeError> {
+		self.search_works(query, &[], 1).await
+	}
+	/// Lists the most liked works of `author`, counting the works posted
+	/// under any of their pseuds.
+	async fn get_by_author(&self, author: &str) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut url = Url::parse(&format!("{}/works/search", BASE_URL))?;
+		url.query_pairs_mut()
+			.append_pair("work_search[creators]", author.trim())
+			.append_pair("work_search[sort_column]", "kudos_count");
+		let body = fetch_url(client, url.clone()).await?;
+
+		Ok(works(&url, &body)?
+			.into_iter()
+			.map(|(work, _)| work)
+			.collect())
+	}
+	/// Fetches the chapters of the work `novel` belongs to, first to last,
+	/// from its chapter index.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let url = novel.url.clone();
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let work = work_url(&url)?;
+		let index = Url::parse(&format!("{}/navigate", work))?;
+		let body = fetch_url(client, index.clone()).await?;
+
+		let mut chapters = vec![];
+		for chapter in CHAPTER_ROW_RE.captures_iter(&body) {
+			chapters.push(Chapter {
+				title: strip_tags(chapter.get(2).unwrap().as_str()),
+				url: index.join(chapter.get(1).unwrap().as_str())?,
+				novel: Some(work.clone()),
+				index: Some(chapters.len()),
+				published: chapter
+					.get(3)
+					.and_then(|updated| date::parse(updated.as_str(), Local::now())),
+				lock: None,
+			});
+		}
+
+		if chapters.is_empty() {
+			return Err(selector_failed(NAME, &index, "chapter index", &body));
+		}
+
+		Ok(chapters)
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = with_adult_view(&work_url(&url)?);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match WORK_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "work title", &body)),
+		}
+
+		let authors = AUTHOR_RE
+			.captures_iter(&body)
+			.map(|author| strip_tags(author.get(1).unwrap().as_str()))
+			.collect::<Vec<_>>();
+		metadata.author = (!authors.is_empty()).then(|| authors.join(", "));
+		for tags in TAGS_RE.captures_iter(&body) {
+			metadata.genres.extend(
+				TAG_LINK_RE
+					.captures_iter(tags.get(1).unwrap().as_str())
+					.map(|tag| strip_tags(tag.get(1).unwrap().as_str())),
+			);
+		}
+		metadata.description = SUMMARY_RE
+			.captures(&body)
+			.map(|summary| strip_tags(summary.get(1).unwrap().as_str()));
+
+		// Works that were never updated only have a publishing date
+		let (status, updated) = match STATUS_RE.captures(&body) {
+			Some(status) => (
+				Some(status.get(1).unwrap().as_str()),
+				Some(status.get(2).unwrap().as_str()),
+			),
+			None => (
+				None,
+				PUBLISHED_RE
+					.captures(&body)
+					.map(|published| published.get(1).unwrap().as_str()),
+			),
+		};
+		metadata.status = status.map(|status| {
+			match status {
+				"Completed" => "Completed",
+				_ => "Ongoing",
+			}
+			.to_string()
+		});
+		metadata.updated = updated.and_then(|updated| date::parse(updated, Local::now()));
+
+		metadata.chapters = CHAPTERS_RE
+			.captures(&body)
+			.and_then(|chapters| chapters.get(1).unwrap().as_str().parse().ok());
+		// Kudos are the only rating of the archive
+		metadata.reviews = KUDOS_RE
+			.captures(&body)
+			.and_then(|kudos| kudos.get(1).unwrap().as_str().replace(',', "").parse().ok());
+
+		Ok(metadata)
+	}
+}
@@ -0,0 +1,324 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use surf::utils::async_trait;
+use surf::Url;
+
+use crate::{
+	config::Config,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+
+use super::{
+	highlight_quotes, to_markdown, Capabilities, Chapter, Listed, Metadata, Novel, RanobeScraper,
+};
+
+/// Most pages a chapter is followed over with `next_page`.
+const MAX_TEXT_PAGES: usize = 20;
+
+lazy_static! {
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+}
+
+/// A site as described in its definition file.
+///
+/// Selectors are regular expressions like the ones of the built-in
+/// providers, matched against the html of a page.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Definition {
+	/// Url of the latest updates, `{page}` is replaced by the page number.
+	latest_url: String,
+	/// Matches an entry of the latest updates, capturing its url then its
+	/// title.
+	latest: String,
+	/// Matches the title of a chapter, pages it does not match are not
+	/// chapters.
+	title: String,
+	/// Captures the html holding the text of a chapter.
+	content: String,
+	/// Captures a paragraph of the text, `<p>` elements by default.
+	paragraph: Option<String>,
+	/// Captures the url of the next page of a chapter split over several.
+	next_page: Option<String>,
+	/// Url of the catalogue, `{page}` and `{genre}` are replaced.
+	catalogue_url: Option<String>,
+	/// Matches an entry of the catalogue like `latest` does, `latest` is
+	/// used if omitted.
+	catalogue: Option<String>,
+	/// Captures the title of a novel on its page.
+	novel_title: Option<String>,
+}
+
+/// The compiled selectors of a definition.
+#[derive(Debug)]
+struct Site {
+	latest_url: String,
+	latest: Regex,
+	title: Regex,
+	content: Regex,
+	paragraph: Regex,
+	next_page: Option<Regex>,
+	catalogue_url: Option<String>,
+	catalogue: Option<Regex>,
+	novel_title: Option<Regex>,
+}
+
+impl Site {
+	fn compile(definition: Definition) -> Result<Self, regex::Error> {
+		let optional = |selector: Option<String>| selector.as_deref().map(Regex::new).transpose();
+		Ok(Self {
+			latest_url: definition.latest_url,
+			latest: Regex::new(&definition.latest)?,
+			title: Regex::new(&definition.title)?,
+			content: Regex::new(&definition.content)?,
+			paragraph: optional(definition.paragraph)?.unwrap_or_else(|| PARAGRAPH_RE.clone()),
+			next_page: optional(definition.next_page)?,
+			catalogue_url: definition.catalogue_url,
+			catalogue: optional(definition.catalogue)?,
+			novel_title: optional(definition.novel_title)?,
+		})
+	}
+}
+
+/// Returns the directory definitions are loaded from, next to the
+/// configuration.
+pub fn default_dir() -> PathBuf {
+	Config::default_path().with_file_name("providers")
+}
+
+/// Returns the names of the providers defined in `dir`, the names of
+/// their files without the `.toml` extension.
+pub fn names<P: AsRef<Path>>(dir: P) -> Vec<String> {
+	let mut names = fs::read_dir(dir)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.map(|entry| entry.path())
+		.filter(|path| {
+			path.extension()
+				.is_some_and(|extension| extension == "toml")
+		})
+		.filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+		.collect::<Vec<_>>();
+	names.sort();
+	names
+}
+
+/// A provider for a site described by a definition file, so sites can be
+/// added without building ranobe again.
+#[derive(Debug, Clone)]
+pub struct DeclarativeProvider {
+	name: &'static str,
+	page: u32,
+	site: Arc<Site>,
+}
+
+impl DeclarativeProvider {
+	/// Loads the provider defined in the file `name`.toml of `dir`.
+	pub fn load<P: AsRef<Path>>(dir: P, name: &str) -> Result<Self, RanobeError> {
+		let path = dir.as_ref().join(format!("{}.toml", name));
+		let invalid = |err: &dyn std::fmt::Display| {
+			RanobeError::Parse(format!("{}: {}", path.display(), err))
+		};
+
+		let definition: Definition =
+			toml::from_str(&fs::read_to_string(&path)?).map_err(|err| invalid(&err))?;
+		let site = Site::compile(definition).map_err(|err| invalid(&err))?;
+
+		Ok(Self {
+			// Names are `'static` for the built-in providers, a provider is
+			// only loaded a few times a run
+			name: Box::leak(name.to_string().into_boxed_str()),
+			page: 1,
+			site: Arc::new(site),
+		})
+	}
+
+	/// Parses the entries `selector` matches on the page at `url`, chapters
+	/// of the latest updates or novels of the catalogue.
+	fn entries<E: Listed>(url: &Url, body: &str, selector: &Regex) -> Result<Vec<E>, RanobeError> {
+		let mut ranobe_list = vec![];
+		for entry in selector.captures_iter(body) {
+			let (link, title) = match (entry.get(1), entry.get(2)) {
+				(Some(link), Some(title)) => (link, title),
+				_ => continue,
+			};
+			ranobe_list.push(E::new(
+				to_markdown(title.as_str()),
+				url.join(link.as_str().trim())?,
+			));
+		}
+
+		Ok(ranobe_list)
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for DeclarativeProvider {
+	fn name(&self) -> &'static str {
+		self.name
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			catalogue: self.site.catalogue_url.is_some(),
+			..Capabilities::default()
+		}
+	}
+	/// `{page}` counts from one, as sites number their pages.
+	fn latest_page(&self) -> Option<u32> {
+		Some(self.page - 1)
+	}
+	fn set_latest_page(&mut self, page: u32) -> bool {
+		self.page = page + 1;
+		true
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(
+			&self
+				.site
+				.latest_url
+				.replace("{page}", &self.page.to_string()),
+		)?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		let ranobe_list = Self::entries(&url, &body, &self.site.latest)?;
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(self.name, &url, "latest", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	/// Fetches the text of the chapter at `url`, following `next_page`
+	/// over the pages it is split into.
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut paragraphs = vec![];
+		let mut page = Some(url);
+		for _ in 0..MAX_TEXT_PAGES {
+			let url = match page.take() {
+				Some(url) => url,
+				None => break,
+			};
+			let body = fetch_url(client, url.clone()).await?;
+
+			if !self.site.title.is_match(&body) {
+				return Err(selector_failed(self.name, &url, "title", &body));
+			}
+			let content = match self.site.content.captures(&body) {
+				Some(content) => content.get(1).map_or("", |content| content.as_str()),
+				None => return Err(selector_failed(self.name, &url, "content", &body)),
+			};
+			paragraphs.extend(
+				self.site
+					.paragraph
+					.captures_iter(content)
+					.filter_map(|paragraph| Some(to_markdown(paragraph.get(1)?.as_str())))
+					.filter(|paragraph| !paragraph.is_empty()),
+			);
+
+			if let Some(next) = self
+				.site
+				.next_page
+				.as_ref()
+				.and_then(|next_page| next_page.captures(&body))
+				.and_then(|next| next.get(1))
+			{
+				page = Some(url.join(next.as_str().trim())?);
+			}
+		}
+
+		let text = paragraphs.join("\n\n");
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match &self.site.catalogue_url {
+			Some(url) => url
+				.replace("{page}", &page.to_string())
+				.replace("{genre}", genre.map(str::trim).unwrap_or_default()),
+			None => return Ok(vec![]),
+		};
+		let url = Url::parse(&url)?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		let selector = self.site.catalogue.as_ref().unwrap_or(&self.site.latest);
+		Self::entries(&url, &body, selector)
+	}
+	/// Fetches the title of the novel at `url` with `novel_title`, or the
+	/// title of the chapter at `url` without it.
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, url.clone()).await?;
+		let selector = self.site.novel_title.as_ref().unwrap_or(&self.site.title);
+		let title = match selector.captures(&body).and_then(|title| title.get(1)) {
+			Some(title) => to_markdown(title.as_str()),
+			None => return Err(selector_failed(self.name, &url, "title", &body)),
+		};
+
+		Ok(Metadata {
+			title,
+			..Metadata::default()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn loads_definitions() {
+		let dir = TestDir::new("declarative");
+		fs::write(
+			dir.join("example.toml"),
+			r#"
+latest_url = "https://example.com/latest/{page}"
+latest = '<a class="chapter" href="([^"]+)">([^<]+)</a>'
+title = '<h1>([^<]+)</h1>'
+content = '<div id="text">([\S\s]+?)</div>'
+"#,
+		)
+		.unwrap();
+		fs::write(
+			dir.join("broken.toml"),
+			"latest_url = \"https://example.com\"",
+		)
+		.unwrap();
+		fs::write(dir.join("notes.txt"), "").unwrap();
+
+		assert_eq!(names(&dir), vec!["broken", "example"]);
+		let provider = DeclarativeProvider::load(&dir, "example").unwrap();
+		assert_eq!(provider.name(), "example");
+		assert!(DeclarativeProvider::load(&dir, "broken").is_err());
+		assert!(DeclarativeProvider::load(&dir, "missing").is_err());
+
+		let url = Url::parse("https://example.com/latest/1").unwrap();
+		let body = r#"<a class="chapter" href="/novel/chapter-2">Chapter 2 &amp; more</a>"#;
+		let entries: Vec<Chapter> =
+			DeclarativeProvider::entries(&url, body, &provider.site.latest).unwrap();
+		assert_eq!(entries[0].title, "Chapter 2 & more");
+		assert_eq!(
+			entries[0].url.as_str(),
+			"https://example.com/novel/chapter-2"
+		);
+	}
+}
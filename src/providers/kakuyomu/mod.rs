@@ -0,0 +1,386 @@
+use crate::{
+	date,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use chrono::Local;
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+use surf::Url;
+
+use super::{
+	canonical_url, highlight_quotes, script, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper, Volume,
+};
+
+pub const NAME: &str = "kakuyomu";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["kakuyomu.jp"];
+
+const BASE_URL: &str = "https://kakuyomu.jp";
+
+lazy_static! {
+	static ref NEXT_DATA_RE: Regex =
+		Regex::new(r#"<script id="__NEXT_DATA__" type="application/json">([\S\s]+?)</script>"#)
+			.unwrap();
+	static ref WORK_LINK_RE: Regex =
+		Regex::new(r#"<a href="(/works/\d+)"[^>]*>([^<]+)</a>"#).unwrap();
+	static ref EPISODE_TITLE_RE: Regex =
+		Regex::new(r#"<p class="widget-episodeTitle[^"]*">([\S\s]+?)</p>"#).unwrap();
+	static ref CONTENT_RE: Regex =
+		Regex::new(r#"<div class="widget-episodeBody[^"]*">([\S\s]+?)</div>"#).unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+}
+
+/// Returns the id of the work `url` belongs to.
+///
+/// Episode urls look like `https://kakuyomu.jp/works/<id>/episodes/<id>`.
+fn work_id(url: &Url) -> Option<String> {
+	let mut segments = url.path_segments()?;
+	match (segments.next(), segments.next()) {
+		(Some("works"), Some(id)) if !id.is_empty() => Some(id.to_string()),
+		_ => None,
+	}
+}
+
+fn is_episode(url: &Url) -> bool {
+	url.path_segments()
+		.is_some_and(|mut segments| segments.nth(2) == Some("episodes"))
+}
+
+/// Returns the Apollo state the page is rendered from, which holds the
+/// work and its table of contents.
+pub(crate) fn apollo_state(body: &str) -> Option<Value> {
+	let data = NEXT_DATA_RE.captures(body)?;
+	let mut data: Value = serde_json::from_str(data.get(1).unwrap().as_str()).ok()?;
+	Some(data["props"]["pageProps"]["__APOLLO_STATE__"].take())
+}
+
+/// Follows `value` to the entry of `state` it refers to, if it is a
+/// reference.
+fn resolve<'a>(state: &'a Value, value: &'a Value) -> &'a Value {
+	match value["__ref"].as_str() {
+		Some(key) => &state[key],
+		None => value,
+	}
+}
+
+/// Parses the time of the state, like `2023-02-01T12:00:00Z`.
+fn parse_time(time: &Value) -> Option<std::time::SystemTime> {
+	date::parse(time.as_str()?.get(..10)?, Local::now())
+}
+
+/// Lists the episodes of the entry `toc` of the table of contents of the
+/// work `id`.
+fn toc_episodes(state: &Value, toc: &Value, id: &str) -> Result<Vec<Chapter>, RanobeError> {
+	let work = Url::parse(&format!("{}/works/{}", BASE_URL, id))?;
+	let mut chapters = vec![];
+	for episode in toc["episodeUnions"].as_array().into_iter().flatten() {
+		let episode = resolve(state, episode);
+		let episode_id = match episode["id"].as_str() {
+			Some(episode_id) => episode_id,
+			None => continue,
+		};
+		chapters.push(Chapter {
+			title: episode["title"].as_str().unwrap_or_default().to_string(),
+			url: Url::parse(&format!(
+				"{}/works/{}/episodes/{}",
+				BASE_URL, id, episode_id
+			))?,
+			novel: Some(work.clone()),
+			index: None,
+			published: parse_time(&episode["publishedAt"]),
+			lock: None,
+		});
+	}
+
+	Ok(chapters)
+}
+
+/// Lists the episodes of the work `id` from the `state` of its page.
+pub(crate) fn episodes(state: &Value, id: &str) -> Result<Vec<Chapter>, RanobeError> {
+	let work = &state[format!("Work:{}", id)];
+	let mut chapters = vec![];
+	for toc in work["tableOfContents"].as_array().into_iter().flatten() {
+		chapters.extend(toc_episodes(state, resolve(state, toc), id)?);
+	}
+	for (i, chapter) in chapters.iter_mut().enumerate() {
+		chapter.index = Some(i);
+	}
+
+	Ok(chapters)
+}
+
+/// Lists the chapters the table of contents of the work `id` is split into
+/// as volumes, episodes before the first chapter are left out.
+pub(crate) fn volumes(state: &Value, id: &str) -> Result<Vec<Volume>, RanobeError> {
+	let work = &state[format!("Work:{}", id)];
+	let mut volumes = vec![];
+	for toc in work["tableOfContents"].as_array().into_iter().flatten() {
+		let toc = resolve(state, toc);
+		let title = match resolve(state, &toc["chapter"])["title"].as_str() {
+			Some(title) => title.to_string(),
+			None => continue,
+		};
+		volumes.push(Volume {
+			title,
+			chapters: toc_episodes(state, toc, id)?
+				.iter()
+				.map(|chapter| canonical_url(&chapter.url))
+				.collect(),
+		});
+	}
+
+	Ok(volumes)
+}
+
+/// Parses the works of a listing like the ranking, or the latest updates
+/// where works stand for their episodes.
+fn works<E: Listed>(url: &Url, body: &str) -> Result<Vec<E>, RanobeError> {
+	let mut ranobe_list: Vec<E> = vec![];
+	for work in WORK_LINK_RE.captures_iter(body) {
+		let work_url = url.join(work.get(1).unwrap().as_str())?;
+		let title = to_markdown(work.get(2).unwrap().as_str());
+		// Covers and titles both link to the work
+		if title.is_empty() || ranobe_list.iter().any(|ranobe| *ranobe.url() == work_url) {
+			continue;
+		}
+		ranobe_list.push(E::new(title, work_url));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct Kakuyomu {
+	page: u32,
+}
+
+impl Kakuyomu {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for Kakuyomu {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			catalogue: true,
+			ranking: true,
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/search?order=last_episode_published_at&page={}",
+			BASE_URL, self.page
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		let ranobe_list = works(&url, &body)?;
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	/// Fetches the text of an episode, or of the first episode of a work as
+	/// listings link to works.
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = if is_episode(&url) {
+			url
+		} else {
+			// Fails rather than finding no episode
+			let work = Novel::new(String::new(), url);
+			self.get_chapters(&work).await?.remove(0).url
+		};
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		if !EPISODE_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "episode title", &body));
+		}
+		let content = match CONTENT_RE.captures(&body) {
+			Some(content) => content.get(1).unwrap().as_str(),
+			None => return Err(selector_failed(NAME, &url, "episode text", &body)),
+		};
+
+		// Blank paragraphs only space out the text
+		let text = PARAGRAPH_RE
+			.captures_iter(content)
+			.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "episode text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	/// Lists the popular works of `genre`, named like in the urls of the
+	/// site, e.g. `fantasy`, `sf` or `love_story`.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut url = Url::parse(&format!("{}/search", BASE_URL))?;
+		url.query_pairs_mut()
+			.append_pair("order", "popular")
+			.append_pair("page", &page.to_string());
+		if let Some(genre) = genre {
+			url.query_pairs_mut()
+				.append_pair("genre_name", &genre.trim().replace(' ', "_"));
+		}
+		let body = fetch_url(client, url.clone()).await?;
+
+		works(&url, &body)
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/rankings/all/daily", BASE_URL))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		works(&url, &body)
+	}
+	async fn get_volumes(&self, url: Url) -> Result<Vec<Volume>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let id = match work_id(&url) {
+			Some(id) => id,
+			None => {
+				return Err(RanobeError::Invalid(format!(
+					"{} is not a work of {}",
+					url, NAME
+				)))
+			}
+		};
+		let page = Url::parse(&format!("{}/works/{}", BASE_URL, id))?;
+		let body = fetch_url(client, page.clone()).await?;
+
+		match apollo_state(&body) {
+			Some(state) => volumes(&state, &id),
+			None => Err(selector_failed(NAME, &page, "work state", &body)),
+		}
+	}
+	/// Fetches the episodes of the work `novel` belongs to, first to last.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let url = novel.url.clone();
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let id = match work_id(&url) {
+			Some(id) => id,
+			None => {
+				return Err(RanobeError::Invalid(format!(
+					"{} is not a work of {}",
+					url, NAME
+				)))
+			}
+		};
+		let page = Url::parse(&format!("{}/works/{}", BASE_URL, id))?;
+		let body = fetch_url(client, page.clone()).await?;
+
+		let chapters = match apollo_state(&body) {
+			Some(state) => episodes(&state, &id)?,
+			None => vec![],
+		};
+		if chapters.is_empty() {
+			return Err(selector_failed(NAME, &page, "episode list", &body));
+		}
+
+		Ok(chapters)
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let id = match work_id(&url) {
+			Some(id) => id,
+			None => {
+				return Err(RanobeError::Invalid(format!(
+					"{} is not a work of {}",
+					url, NAME
+				)))
+			}
+		};
+		let page = Url::parse(&format!("{}/works/{}", BASE_URL, id))?;
+		let body = fetch_url(client, page.clone()).await?;
+
+		let state = match apollo_state(&body) {
+			Some(state) => state,
+			None => return Err(selector_failed(NAME, &page, "work state", &body)),
+		};
+		let work = &state[format!("Work:{}", id)];
+		let title = match work["title"].as_str() {
+			Some(title) => title.to_string(),
+			None => return Err(selector_failed(NAME, &page, "work title", &body)),
+		};
+
+		let mut genres = vec![];
+		if let Some(genre) = work["genre"].as_str() {
+			genres.push(genre.to_lowercase());
+		}
+		for tag in work["tagLabels"].as_array().into_iter().flatten() {
+			if let Some(tag) = tag.as_str() {
+				genres.push(tag.to_string());
+			}
+		}
+
+		Ok(Metadata {
+			title,
+			author: resolve(&state, &work["author"])["activityName"]
+				.as_str()
+				.map(String::from),
+			status: work["serialStatus"].as_str().map(|status| {
+				match status {
+					"COMPLETED" => "Completed",
+					_ => "Ongoing",
+				}
+				.to_string()
+			}),
+			genres,
+			description: work["introduction"]
+				.as_str()
+				.map(|introduction| introduction.trim().to_string())
+				.filter(|introduction| !introduction.is_empty()),
+			origin: Some("JP".to_string()),
+			reviews: work["totalReviewCount"]
+				.as_u64()
+				.map(|reviews| reviews as usize),
+			chapters: work["publicEpisodeCount"]
+				.as_u64()
+				.map(|chapters| chapters as usize),
+			updated: parse_time(&work["lastEpisodePublishedAt"]),
+			..Metadata::default()
+		})
+	}
+}
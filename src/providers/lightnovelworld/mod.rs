@@ -0,0 +1,340 @@
+use crate::{
+	date,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use chrono::Local;
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper,
+};
+
+pub const NAME: &str = "lightnovelworld";
+
+/// Hosts the provider serves novels from, the mirrors share one layout.
+pub const HOSTS: [&str; 3] = [
+	"lightnovelworld.com",
+	"lightnovelpub.com",
+	"lightnovelworld.co",
+];
+
+const BASE_URL: &str = "https://www.lightnovelworld.com";
+
+lazy_static! {
+	static ref NOVEL_ITEM_RE: Regex =
+		Regex::new(r#"<li class="novel-item[^"]*"[^>]*>"#).unwrap();
+	static ref NOVEL_LINK_RE: Regex =
+		Regex::new(r#"<a[^>]*href="(/novel/[^"/]+)/?"[^>]*title="([^"]+)""#).unwrap();
+	static ref LATEST_CHAPTER_RE: Regex = Regex::new(
+		r#"<a[^>]*href="(/novel/[^"/]+/chapter-[^"]+)"[^>]*>[\S\s]*?<(?:span|strong) class="chapter-title">([\S\s]+?)</(?:span|strong)>"#
+	)
+	.unwrap();
+	static ref LATEST_TIME_RE: Regex = Regex::new(r#"<time[^>]*>([^<]+)</time>"#).unwrap();
+	static ref CHAPTER_ROW_RE: Regex = Regex::new(
+		r#"<a href="(/novel/[^"/]+/chapter-[^"]+)"[^>]*title="([^"]*)"[^>]*>[\S\s]*?<time[^>]*datetime="([^"]+)""#
+	)
+	.unwrap();
+	static ref LAST_PAGE_RE: Regex = Regex::new(r#"[?&]page=(\d+)"[^>]*>\s*(?:&gt;&gt;|»|Last)"#).unwrap();
+	static ref CHAPTER_TITLE_RE: Regex =
+		Regex::new(r#"<span class="chapter-title">([\S\s]+?)</span>"#).unwrap();
+	static ref CONTENT_RE: Regex =
+		Regex::new(r#"<div id="chapter-container"[^>]*>([\S\s]+?)</div>\s*(?:<div|</article)"#)
+			.unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<h1[^>]*class="novel-title[^"]*"[^>]*>([\S\s]+?)</h1>"#).unwrap();
+	static ref AUTHOR_RE: Regex =
+		Regex::new(r#"<span itemprop="author">([\S\s]+?)</span>"#).unwrap();
+	static ref CATEGORIES_RE: Regex =
+		Regex::new(r#"<div class="categories">([\S\s]+?)</div>"#).unwrap();
+	static ref LINK_TEXT_RE: Regex = Regex::new(r#"<a[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref DESCRIPTION_RE: Regex =
+		Regex::new(r#"<div class="content expand-wrapper">([\S\s]+?)</div>"#).unwrap();
+	static ref STATUS_RE: Regex =
+		Regex::new(r#"<strong[^>]*>\s*(Ongoing|Completed)\s*</strong>\s*<small>Status</small>"#)
+			.unwrap();
+	static ref RATING_RE: Regex =
+		Regex::new(r#"<strong>\s*([\d.]+)\s*</strong>\s*<span class="rating-star""#).unwrap();
+	static ref CHAPTERS_RE: Regex =
+		Regex::new(r#"<strong>\s*(?:<i[^>]*></i>\s*)?([\d,]+)\s*</strong>\s*<small>Chapters</small>"#)
+			.unwrap();
+	static ref COVER_RE: Regex =
+		Regex::new(r#"<figure class="cover">\s*<img[^>]*data-src="([^"]+)""#).unwrap();
+}
+
+/// Returns the url of the novel page `url` belongs to.
+///
+/// Chapter urls look like
+/// `https://www.lightnovelworld.com/novel/<slug>/chapter-<n>`, so only the
+/// first two path segments are kept.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	let path = url
+		.path_segments()
+		.map(|segments| segments.take(2).collect::<Vec<_>>().join("/"))
+		.unwrap_or_default();
+	novel.set_path(&path);
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Parses the novels of a listing like the ranking.
+pub(crate) fn novels(url: &Url, body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list: Vec<Novel> = vec![];
+	for novel in NOVEL_LINK_RE.captures_iter(body) {
+		let novel_url = url.join(novel.get(1).unwrap().as_str())?;
+		// Covers and titles both link to the novel
+		if ranobe_list.iter().any(|ranobe| ranobe.url == novel_url) {
+			continue;
+		}
+		ranobe_list.push(Novel::new(
+			to_markdown(novel.get(2).unwrap().as_str()),
+			novel_url,
+		));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct LightNovelWorld {
+	page: u32,
+}
+
+impl LightNovelWorld {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for LightNovelWorld {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			..Capabilities::default()
+		}
+	}
+	fn freshness_url(&self, url: &Url) -> Option<Url> {
+		Some(novel_url(url))
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/latest-updates-{}", BASE_URL, self.page))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		// Every novel lists the chapter it was last updated with
+		let now = Local::now();
+		let mut ranobe_list = vec![];
+		let starts = NOVEL_ITEM_RE
+			.find_iter(&body)
+			.map(|item| item.start())
+			.collect::<Vec<_>>();
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let item = &body[start..end];
+
+			let (novel, chapter) = match (
+				NOVEL_LINK_RE.captures(item),
+				LATEST_CHAPTER_RE.captures(item),
+			) {
+				(Some(novel), Some(chapter)) => (novel, chapter),
+				_ => continue,
+			};
+			ranobe_list.push(Chapter {
+				title: format!(
+					"{}: {}",
+					to_markdown(novel.get(2).unwrap().as_str()),
+					strip_tags(chapter.get(2).unwrap().as_str())
+				),
+				url: url.join(chapter.get(1).unwrap().as_str())?,
+				novel: Some(url.join(novel.get(1).unwrap().as_str())?),
+				index: None,
+				published: LATEST_TIME_RE
+					.captures(item)
+					.and_then(|time| date::parse(time.get(1).unwrap().as_str().trim(), now)),
+				lock: None,
+			});
+		}
+
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		if !CHAPTER_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "chapter title", &body));
+		}
+		let content = match CONTENT_RE.captures(&body) {
+			Some(content) => content.get(1).unwrap().as_str(),
+			None => return Err(selector_failed(NAME, &url, "chapter text", &body)),
+		};
+
+		let text = PARAGRAPH_RE
+			.captures_iter(content)
+			.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "chapter text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let genre = genre.map_or("all".to_string(), |genre| {
+			genre.trim().to_lowercase().replace(' ', "-")
+		});
+		let url = Url::parse(&format!(
+			"{}/genre/{}/popular/all/{}",
+			BASE_URL, genre, page
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		novels(&url, &body)
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/ranking", BASE_URL))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		novels(&url, &body)
+	}
+	/// Fetches the chapters of the novel `novel` belongs to, first to last.
+	///
+	/// The chapter list is split over pages of a hundred chapters.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let url = novel.url.clone();
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let novel = novel_url(&url);
+		let mut chapters = vec![];
+		let mut page = 1;
+		loop {
+			let list = Url::parse(&format!("{}/chapters?page={}", novel, page))?;
+			let body = fetch_url(client, list.clone()).await?;
+
+			let now = Local::now();
+			let before = chapters.len();
+			for chapter in CHAPTER_ROW_RE.captures_iter(&body) {
+				// Only the day of `datetime` attributes like `2023-02-01 14:03` is kept
+				let day = chapter.get(3).unwrap().as_str();
+				chapters.push(Chapter {
+					title: to_markdown(chapter.get(2).unwrap().as_str()),
+					url: list.join(chapter.get(1).unwrap().as_str())?,
+					novel: Some(novel.clone()),
+					index: Some(chapters.len()),
+					published: date::parse(day.get(..10).unwrap_or(day), now),
+					lock: None,
+				});
+			}
+
+			if chapters.len() == before {
+				if chapters.is_empty() {
+					return Err(selector_failed(NAME, &list, "chapter list", &body));
+				}
+				break;
+			}
+
+			let last = LAST_PAGE_RE
+				.captures(&body)
+				.and_then(|last| last.get(1).unwrap().as_str().parse().ok())
+				.unwrap_or(page);
+			if page >= last {
+				break;
+			}
+			page += 1;
+		}
+
+		Ok(chapters)
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = novel_url(&url);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "novel title", &body)),
+		}
+
+		metadata.author = AUTHOR_RE
+			.captures(&body)
+			.map(|author| strip_tags(author.get(1).unwrap().as_str()));
+		metadata.status = STATUS_RE
+			.captures(&body)
+			.map(|status| status.get(1).unwrap().as_str().to_string());
+		if let Some(categories) = CATEGORIES_RE.captures(&body) {
+			metadata.genres = LINK_TEXT_RE
+				.captures_iter(categories.get(1).unwrap().as_str())
+				.map(|genre| strip_tags(genre.get(1).unwrap().as_str()))
+				.collect();
+		}
+		metadata.description = DESCRIPTION_RE
+			.captures(&body)
+			.map(|description| strip_tags(description.get(1).unwrap().as_str()));
+		metadata.rating = RATING_RE
+			.captures(&body)
+			.and_then(|rating| rating.get(1).unwrap().as_str().parse().ok());
+		metadata.chapters = CHAPTERS_RE.captures(&body).and_then(|chapters| {
+			chapters
+				.get(1)
+				.unwrap()
+				.as_str()
+				.replace(',', "")
+				.parse()
+				.ok()
+		});
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = page.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		Ok(metadata)
+	}
+}
@@ -0,0 +1,241 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+use lazy_static::lazy_static;
+use surf::utils::async_trait;
+use surf::Url;
+
+use crate::config::Config;
+use crate::error::RanobeError;
+use crate::import::{self, Imported, LOCAL_PROVIDER};
+
+use super::{Capabilities, Chapter, Metadata, Novel, RanobeScraper};
+
+/// Name of the provider, the one imported novels are pinned to so they are
+/// read from their files as well.
+pub const NAME: &str = LOCAL_PROVIDER;
+
+lazy_static! {
+	/// Directory set in the configuration, `default_dir()` if unset.
+	static ref DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Returns the directory books are read from unless the configuration sets
+/// another, next to the configuration.
+pub fn default_dir() -> PathBuf {
+	Config::default_path().with_file_name("books")
+}
+
+/// Reads books from `dir` instead of `default_dir()`.
+pub fn set_dir(dir: Option<PathBuf>) {
+	*DIR.write().unwrap() = dir;
+}
+
+fn not_local(url: &Url) -> RanobeError {
+	RanobeError::NotFound(format!("{} is not a chapter of a local book.", url))
+}
+
+/// A book of the directory with when its file last changed.
+struct Book {
+	imported: Imported,
+	modified: Option<SystemTime>,
+}
+
+/// A provider for a directory of epub, txt and md files, so the books at
+/// hand are browsed and read like the novels of a site.
+#[derive(Debug, Clone)]
+pub struct LocalProvider {
+	dir: PathBuf,
+}
+
+impl LocalProvider {
+	/// Reads the books of the configured directory.
+	pub fn new() -> Result<Self, RanobeError> {
+		let dir = DIR.read().unwrap().clone().unwrap_or_else(default_dir);
+		Ok(Self::open(dir))
+	}
+
+	/// Reads the books of `dir`.
+	pub fn open<P: AsRef<Path>>(dir: P) -> Self {
+		Self {
+			dir: dir.as_ref().to_path_buf(),
+		}
+	}
+
+	/// Reads every book of the directory, most recently changed first.
+	fn books(&self) -> Result<Vec<Book>, RanobeError> {
+		let mut paths = match fs::read_dir(&self.dir) {
+			Ok(entries) => entries
+				.map(|entry| entry.map(|entry| entry.path()))
+				.collect::<std::io::Result<Vec<_>>>()?,
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err.into()),
+		};
+		paths.retain(|path| import::is_importable(path));
+		paths.sort();
+
+		let mut books = vec![];
+		for path in paths {
+			let modified = fs::metadata(&path)?.modified().ok();
+			for imported in import::read_path(&path)? {
+				books.push(Book { imported, modified });
+			}
+		}
+		books.sort_by_key(|book| std::cmp::Reverse(book.modified));
+
+		if books.is_empty() {
+			return Err(RanobeError::NotFound(format!(
+				"No epub, txt or md files in {}, add some or set local_dir in the configuration.",
+				self.dir.display()
+			)));
+		}
+		Ok(books)
+	}
+
+	/// Reads the book of the file `url` points into.
+	fn book(url: &Url) -> Result<Book, RanobeError> {
+		let path = url.to_file_path().map_err(|_| not_local(url))?;
+		let modified = fs::metadata(&path)?.modified().ok();
+		let imported = import::read_path(&path)?
+			.into_iter()
+			.next()
+			.ok_or_else(|| not_local(url))?;
+
+		Ok(Book { imported, modified })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for LocalProvider {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			catalogue: true,
+			..Capabilities::default()
+		}
+	}
+	fn request_interval(&self) -> Duration {
+		Duration::ZERO
+	}
+	/// Lists every chapter of every book, the books most recently changed
+	/// first.
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let mut ranobe_list = vec![];
+		for book in self.books()? {
+			let novel = &book.imported.title;
+			// Books are listed by their first chapter, like in the catalogue
+			let first = book
+				.imported
+				.chapters
+				.first()
+				.map(|(_, url, _)| url.clone());
+			for (i, (title, url, _)) in book.imported.chapters.iter().enumerate() {
+				ranobe_list.push(Chapter {
+					title: if title == novel {
+						title.clone()
+					} else {
+						format!("{}: {}", novel, title)
+					},
+					url: url.clone(),
+					novel: first.clone(),
+					index: Some(i),
+					published: book.modified,
+					lock: None,
+				});
+			}
+		}
+
+		Ok(ranobe_list)
+	}
+	/// Reads the chapter `url` points to, the first one of the book for the
+	/// url of the file itself.
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let book = Self::book(&url)?;
+		let chapter = match url.fragment() {
+			Some(_) => book
+				.imported
+				.chapters
+				.into_iter()
+				.find(|(_, chapter, _)| *chapter == url),
+			None => book.imported.chapters.into_iter().next(),
+		};
+
+		chapter
+			.map(|(_, _, text)| text)
+			.ok_or_else(|| not_local(&url))
+	}
+	/// Lists the books, all on the first page as they are read at once.
+	///
+	/// Books have no genres to pick from.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		if genre.is_some() || page > 1 {
+			return Ok(vec![]);
+		}
+
+		Ok(self
+			.books()?
+			.into_iter()
+			.filter_map(|book| {
+				let (_, url, _) = book.imported.chapters.first()?;
+				Some(Novel {
+					title: book.imported.title.clone(),
+					url: url.clone(),
+					updated: book.modified,
+				})
+			})
+			.collect())
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let book = Self::book(&url)?;
+
+		Ok(Metadata {
+			title: book.imported.title,
+			chapters: Some(book.imported.chapters.len()),
+			updated: book.modified,
+			..Metadata::default()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn reads_books_of_a_directory() {
+		let dir = TestDir::new("local");
+		fs::write(
+			dir.join("Overlord.md"),
+			"# Chapter 1\n\nOne.\n\n# Chapter 2\n\nTwo.\n",
+		)
+		.unwrap();
+		fs::write(dir.join("cover.jpg"), "").unwrap();
+
+		let mut provider = LocalProvider::open(&dir);
+		let latest = async_std::task::block_on(provider.get_latest()).unwrap();
+		let titles = latest
+			.iter()
+			.map(|ranobe| ranobe.title.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(titles, vec!["Overlord: Chapter 1", "Overlord: Chapter 2"]);
+
+		let text = async_std::task::block_on(provider.get_text(latest[1].url.clone()));
+		assert_eq!(text.unwrap(), "Two.");
+		let metadata = async_std::task::block_on(provider.get_metadata(latest[1].url.clone()));
+		assert_eq!(metadata.unwrap().chapters, Some(2));
+
+		assert!(
+			async_std::task::block_on(LocalProvider::open(dir.join("missing")).get_latest())
+				.is_err()
+		);
+	}
+}
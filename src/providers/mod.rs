@@ -1,28 +1,1032 @@
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::{Duration, SystemTime};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use surf::utils::async_trait;
 use surf::Url;
 
+use crate::error::RanobeError;
+use crate::keyring::Credentials;
+use crate::text::paragraphs;
+use crate::utils::italicize;
+
+#[cfg(feature = "ao3")]
+pub mod ao3;
+pub mod declarative;
+#[cfg(feature = "kakuyomu")]
+pub mod kakuyomu;
+#[cfg(feature = "lightnovelworld")]
+pub mod lightnovelworld;
+pub mod local;
+#[cfg(feature = "novelfull")]
+pub mod novelfull;
+#[cfg(feature = "novelupdates")]
+pub mod novelupdates;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "readlightnovel")]
 pub mod readlightnovel;
+pub mod registry;
+#[cfg(feature = "royalroad")]
+pub mod royalroad;
+#[cfg(feature = "scribblehub")]
+pub mod scribblehub;
+pub mod script;
+#[cfg(feature = "syosetu")]
+pub mod syosetu;
+#[cfg(feature = "webnovel")]
+pub mod webnovel;
 
-#[derive(Debug, Clone)]
-pub struct Ranobe {
+/// A novel as a provider lists it, in its search results or rankings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Novel {
 	pub title: String,
 	pub url: Url,
+	/// When the listing says the novel was last updated.
+	pub updated: Option<SystemTime>,
 }
 
-#[async_trait]
-pub trait RanobeScraper {
-	async fn get_latest(&mut self) -> Result<Vec<Ranobe>, surf::Error>;
-	async fn get_next_page(id: &str, page: &u32) -> Result<String, surf::Error>;
-	async fn get_prev_page(id: &str, page: &u32) -> Result<String, surf::Error>;
-	async fn get_list(html: &str) -> Result<String, surf::Error>;
-	async fn get_text(&self, url: Url) -> Result<String, surf::Error>;
+/// A chapter as a provider lists it, in the table of contents of its novel
+/// or the latest updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+	pub title: String,
+	pub url: Url,
+	/// Url of the novel the chapter belongs to, when the listing links it.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub novel: Option<Url>,
+	/// Position of the chapter in the table of contents of its novel,
+	/// counted from zero.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub index: Option<usize>,
+	/// When the listing says the chapter was published, or last updated.
+	#[serde(default, alias = "updated")]
+	pub published: Option<SystemTime>,
+	/// Whether the chapter is locked, on sites where paying readers get
+	/// chapters first.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub lock: Option<Lock>,
+}
+
+/// What novels and chapters have in common, for code listing either.
+pub trait Listed: Clone {
+	/// Returns the entry titled `title` at `url`, with nothing else known.
+	fn new(title: String, url: Url) -> Self;
+	fn title(&self) -> &str;
+	fn url(&self) -> &Url;
+	/// When the listing says the entry was published, or last updated.
+	fn updated(&self) -> Option<SystemTime>;
+}
+
+impl Listed for Novel {
+	fn new(title: String, url: Url) -> Self {
+		Self {
+			title,
+			url,
+			updated: None,
+		}
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+	fn url(&self) -> &Url {
+		&self.url
+	}
+	fn updated(&self) -> Option<SystemTime> {
+		self.updated
+	}
 }
 
-impl Ranobe {
-	pub async fn new(title: String, url: &str) -> Result<Self, surf::Error> {
-		Ok(Self {
+impl Listed for Chapter {
+	fn new(title: String, url: Url) -> Self {
+		Self {
 			title,
-			url: Url::parse(url)?,
+			url,
+			novel: None,
+			index: None,
+			published: None,
+			lock: None,
+		}
+	}
+	fn title(&self) -> &str {
+		&self.title
+	}
+	fn url(&self) -> &Url {
+		&self.url
+	}
+	fn updated(&self) -> Option<SystemTime> {
+		self.published
+	}
+}
+
+impl Novel {
+	/// Returns the `index`th chapter of the novel, titled `title` at `url`.
+	pub fn chapter(&self, index: usize, title: String, url: Url) -> Chapter {
+		Chapter {
+			novel: Some(self.url.clone()),
+			index: Some(index),
+			..Chapter::new(title, url)
+		}
+	}
+}
+
+/// Until when a chapter is locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lock {
+	/// Until it is bought.
+	Paid,
+	/// Until it becomes free at that time.
+	Until(SystemTime),
+}
+
+/// What a provider can serve, so modes it can not serve are hidden rather
+/// than failing once picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+	/// Lists its latest updates.
+	pub latest: bool,
+	/// Searches novels by title.
+	pub search: bool,
+	/// Lists the chapters of a novel.
+	pub toc: bool,
+	/// Logs in with an account, to read what only members can.
+	pub login: bool,
+	/// Has covers in the metadata of novels.
+	pub covers: bool,
+	/// Has a catalogue to pick novels from.
+	pub catalogue: bool,
+	/// Ranks its novels by popularity.
+	pub ranking: bool,
+	/// Lists the other works of an author.
+	pub by_author: bool,
+	/// Lists its novels by the letter their title starts with.
+	pub index: bool,
+}
+
+impl Default for Capabilities {
+	/// Every provider lists its latest updates unless it says otherwise.
+	fn default() -> Self {
+		Self {
+			latest: true,
+			search: false,
+			toc: false,
+			login: false,
+			covers: false,
+			catalogue: false,
+			ranking: false,
+			by_author: false,
+			index: false,
+		}
+	}
+}
+
+impl Capabilities {
+	/// Returns the names of what the provider can serve.
+	pub fn names(&self) -> Vec<&'static str> {
+		[
+			(self.latest, "latest"),
+			(self.search, "search"),
+			(self.toc, "toc"),
+			(self.login, "login"),
+			(self.covers, "covers"),
+			(self.catalogue, "catalogue"),
+			(self.ranking, "ranking"),
+			(self.by_author, "by author"),
+			(self.index, "index"),
+		]
+		.into_iter()
+		.filter(|(supported, _)| *supported)
+		.map(|(_, name)| name)
+		.collect()
+	}
+}
+
+/// A volume of a novel, with the chapters its table of contents lists
+/// under it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Volume {
+	pub title: String,
+	/// Canonical urls of the chapters of the volume, first to last.
+	pub chapters: Vec<Url>,
+}
+
+/// Another series related to a novel, like a sequel or a spin-off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Related {
+	pub title: String,
+	/// How the series relates to the novel, as named by the provider.
+	pub relation: String,
+	pub url: Option<Url>,
+}
+
+impl Related {
+	pub fn is_sequel(&self) -> bool {
+		self.relation.eq_ignore_ascii_case("sequel")
+	}
+}
+
+/// Extra information about a novel that listing pages do not carry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+	pub title: String,
+	/// Other titles of the novel, like its original title or romanization.
+	#[serde(default)]
+	pub alternative_titles: Vec<String>,
+	pub author: Option<String>,
+	pub status: Option<String>,
+	pub genres: Vec<String>,
+	pub description: Option<String>,
+	pub cover: Option<Url>,
+	/// Country the novel was originally published in, e.g. `CN`, `KR`, `JP`.
+	#[serde(default)]
+	pub origin: Option<String>,
+	/// Group translating the novel.
+	#[serde(default)]
+	pub translator: Option<String>,
+	/// Average community rating, out of 5.
+	#[serde(default)]
+	pub rating: Option<f32>,
+	/// Number of community ratings or reviews.
+	#[serde(default)]
+	pub reviews: Option<usize>,
+	/// Related series, for providers that list them.
+	#[serde(default)]
+	pub related: Vec<Related>,
+	/// Number of chapters released so far.
+	pub chapters: Option<usize>,
+	/// When the last chapter was released.
+	pub updated: Option<SystemTime>,
+}
+
+/// A reader comment on a chapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+	pub author: String,
+	pub body: String,
+	pub likes: Option<u32>,
+}
+
+impl Comment {
+	/// Renders `comments` as quoted Markdown, one quote per comment.
+	pub fn to_markdown(comments: &[Comment]) -> String {
+		comments
+			.iter()
+			.map(|comment| {
+				let likes = comment
+					.likes
+					.map_or(String::new(), |likes| format!(" ({} likes)", likes));
+				let body = comment
+					.body
+					.lines()
+					.map(|line| format!("> {}", line).trim_end().to_string())
+					.collect::<Vec<_>>()
+					.join("\n");
+				format!("**{}**{}\n\n{}", comment.author, likes, body)
+			})
+			.collect::<Vec<_>>()
+			.join("\n\n")
+	}
+}
+
+/// A paragraph of a chapter with the html node it was extracted from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Paragraph {
+	pub text: String,
+	/// Selector of the node in the chapter text, like `p:nth-of-type(3)`.
+	pub source: Option<String>,
+}
+
+impl Paragraph {
+	/// Quotes the paragraph as Markdown, citing the `index`th paragraph,
+	/// counted from zero, of `chapter` of `novel`.
+	pub fn cite(&self, index: usize, novel: &str, chapter: &Chapter) -> String {
+		let quote = self
+			.text
+			.lines()
+			.map(|line| format!("> {}", line).trim_end().to_string())
+			.collect::<Vec<_>>()
+			.join("\n");
+		let source = self
+			.source
+			.as_ref()
+			.map_or(String::new(), |source| format!(" <!-- {} -->", source));
+
+		format!(
+			"{}\n>\n> — *{}*, [{}]({}), paragraph {}{}",
+			quote,
+			novel,
+			chapter.title,
+			chapter.url,
+			index + 1,
+			source
+		)
+	}
+}
+
+/// Returns the names of the providers compiled into the crate.
+pub fn available() -> Vec<&'static str> {
+	vec![
+		#[cfg(feature = "readlightnovel")]
+		readlightnovel::NAME,
+		#[cfg(feature = "royalroad")]
+		royalroad::NAME,
+		#[cfg(feature = "scribblehub")]
+		scribblehub::NAME,
+		#[cfg(feature = "novelupdates")]
+		novelupdates::NAME,
+		#[cfg(feature = "webnovel")]
+		webnovel::NAME,
+		#[cfg(feature = "lightnovelworld")]
+		lightnovelworld::NAME,
+		#[cfg(feature = "novelfull")]
+		novelfull::NAME,
+		#[cfg(feature = "syosetu")]
+		syosetu::NAME,
+		#[cfg(feature = "kakuyomu")]
+		kakuyomu::NAME,
+		#[cfg(feature = "ao3")]
+		ao3::NAME,
+		local::NAME,
+	]
+}
+
+/// Returns the hosts of every built-in provider.
+fn all_hosts() -> Vec<(&'static str, &'static [&'static str])> {
+	vec![
+		#[cfg(feature = "readlightnovel")]
+		(readlightnovel::NAME, &readlightnovel::HOSTS),
+		#[cfg(feature = "royalroad")]
+		(royalroad::NAME, &royalroad::HOSTS),
+		#[cfg(feature = "scribblehub")]
+		(scribblehub::NAME, &scribblehub::HOSTS),
+		#[cfg(feature = "novelupdates")]
+		(novelupdates::NAME, &novelupdates::HOSTS),
+		#[cfg(feature = "webnovel")]
+		(webnovel::NAME, &webnovel::HOSTS),
+		#[cfg(feature = "lightnovelworld")]
+		(lightnovelworld::NAME, &lightnovelworld::HOSTS),
+		#[cfg(feature = "novelfull")]
+		(novelfull::NAME, &novelfull::HOSTS),
+		#[cfg(feature = "syosetu")]
+		(syosetu::NAME, &syosetu::HOSTS),
+		#[cfg(feature = "kakuyomu")]
+		(kakuyomu::NAME, &kakuyomu::HOSTS),
+		#[cfg(feature = "ao3")]
+		(ao3::NAME, &ao3::HOSTS),
+	]
+}
+
+/// Returns the hosts the built-in provider `name` serves novels from.
+pub fn hosts(name: &str) -> &'static [&'static str] {
+	all_hosts()
+		.into_iter()
+		.find(|(provider, _)| *provider == name)
+		.map_or(&[], |(_, hosts)| hosts)
+}
+
+/// Returns the name of the provider serving `url`, by its hostname.
+pub fn from_url(url: &Url) -> Option<&'static str> {
+	let host = url.host_str()?;
+
+	all_hosts()
+		.into_iter()
+		.find(|(_, hosts)| {
+			hosts
+				.iter()
+				.any(|h| host == *h || host.ends_with(&format!(".{}", h)))
 		})
+		.map(|(name, _)| name)
+}
+
+/// Returns the letter of the alphabetical index `title` is listed under,
+/// `#` for titles that do not start with a latin letter.
+pub fn index_letter(title: &str) -> char {
+	match title.chars().find(|chr| chr.is_alphanumeric()) {
+		Some(chr) if chr.is_ascii_alphabetic() => chr.to_ascii_uppercase(),
+		_ => '#',
+	}
+}
+
+/// Returns the form titles are compared in, so titles differing only in
+/// case, spacing or punctuation are the same.
+pub fn title_key(title: &str) -> String {
+	title
+		.chars()
+		.filter(|c| c.is_alphanumeric())
+		.flat_map(char::to_lowercase)
+		.collect()
+}
+
+lazy_static! {
+	static ref EMPHASIS_RE: Regex = Regex::new(r#"</?(?:em|i)>"#).unwrap();
+	static ref STRONG_RE: Regex = Regex::new(r#"</?(?:strong|b)>"#).unwrap();
+	static ref BREAK_RE: Regex = Regex::new(r#"<br\s*/?>"#).unwrap();
+	static ref TAG_RE: Regex = Regex::new(r#"<[^>]+>"#).unwrap();
+}
+
+/// Strips every html tag from `html` and collapses the remaining whitespace.
+pub fn strip_tags(html: &str) -> String {
+	TAG_RE
+		.replace_all(html, " ")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Turns the html of a paragraph into Markdown.
+///
+/// Furigana are kept after their kanji, as `<rp>` wraps them in
+/// parentheses.
+pub fn to_markdown(html: &str) -> String {
+	let text = EMPHASIS_RE.replace_all(html, "_");
+	let text = STRONG_RE.replace_all(&text, "**");
+	let text = BREAK_RE.replace_all(&text, "\n");
+	let text = TAG_RE.replace_all(&text, "");
+
+	text.replace("&nbsp;", " ")
+		.replace("&#39;", "'")
+		.replace("&#8217;", "’")
+		.replace("&quot;", "\"")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&amp;", "&")
+		.trim()
+		.to_string()
+}
+
+/// Highlights the text inside double quotes of a chapter, the dialogue.
+pub fn highlight_quotes(text: &str) -> String {
+	italicize(&text.to_string())
+}
+
+/// Query parameters that only track where a link was followed from.
+const TRACKING_PARAMS: [&str; 6] = ["fbclid", "gclid", "ref", "source", "spm", "_ga"];
+
+/// Returns the canonical form of `url`, so a chapter reached through
+/// different links is stored and counted once.
+///
+/// Tracking parameters and the fragment are dropped, http is upgraded to
+/// https and trailing slashes are removed before the provider serving `url`
+/// applies its own rules.
+pub fn canonical_url(url: &Url) -> Url {
+	let mut url = url.clone();
+	if !matches!(url.scheme(), "http" | "https") {
+		return url;
+	}
+	let _ = url.set_scheme("https");
+	url.set_fragment(None);
+
+	let query = url
+		.query_pairs()
+		.filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+		.map(|(key, value)| (key.into_owned(), value.into_owned()))
+		.collect::<Vec<_>>();
+	if query.is_empty() {
+		url.set_query(None);
+	} else {
+		url.query_pairs_mut().clear().extend_pairs(query);
+	}
+
+	let path = url.path().trim_end_matches('/').to_string();
+	url.set_path(if path.is_empty() { "/" } else { &path });
+
+	match from_url(&url) {
+		#[cfg(feature = "readlightnovel")]
+		Some(readlightnovel::NAME) => readlightnovel::canonical_url(url),
+		#[cfg(feature = "royalroad")]
+		Some(royalroad::NAME) => royalroad::canonical_url(url),
+		#[cfg(feature = "syosetu")]
+		Some(syosetu::NAME) => syosetu::canonical_url(url),
+		_ => url,
+	}
+}
+
+/// A future of a fetch started by `bounded`.
+type Fetch<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Runs `fetches` with at most `concurrency` of them at once, starting one
+/// every `interval`, and returns their results in the order of `fetches`.
+async fn bounded<'a, T>(
+	fetches: Vec<Fetch<'a, T>>,
+	concurrency: usize,
+	interval: Duration,
+) -> Vec<T> {
+	let mut pending = fetches.into_iter().enumerate().peekable();
+	let mut running: Vec<(usize, Fetch<'a, T>)> = vec![];
+	let mut results: Vec<Option<T>> = vec![];
+	// Waits out the interval since the last fetch started
+	let mut timer: Option<Fetch<'static, ()>> = None;
+
+	while pending.peek().is_some() || !running.is_empty() {
+		if timer.is_none() && running.len() < concurrency.max(1) {
+			if let Some(fetch) = pending.next() {
+				running.push(fetch);
+				results.push(None);
+				timer = Some(Box::pin(async_std::task::sleep(interval)));
+			}
+		}
+
+		poll_fn(|cx| {
+			let mut progressed = false;
+			if let Some(sleep) = &mut timer {
+				if sleep.as_mut().poll(cx).is_ready() {
+					timer = None;
+					progressed = true;
+				}
+			}
+			running.retain_mut(|(i, fetch)| match fetch.as_mut().poll(cx) {
+				Poll::Ready(result) => {
+					results[*i] = Some(result);
+					progressed = true;
+					false
+				}
+				Poll::Pending => true,
+			});
+
+			if progressed {
+				Poll::Ready(())
+			} else {
+				Poll::Pending
+			}
+		})
+		.await;
+	}
+
+	results.into_iter().flatten().collect()
+}
+
+/// Clones a provider behind a trait object, as `Clone` itself would make
+/// `RanobeScraper` unusable as one.
+pub trait CloneScraper {
+	fn clone_boxed(&self) -> registry::Provider;
+}
+
+impl<T: RanobeScraper + Clone + Send + Sync + 'static> CloneScraper for T {
+	fn clone_boxed(&self) -> registry::Provider {
+		Box::new(self.clone())
+	}
+}
+
+/// A site novels are read from.
+///
+/// Every method takes the provider itself, so providers picked at runtime
+/// are held as a `registry::Provider`, which implements the trait too.
+#[async_trait]
+pub trait RanobeScraper: CloneScraper {
+	/// Name of the provider, as passed to `--provider`.
+	fn name(&self) -> &'static str;
+	/// What the provider can serve besides the text of chapters.
+	fn capabilities(&self) -> Capabilities {
+		Capabilities::default()
+	}
+	/// How long to wait between two requests of a bulk operation.
+	fn request_interval(&self) -> Duration {
+		Duration::from_secs(1)
+	}
+	/// Asks for listings of `size` entries a page and returns whether the
+	/// provider can list that many at once.
+	///
+	/// Providers with a fixed page size keep it, so longer listings are
+	/// fetched over several pages.
+	fn set_page_size(&mut self, _size: usize) -> bool {
+		false
+	}
+	/// Page of the latest updates the next `get_latest` fetches, counted
+	/// from zero, for providers that can go back to an earlier page.
+	fn latest_page(&self) -> Option<u32> {
+		None
+	}
+	/// Makes the next `get_latest` fetch `page` of the latest updates and
+	/// returns whether the provider can go to any page.
+	fn set_latest_page(&mut self, _page: u32) -> bool {
+		false
+	}
+	/// Whether an entry of the latest updates is a chapter update rather
+	/// than something the feed mixes in, like a site announcement.
+	fn is_update(&self, _chapter: &Chapter) -> bool {
+		true
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError>;
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError>;
+	/// Fetches the texts of the chapters at `urls`, `concurrency` of them at
+	/// once, in the order of `urls`.
+	///
+	/// Requests start `request_interval` apart however many are running, so
+	/// the provider sees the same rate as fetching one chapter after the
+	/// other but slow responses do not hold the next ones up.
+	async fn get_texts(
+		&self,
+		urls: &[Url],
+		concurrency: usize,
+	) -> Vec<Result<String, RanobeError>> {
+		let fetches = urls
+			.iter()
+			.map(|url| -> Fetch<'_, _> { Box::pin(self.get_text(url.clone())) })
+			.collect();
+		bounded(fetches, concurrency, self.request_interval()).await
+	}
+	/// Fetches the paragraphs of the chapter at `url` with the html nodes
+	/// they came from.
+	///
+	/// Providers that do not track the nodes return the paragraphs of
+	/// `get_text` without a source.
+	async fn get_paragraphs(&self, url: Url) -> Result<Vec<Paragraph>, RanobeError> {
+		let text = self.get_text(url).await?;
+		Ok(paragraphs(&text)
+			.map(|text| Paragraph {
+				text: text.to_string(),
+				source: None,
+			})
+			.collect())
+	}
+	/// Fetches a page of the catalogue, or of the novels of `genre`.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError>;
+	/// Fetches the top comments of the chapter at `url`, best first.
+	///
+	/// Providers that do not host comments return an empty list.
+	async fn get_comments(&self, _url: Url) -> Result<Vec<Comment>, RanobeError> {
+		Ok(vec![])
+	}
+	/// Fetches the most popular novels of the provider, most popular first.
+	///
+	/// Providers without a ranking return an empty list.
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		Ok(vec![])
+	}
+	/// Fetches the novels written by `author`, as named in the metadata of
+	/// the provider.
+	///
+	/// Providers without author pages return an empty list.
+	async fn get_by_author(&self, _author: &str) -> Result<Vec<Novel>, RanobeError> {
+		Ok(vec![])
+	}
+	/// Fetches the volumes the table of contents of the novel `url` belongs
+	/// to is split into, first to last.
+	///
+	/// Providers whose tables of contents are not split into volumes return
+	/// an empty list.
+	async fn get_volumes(&self, _url: Url) -> Result<Vec<Volume>, RanobeError> {
+		Ok(vec![])
+	}
+	/// Returns the page that changes whenever the novel `url` belongs to
+	/// gets a new chapter, like its table of contents or a feed, so a HEAD
+	/// request tells whether it has to be fetched again.
+	///
+	/// Providers whose pages do not send validators return `None`.
+	fn freshness_url(&self, _url: &Url) -> Option<Url> {
+		None
+	}
+	/// Fetches the table of contents of the novel `novel` belongs to, first
+	/// chapter to last, so any chapter can be read rather than only the
+	/// latest ones.
+	///
+	/// `novel` can either point at the novel itself or at one of its
+	/// chapters. Providers without a table of contents fail.
+	async fn get_chapters(&self, _novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		Err(RanobeError::Unsupported(format!(
+			"{} can not list the chapters of a novel.",
+			self.name()
+		)))
+	}
+	/// Fetches the novels of the alphabetical index under `letter`, as
+	/// `index_letter` tells, in the order of their titles.
+	///
+	/// Providers without an index fail.
+	async fn get_by_letter(&self, _letter: char) -> Result<Vec<Novel>, RanobeError> {
+		Err(RanobeError::Unsupported(format!(
+			"{} has no index of its novels.",
+			self.name()
+		)))
+	}
+	/// Searches the novels of the provider whose title matches `query`,
+	/// best match first.
+	///
+	/// Providers without a search fail.
+	async fn search(&self, _query: &str) -> Result<Vec<Novel>, RanobeError> {
+		Err(RanobeError::Unsupported(format!(
+			"{} can not search its novels.",
+			self.name()
+		)))
+	}
+	/// Logs in with `credentials`, the session lasting across runs in the
+	/// cookies of the HTTP client.
+	///
+	/// Providers without accounts fail.
+	async fn login(&self, _credentials: &Credentials) -> Result<(), RanobeError> {
+		Err(RanobeError::Unsupported(format!(
+			"{} has no accounts to log in with.",
+			self.name()
+		)))
+	}
+	/// Fetches the metadata of the novel `url` belongs to.
+	///
+	/// `url` can either point at the novel itself or at one of its chapters.
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError>;
+	/// Counts what the patterns of the provider match on its pages, by what
+	/// they scrape, for `ranobe doctor` to notice the markup drifting before
+	/// the provider breaks.
+	///
+	/// Providers without patterns of their own count their latest updates.
+	async fn probe(&self) -> Result<Vec<(&'static str, usize)>, RanobeError> {
+		if !self.capabilities().latest {
+			return Ok(vec![]);
+		}
+		let mut provider = self.clone_boxed();
+		Ok(vec![("latest updates", provider.get_latest().await?.len())])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn files_titles_under_index_letters() {
+		assert_eq!(index_letter("overlord"), 'O');
+		assert_eq!(index_letter("\"Zero\" Kara"), 'Z');
+		assert_eq!(index_letter("86: Eighty-Six"), '#');
+		assert_eq!(index_letter("全職高手"), '#');
+	}
+
+	#[test]
+	fn canonicalizes_urls() {
+		let canonical = |url| canonical_url(&Url::parse(url).unwrap()).to_string();
+
+		assert_eq!(
+			canonical("http://novelfull.com/a-novel/chapter-1.html?utm_source=x&page=2#top"),
+			"https://novelfull.com/a-novel/chapter-1.html?page=2"
+		);
+		assert_eq!(
+			canonical("https://kakuyomu.jp/works/1/episodes/2/?ref=home"),
+			"https://kakuyomu.jp/works/1/episodes/2"
+		);
+		assert_eq!(canonical("file:///tmp/a/"), "file:///tmp/a/");
+
+		#[cfg(feature = "readlightnovel")]
+		assert_eq!(
+			canonical("https://readlightnovel.today/a-novel/chapter-1"),
+			"https://www.readlightnovel.me/a-novel/chapter-1"
+		);
+		#[cfg(feature = "royalroad")]
+		assert_eq!(
+			canonical("https://royalroad.com/fiction/1/a/"),
+			"https://www.royalroad.com/fiction/1/a"
+		);
+		#[cfg(feature = "syosetu")]
+		assert_eq!(
+			canonical("http://ncode.syosetu.com/N1234AB/5"),
+			"https://ncode.syosetu.com/n1234ab/5/"
+		);
+	}
+
+	#[test]
+	fn reads_chapters_saved_before_the_split() {
+		let chapter: Chapter = serde_json::from_str(
+			r#"{"title":"Chapter 1","url":"https://example.com/a/1","updated":null}"#,
+		)
+		.unwrap();
+		assert!(chapter.novel.is_none() && chapter.published.is_none());
+
+		let novel = Novel::new(
+			"A".to_string(),
+			Url::parse("https://example.com/a").unwrap(),
+		);
+		let chapter = novel.chapter(0, chapter.title, chapter.url);
+		assert_eq!(chapter.novel.as_ref(), Some(&novel.url));
+		assert_eq!(chapter.index, Some(0));
+	}
+
+	#[test]
+	fn names_capabilities() {
+		assert_eq!(Capabilities::default().names(), vec!["latest"]);
+		let capabilities = Capabilities {
+			latest: false,
+			toc: true,
+			by_author: true,
+			..Capabilities::default()
+		};
+		assert_eq!(capabilities.names(), vec!["toc", "by author"]);
+	}
+
+	#[test]
+	fn bounds_concurrent_fetches() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+
+		let running = AtomicUsize::new(0);
+		let most = AtomicUsize::new(0);
+		let fetches = (0..6u64).map(|i| -> Fetch<'_, u64> {
+			let (running, most) = (&running, &most);
+			Box::pin(async move {
+				let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+				most.fetch_max(now, Ordering::SeqCst);
+				// Later fetches finish first
+				async_std::task::sleep(Duration::from_millis(30 - i * 5)).await;
+				running.fetch_sub(1, Ordering::SeqCst);
+				i
+			})
+		});
+		let fetches = fetches.collect();
+
+		let results = async_std::task::block_on(bounded(fetches, 2, Duration::ZERO));
+		assert_eq!(results, vec![0, 1, 2, 3, 4, 5]);
+		assert_eq!(most.load(Ordering::SeqCst), 2);
+	}
+
+	/// Returns the titles and urls of `entries`, to compare them at once.
+	#[allow(dead_code)]
+	fn listed<E: Listed>(entries: &[E]) -> Vec<(&str, &str)> {
+		entries
+			.iter()
+			.map(|entry| (entry.title(), entry.url().as_str()))
+			.collect()
+	}
+
+	#[cfg(feature = "royalroad")]
+	#[test]
+	fn parses_royalroad_listings() {
+		let url = Url::parse("https://www.royalroad.com/fictions/rising-stars").unwrap();
+		let body = include_str!("../../tests/fixtures/royalroad-rising-stars.html");
+		let novels = async_std::task::block_on(royalroad::fictions(&url, body)).unwrap();
+		assert_eq!(
+			listed(&novels),
+			vec![
+				(
+					"Mother of Learning",
+					"https://www.royalroad.com/fiction/21220/mother-of-learning"
+				),
+				(
+					"The Wandering Inn",
+					"https://www.royalroad.com/fiction/10073/the-wandering-inn"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "scribblehub")]
+	#[test]
+	fn parses_scribblehub_listings() {
+		let body = include_str!("../../tests/fixtures/scribblehub-ranking.html");
+		let novels = async_std::task::block_on(scribblehub::series(body)).unwrap();
+		assert_eq!(
+			listed(&novels),
+			vec![
+				(
+					"The Villainess Wants to Retire",
+					"https://www.scribblehub.com/series/123456/the-villainess/"
+				),
+				(
+					"Reborn as a Slime",
+					"https://www.scribblehub.com/series/654321/reborn-as-a-slime/"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "novelupdates")]
+	#[test]
+	fn parses_novelupdates_releases() {
+		let url = Url::parse("https://www.novelupdates.com/").unwrap();
+		let body = include_str!("../../tests/fixtures/novelupdates-releases.html");
+		let releases = novelupdates::releases(&url, body).unwrap();
+		assert_eq!(
+			listed(&releases),
+			vec![
+				(
+					"Lord of the Mysteries c12 (Webnovel)",
+					"https://www.novelupdates.com/extnu/5436001/"
+				),
+				(
+					"The Beginning After the End v2c3 part1",
+					"https://www.novelupdates.com/extnu/5436002/"
+				),
+			]
+		);
+		assert_eq!(
+			releases[0].novel.as_ref().map(Url::as_str),
+			Some("https://www.novelupdates.com/series/lord-of-the-mysteries/")
+		);
+	}
+
+	#[cfg(feature = "webnovel")]
+	#[test]
+	fn parses_webnovel_listings() {
+		let body = include_str!("../../tests/fixtures/webnovel-ranking.html");
+		let novels = webnovel::books(body).unwrap();
+		assert_eq!(
+			listed(&novels),
+			vec![
+				(
+					"The King's Avatar",
+					"https://www.webnovel.com/book/the-kings-avatar_7176992105000305"
+				),
+				(
+					"Library of Heaven's Path",
+					"https://www.webnovel.com/book/library-of-heavens-path_7853880705001905"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "lightnovelworld")]
+	#[test]
+	fn parses_lightnovelworld_listings() {
+		let url = Url::parse("https://www.lightnovelworld.com/ranking").unwrap();
+		let body = include_str!("../../tests/fixtures/lightnovelworld-ranking.html");
+		let novels = lightnovelworld::novels(&url, body).unwrap();
+		assert_eq!(
+			listed(&novels),
+			vec![
+				(
+					"Shadow Slave",
+					"https://www.lightnovelworld.com/novel/shadow-slave"
+				),
+				(
+					"Martial Peak & Beyond",
+					"https://www.lightnovelworld.com/novel/martial-peak"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "novelfull")]
+	#[test]
+	fn parses_novelfull_listings() {
+		let url = Url::parse("https://novelfull.com/most-popular").unwrap();
+		let body = include_str!("../../tests/fixtures/novelfull-most-popular.html");
+		let novels = novelfull::novels(&url, body).unwrap();
+		assert_eq!(
+			listed(&novels),
+			vec![
+				(
+					"Release That Witch",
+					"https://novelfull.com/release-that-witch.html"
+				),
+				(
+					"A Will Eternal",
+					"https://novelfull.com/a-will-eternal.html"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "syosetu")]
+	#[test]
+	fn parses_syosetu_chapters() {
+		let body = include_str!("../../tests/fixtures/syosetu-chapter.html");
+		assert_eq!(
+			syosetu::chapter_text(body),
+			"前書きです。\n\n* * *\n\n\
+			 目が覚めると、異世界(いせかい)だった。\n\n「ここはどこだ？」\n\n* * *\n\n\
+			 読んでいただきありがとうございます。"
+		);
+	}
+
+	#[cfg(feature = "kakuyomu")]
+	#[test]
+	fn parses_kakuyomu_tables_of_contents() {
+		let id = "1177354054880238351";
+		let body = include_str!("../../tests/fixtures/kakuyomu-work.html");
+		let state = kakuyomu::apollo_state(body).unwrap();
+
+		let episodes = kakuyomu::episodes(&state, id).unwrap();
+		assert_eq!(
+			episodes
+				.iter()
+				.map(|episode| (episode.title.as_str(), episode.index))
+				.collect::<Vec<_>>(),
+			vec![
+				("プロローグ", Some(0)),
+				("第1話　出会い", Some(1)),
+				("第2話　別れ", Some(2)),
+			]
+		);
+		assert_eq!(
+			episodes[1].url.as_str(),
+			"https://kakuyomu.jp/works/1177354054880238351/episodes/1177354054880238401"
+		);
+
+		// The prologue is listed before the first chapter
+		let volumes = kakuyomu::volumes(&state, id).unwrap();
+		assert_eq!(volumes.len(), 1);
+		assert_eq!(volumes[0].title, "第一章　旅立ち");
+		assert_eq!(volumes[0].chapters.len(), 2);
+	}
+
+	#[cfg(feature = "ao3")]
+	#[test]
+	fn parses_ao3_chapters() {
+		let body = include_str!("../../tests/fixtures/ao3-chapter.html");
+		assert_eq!(
+			ao3::chapter_text(body).unwrap(),
+			"The rain had not _stopped_ for days.\n\nShe opened the door.\nNobody was there."
+		);
 	}
 }
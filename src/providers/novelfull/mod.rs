@@ -0,0 +1,364 @@
+use crate::{
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper,
+};
+
+pub const NAME: &str = "novelfull";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["novelfull.com"];
+
+const BASE_URL: &str = "https://novelfull.com";
+
+lazy_static! {
+	static ref NOVEL_ITEM_RE: Regex =
+		Regex::new(r#"<div class="row"[^>]*itemtype="https://schema.org/Book"[^>]*>"#).unwrap();
+	static ref NOVEL_LINK_RE: Regex =
+		Regex::new(r#"<h3 class="truyen-title"[^>]*>\s*<a href="([^"]+)"[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref LATEST_CHAPTER_RE: Regex = Regex::new(
+		r#"<a href="(/[^"/]+/[^"/]+\.html)"[^>]*>\s*<span class="chapter-text">([\S\s]+?)</span>"#
+	)
+	.unwrap();
+	static ref CHAPTER_ROW_RE: Regex =
+		Regex::new(r#"<li>\s*<a href="(/[^"/]+/[^"/]+\.html)"[^>]*title="([^"]*)""#).unwrap();
+	static ref LAST_PAGE_RE: Regex =
+		Regex::new(r#"<li class="last">\s*<a[^>]*href="[^"]*[?&]page=(\d+)""#).unwrap();
+	static ref CHAPTER_TITLE_RE: Regex =
+		Regex::new(r#"<a class="chapter-title"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref CONTENT_RE: Regex = Regex::new(
+		r#"<div id="chapter-content"[^>]*>([\S\s]+?)</div>\s*(?:<hr|<div class="chapter-nav|</div>)"#
+	)
+	.unwrap();
+	static ref SCRIPT_RE: Regex =
+		Regex::new(r#"(?s)<script.*?</script>|<div[^>]*class="[^"]*ads[^"]*"[^>]*>.*?</div>"#)
+			.unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<h3 class="title"[^>]*>([\S\s]+?)</h3>"#).unwrap();
+	static ref AUTHOR_RE: Regex =
+		Regex::new(r#"<h3>Author:</h3>\s*<a[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref ALTERNATIVE_TITLES_RE: Regex =
+		Regex::new(r#"<h3>Alternative names:</h3>([\S\s]+?)</div>"#).unwrap();
+	static ref GENRES_RE: Regex = Regex::new(r#"<h3>Genre:</h3>([\S\s]+?)</div>"#).unwrap();
+	static ref LINK_TEXT_RE: Regex = Regex::new(r#"<a[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref STATUS_RE: Regex =
+		Regex::new(r#"<h3>Status:</h3>\s*<a[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref DESCRIPTION_RE: Regex =
+		Regex::new(r#"<div class="desc-text"[^>]*>([\S\s]+?)</div>"#).unwrap();
+	static ref RATING_RE: Regex =
+		Regex::new(r#"<span itemprop="ratingValue">([\d.]+)</span>"#).unwrap();
+	static ref REVIEWS_RE: Regex =
+		Regex::new(r#"<span itemprop="reviewCount">(\d+)</span>"#).unwrap();
+	static ref COVER_RE: Regex = Regex::new(r#"<div class="book">\s*<img src="([^"]+)""#).unwrap();
+}
+
+/// Returns the url of the novel page `url` belongs to.
+///
+/// Chapter urls look like `https://novelfull.com/<slug>/chapter-<n>.html`
+/// while novel pages are at `https://novelfull.com/<slug>.html`.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	let mut segments = url.path_segments().into_iter().flatten();
+	if let (Some(slug), Some(_)) = (segments.next(), segments.next()) {
+		novel.set_path(&format!("/{}.html", slug));
+	}
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Parses the novels of a listing like the most popular ones.
+pub(crate) fn novels(url: &Url, body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list = vec![];
+	for novel in NOVEL_LINK_RE.captures_iter(body) {
+		ranobe_list.push(Novel::new(
+			strip_tags(novel.get(2).unwrap().as_str()),
+			url.join(novel.get(1).unwrap().as_str())?,
+		));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct NovelFull {
+	page: u32,
+}
+
+impl NovelFull {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for NovelFull {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			by_author: true,
+			..Capabilities::default()
+		}
+	}
+	fn freshness_url(&self, url: &Url) -> Option<Url> {
+		Some(novel_url(url))
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/latest-release-novel?page={}",
+			BASE_URL, self.page
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		// Every novel lists the chapter it was last updated with
+		let mut ranobe_list = vec![];
+		let starts = NOVEL_ITEM_RE
+			.find_iter(&body)
+			.map(|item| item.start())
+			.collect::<Vec<_>>();
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let item = &body[start..end];
+
+			let (novel, chapter) = match (
+				NOVEL_LINK_RE.captures(item),
+				LATEST_CHAPTER_RE.captures(item),
+			) {
+				(Some(novel), Some(chapter)) => (novel, chapter),
+				_ => continue,
+			};
+			let title = format!(
+				"{}: {}",
+				strip_tags(novel.get(2).unwrap().as_str()),
+				strip_tags(chapter.get(2).unwrap().as_str())
+			);
+			ranobe_list.push(Chapter {
+				novel: Some(url.join(novel.get(1).unwrap().as_str())?),
+				..Chapter::new(title, url.join(chapter.get(1).unwrap().as_str())?)
+			});
+		}
+
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		if !CHAPTER_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "chapter title", &body));
+		}
+		let content = match CONTENT_RE.captures(&body) {
+			Some(content) => content.get(1).unwrap().as_str(),
+			None => return Err(selector_failed(NAME, &url, "chapter text", &body)),
+		};
+		// Ads are injected between the paragraphs
+		let content = SCRIPT_RE.replace_all(content, "");
+
+		let text = PARAGRAPH_RE
+			.captures_iter(&content)
+			.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		// Some chapters are written without paragraphs
+		let text = if text.is_empty() {
+			to_markdown(&content)
+		} else {
+			text
+		};
+
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "chapter text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"{}/genre/{}?page={}",
+				BASE_URL,
+				genre.trim().replace(' ', "+"),
+				page
+			),
+			None => format!("{}/completed-novel?page={}", BASE_URL, page),
+		};
+		let url = Url::parse(&url)?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		novels(&url, &body)
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/most-popular", BASE_URL))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		novels(&url, &body)
+	}
+	async fn get_by_author(&self, author: &str) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/author/{}",
+			BASE_URL,
+			author.trim().replace(' ', "+")
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		novels(&url, &body)
+	}
+	/// Fetches the chapters of the novel `novel` belongs to, first to last.
+	///
+	/// The novel page only lists fifty chapters, the rest are on its
+	/// `?page=N` pages.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let url = novel.url.clone();
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let novel = novel_url(&url);
+		let mut chapters = vec![];
+		let mut last = 1;
+		let mut page = 1;
+		while page <= last {
+			let mut list = novel.clone();
+			list.set_query(Some(&format!("page={}", page)));
+			let body = fetch_url(client, list.clone()).await?;
+
+			let before = chapters.len();
+			for chapter in CHAPTER_ROW_RE.captures_iter(&body) {
+				let title = to_markdown(chapter.get(2).unwrap().as_str());
+				chapters.push(Chapter {
+					novel: Some(novel.clone()),
+					index: Some(chapters.len()),
+					..Chapter::new(title, list.join(chapter.get(1).unwrap().as_str())?)
+				});
+			}
+			if chapters.len() == before {
+				return Err(selector_failed(NAME, &list, "chapter list", &body));
+			}
+
+			// The last page is only linked to from the pages before it
+			if let Some(pages) = LAST_PAGE_RE
+				.captures(&body)
+				.and_then(|last| last.get(1).unwrap().as_str().parse().ok())
+			{
+				last = last.max(pages);
+			}
+			page += 1;
+		}
+
+		Ok(chapters)
+	}
+	/// Counts the novels of the latest updates, their links and the chapters
+	/// they were updated with.
+	async fn probe(&self) -> Result<Vec<(&'static str, usize)>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/latest-release-novel?page=1", BASE_URL))?;
+		let body = fetch_url(client, url).await?;
+
+		Ok(vec![
+			("latest novels", NOVEL_ITEM_RE.find_iter(&body).count()),
+			("novel links", NOVEL_LINK_RE.find_iter(&body).count()),
+			(
+				"latest chapters",
+				LATEST_CHAPTER_RE.find_iter(&body).count(),
+			),
+		])
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = novel_url(&url);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "novel title", &body)),
+		}
+
+		metadata.author = AUTHOR_RE
+			.captures(&body)
+			.map(|author| strip_tags(author.get(1).unwrap().as_str()));
+		if let Some(titles) = ALTERNATIVE_TITLES_RE.captures(&body) {
+			metadata.alternative_titles = strip_tags(titles.get(1).unwrap().as_str())
+				.split(',')
+				.map(|title| title.trim().to_string())
+				.filter(|title| !title.is_empty())
+				.collect();
+		}
+		metadata.status = STATUS_RE
+			.captures(&body)
+			.map(|status| strip_tags(status.get(1).unwrap().as_str()));
+		if let Some(genres) = GENRES_RE.captures(&body) {
+			metadata.genres = LINK_TEXT_RE
+				.captures_iter(genres.get(1).unwrap().as_str())
+				.map(|genre| strip_tags(genre.get(1).unwrap().as_str()))
+				.collect();
+		}
+		metadata.description = DESCRIPTION_RE
+			.captures(&body)
+			.map(|description| strip_tags(description.get(1).unwrap().as_str()));
+		// Rated out of 10
+		metadata.rating = RATING_RE
+			.captures(&body)
+			.and_then(|rating| rating.get(1).unwrap().as_str().parse::<f32>().ok())
+			.map(|rating| rating / 2.0);
+		metadata.reviews = REVIEWS_RE
+			.captures(&body)
+			.and_then(|reviews| reviews.get(1).unwrap().as_str().parse().ok());
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = page.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		Ok(metadata)
+	}
+}
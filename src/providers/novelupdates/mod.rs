@@ -0,0 +1,378 @@
+use crate::{
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, resolve_redirect, CLIENT},
+};
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	script, strip_tags, Capabilities, Chapter, Listed, Metadata, Novel, RanobeScraper, Related,
+};
+
+pub const NAME: &str = "novelupdates";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["novelupdates.com"];
+
+const BASE_URL: &str = "https://www.novelupdates.com";
+
+lazy_static! {
+	static ref ROW_RE: Regex = Regex::new(r#"<tr[\s>]"#).unwrap();
+	static ref RELEASE_SERIES_RE: Regex = Regex::new(
+		r#"<a[^>]*href="(https://www\.novelupdates\.com/series/[^"]+)"[^>]*>([\S\s]+?)</a>"#
+	)
+	.unwrap();
+	static ref RELEASE_RE: Regex =
+		Regex::new(r#"<a[^>]*class="chp-release"[^>]*href="([^"]+)"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref RELEASE_GROUP_RE: Regex = Regex::new(
+		r#"<a[^>]*href="https://www\.novelupdates\.com/group/[^"]+"[^>]*>([\S\s]+?)</a>"#
+	)
+	.unwrap();
+	static ref SEARCH_TITLE_RE: Regex =
+		Regex::new(r#"<div class="search_title">\s*<a href="([^"]+)"[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<div class="seriestitlenu"[^>]*>([\S\s]+?)</div>"#).unwrap();
+	static ref FIELD_RE: Regex =
+		Regex::new(r#"<div (?:id|class)="(show\w+|series\w+|edit\w+)"[^>]*>([\S\s]*?)</div>"#)
+			.unwrap();
+	static ref LINK_RE: Regex = Regex::new(r#"<a[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref VOTES_RE: Regex =
+		Regex::new(r#"<span class="uvotes">\(([\d.]+) / 5\.0, (\d+) votes"#).unwrap();
+	static ref COVER_RE: Regex =
+		Regex::new(r#"<div class="seriesimg">\s*<img src="([^"]+)""#).unwrap();
+	static ref RELATED_SECTION_RE: Regex =
+		Regex::new(r#"<h5 class="seriesother">Related Series</h5>([\S\s]+?)<h5"#).unwrap();
+	static ref RELATED_RE: Regex =
+		Regex::new(r#"<a[^>]*href="([^"]+)"[^>]*>([\S\s]+?)</a>\s*\(([^)]+)\)"#).unwrap();
+	static ref BREAK_RE: Regex = Regex::new(r#"<br\s*/?>"#).unwrap();
+}
+
+/// Turns the original language of a novel, like `Chinese`, into its
+/// country code.
+fn origin(language: &str) -> Option<String> {
+	let language = language.to_lowercase();
+	let origin = if language.contains("chinese") {
+		"CN"
+	} else if language.contains("korean") {
+		"KR"
+	} else if language.contains("japanese") {
+		"JP"
+	} else {
+		return None;
+	};
+	Some(origin.to_string())
+}
+
+/// Parses a table of releases into one entry per release, like
+/// `Series c12 (Group)`, pointing at the redirect to the hosting site.
+pub(crate) fn releases(url: &Url, body: &str) -> Result<Vec<Chapter>, RanobeError> {
+	let mut ranobe_list = vec![];
+	let starts = ROW_RE
+		.find_iter(body)
+		.map(|row| row.start())
+		.collect::<Vec<_>>();
+	for (i, &start) in starts.iter().enumerate() {
+		let end = starts.get(i + 1).copied().unwrap_or(body.len());
+		let row = &body[start..end];
+
+		let release = match RELEASE_RE.captures(row) {
+			Some(release) => release,
+			None => continue,
+		};
+		// Group pages leave out the group and series pages the series
+		let (series, novel) = match RELEASE_SERIES_RE.captures(row) {
+			Some(series) => (
+				strip_tags(series.get(2).unwrap().as_str()) + " ",
+				Some(Url::parse(series.get(1).unwrap().as_str())?),
+			),
+			None => (String::new(), None),
+		};
+		let group = RELEASE_GROUP_RE
+			.captures(row)
+			.map_or(String::new(), |group| {
+				format!(" ({})", strip_tags(group.get(1).unwrap().as_str()))
+			});
+
+		ranobe_list.push(Chapter {
+			title: format!(
+				"{}{}{}",
+				series,
+				strip_tags(release.get(2).unwrap().as_str()),
+				group
+			),
+			url: url.join(release.get(1).unwrap().as_str())?,
+			novel,
+			index: None,
+			published: None,
+			lock: None,
+		});
+	}
+
+	Ok(ranobe_list)
+}
+
+/// Parses the series of a listing like the search results.
+async fn series(body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list = vec![];
+	for series in SEARCH_TITLE_RE.captures_iter(body) {
+		let url = Url::parse(series.get(1).unwrap().as_str().trim())?;
+		let title = strip_tags(series.get(2).unwrap().as_str());
+		ranobe_list.push(Novel::new(title, url));
+	}
+
+	Ok(ranobe_list)
+}
+
+/// An index of translated novels, chapters are read on the sites hosting
+/// them.
+#[derive(Debug, Clone)]
+pub struct NovelUpdates {
+	page: u32,
+}
+
+impl NovelUpdates {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+
+	/// Fetches the latest releases of the translation group `group`, as
+	/// named in its url.
+	pub async fn get_group_releases(
+		&self,
+		group: &str,
+		page: u32,
+	) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/group/{}/?pg={}", BASE_URL, group, page))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		let releases = releases(&url, &body)?;
+		if releases.is_empty() && page == 1 {
+			return Err(selector_failed(NAME, &url, "group releases", &body));
+		}
+
+		Ok(releases)
+	}
+
+	/// Resolves a release to the url of the chapter on the hosting site.
+	pub async fn resolve(&self, url: Url) -> Result<Url, RanobeError> {
+		// Releases link to a redirect like `/extnu/<id>/`
+		if url.path().starts_with("/extnu/") {
+			resolve_redirect(url).await
+		} else {
+			Ok(url)
+		}
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for NovelUpdates {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			search: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			by_author: true,
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/?pg={}", BASE_URL, self.page))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		let releases = releases(&url, &body)?;
+		if releases.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest releases", &body));
+		}
+
+		self.page += 1;
+
+		Ok(releases)
+	}
+	/// Resolves the release and reads it through the provider of the
+	/// hosting site.
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let chapter = self.resolve(url).await?;
+
+		match super::from_url(&chapter) {
+			#[cfg(feature = "readlightnovel")]
+			Some(super::readlightnovel::NAME) => {
+				super::readlightnovel::ReadLightNovel::new()?
+					.get_text(chapter)
+					.await
+			}
+			#[cfg(feature = "royalroad")]
+			Some(super::royalroad::NAME) => super::royalroad::RoyalRoad::new()?.get_text(chapter).await,
+			#[cfg(feature = "scribblehub")]
+			Some(super::scribblehub::NAME) => {
+				super::scribblehub::ScribbleHub::new()?
+					.get_text(chapter)
+					.await
+			}
+			#[cfg(feature = "webnovel")]
+			Some(super::webnovel::NAME) => super::webnovel::Webnovel::new()?.get_text(chapter).await,
+			#[cfg(feature = "lightnovelworld")]
+			Some(super::lightnovelworld::NAME) => {
+				super::lightnovelworld::LightNovelWorld::new()?
+					.get_text(chapter)
+					.await
+			}
+			#[cfg(feature = "novelfull")]
+			Some(super::novelfull::NAME) => super::novelfull::NovelFull::new()?.get_text(chapter).await,
+			#[cfg(feature = "syosetu")]
+			Some(super::syosetu::NAME) => super::syosetu::Syosetu::new()?.get_text(chapter).await,
+			#[cfg(feature = "kakuyomu")]
+			Some(super::kakuyomu::NAME) => super::kakuyomu::Kakuyomu::new()?.get_text(chapter).await,
+			#[cfg(feature = "ao3")]
+			Some(super::ao3::NAME) => super::ao3::Ao3::new()?.get_text(chapter).await,
+			_ => Err(RanobeError::NotFound(format!(
+				"The chapter is hosted at {}, which no provider serves.",
+				chapter
+			))),
+		}
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"{}/genre/{}/?pg={}",
+				BASE_URL,
+				genre.trim().to_lowercase().replace(' ', "-"),
+				page
+			),
+			None => format!("{}/series-ranking/?rank=popular&pg={}", BASE_URL, page),
+		};
+		let body = fetch_url(client, Url::parse(&url)?).await?;
+
+		series(&body).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/series-ranking/?rank=week", BASE_URL))?;
+		let body = fetch_url(client, url).await?;
+
+		series(&body).await
+	}
+	async fn get_by_author(&self, author: &str) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/nauthor/{}/",
+			BASE_URL,
+			author.trim().to_lowercase().replace(' ', "-")
+		))?;
+		let body = fetch_url(client, url).await?;
+
+		series(&body).await
+	}
+	/// Fetches the metadata of the series at `url`.
+	///
+	/// Releases only redirect to the hosting site, so `url` has to be the
+	/// series page.
+	/// Searches the series whose title matches `query`.
+	async fn search(&self, query: &str) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut url = Url::parse(&format!("{}/series-finder/", BASE_URL))?;
+		url.query_pairs_mut()
+			.append_pair("sf", "1")
+			.append_pair("sh", query)
+			.append_pair("sort", "sdate")
+			.append_pair("order", "desc");
+		let body = fetch_url(client, url).await?;
+
+		series(&body).await
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		if !url.path().starts_with("/series/") {
+			return Err(RanobeError::Invalid(format!(
+				"{} is not a series page of {}.",
+				url, NAME
+			)));
+		}
+		let body = fetch_url(client, url.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &url, "novel title", &body)),
+		}
+
+		for field in FIELD_RE.captures_iter(&body) {
+			let content = field.get(2).unwrap().as_str();
+			let links = || {
+				LINK_RE
+					.captures_iter(content)
+					.map(|link| strip_tags(link.get(1).unwrap().as_str()))
+					.collect::<Vec<_>>()
+			};
+
+			match field.get(1).unwrap().as_str() {
+				"showauthors" => metadata.author = Some(links().join(", ")),
+				"seriesgenre" => metadata.genres = links(),
+				"editdescription" => metadata.description = Some(strip_tags(content)),
+				"editstatus" => metadata.status = Some(strip_tags(content)),
+				"showlang" => metadata.origin = origin(&strip_tags(content)),
+				"showtranslators" => metadata.translator = Some(links().join(", ")),
+				// One name per line
+				"editassociated" => {
+					metadata.alternative_titles = BREAK_RE
+						.split(content)
+						.map(strip_tags)
+						.filter(|title| !title.is_empty())
+						.collect()
+				}
+				_ => {}
+			}
+		}
+
+		if let Some(votes) = VOTES_RE.captures(&body) {
+			metadata.rating = votes.get(1).unwrap().as_str().parse().ok();
+			metadata.reviews = votes.get(2).unwrap().as_str().parse().ok();
+		}
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = url.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		if let Some(section) = RELATED_SECTION_RE.captures(&body) {
+			metadata.related = RELATED_RE
+				.captures_iter(section.get(1).unwrap().as_str())
+				.map(|related| Related {
+					title: strip_tags(related.get(2).unwrap().as_str()),
+					relation: related.get(3).unwrap().as_str().trim().to_string(),
+					url: url.join(related.get(1).unwrap().as_str()).ok(),
+				})
+				.collect();
+		}
+
+		Ok(metadata)
+	}
+}
@@ -0,0 +1,380 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_std::task;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use surf::utils::async_trait;
+use surf::Url;
+use wasmtime::{
+	AsContext, AsContextMut, Caller, Engine, Extern, Instance, Linker, Memory, Module, Store,
+	TypedFunc,
+};
+
+use crate::{
+	config::Config,
+	error::RanobeError,
+	http::{client_init, fetch_url, CLIENT},
+};
+
+use super::{highlight_quotes, Capabilities, Chapter, Listed, Metadata, Novel, RanobeScraper};
+
+/// Module of the functions the host provides to plugins.
+const HOST_MODULE: &str = "ranobe";
+
+/// Instructions a call of a plugin may run, a few seconds worth, so a
+/// plugin looping forever fails instead of hanging ranobe.
+const FUEL: u64 = 5_000_000_000;
+
+/// An entry of a listing, as returned by plugins.
+#[derive(Debug, Deserialize)]
+struct Entry {
+	title: String,
+	url: Url,
+}
+
+/// What an export of a plugin is called with.
+#[derive(Debug, Clone)]
+enum Input {
+	/// A string, written into the memory of the plugin.
+	Text(String),
+	Page(u32),
+}
+
+/// The answer of a plugin, either `{"ok": ...}` or `{"error": "..."}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Answer<T> {
+	Ok(T),
+	Error(String),
+}
+
+/// Returns the directory plugins are loaded from, next to the
+/// configuration.
+pub fn default_dir() -> PathBuf {
+	Config::default_path().with_file_name("plugins")
+}
+
+/// Returns the names of the plugins in `dir`, the names of their files
+/// without the `.wasm` extension.
+pub fn names<P: AsRef<Path>>(dir: P) -> Vec<String> {
+	let mut names = fs::read_dir(dir)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.map(|entry| entry.path())
+		.filter(|path| {
+			path.extension()
+				.is_some_and(|extension| extension == "wasm")
+		})
+		.filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+		.collect::<Vec<_>>();
+	names.sort();
+	names
+}
+
+/// Packs the position and length of a string in the memory of a plugin.
+fn pack(ptr: i32, len: i32) -> i64 {
+	((ptr as u32 as i64) << 32) | len as u32 as i64
+}
+
+fn unpack(packed: i64) -> (usize, usize) {
+	((packed as u64 >> 32) as usize, packed as u32 as usize)
+}
+
+/// Reads the string at `ptr` in `memory`.
+fn read(memory: &Memory, store: impl AsContext, (ptr, len): (usize, usize)) -> Option<String> {
+	let bytes = memory.data(&store).get(ptr..ptr.checked_add(len)?)?;
+	String::from_utf8(bytes.to_vec()).ok()
+}
+
+/// Copies `bytes` into memory the plugin allocated for them.
+fn write(
+	memory: &Memory,
+	alloc: &TypedFunc<i32, i32>,
+	mut store: impl AsContextMut,
+	bytes: &[u8],
+) -> wasmtime::Result<i64> {
+	let len = i32::try_from(bytes.len())?;
+	let ptr = alloc.call(&mut store, len)?;
+	memory.write(&mut store, ptr as u32 as usize, bytes)?;
+	Ok(pack(ptr, len))
+}
+
+/// Returns the memory and allocator a plugin exports.
+fn exports(
+	instance: &Instance,
+	mut store: impl AsContextMut,
+) -> wasmtime::Result<(Memory, TypedFunc<i32, i32>)> {
+	let memory = instance
+		.get_memory(&mut store, "memory")
+		.ok_or_else(|| wasmtime::Error::msg("missing export memory"))?;
+	let alloc = instance.get_typed_func::<i32, i32>(&mut store, "ranobe_alloc")?;
+	Ok((memory, alloc))
+}
+
+/// Fetches the page at the url the plugin passed, for `ranobe.fetch`.
+///
+/// Returns the page like the exports of a plugin return their answer, or
+/// `-1` if it could not be fetched.
+fn host_fetch(mut caller: Caller<'_, ()>, ptr: i32, len: i32) -> i64 {
+	let exports = (
+		caller.get_export("memory").and_then(Extern::into_memory),
+		caller
+			.get_export("ranobe_alloc")
+			.and_then(Extern::into_func)
+			.and_then(|alloc| alloc.typed::<i32, i32>(&caller).ok()),
+	);
+	let (memory, alloc) = match exports {
+		(Some(memory), Some(alloc)) => (memory, alloc),
+		_ => return -1,
+	};
+	let url = match read(&memory, &caller, (ptr as u32 as usize, len as u32 as usize))
+		.and_then(|url| Url::parse(&url).ok())
+	{
+		Some(url) => url,
+		None => return -1,
+	};
+
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+	// Plugins run on a thread of their own, the page is waited for there
+	match task::block_on(fetch_url(client, url)) {
+		Ok(body) => write(&memory, &alloc, &mut caller, body.as_bytes()).unwrap_or(-1),
+		Err(_) => -1,
+	}
+}
+
+/// A provider implemented by a WebAssembly module, so sites can be
+/// supported outside of ranobe.
+///
+/// Plugins export `memory`, `ranobe_alloc(len) -> ptr` for the host to
+/// pass strings and the functions of the provider, which answer with the
+/// position and length of a JSON string packed into an `i64`:
+///
+/// - `latest(page) -> [{"title", "url"}]`
+/// - `search(query) -> [{"title", "url"}]`
+/// - `chapters(novel url) -> [{"title", "url"}]`
+/// - `text(chapter url) -> "Markdown"`
+///
+/// Answers are wrapped in `{"ok": ...}`, or are `{"error": "..."}`. Pages
+/// are fetched with the `ranobe.fetch(url) -> page` import, which returns
+/// `-1` on failure.
+#[derive(Clone)]
+pub struct PluginProvider {
+	name: &'static str,
+	page: u32,
+	engine: Engine,
+	module: Module,
+	/// Instructions a call may run.
+	fuel: u64,
+}
+
+impl PluginProvider {
+	/// Loads the plugin in the file `name`.wasm of `dir`.
+	pub fn load<P: AsRef<Path>>(dir: P, name: &str) -> Result<Self, RanobeError> {
+		let path = dir.as_ref().join(format!("{}.wasm", name));
+		let engine = Engine::new(wasmtime::Config::new().consume_fuel(true))
+			.map_err(|err| RanobeError::Tool(err.to_string()))?;
+		let module = Module::from_file(&engine, &path)
+			.map_err(|err| RanobeError::Parse(format!("{}: {}", path.display(), err)))?;
+
+		Ok(Self {
+			// Names are `'static` for the built-in providers, a plugin is only
+			// loaded a few times a run
+			name: Box::leak(name.to_string().into_boxed_str()),
+			page: 1,
+			engine,
+			module,
+			fuel: FUEL,
+		})
+	}
+
+	fn error(&self, err: impl Display) -> RanobeError {
+		RanobeError::Tool(format!("Plugin {} failed: {}", self.name, err))
+	}
+
+	/// Calls the export `function` of a new instance of the plugin with
+	/// `input` and returns its raw answer.
+	///
+	/// Every call gets its own instance, so plugins keep no state, and runs
+	/// out of fuel after `fuel` instructions.
+	fn run(&self, function: &str, input: Input) -> wasmtime::Result<String> {
+		let mut linker = Linker::new(&self.engine);
+		linker.func_wrap(HOST_MODULE, "fetch", host_fetch)?;
+		let mut store = Store::new(&self.engine, ());
+		store.set_fuel(self.fuel)?;
+		let instance = linker.instantiate(&mut store, &self.module)?;
+		let (memory, alloc) = exports(&instance, &mut store)?;
+
+		let packed = match input {
+			Input::Text(text) => {
+				let (ptr, len) = unpack(write(&memory, &alloc, &mut store, text.as_bytes())?);
+				instance
+					.get_typed_func::<(i32, i32), i64>(&mut store, function)?
+					.call(&mut store, (ptr as i32, len as i32))?
+			}
+			Input::Page(page) => instance
+				.get_typed_func::<i32, i64>(&mut store, function)?
+				.call(&mut store, page as i32)?,
+		};
+
+		read(&memory, &store, unpack(packed))
+			.ok_or_else(|| wasmtime::Error::msg(format!("{} answered out of bounds", function)))
+	}
+
+	/// Calls the export `function` with `input` and decodes its answer.
+	///
+	/// Plugins run on a blocking thread, as they wait for the pages they
+	/// fetch.
+	async fn call<T: DeserializeOwned>(
+		&self,
+		function: &'static str,
+		input: Input,
+	) -> Result<T, RanobeError> {
+		let plugin = self.clone();
+		let answer = task::spawn_blocking(move || plugin.run(function, input))
+			.await
+			.map_err(|err| self.error(err))?;
+
+		match serde_json::from_str(&answer).map_err(|err| self.error(err))? {
+			Answer::Ok(value) => Ok(value),
+			Answer::Error(err) => Err(self.error(err)),
+		}
+	}
+
+	async fn entries<E: Listed>(
+		&self,
+		function: &'static str,
+		input: Input,
+	) -> Result<Vec<E>, RanobeError> {
+		let entries: Vec<Entry> = self.call(function, input).await?;
+		Ok(entries
+			.into_iter()
+			.map(|entry| E::new(entry.title, entry.url))
+			.collect())
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for PluginProvider {
+	fn name(&self) -> &'static str {
+		self.name
+	}
+	/// Plugins serve what they export.
+	fn capabilities(&self) -> Capabilities {
+		let exports = |function| self.module.get_export(function).is_some();
+		Capabilities {
+			latest: exports("latest"),
+			search: exports("search"),
+			toc: exports("chapters"),
+			catalogue: exports("search"),
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let ranobe_list = self.entries("latest", Input::Page(self.page)).await?;
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let text: String = self.call("text", Input::Text(url.to_string())).await?;
+		Ok(highlight_quotes(&text))
+	}
+	/// Searches the plugin for `genre`, as plugins have no catalogue.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		// Searches are a single page
+		if page > 1 {
+			return Ok(vec![]);
+		}
+		self.search(genre.unwrap_or_default()).await
+	}
+	/// Searches the novels of the plugin for `query`.
+	async fn search(&self, query: &str) -> Result<Vec<Novel>, RanobeError> {
+		self.entries("search", Input::Text(query.to_string())).await
+	}
+	/// Fetches the chapters of `novel`, first to last.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let chapters: Vec<Chapter> = self
+			.entries("chapters", Input::Text(novel.url.to_string()))
+			.await?;
+		Ok(chapters
+			.into_iter()
+			.enumerate()
+			.map(|(i, chapter)| novel.chapter(i, chapter.title, chapter.url))
+			.collect())
+	}
+	/// Counts the chapters of the novel at `url`, plugins know nothing else
+	/// about it.
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let chapters: Vec<Chapter> = self
+			.entries("chapters", Input::Text(url.to_string()))
+			.await?;
+
+		Ok(Metadata {
+			title: url.to_string(),
+			chapters: Some(chapters.len()),
+			..Metadata::default()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn calls_plugins() {
+		let answer = r#"{"ok":[{"title":"Chapter 1","url":"https://example.com/1"}]}"#;
+		let dir = TestDir::new("plugin");
+		fs::write(
+			dir.join("example.wasm"),
+			format!(
+				r#"(module
+					(memory (export "memory") 1)
+					(global $next (mut i32) (i32.const 1024))
+					(func (export "ranobe_alloc") (param $len i32) (result i32)
+						(global.get $next)
+						(global.set $next (i32.add (global.get $next) (local.get $len))))
+					(data (i32.const 0) "{}")
+					(func (export "latest") (param i32) (result i64) (i64.const {}))
+					(func (export "search") (param i32 i32) (result i64)
+						(loop $forever (br $forever))
+						(i64.const 0))
+					(func (export "text") (param i32 i32) (result i64)
+						(i64.or (i64.shl (i64.const 1024) (i64.const 32)) (i64.extend_i32_u (local.get 1)))))"#,
+				answer.replace('"', "\\\""),
+				answer.len()
+			),
+		)
+		.unwrap();
+
+		assert_eq!(names(&dir), vec!["example"]);
+		let mut provider = PluginProvider::load(&dir, "example").unwrap();
+		let latest = task::block_on(provider.entries::<Chapter>("latest", Input::Page(1))).unwrap();
+		assert_eq!(latest[0].title, "Chapter 1");
+		assert_eq!(latest[0].url.as_str(), "https://example.com/1");
+		// `text` answers with its input
+		let text = |input: &str| {
+			task::block_on(provider.call::<String>("text", Input::Text(input.to_string())))
+		};
+		assert_eq!(text(r#"{"ok":"Some text"}"#).unwrap(), "Some text");
+		assert!(text(r#"{"error":"down"}"#).is_err());
+		assert!(text("not json").is_err());
+		assert!(task::block_on(provider.search("")).is_err());
+		// A plugin looping forever runs out of fuel
+		provider.fuel = 1_000_000;
+		assert!(task::block_on(provider.search("")).is_err());
+		assert!(task::block_on(provider.get_chapters(&Novel::new(
+			"Novel".to_string(),
+			Url::parse("https://example.com/novel").unwrap()
+		)))
+		.is_err());
+	}
+}
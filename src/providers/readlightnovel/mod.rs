@@ -1,6 +1,7 @@
 use crate::{
-	http::{client_init, fetch_url, CLIENT},
-	utils::italicize,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_mirrored, CLIENT},
 };
 use std::fmt::format;
 use surf::utils::async_trait;
@@ -10,7 +11,27 @@ use regex::Regex;
 use surf::Client;
 use surf::Url;
 
-use super::{Ranobe, RanobeScraper};
+use super::{
+	highlight_quotes, index_letter, script, strip_tags, Capabilities, Chapter, Listed, Metadata,
+	Novel, Paragraph, RanobeScraper,
+};
+use crate::text::paragraphs;
+
+pub const NAME: &str = "readlightnovel";
+
+/// Listing of the latest updates, paged from zero.
+const LATEST: &str = "latest-update";
+
+/// Most pages of results fetched for a search.
+const MAX_SEARCH_PAGES: u32 = 5;
+
+/// Hosts the provider serves novels from, the site keeps moving to new
+/// domains so they are mirrors of each other, tried in turn.
+pub const HOSTS: [&str; 3] = [
+	"readlightnovel.me",
+	"readlightnovel.app",
+	"readlightnovel.today",
+];
 
 lazy_static! {
 	static ref LATEST_RE: Regex =
@@ -21,89 +42,435 @@ lazy_static! {
 		Regex::new(r#"<!-- audio -->[\S\s]+?<!-- audio -->([\S\s]+?)<!-- .+ desktop start -->"#)
 			.unwrap();
 	static ref BREAK_RE: Regex = Regex::new("<br>").unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<div class="block-title">\s*<h1>(.+?)</h1>"#).unwrap();
+	static ref DETAIL_RE: Regex = Regex::new(
+		r#"<div class="novel-detail-header">\s*<h\d>(.+?)</h\d>\s*</div>\s*<div class="novel-detail-body">([\S\s]+?)</div>"#
+	)
+	.unwrap();
+	static ref ITEM_RE: Regex = Regex::new(r#"<li>([\S\s]+?)</li>"#).unwrap();
+	static ref COVER_RE: Regex =
+		Regex::new(r#"<div class="novel-cover">[\S\s]*?<img src="(.+?)""#).unwrap();
+	static ref CATALOGUE_RE: Regex = Regex::new(
+		r#"<div class="top-novel-header">\s*<h2>\s*<a href="(.+?)"[^>]*>(.+?)</a>"#
+	)
+	.unwrap();
+	static ref RATING_RE: Regex = Regex::new(r#"(\d+(?:\.\d+)?)(?:\s*/\s*(\d+))?"#).unwrap();
+	static ref VOTES_RE: Regex = Regex::new(r#"(?i)(\d+)\s*(?:votes|ratings|reviews)"#).unwrap();
+	static ref CHAPTER_RE: Regex =
+		Regex::new(r#"<li>\s*<a href="([^"]+/chapter-[^"]+)"[^>]*>([\S\s]*?)</a>"#).unwrap();
+	static ref CHAPTER_PATH_RE: Regex = Regex::new(r#"^/[^/]+/chapter-[^/]+/?$"#).unwrap();
+	/// Matches a novel of the novel list, which lists every novel of the
+	/// site under the letter of its title.
+	static ref NOVEL_LIST_RE: Regex = Regex::new(
+		r#"<li>\s*<a href="([^"]+)"[^>]*data-toggle="popover"[^>]*>([\S\s]*?)</a>"#
+	)
+	.unwrap();
+	static ref NOTICE_RE: Regex =
+		Regex::new(r#"(?i)^(?:site )?(?:announcement|notice|maintenance)\b"#).unwrap();
+}
+
+/// Fetches `page` of the listing `id` of the site, like `latest-update`.
+async fn fetch_listing(id: &str, page: u32) -> Result<(Url, String), RanobeError> {
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+	let url = Url::parse(&format!("https://readlightnovel.me/{}/{}", id, page))?;
+	fetch_mirrored(client, NAME, &HOSTS, url).await
+}
+
+/// Parses the novels of a listing like the catalogue or the search results.
+async fn novels(body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list = vec![];
+	for ranobe in CATALOGUE_RE.captures_iter(body) {
+		let url = Url::parse(ranobe.get(1).unwrap().as_str().trim())?;
+		let title = strip_tags(ranobe.get(2).unwrap().as_str());
+		ranobe_list.push(Novel::new(title, url));
+	}
+
+	Ok(ranobe_list)
+}
+
+/// Turns the type of a novel, like `Chinese Novel`, into its country code.
+fn origin(novel_type: &str) -> Option<String> {
+	let novel_type = novel_type.to_lowercase();
+	let origin = if novel_type.contains("chinese") {
+		"CN"
+	} else if novel_type.contains("korean") {
+		"KR"
+	} else if novel_type.contains("japanese") {
+		"JP"
+	} else {
+		return None;
+	};
+	Some(origin.to_string())
 }
 
-#[derive(Debug)]
+/// Returns the url of the novel page `url` belongs to.
+///
+/// Chapter urls look like `https://www.readlightnovel.me/<novel>/chapter-1`,
+/// so only the first path segment is kept.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	let slug = url
+		.path_segments()
+		.and_then(|mut segments| segments.next())
+		.unwrap_or("")
+		.to_string();
+	novel.set_path(&slug);
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Returns `url` on the first domain of the site, so chapters read on a
+/// mirror are stored once.
+pub fn canonical_url(mut url: Url) -> Url {
+	let _ = url.set_host(Some("www.readlightnovel.me"));
+	url
+}
+
+#[derive(Debug, Clone)]
 pub struct ReadLightNovel {
 	// client: Client,
 	page: u32,
 }
 
 impl ReadLightNovel {
-	pub fn new() -> Result<Self, surf::Error> {
+	pub fn new() -> Result<Self, RanobeError> {
 		Ok(Self {
 			// client: client_init()?,
 			page: 0,
 		})
 	}
+
+	/// Fetches the html blocks of the chapter text at `url`, each with the
+	/// selector of its node if the text is split into paragraphs.
+	async fn get_blocks(&self, url: &Url) -> Result<Vec<(String, Option<String>)>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let (url, body) = fetch_mirrored(client, NAME, &HOSTS, url.clone()).await?;
+		let url = &url;
+
+		if let Some(text) = script::parse_chapter(NAME, url, &body) {
+			return Ok(vec![(text?, None)]);
+		}
+
+		if !TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, url, "chapter title", &body));
+		}
+
+		let mut _text = String::new();
+
+		let _text = RAW_TEXT_RE
+			.captures_iter(&*body)
+			.fold(String::new(), |acc, cap| {
+				format!("{}{}", _text, cap.get(1).unwrap().as_str().trim())
+			});
+
+		// Only get block content
+		let blocks = TEXT_RE
+			.captures_iter(_text.as_str())
+			.enumerate()
+			.map(|(i, cap)| {
+				(
+					cap.get(1).unwrap().as_str().to_string(),
+					Some(format!("p:nth-of-type({})", i + 1)),
+				)
+			})
+			.collect::<Vec<_>>();
+
+		if blocks.is_empty() && _text.trim().is_empty() {
+			return Err(selector_failed(NAME, url, "chapter text", &body));
+		}
+		if blocks.is_empty() {
+			return Ok(vec![(_text, None)]);
+		}
+
+		Ok(blocks)
+	}
+}
+
+/// Turns chapter html into Markdown.
+fn render(html: &str) -> String {
+	let text = highlight_quotes(html);
+
+	// Convert all <br> into \n
+	BREAK_RE.replace_all(&text, "\n").to_string()
 }
 
 #[async_trait]
 impl RanobeScraper for ReadLightNovel {
-	async fn get_latest(&mut self) -> Result<Vec<Ranobe>, surf::Error> {
-		let client = CLIENT.get_or_init(|| client_init().unwrap());
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			search: true,
+			toc: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			index: true,
+			..Capabilities::default()
+		}
+	}
+	/// Only keeps the rows linking to a chapter of a novel, as the feed
+	/// also lists site announcements and notices.
+	fn is_update(&self, chapter: &Chapter) -> bool {
+		CHAPTER_PATH_RE.is_match(chapter.url.path()) && !NOTICE_RE.is_match(&chapter.title)
+	}
+	fn latest_page(&self) -> Option<u32> {
+		Some(self.page)
+	}
+	fn set_latest_page(&mut self, page: u32) -> bool {
+		self.page = page;
+		true
+	}
+	/// Fetches the current page of the latest updates and moves to the next
+	/// one, staying past the last page, which lists nothing.
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let (url, body) = fetch_listing(LATEST, self.page).await?;
 
-		let body = fetch_url(
-			&client,
-			Url::parse(&*format!(
-				"https://readlightnovel.me/latest-update/{}",
-				self.page
-			))?,
-		)
-		.await?;
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
 
-		let mut ranobe_list: Vec<Ranobe> = Vec::new();
+		let mut ranobe_list: Vec<Chapter> = Vec::new();
 		for ranobe in LATEST_RE.captures_iter(&*body) {
-			let url = ranobe.get(1).unwrap().as_str().trim();
+			let url = Url::parse(ranobe.get(1).unwrap().as_str().trim())?;
 			let title = ranobe.get(2).unwrap().as_str().trim().to_string();
-			ranobe_list.push(Ranobe::new(title, url).await?);
+			// Announcements are not under a novel
+			let novel = CHAPTER_PATH_RE
+				.is_match(url.path())
+				.then(|| novel_url(&url));
+			ranobe_list.push(Chapter {
+				novel,
+				..Chapter::new(title, url)
+			});
+		}
+
+		// Only the first page is sure to list something
+		if ranobe_list.is_empty() && self.page == 0 {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+		if ranobe_list.is_empty() {
+			return Ok(ranobe_list);
 		}
 
 		self.page += 1;
 
 		Ok(ranobe_list)
 	}
-	async fn get_next_page(id: &str, page: &u32) -> Result<String, surf::Error> {
-		Ok(String::new())
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let blocks = self.get_blocks(&url).await?;
+		let text = blocks
+			.iter()
+			.map(|(block, source)| match source {
+				Some(_) => format!("{}\n", block),
+				None => block.clone(),
+			})
+			.collect::<String>();
+
+		Ok(render(&text))
 	}
-	async fn get_prev_page(id: &str, page: &u32) -> Result<String, surf::Error> {
-		Ok(String::new())
+	async fn get_paragraphs(&self, url: Url) -> Result<Vec<Paragraph>, RanobeError> {
+		let blocks = self.get_blocks(&url).await?;
+
+		// A block split by <br> gives several paragraphs of the same node
+		Ok(blocks
+			.iter()
+			.flat_map(|(block, source)| {
+				paragraphs(&render(block))
+					.map(|text| Paragraph {
+						text: text.to_string(),
+						source: source.clone(),
+					})
+					.collect::<Vec<_>>()
+			})
+			.collect())
 	}
-	async fn get_list(html: &str) -> Result<String, surf::Error> {
-		Ok(String::new())
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"https://www.readlightnovel.me/genre/{}/{}",
+				genre.trim().to_lowercase().replace(' ', "-"),
+				page
+			),
+			None => format!("https://www.readlightnovel.me/top-novels/new/{}", page),
+		};
+		let (_, body) = fetch_mirrored(client, NAME, &HOSTS, Url::parse(&url)?).await?;
+
+		novels(&body).await
 	}
-	async fn get_text(&self, url: Url) -> Result<String, surf::Error> {
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
 		let client = CLIENT.get_or_init(|| client_init().unwrap());
 
-		let body = fetch_url(&client, url).await?;
+		let (_, body) = fetch_mirrored(
+			client,
+			NAME,
+			&HOSTS,
+			Url::parse("https://www.readlightnovel.me/top-novels/most-viewed/1")?,
+		)
+		.await?;
 
-		let title = TITLE_RE.captures(body.as_str()).unwrap().get(1).unwrap();
+		novels(&body).await
+	}
+	/// Lists the novels of the novel list under `letter`, the site lists
+	/// every novel on one page.
+	async fn get_by_letter(&self, letter: char) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
 
-		let mut _text = String::new();
+		let url = Url::parse("https://www.readlightnovel.me/novel-list")?;
+		let (page, body) = fetch_mirrored(client, NAME, &HOSTS, url).await?;
 
-		let _text = RAW_TEXT_RE
-			.captures_iter(&*body)
-			.fold(String::new(), |acc, cap| {
-				format!("{}{}", _text, cap.get(1).unwrap().as_str().trim())
-			});
+		let mut ranobe_list: Vec<Novel> = vec![];
+		let mut listed = 0;
+		for ranobe in NOVEL_LIST_RE.captures_iter(&body) {
+			listed += 1;
+			let title = strip_tags(ranobe.get(2).unwrap().as_str());
+			if index_letter(&title) != letter.to_ascii_uppercase() {
+				continue;
+			}
+			let url = page.join(ranobe.get(1).unwrap().as_str().trim())?;
+			if !ranobe_list.iter().any(|seen| seen.url == url) {
+				ranobe_list.push(Novel::new(title, url));
+			}
+		}
+		if listed == 0 {
+			return Err(selector_failed(NAME, &page, "novel list", &body));
+		}
 
-		// Only get block content
-		let mut text = TEXT_RE
-			.captures_iter(_text.as_str())
-			.fold(String::new(), |acc, cap| {
-				format!("{}{}\n", acc, cap.get(1).unwrap().as_str())
-			});
+		ranobe_list.sort_by_cached_key(|ranobe| ranobe.title.to_lowercase());
+		Ok(ranobe_list)
+	}
+	/// Searches novels by title, following the pages of results until one
+	/// has nothing new.
+	async fn search(&self, query: &str) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let mut ranobe_list: Vec<Novel> = vec![];
+		for page in 1..=MAX_SEARCH_PAGES {
+			let mut url = Url::parse("https://www.readlightnovel.me/detailed-search")?;
+			url.query_pairs_mut()
+				.append_pair("title", query.trim())
+				.append_pair("page", &page.to_string());
+			let (_, body) = fetch_mirrored(client, NAME, &HOSTS, url).await?;
+
+			let before = ranobe_list.len();
+			for ranobe in novels(&body).await? {
+				// Pages past the last one repeat it on some mirrors
+				if !ranobe_list.iter().any(|seen| seen.url == ranobe.url) {
+					ranobe_list.push(ranobe);
+				}
+			}
+			if ranobe_list.len() == before {
+				break;
+			}
+		}
+
+		Ok(ranobe_list)
+	}
+	/// Fetches the chapters of the novel `novel` belongs to, first to last.
+	///
+	/// The novel page lists every chapter at once.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let (page, body) = fetch_mirrored(client, NAME, &HOSTS, novel_url(&novel.url)).await?;
+
+		let mut chapters: Vec<Chapter> = vec![];
+		for chapter in CHAPTER_RE.captures_iter(&body) {
+			let url = canonical_url(page.join(chapter.get(1).unwrap().as_str().trim())?);
+			if chapters.iter().any(|seen| seen.url == url) {
+				continue;
+			}
+			let title = strip_tags(chapter.get(2).unwrap().as_str());
+			chapters.push(novel.chapter(chapters.len(), title, url));
+		}
 
-		if text.is_empty() {
-			text = _text
+		if chapters.is_empty() {
+			return Err(selector_failed(NAME, &page, "chapter list", &body));
 		}
 
-		// Highlight text inside double quotes
-		let text = italicize(&text);
+		Ok(chapters)
+	}
+	/// Counts the latest updates and the novels of the novel list.
+	async fn probe(&self) -> Result<Vec<(&'static str, usize)>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let (_, latest) = fetch_listing(LATEST, 0).await?;
+		let url = Url::parse("https://www.readlightnovel.me/novel-list")?;
+		let (_, novel_list) = fetch_mirrored(client, NAME, &HOSTS, url).await?;
 
-		// Convert all <br> into \n
-		let text = BREAK_RE.replace_all(&*text, "\n").to_string();
+		Ok(vec![
+			("latest updates", LATEST_RE.find_iter(&latest).count()),
+			("novel list", NOVEL_LIST_RE.find_iter(&novel_list).count()),
+		])
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let (page, body) = fetch_mirrored(client, NAME, &HOSTS, novel_url(&url)).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "novel title", &body)),
+		}
+
+		for detail in DETAIL_RE.captures_iter(&body) {
+			let header = strip_tags(detail.get(1).unwrap().as_str());
+			let content = detail.get(2).unwrap().as_str();
+
+			match header.as_str() {
+				"Author(s)" => metadata.author = Some(strip_tags(content)),
+				"Status" => metadata.status = Some(strip_tags(content)),
+				"Genre" => {
+					metadata.genres = ITEM_RE
+						.captures_iter(content)
+						.map(|genre| strip_tags(genre.get(1).unwrap().as_str()))
+						.collect()
+				}
+				"Description" => metadata.description = Some(strip_tags(content)),
+				"Rating" => {
+					let rating = strip_tags(content);
+					// Ratings are either out of 5 or written as `8.2 / 10`
+					metadata.rating = RATING_RE.captures(&rating).and_then(|captures| {
+						let rating = captures.get(1)?.as_str().parse::<f32>().ok()?;
+						let scale = captures
+							.get(2)
+							.and_then(|scale| scale.as_str().parse::<f32>().ok())
+							.unwrap_or(5.0);
+						Some(rating / scale * 5.0)
+					});
+					metadata.reviews = VOTES_RE
+						.captures(&rating)
+						.and_then(|votes| votes.get(1).unwrap().as_str().parse().ok());
+				}
+				"Type" => metadata.origin = origin(&strip_tags(content)),
+				"Translator" | "Translator(s)" => metadata.translator = Some(strip_tags(content)),
+				_ => {}
+			}
+		}
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = page.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		let chapters = CHAPTER_RE.find_iter(&body).count();
+		if chapters > 0 {
+			metadata.chapters = Some(chapters);
+		}
 
-		Ok(text)
+		Ok(metadata)
 	}
 }
@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use surf::utils::async_trait;
+use surf::Url;
+
+use crate::error::RanobeError;
+use crate::keyring::Credentials;
+
+use crate::suggest;
+
+use super::declarative::{self, DeclarativeProvider};
+use super::local::{self, LocalProvider};
+#[cfg(feature = "plugins")]
+use super::plugin::{self, PluginProvider};
+use super::{
+	available, Capabilities, Chapter, Comment, Metadata, Novel, Paragraph, RanobeScraper, Volume,
+};
+
+#[cfg(feature = "ao3")]
+use super::ao3::{self, Ao3};
+#[cfg(feature = "kakuyomu")]
+use super::kakuyomu::{self, Kakuyomu};
+#[cfg(feature = "lightnovelworld")]
+use super::lightnovelworld::{self, LightNovelWorld};
+#[cfg(feature = "novelfull")]
+use super::novelfull::{self, NovelFull};
+#[cfg(feature = "novelupdates")]
+use super::novelupdates::{self, NovelUpdates};
+#[cfg(feature = "readlightnovel")]
+use super::readlightnovel::{self, ReadLightNovel};
+#[cfg(feature = "royalroad")]
+use super::royalroad::{self, RoyalRoad};
+#[cfg(feature = "scribblehub")]
+use super::scribblehub::{self, ScribbleHub};
+#[cfg(feature = "syosetu")]
+use super::syosetu::{self, Syosetu};
+#[cfg(feature = "webnovel")]
+use super::webnovel::{self, Webnovel};
+
+/// A provider picked at runtime.
+pub type Provider = Box<dyn RanobeScraper + Send + Sync>;
+
+impl Clone for Provider {
+	fn clone(&self) -> Self {
+		(**self).clone_boxed()
+	}
+}
+
+/// Serves everything from the provider picked, so code written for any
+/// provider runs on one picked at runtime.
+#[async_trait]
+impl RanobeScraper for Provider {
+	fn name(&self) -> &'static str {
+		(**self).name()
+	}
+	fn capabilities(&self) -> Capabilities {
+		(**self).capabilities()
+	}
+	fn request_interval(&self) -> Duration {
+		(**self).request_interval()
+	}
+	fn set_page_size(&mut self, size: usize) -> bool {
+		(**self).set_page_size(size)
+	}
+	fn latest_page(&self) -> Option<u32> {
+		(**self).latest_page()
+	}
+	fn set_latest_page(&mut self, page: u32) -> bool {
+		(**self).set_latest_page(page)
+	}
+	fn is_update(&self, chapter: &Chapter) -> bool {
+		(**self).is_update(chapter)
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		(**self).get_latest().await
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		(**self).get_text(url).await
+	}
+	async fn get_texts(
+		&self,
+		urls: &[Url],
+		concurrency: usize,
+	) -> Vec<Result<String, RanobeError>> {
+		(**self).get_texts(urls, concurrency).await
+	}
+	async fn get_paragraphs(&self, url: Url) -> Result<Vec<Paragraph>, RanobeError> {
+		(**self).get_paragraphs(url).await
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		(**self).get_catalogue(genre, page).await
+	}
+	async fn get_comments(&self, url: Url) -> Result<Vec<Comment>, RanobeError> {
+		(**self).get_comments(url).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		(**self).get_ranking().await
+	}
+	async fn get_by_author(&self, author: &str) -> Result<Vec<Novel>, RanobeError> {
+		(**self).get_by_author(author).await
+	}
+	async fn get_volumes(&self, url: Url) -> Result<Vec<Volume>, RanobeError> {
+		(**self).get_volumes(url).await
+	}
+	fn freshness_url(&self, url: &Url) -> Option<Url> {
+		(**self).freshness_url(url)
+	}
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		(**self).get_chapters(novel).await
+	}
+	async fn get_by_letter(&self, letter: char) -> Result<Vec<Novel>, RanobeError> {
+		(**self).get_by_letter(letter).await
+	}
+	async fn search(&self, query: &str) -> Result<Vec<Novel>, RanobeError> {
+		(**self).search(query).await
+	}
+	async fn login(&self, credentials: &Credentials) -> Result<(), RanobeError> {
+		(**self).login(credentials).await
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		(**self).get_metadata(url).await
+	}
+	async fn probe(&self) -> Result<Vec<(&'static str, usize)>, RanobeError> {
+		(**self).probe().await
+	}
+}
+
+/// Short names accepted in place of the name of a provider.
+const ALIASES: [(&str, &str); 12] = [
+	("rln", "readlightnovel"),
+	("rr", "royalroad"),
+	("sh", "scribblehub"),
+	("nu", "novelupdates"),
+	("wn", "webnovel"),
+	("qidian", "webnovel"),
+	("lnw", "lightnovelworld"),
+	("lightnovelpub", "lightnovelworld"),
+	("nf", "novelfull"),
+	("narou", "syosetu"),
+	("archive", "ao3"),
+	("archiveofourown", "ao3"),
+];
+
+/// Returns the aliases of the provider `name`.
+pub fn aliases(name: &str) -> Vec<&'static str> {
+	ALIASES
+		.iter()
+		.filter(|(_, provider)| *provider == name)
+		.map(|(alias, _)| *alias)
+		.collect()
+}
+
+/// Returns the names of the plugins in `plugin::default_dir()`.
+pub fn plugins() -> Vec<String> {
+	#[cfg(feature = "plugins")]
+	return plugin::names(plugin::default_dir());
+	#[cfg(not(feature = "plugins"))]
+	return vec![];
+}
+
+/// Returns the names of every provider, the built-in ones first, then the
+/// plugins and the ones defined in `declarative::default_dir()`.
+pub fn names() -> Vec<String> {
+	available()
+		.into_iter()
+		.map(String::from)
+		.chain(plugins())
+		.chain(declarative::names(declarative::default_dir()))
+		.collect()
+}
+
+/// Returns the error for the unknown provider `name`, suggesting the
+/// providers and aliases it is close to, or listing the available ones.
+fn unknown(name: &str) -> RanobeError {
+	let names = names();
+	let candidates = names
+		.iter()
+		.map(String::as_str)
+		.chain(ALIASES.iter().map(|(alias, _)| *alias));
+	let message = match suggest::similar(name, candidates).as_slice() {
+		[] => format!(
+			"Unknown provider {}, available providers: {}.",
+			name,
+			names.join(", ")
+		),
+		suggestions => suggest::did_you_mean(&format!("Unknown provider {}.", name), suggestions),
+	};
+	RanobeError::NotFound(message)
+}
+
+/// Returns the name of the provider `name` refers to, which is either its
+/// name or one of its aliases, ignoring case.
+///
+/// Built-in providers take precedence over plugins, which take precedence
+/// over defined providers of the same name.
+pub fn resolve(name: &str) -> Result<String, RanobeError> {
+	let lowercase = name.trim().to_lowercase();
+	let resolved = ALIASES
+		.iter()
+		.find(|(alias, _)| *alias == lowercase)
+		.map_or(lowercase.as_str(), |(_, provider)| provider);
+
+	if let Some(provider) = available()
+		.into_iter()
+		.find(|provider| *provider == resolved)
+	{
+		return Ok(provider.to_string());
+	}
+
+	plugins()
+		.into_iter()
+		.chain(declarative::names(declarative::default_dir()))
+		.find(|provider| provider.to_lowercase() == lowercase)
+		.ok_or_else(|| unknown(name))
+}
+
+/// Returns a new instance of the provider `name` refers to.
+pub fn get(name: &str) -> Result<Provider, RanobeError> {
+	Ok(match resolve(name)?.as_str() {
+		#[cfg(feature = "readlightnovel")]
+		readlightnovel::NAME => Box::new(ReadLightNovel::new()?),
+		#[cfg(feature = "royalroad")]
+		royalroad::NAME => Box::new(RoyalRoad::new()?),
+		#[cfg(feature = "scribblehub")]
+		scribblehub::NAME => Box::new(ScribbleHub::new()?),
+		#[cfg(feature = "novelupdates")]
+		novelupdates::NAME => Box::new(NovelUpdates::new()?),
+		#[cfg(feature = "webnovel")]
+		webnovel::NAME => Box::new(Webnovel::new()?),
+		#[cfg(feature = "lightnovelworld")]
+		lightnovelworld::NAME => Box::new(LightNovelWorld::new()?),
+		#[cfg(feature = "novelfull")]
+		novelfull::NAME => Box::new(NovelFull::new()?),
+		#[cfg(feature = "syosetu")]
+		syosetu::NAME => Box::new(Syosetu::new()?),
+		#[cfg(feature = "kakuyomu")]
+		kakuyomu::NAME => Box::new(Kakuyomu::new()?),
+		#[cfg(feature = "ao3")]
+		ao3::NAME => Box::new(Ao3::new()?),
+		local::NAME => Box::new(LocalProvider::new()?),
+		#[cfg(feature = "plugins")]
+		name if plugins().iter().any(|plugin| plugin == name) => {
+			Box::new(PluginProvider::load(plugin::default_dir(), name)?)
+		}
+		name => Box::new(DeclarativeProvider::load(declarative::default_dir(), name)?),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolves_names_and_aliases() {
+		for name in available() {
+			assert_eq!(resolve(name).unwrap(), name);
+			assert_eq!(resolve(&name.to_uppercase()).unwrap(), name);
+			for alias in aliases(name) {
+				assert_eq!(resolve(alias).unwrap(), name);
+			}
+		}
+		assert!(resolve("nowhere").is_err());
+	}
+
+	#[test]
+	fn serves_from_the_provider_picked() {
+		let provider = get(local::NAME).unwrap().clone();
+		assert_eq!(provider.name(), local::NAME);
+		assert_eq!(
+			provider.capabilities(),
+			LocalProvider::open("books").capabilities()
+		);
+		assert_eq!(provider.request_interval(), Duration::ZERO);
+	}
+}
@@ -0,0 +1,353 @@
+use crate::{
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use std::time::{Duration, SystemTime};
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper,
+};
+
+pub const NAME: &str = "royalroad";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["royalroad.com"];
+
+const BASE_URL: &str = "https://www.royalroad.com";
+
+lazy_static! {
+	static ref FICTION_ITEM_RE: Regex =
+		Regex::new(r#"<div class="[^"]*fiction-list-item[^"]*"[^>]*>"#).unwrap();
+	static ref FICTION_TITLE_RE: Regex =
+		Regex::new(r#"<h2 class="fiction-title">\s*<a href="([^"]+)"[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref LATEST_CHAPTER_RE: Regex = Regex::new(
+		r#"<a href="(/fiction/\d+/[^"/]+/chapter/[^"]+)"[^>]*>\s*<span[^>]*>([\S\s]+?)</span>\s*<time[^>]*unixtime="(\d+)""#
+	)
+	.unwrap();
+	static ref CHAPTER_ROW_RE: Regex = Regex::new(
+		r#"<tr[^>]*data-url="(/fiction/\d+/[^"/]+/chapter/[^"]+)"[^>]*>\s*<td>\s*<a[^>]*>([\S\s]+?)</a>[\S\s]*?unixtime="(\d+)""#
+	)
+	.unwrap();
+	static ref CHAPTER_TITLE_RE: Regex =
+		Regex::new(r#"<h1[^>]*>([\S\s]+?)</h1>"#).unwrap();
+	static ref CONTENT_RE: Regex = Regex::new(
+		r#"<div class="chapter-inner chapter-content">([\S\s]+?)</div>\s*(?:<div class="portlet|<h6|<hr)"#
+	)
+	.unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p( class="([^"]*)")?[^>]*>([\S\s]*?)</p>"#).unwrap();
+	/// Classes of the hidden paragraphs Royal Road plants to find copies.
+	static ref HIDDEN_CLASS_RE: Regex =
+		Regex::new(r#"\.([\w-]+)\s*\{\s*display:\s*none;"#).unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<h1[^>]*class="font-white"[^>]*>([\S\s]+?)</h1>"#).unwrap();
+	static ref AUTHOR_RE: Regex =
+		Regex::new(r#"<h4[^>]*>\s*<span[^>]*>by</span>\s*<span[^>]*>\s*<a[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref TAG_LINK_RE: Regex =
+		Regex::new(r#"<a[^>]*class="[^"]*fiction-tag[^"]*"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref DESCRIPTION_RE: Regex =
+		Regex::new(r#"<div class="description">\s*<div class="hidden-content">([\S\s]+?)</div>"#)
+			.unwrap();
+	static ref STATUS_RE: Regex =
+		Regex::new(r#"<span class="label[^"]*">\s*(ONGOING|COMPLETED|HIATUS|STUB|DROPPED)\s*</span>"#)
+			.unwrap();
+	static ref RATING_RE: Regex =
+		Regex::new(r#"<meta property="books:rating:value" content="([\d.]+)""#).unwrap();
+	static ref REVIEWS_RE: Regex =
+		Regex::new(r#"<meta property="books:rating:count" content="(\d+)""#).unwrap();
+	static ref COVER_RE: Regex =
+		Regex::new(r#"<img[^>]*class="thumbnail inline-block"[^>]*src="([^"]+)""#).unwrap();
+}
+
+/// Parses a `unixtime` attribute.
+fn unixtime(secs: &str) -> Option<SystemTime> {
+	let secs = secs.parse().ok()?;
+	SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Returns `url` on the `www` host the site redirects to.
+pub fn canonical_url(mut url: Url) -> Url {
+	let _ = url.set_host(Url::parse(BASE_URL).unwrap().host_str());
+	url
+}
+
+/// Returns the url of the fiction page `url` belongs to.
+///
+/// Chapter urls look like
+/// `https://www.royalroad.com/fiction/<id>/<slug>/chapter/<id>/<slug>`, so
+/// only the first three path segments are kept.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	let path = url
+		.path_segments()
+		.map(|segments| segments.take(3).collect::<Vec<_>>().join("/"))
+		.unwrap_or_default();
+	novel.set_path(&path);
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Parses the fictions of a listing like Rising Stars.
+pub(crate) async fn fictions(url: &Url, body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list = vec![];
+	for fiction in FICTION_TITLE_RE.captures_iter(body) {
+		let url = url.join(fiction.get(1).unwrap().as_str())?;
+		let title = strip_tags(fiction.get(2).unwrap().as_str());
+		ranobe_list.push(Novel::new(title, url));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct RoyalRoad {
+	page: u32,
+}
+
+impl RoyalRoad {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for RoyalRoad {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			..Capabilities::default()
+		}
+	}
+	/// Fictions have an RSS feed of their chapters, lighter than their
+	/// page.
+	fn freshness_url(&self, url: &Url) -> Option<Url> {
+		let id = url
+			.path_segments()?
+			.nth(1)
+			.filter(|id| id.parse::<u64>().is_ok())?;
+		Url::parse(&format!("{}/fiction/syndication/{}", BASE_URL, id)).ok()
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/fictions/latest-updates?page={}",
+			BASE_URL, self.page
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		// Every fiction lists its latest chapters below its title
+		let mut ranobe_list = vec![];
+		let starts = FICTION_ITEM_RE
+			.find_iter(&body)
+			.map(|item| item.start())
+			.collect::<Vec<_>>();
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let item = &body[start..end];
+
+			let (fiction, novel) = match FICTION_TITLE_RE.captures(item) {
+				Some(fiction) => (
+					strip_tags(fiction.get(2).unwrap().as_str()),
+					url.join(fiction.get(1).unwrap().as_str())?,
+				),
+				None => continue,
+			};
+			for chapter in LATEST_CHAPTER_RE.captures_iter(item) {
+				ranobe_list.push(Chapter {
+					title: format!(
+						"{}: {}",
+						fiction,
+						strip_tags(chapter.get(2).unwrap().as_str())
+					),
+					url: url.join(chapter.get(1).unwrap().as_str())?,
+					novel: Some(novel.clone()),
+					index: None,
+					published: unixtime(chapter.get(3).unwrap().as_str()),
+					lock: None,
+				});
+			}
+		}
+
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		if !CHAPTER_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "chapter title", &body));
+		}
+		let content = match CONTENT_RE.captures(&body) {
+			Some(content) => content.get(1).unwrap().as_str(),
+			None => return Err(selector_failed(NAME, &url, "chapter text", &body)),
+		};
+
+		let hidden = HIDDEN_CLASS_RE
+			.captures_iter(&body)
+			.map(|class| class.get(1).unwrap().as_str())
+			.collect::<Vec<_>>();
+
+		let text = PARAGRAPH_RE
+			.captures_iter(content)
+			.filter(|paragraph| {
+				paragraph
+					.get(2)
+					.is_none_or(|class| !hidden.contains(&class.as_str()))
+			})
+			.map(|paragraph| to_markdown(paragraph.get(3).unwrap().as_str()))
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		// Some chapters are written without paragraphs
+		let text = if text.is_empty() {
+			to_markdown(content)
+		} else {
+			text
+		};
+
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "chapter text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"{}/fictions/search?tagsAdd={}&page={}",
+				BASE_URL,
+				genre.trim().to_lowercase().replace(' ', "_"),
+				page
+			),
+			None => format!("{}/fictions/best-rated?page={}", BASE_URL, page),
+		};
+		let url = Url::parse(&url)?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		fictions(&url, &body).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/fictions/rising-stars", BASE_URL))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		fictions(&url, &body).await
+	}
+	/// Fetches the chapters of the fiction `novel` belongs to, first to last.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		let url = novel.url.clone();
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = novel_url(&url);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut chapters = vec![];
+		for chapter in CHAPTER_ROW_RE.captures_iter(&body) {
+			chapters.push(Chapter {
+				title: strip_tags(chapter.get(2).unwrap().as_str()),
+				url: page.join(chapter.get(1).unwrap().as_str())?,
+				novel: Some(page.clone()),
+				index: Some(chapters.len()),
+				published: unixtime(chapter.get(3).unwrap().as_str()),
+				lock: None,
+			});
+		}
+
+		if chapters.is_empty() {
+			return Err(selector_failed(NAME, &page, "chapter list", &body));
+		}
+
+		Ok(chapters)
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = novel_url(&url);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "novel title", &body)),
+		}
+
+		metadata.author = AUTHOR_RE
+			.captures(&body)
+			.map(|author| strip_tags(author.get(1).unwrap().as_str()));
+		metadata.status = STATUS_RE.captures(&body).map(|status| {
+			// Statuses are shouted, like `ONGOING`
+			let status = status.get(1).unwrap().as_str();
+			status[..1].to_string() + &status[1..].to_lowercase()
+		});
+		metadata.genres = TAG_LINK_RE
+			.captures_iter(&body)
+			.map(|tag| strip_tags(tag.get(1).unwrap().as_str()))
+			.collect();
+		metadata.description = DESCRIPTION_RE
+			.captures(&body)
+			.map(|description| strip_tags(description.get(1).unwrap().as_str()));
+		metadata.rating = RATING_RE
+			.captures(&body)
+			.and_then(|rating| rating.get(1).unwrap().as_str().parse().ok());
+		metadata.reviews = REVIEWS_RE
+			.captures(&body)
+			.and_then(|reviews| reviews.get(1).unwrap().as_str().parse().ok());
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = page.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		let chapters = CHAPTER_ROW_RE
+			.captures_iter(&body)
+			.map(|chapter| unixtime(chapter.get(3).unwrap().as_str()))
+			.collect::<Vec<_>>();
+		if !chapters.is_empty() {
+			metadata.chapters = Some(chapters.len());
+			metadata.updated = chapters.into_iter().flatten().max();
+		}
+
+		Ok(metadata)
+	}
+}
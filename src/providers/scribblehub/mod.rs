@@ -0,0 +1,277 @@
+use crate::{
+	date,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use chrono::Local;
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter, Listed, Metadata,
+	Novel, RanobeScraper,
+};
+
+pub const NAME: &str = "scribblehub";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["scribblehub.com"];
+
+const BASE_URL: &str = "https://www.scribblehub.com";
+
+lazy_static! {
+	static ref SERIES_ITEM_RE: Regex =
+		Regex::new(r#"<div class="search_main_box[^"]*"[^>]*>"#).unwrap();
+	static ref SERIES_TITLE_RE: Regex =
+		Regex::new(r#"<div class="search_title">\s*<a href="([^"]+)"[^>]*>([\S\s]+?)</a>"#)
+			.unwrap();
+	static ref LATEST_CHAPTER_RE: Regex = Regex::new(
+		r#"<a[^>]*href="(https://www\.scribblehub\.com/read/\d+-[^/"]+/chapter/\d+/?)"[^>]*>([\S\s]+?)</a>"#
+	)
+	.unwrap();
+	static ref LATEST_TIME_RE: Regex =
+		Regex::new(r#"<span class="[^"]*(?:fe_time|release_date)[^"]*"[^>]*>([^<]+)</span>"#)
+			.unwrap();
+	static ref CHAPTER_TITLE_RE: Regex =
+		Regex::new(r#"<div class="chapter-title">([\S\s]+?)</div>"#).unwrap();
+	static ref CONTENT_RE: Regex =
+		Regex::new(r#"<div id="chp_raw" class="chp_raw">([\S\s]+?)</div>\s*<div"#).unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+	static ref NOVEL_TITLE_RE: Regex =
+		Regex::new(r#"<div class="fic_title"[^>]*>([\S\s]+?)</div>"#).unwrap();
+	static ref AUTHOR_RE: Regex =
+		Regex::new(r#"<span class="auth_name_fic">([\S\s]+?)</span>"#).unwrap();
+	static ref GENRE_RE: Regex =
+		Regex::new(r#"<a[^>]*class="fic_genre[^"]*"[^>]*>([\S\s]+?)</a>"#).unwrap();
+	static ref DESCRIPTION_RE: Regex =
+		Regex::new(r#"<div class="wi_fic_desc"[^>]*>([\S\s]+?)</div>"#).unwrap();
+	static ref STATUS_RE: Regex =
+		Regex::new(r#"(?i)<span class="rnd_stats"[^>]*>[\S\s]*?</span>\s*(Ongoing|Completed|Hiatus|Dropped)"#)
+			.unwrap();
+	static ref RATING_RE: Regex =
+		Regex::new(r#"itemprop="ratingValue" content="([\d.]+)""#).unwrap();
+	static ref REVIEWS_RE: Regex = Regex::new(r#"itemprop="ratingCount" content="(\d+)""#).unwrap();
+	static ref CHAPTERS_RE: Regex = Regex::new(r#"<span class="cnt_toc">(\d+)</span>"#).unwrap();
+	static ref COVER_RE: Regex =
+		Regex::new(r#"<div class="fic_image">\s*<img src="([^"]+)""#).unwrap();
+	static ref CHAPTER_URL_RE: Regex = Regex::new(r#"^/read/(\d+)-([^/]+)/chapter/"#).unwrap();
+}
+
+/// Returns the url of the series page `url` belongs to.
+///
+/// Chapter urls look like
+/// `https://www.scribblehub.com/read/<id>-<slug>/chapter/<id>/` while series
+/// pages are at `https://www.scribblehub.com/series/<id>/<slug>/`.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	if let Some(chapter) = CHAPTER_URL_RE.captures(url.path()) {
+		novel.set_path(&format!("/series/{}/{}/", &chapter[1], &chapter[2]));
+	}
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Parses the series of a listing like the ranking.
+pub(crate) async fn series(body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let mut ranobe_list = vec![];
+	for series in SERIES_TITLE_RE.captures_iter(body) {
+		let url = Url::parse(series.get(1).unwrap().as_str().trim())?;
+		let title = strip_tags(series.get(2).unwrap().as_str());
+		ranobe_list.push(Novel::new(title, url));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct ScribbleHub {
+	page: u32,
+}
+
+impl ScribbleHub {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for ScribbleHub {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/latest-series/?pg={}", BASE_URL, self.page))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		// Every series lists its latest chapter and when it was released
+		let now = Local::now();
+		let mut ranobe_list = vec![];
+		let starts = SERIES_ITEM_RE
+			.find_iter(&body)
+			.map(|item| item.start())
+			.collect::<Vec<_>>();
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let item = &body[start..end];
+
+			let (series, chapter) = match (
+				SERIES_TITLE_RE.captures(item),
+				LATEST_CHAPTER_RE.captures(item),
+			) {
+				(Some(series), Some(chapter)) => (series, chapter),
+				_ => continue,
+			};
+			ranobe_list.push(Chapter {
+				title: format!(
+					"{}: {}",
+					strip_tags(series.get(2).unwrap().as_str()),
+					strip_tags(chapter.get(2).unwrap().as_str())
+				),
+				url: Url::parse(chapter.get(1).unwrap().as_str())?,
+				novel: Some(Url::parse(series.get(1).unwrap().as_str().trim())?),
+				index: None,
+				published: LATEST_TIME_RE
+					.captures(item)
+					.and_then(|time| date::parse(time.get(1).unwrap().as_str(), now)),
+				lock: None,
+			});
+		}
+
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		if !CHAPTER_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "chapter title", &body));
+		}
+		let content = match CONTENT_RE.captures(&body) {
+			Some(content) => content.get(1).unwrap().as_str(),
+			None => return Err(selector_failed(NAME, &url, "chapter text", &body)),
+		};
+
+		let text = PARAGRAPH_RE
+			.captures_iter(content)
+			.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		// Some chapters are written without paragraphs
+		let text = if text.is_empty() {
+			to_markdown(content)
+		} else {
+			text
+		};
+
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "chapter text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"{}/genre/{}/?pg={}",
+				BASE_URL,
+				genre.trim().to_lowercase().replace(' ', "-"),
+				page
+			),
+			None => format!("{}/series-ranking/?sort=5&order=1&pg={}", BASE_URL, page),
+		};
+		let body = fetch_url(client, Url::parse(&url)?).await?;
+
+		series(&body).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		// Ranked by the views of the week
+		let url = Url::parse(&format!("{}/series-ranking/?sort=1&order=1", BASE_URL))?;
+		let body = fetch_url(client, url).await?;
+
+		series(&body).await
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let page = novel_url(&url);
+		let body = fetch_url(client, page.clone()).await?;
+
+		let mut metadata = Metadata::default();
+
+		match NOVEL_TITLE_RE.captures(&body) {
+			Some(title) => metadata.title = strip_tags(title.get(1).unwrap().as_str()),
+			None => return Err(selector_failed(NAME, &page, "novel title", &body)),
+		}
+
+		metadata.author = AUTHOR_RE
+			.captures(&body)
+			.map(|author| strip_tags(author.get(1).unwrap().as_str()));
+		metadata.status = STATUS_RE
+			.captures(&body)
+			.map(|status| status.get(1).unwrap().as_str().to_string());
+		metadata.genres = GENRE_RE
+			.captures_iter(&body)
+			.map(|genre| strip_tags(genre.get(1).unwrap().as_str()))
+			.collect();
+		metadata.description = DESCRIPTION_RE
+			.captures(&body)
+			.map(|description| strip_tags(description.get(1).unwrap().as_str()));
+		metadata.rating = RATING_RE
+			.captures(&body)
+			.and_then(|rating| rating.get(1).unwrap().as_str().parse().ok());
+		metadata.reviews = REVIEWS_RE
+			.captures(&body)
+			.and_then(|reviews| reviews.get(1).unwrap().as_str().parse().ok());
+		metadata.chapters = CHAPTERS_RE
+			.captures(&body)
+			.and_then(|chapters| chapters.get(1).unwrap().as_str().parse().ok());
+
+		if let Some(cover) = COVER_RE.captures(&body) {
+			metadata.cover = page.join(cover.get(1).unwrap().as_str()).ok();
+		}
+
+		Ok(metadata)
+	}
+}
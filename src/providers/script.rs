@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+use surf::Url;
+
+use crate::config::Config;
+use crate::error::RanobeError;
+
+use super::Chapter;
+
+#[cfg(feature = "lua")]
+use std::fs;
+#[cfg(feature = "lua")]
+use std::path::Path;
+
+#[cfg(feature = "lua")]
+use mlua::{FromLua, Function, Lua, Value};
+
+/// An entry of the latest updates, as returned by scripts.
+#[cfg(feature = "lua")]
+struct Entry {
+	title: String,
+	url: String,
+}
+
+#[cfg(feature = "lua")]
+impl<'lua> FromLua<'lua> for Entry {
+	fn from_lua(value: Value<'lua>, lua: &'lua Lua) -> mlua::Result<Self> {
+		let entry = mlua::Table::from_lua(value, lua)?;
+		Ok(Self {
+			title: entry.get("title")?,
+			url: entry.get("url")?,
+		})
+	}
+}
+
+/// Returns the directory scripts are loaded from, next to the
+/// configuration.
+pub fn default_dir() -> PathBuf {
+	Config::default_path().with_file_name("scripts")
+}
+
+/// Calls `function` of the script of `provider` with the html of the page
+/// at `url`.
+///
+/// Returns `None` when there is no script, when it does not define
+/// `function` or when `function` returns `nil`, so the provider parses the
+/// page itself.
+#[cfg(feature = "lua")]
+fn call<T: for<'lua> FromLua<'lua>>(
+	provider: &str,
+	function: &str,
+	url: &Url,
+	body: &str,
+) -> Option<Result<T, RanobeError>> {
+	let path = default_dir().join(format!("{}.lua", provider));
+	let source = fs::read_to_string(&path).ok()?;
+
+	run(&Lua::new(), &path, &source, function, (body, url.as_str()))
+		.map_err(|err| RanobeError::Tool(format!("{}: {}", path.display(), err)))
+		.transpose()
+}
+
+/// Runs the script at `path` then calls its `function` with `args`, if it
+/// defines it.
+#[cfg(feature = "lua")]
+fn run<T: for<'lua> FromLua<'lua>>(
+	lua: &Lua,
+	path: &Path,
+	source: &str,
+	function: &str,
+	args: (&str, &str),
+) -> mlua::Result<Option<T>> {
+	lua.load(source)
+		.set_name(path.display().to_string())
+		.exec()?;
+	match lua.globals().get::<_, Option<Function>>(function)? {
+		Some(function) => function.call(args),
+		None => Ok(None),
+	}
+}
+
+/// Parses the latest updates at `url` with the `parse_latest` function of
+/// the script of `provider`, if it has one.
+///
+/// `parse_latest(html, url)` returns a list of `{title = ..., url = ...}`,
+/// urls being relative to the page.
+pub fn parse_latest(
+	provider: &str,
+	url: &Url,
+	body: &str,
+) -> Option<Result<Vec<Chapter>, RanobeError>> {
+	#[cfg(feature = "lua")]
+	return call::<Vec<Entry>>(provider, "parse_latest", url, body).map(|entries| {
+		entries?
+			.into_iter()
+			.map(|entry| {
+				Ok(Chapter {
+					title: entry.title.trim().to_string(),
+					url: url.join(entry.url.trim())?,
+					novel: None,
+					index: None,
+					published: None,
+					lock: None,
+				})
+			})
+			.collect()
+	});
+	#[cfg(not(feature = "lua"))]
+	return {
+		let _ = (provider, url, body);
+		None
+	};
+}
+
+/// Parses the text of the chapter at `url` with the `parse_chapter`
+/// function of the script of `provider`, if it has one.
+///
+/// `parse_chapter(html, url)` returns the text as Markdown.
+pub fn parse_chapter(provider: &str, url: &Url, body: &str) -> Option<Result<String, RanobeError>> {
+	#[cfg(feature = "lua")]
+	return call(provider, "parse_chapter", url, body);
+	#[cfg(not(feature = "lua"))]
+	return {
+		let _ = (provider, url, body);
+		None
+	};
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn runs_scripts() {
+		let path = Path::new("example.lua");
+		let source = r#"
+function parse_latest(html, url)
+	local entries = {}
+	for link, title in html:gmatch('<a href="([^"]+)">([^<]+)</a>') do
+		table.insert(entries, { title = title, url = link })
+	end
+	return entries
+end
+
+function parse_chapter(html, url)
+	if html == "" then
+		return nil
+	end
+	return url .. ": " .. html
+end
+"#;
+		let body = r#"<a href="/novel/chapter-2">Chapter 2</a>"#;
+		let url = "https://example.com/latest";
+
+		let entries: Option<Vec<Entry>> =
+			run(&Lua::new(), path, source, "parse_latest", (body, url)).unwrap();
+		let entries = entries.unwrap();
+		assert_eq!(entries[0].title, "Chapter 2");
+		assert_eq!(entries[0].url, "/novel/chapter-2");
+
+		let text: Option<String> =
+			run(&Lua::new(), path, source, "parse_chapter", ("text", url)).unwrap();
+		assert_eq!(text.unwrap(), "https://example.com/latest: text");
+		let text: Option<String> =
+			run(&Lua::new(), path, source, "parse_chapter", ("", url)).unwrap();
+		assert!(text.is_none());
+		let text: Option<String> =
+			run(&Lua::new(), path, source, "parse_missing", ("", url)).unwrap();
+		assert!(text.is_none());
+
+		assert!(run::<String>(&Lua::new(), path, "function(", "parse_chapter", ("", url)).is_err());
+	}
+}
@@ -0,0 +1,510 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+	date,
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, CLIENT},
+};
+use surf::utils::async_trait;
+
+use chrono::Local;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use surf::Url;
+
+use super::{
+	highlight_quotes, script, to_markdown, Capabilities, Chapter, Metadata, Novel, RanobeScraper,
+	Volume,
+};
+
+pub const NAME: &str = "syosetu";
+
+/// Hosts the provider serves novels from, adult novels are on
+/// `novel18.syosetu.com`.
+pub const HOSTS: [&str; 1] = ["syosetu.com"];
+
+const BASE_URL: &str = "https://ncode.syosetu.com";
+
+const R18_HOST: &str = "novel18.syosetu.com";
+
+const API_URL: &str = "https://api.syosetu.com/novelapi/api/";
+
+const R18_API_URL: &str = "https://api.syosetu.com/novel18api/api/";
+
+/// Novels per page of the listings.
+const PAGE_SIZE: u32 = 20;
+
+/// Most novels the api lists at once.
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Whether adult novels may be read, off unless opted in.
+static R18: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+	static ref CHAPTER_ROW_RE: Regex = Regex::new(
+		r#"<a href="(/n\w+/\d+/)"[^>]*>([\S\s]+?)</a>\s*(?:</dd>\s*<dt class="long_update">|<div class="p-eplist__update">)\s*(\d{4}/\d{2}/\d{2})?"#
+	)
+	.unwrap();
+	static ref VOLUME_TITLE_RE: Regex = Regex::new(
+		r#"<div class="(?:chapter_title|p-eplist__chapter-title)">([\S\s]+?)</div>"#
+	)
+	.unwrap();
+	static ref CHAPTER_TITLE_RE: Regex = Regex::new(
+		r#"<p class="novel_subtitle">([\S\s]+?)</p>|<h1 class="p-novel__title[^"]*">([\S\s]+?)</h1>"#
+	)
+	.unwrap();
+	static ref PARAGRAPH_RE: Regex =
+		Regex::new(r#"<p id="L([pa]?)\d+"[^>]*>([\S\s]*?)</p>"#).unwrap();
+}
+
+/// A novel as listed by the api.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ApiNovel {
+	title: String,
+	ncode: String,
+	writer: String,
+	story: String,
+	keyword: String,
+	/// `1` for serials, `2` for short stories.
+	novel_type: u8,
+	/// `0` once completed.
+	end: u8,
+	general_all_no: usize,
+	general_lastup: String,
+}
+
+impl ApiNovel {
+	/// Returns the url of the novel on the site at `base`.
+	fn url(&self, base: &str) -> Result<Url, RanobeError> {
+		Ok(Url::parse(&format!(
+			"{}/{}/",
+			base,
+			self.ncode.to_lowercase()
+		))?)
+	}
+
+	/// Returns the last chapter of the novel, the novel itself for short
+	/// stories.
+	fn latest(&self, base: &str) -> Result<Chapter, RanobeError> {
+		let url = self.url(base)?;
+		let published = date::parse(
+			self.general_lastup.get(..10).unwrap_or_default(),
+			Local::now(),
+		);
+		if self.novel_type == 2 {
+			return Ok(Chapter {
+				title: self.title.clone(),
+				url: url.clone(),
+				novel: Some(url),
+				index: Some(0),
+				published,
+				lock: None,
+			});
+		}
+
+		Ok(Chapter {
+			title: format!("{}: Part {}", self.title, self.general_all_no),
+			url: url.join(&format!("{}/", self.general_all_no))?,
+			novel: Some(url),
+			// Parts are numbered from one
+			index: self.general_all_no.checked_sub(1),
+			published,
+			lock: None,
+		})
+	}
+}
+
+/// Parses the text of the chapter in `body`.
+///
+/// The foreword and afterword of the author are set apart from the text.
+pub(crate) fn chapter_text(body: &str) -> String {
+	let mut parts: Vec<(&str, Vec<String>)> = vec![];
+	for paragraph in PARAGRAPH_RE.captures_iter(body) {
+		let part = paragraph.get(1).unwrap().as_str();
+		let text = to_markdown(paragraph.get(2).unwrap().as_str());
+		if text.is_empty() {
+			continue;
+		}
+		match parts.last_mut() {
+			Some((last, paragraphs)) if *last == part => paragraphs.push(text),
+			_ => parts.push((part, vec![text])),
+		}
+	}
+
+	parts
+		.into_iter()
+		.map(|(_, paragraphs)| paragraphs.join("\n\n"))
+		.collect::<Vec<_>>()
+		.join("\n\n* * *\n\n")
+}
+
+/// Lets adult novels of `novel18.syosetu.com` be read or not.
+pub fn set_r18(r18: bool) {
+	R18.store(r18, Ordering::Relaxed);
+}
+
+fn r18() -> bool {
+	R18.load(Ordering::Relaxed)
+}
+
+/// Returns the ncode of the novel `url` belongs to, like `n1234ab`.
+///
+/// Chapter urls look like `https://ncode.syosetu.com/<ncode>/<n>/`.
+fn ncode(url: &Url) -> Option<String> {
+	url.path_segments()?
+		.next()
+		.filter(|ncode| ncode.starts_with('n') || ncode.starts_with('N'))
+		.map(str::to_lowercase)
+}
+
+/// Returns the url of the novel page `url` belongs to.
+fn novel_url(url: &Url) -> Url {
+	let mut novel = url.clone();
+	if let Some(ncode) = ncode(url) {
+		novel.set_path(&format!("/{}/", ncode));
+	}
+	novel.set_query(None);
+	novel.set_fragment(None);
+	novel
+}
+
+/// Returns `url` the way the site links it, with a lowercase ncode and a
+/// trailing slash.
+pub fn canonical_url(mut url: Url) -> Url {
+	let path = format!("{}/", url.path().trim_end_matches('/'));
+	url.set_path(&match ncode(&url) {
+		Some(ncode) => format!("/{}{}", ncode, &path[ncode.len() + 1..]),
+		None => path,
+	});
+	url
+}
+
+fn is_r18(url: &Url) -> bool {
+	url.host_str() == Some(R18_HOST)
+}
+
+/// Fails if `url` is an adult novel and they were not opted into.
+fn check_r18(url: &Url) -> Result<(), RanobeError> {
+	if is_r18(url) && !r18() {
+		return Err(RanobeError::Unauthorized(format!(
+			"{} is an adult novel, set `r18 = true` in the configuration to read it",
+			url
+		)));
+	}
+
+	Ok(())
+}
+
+/// Fetches `url`, confirming the age check of adult novels if they were
+/// opted into.
+async fn fetch(url: Url) -> Result<String, RanobeError> {
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+	check_r18(&url)?;
+	if !is_r18(&url) {
+		return Ok(client.get(url).recv_string().await?);
+	}
+
+	Ok(client
+		.get(url)
+		.header("cookie", "over18=yes")
+		.recv_string()
+		.await?)
+}
+
+/// Queries the novel api, or the one of adult novels with `r18`.
+async fn api(query: &[(&str, &str)], r18: bool) -> Result<Vec<ApiNovel>, RanobeError> {
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+	let mut url = Url::parse(if r18 { R18_API_URL } else { API_URL })?;
+	url.query_pairs_mut()
+		.append_pair("out", "json")
+		.extend_pairs(query);
+
+	// The first entry only counts the matches
+	let answer: Vec<serde_json::Value> = client.get(url).recv_json().await?;
+	answer
+		.into_iter()
+		.skip(1)
+		.map(|novel| serde_json::from_value(novel).map_err(RanobeError::from))
+		.collect()
+}
+
+/// Lists the novels the api finds for `query` on the `page`th page.
+async fn listing(query: &[(&str, &str)], page: u32) -> Result<Vec<Novel>, RanobeError> {
+	let limit = PAGE_SIZE.to_string();
+	let start = ((page.max(1) - 1) * PAGE_SIZE + 1).to_string();
+	let mut query = query.to_vec();
+	query.extend([("lim", limit.as_str()), ("st", start.as_str())]);
+
+	api(&query, false)
+		.await?
+		.iter()
+		.map(|novel| {
+			Ok(Novel {
+				title: novel.title.clone(),
+				url: novel.url(BASE_URL)?,
+				updated: None,
+			})
+		})
+		.collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Syosetu {
+	page: u32,
+	page_size: u32,
+}
+
+impl Syosetu {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self {
+			page: 1,
+			page_size: PAGE_SIZE,
+		})
+	}
+
+	/// Fetches the chapters of the novel `url` belongs to, first to last,
+	/// with the title of the volume they are listed under.
+	///
+	/// The table of contents lists a hundred chapters a page, the rest are
+	/// on its `?p=N` pages. Short stories are their own only chapter.
+	async fn table_of_contents(
+		&self,
+		url: Url,
+	) -> Result<Vec<(Option<String>, Chapter)>, RanobeError> {
+		let novel = novel_url(&url);
+		let mut chapters = vec![];
+		let mut volume = None;
+		let mut page = 1;
+		loop {
+			let mut list = novel.clone();
+			if page > 1 {
+				list.set_query(Some(&format!("p={}", page)));
+			}
+			let body = fetch(list.clone()).await?;
+
+			let before = chapters.len();
+			let mut last = 0;
+			for chapter in CHAPTER_ROW_RE.captures_iter(&body) {
+				let row = chapter.get(0).unwrap();
+				// Volume titles head the chapters listed under them
+				if let Some(title) = VOLUME_TITLE_RE
+					.captures_iter(&body[last..row.start()])
+					.last()
+				{
+					volume = Some(to_markdown(title.get(1).unwrap().as_str()));
+				}
+				last = row.end();
+
+				chapters.push((
+					volume.clone(),
+					Chapter {
+						title: to_markdown(chapter.get(2).unwrap().as_str()),
+						url: list.join(chapter.get(1).unwrap().as_str())?,
+						novel: Some(novel.clone()),
+						index: Some(chapters.len()),
+						published: chapter
+							.get(3)
+							.and_then(|updated| date::parse(updated.as_str(), Local::now())),
+						lock: None,
+					},
+				));
+			}
+
+			if chapters.len() == before {
+				if page == 1 && PARAGRAPH_RE.is_match(&body) {
+					let metadata = self.get_metadata(novel.clone()).await?;
+					chapters.push((
+						None,
+						Chapter {
+							title: metadata.title,
+							url: novel.clone(),
+							novel: Some(novel),
+							index: Some(0),
+							published: metadata.updated,
+							lock: None,
+						},
+					));
+				} else if page == 1 {
+					return Err(selector_failed(NAME, &list, "chapter list", &body));
+				}
+				break;
+			}
+
+			// Only pages before the last link to the next one
+			if !body.contains(&format!("?p={}\"", page + 1)) {
+				break;
+			}
+			page += 1;
+		}
+
+		Ok(chapters)
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for Syosetu {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			catalogue: true,
+			ranking: true,
+			by_author: true,
+			..Capabilities::default()
+		}
+	}
+	fn freshness_url(&self, url: &Url) -> Option<Url> {
+		Some(novel_url(url))
+	}
+	fn set_page_size(&mut self, size: usize) -> bool {
+		self.page_size = size.clamp(1, MAX_PAGE_SIZE as usize) as u32;
+		size <= MAX_PAGE_SIZE as usize
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let limit = self.page_size.to_string();
+		let start = ((self.page - 1) * self.page_size + 1).to_string();
+		let novels = api(&[("order", "new"), ("lim", &limit), ("st", &start)], false).await?;
+
+		let ranobe_list = novels
+			.iter()
+			.map(|novel| novel.latest(BASE_URL))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let body = fetch(url.clone()).await?;
+
+		if let Some(text) = script::parse_chapter(NAME, &url, &body) {
+			return text;
+		}
+
+		// Short stories have no chapter title, only the novel's
+		if url.path() != novel_url(&url).path() && !CHAPTER_TITLE_RE.is_match(&body) {
+			return Err(selector_failed(NAME, &url, "chapter title", &body));
+		}
+
+		let text = chapter_text(&body);
+		if text.trim().is_empty() {
+			return Err(selector_failed(NAME, &url, "chapter text", &body));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	/// Lists the novels of `genre`, either one of the numeric genres of the
+	/// api like `201` for high fantasy or a keyword like `異世界`.
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let genre = genre.map(str::trim).unwrap_or_default();
+		let query = if genre.is_empty() {
+			vec![("order", "hyoka"), ("type", "er")]
+		} else if genre.chars().all(|c| c.is_ascii_digit()) {
+			vec![("order", "hyoka"), ("genre", genre)]
+		} else {
+			vec![("order", "hyoka"), ("word", genre), ("keyword", "1")]
+		};
+
+		listing(&query, page).await
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		listing(&[("order", "dailypoint")], 1).await
+	}
+	/// Lists the novels of the writer `author`, most recently updated first.
+	async fn get_by_author(&self, author: &str) -> Result<Vec<Novel>, RanobeError> {
+		listing(
+			&[("order", "new"), ("word", author.trim()), ("wname", "1")],
+			1,
+		)
+		.await
+	}
+	/// Chapters listed before the first volume title are left out.
+	async fn get_volumes(&self, url: Url) -> Result<Vec<Volume>, RanobeError> {
+		let mut volumes: Vec<Volume> = vec![];
+		for (volume, chapter) in self.table_of_contents(url).await? {
+			let title = match volume {
+				Some(title) => title,
+				None => continue,
+			};
+			let url = super::canonical_url(&chapter.url);
+			match volumes.last_mut() {
+				Some(last) if last.title == title => last.chapters.push(url),
+				_ => volumes.push(Volume {
+					title,
+					chapters: vec![url],
+				}),
+			}
+		}
+
+		Ok(volumes)
+	}
+	/// Fetches the chapters of the novel `novel` belongs to, first to last.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		Ok(self
+			.table_of_contents(novel.url.clone())
+			.await?
+			.into_iter()
+			.map(|(_, chapter)| chapter)
+			.collect())
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let page = novel_url(&url);
+		let ncode = match ncode(&page) {
+			Some(ncode) => ncode,
+			None => {
+				return Err(RanobeError::Invalid(format!(
+					"{} is not a novel of {}",
+					url, NAME
+				)))
+			}
+		};
+		check_r18(&page)?;
+
+		let novel = match api(&[("ncode", &ncode)], is_r18(&page))
+			.await?
+			.into_iter()
+			.next()
+		{
+			Some(novel) => novel,
+			None => {
+				return Err(RanobeError::NotFound(format!(
+					"{} found no novel {}",
+					NAME, ncode
+				)))
+			}
+		};
+
+		Ok(Metadata {
+			title: novel.title.clone(),
+			author: Some(novel.writer.clone()).filter(|writer| !writer.is_empty()),
+			status: Some(
+				if novel.end == 0 {
+					"Completed"
+				} else {
+					"Ongoing"
+				}
+				.to_string(),
+			),
+			genres: novel.keyword.split_whitespace().map(String::from).collect(),
+			description: Some(novel.story.trim().to_string()).filter(|story| !story.is_empty()),
+			origin: Some("JP".to_string()),
+			chapters: Some(novel.general_all_no),
+			updated: date::parse(
+				novel.general_lastup.get(..10).unwrap_or_default(),
+				Local::now(),
+			),
+			..Metadata::default()
+		})
+	}
+}
@@ -0,0 +1,495 @@
+use crate::{
+	error::RanobeError,
+	failure::selector_failed,
+	http::{client_init, fetch_url, CLIENT},
+};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use surf::utils::async_trait;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize};
+use surf::{StatusCode, Url};
+
+use super::{
+	canonical_url, highlight_quotes, script, strip_tags, to_markdown, Capabilities, Chapter,
+	Listed, Lock, Metadata, Novel, RanobeScraper, Volume,
+};
+
+pub const NAME: &str = "webnovel";
+
+/// Hosts the provider serves novels from.
+pub const HOSTS: [&str; 1] = ["webnovel.com"];
+
+const BASE_URL: &str = "https://www.webnovel.com";
+
+lazy_static! {
+	/// Token the content api wants both as a cookie and a query parameter.
+	static ref CSRF_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+	static ref CSRF_RE: Regex = Regex::new(r#"_csrfToken=([^;]+)"#).unwrap();
+	/// Book and chapter ids of urls like `/book/<slug>_<id>/<slug>_<id>`.
+	static ref BOOK_URL_RE: Regex =
+		Regex::new(r#"^/book/(?:[^/]*_)?(\d+)(?:/(?:[^/]*_)?(\d+))?/?$"#).unwrap();
+	static ref STORY_ITEM_RE: Regex = Regex::new(r#"<li[^>]*class="[^"]*g_col[^"]*"[^>]*>"#).unwrap();
+	static ref BOOK_LINK_RE: Regex =
+		Regex::new(r#"<a[^>]*href="(?:https://www\.webnovel\.com)?(/book/[^"/]*_?\d+)/?"[^>]*title="([^"]+)""#)
+			.unwrap();
+	static ref CHAPTER_LINK_RE: Regex = Regex::new(
+		r#"<a[^>]*href="(?:https://www\.webnovel\.com)?(/book/[^"/]*_?\d+/[^"/]*_?\d+)/?"[^>]*>([\S\s]+?)</a>"#
+	)
+	.unwrap();
+	static ref BOOK_NAME_RE: Regex = Regex::new(r#""bookName"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+	static ref AUTHOR_RE: Regex = Regex::new(r#""authorName"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+	static ref DESCRIPTION_RE: Regex =
+		Regex::new(r#""description"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+	static ref CATEGORY_RE: Regex =
+		Regex::new(r#""categoryName"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+	static ref STATUS_RE: Regex = Regex::new(r#""actionStatus"\s*:\s*(\d+)"#).unwrap();
+	static ref RATING_RE: Regex = Regex::new(r#""totalScore"\s*:\s*"?([\d.]+)"#).unwrap();
+	static ref REVIEWS_RE: Regex = Regex::new(r#""reviewTotal"\s*:\s*"?(\d+)"#).unwrap();
+	static ref CHAPTERS_RE: Regex = Regex::new(r#""totalChapterNum"\s*:\s*"?(\d+)"#).unwrap();
+	static ref PARAGRAPH_RE: Regex = Regex::new(r#"<p[^>]*>([\S\s]*?)</p>"#).unwrap();
+}
+
+/// Envelope of every answer of the api.
+#[derive(Debug, Deserialize)]
+struct Answer<T> {
+	code: i64,
+	#[serde(default)]
+	msg: Option<String>,
+	data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentData {
+	chapter_info: ChapterInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterInfo {
+	chapter_name: String,
+	#[serde(default)]
+	is_vip: u8,
+	#[serde(default)]
+	is_auth: u8,
+	#[serde(default)]
+	contents: Vec<Content>,
+}
+
+impl ChapterInfo {
+	/// Whether the chapter has to be paid for and was not.
+	fn is_locked(&self) -> bool {
+		self.is_vip != 0 && self.is_auth == 0
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+	content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterListData {
+	volume_items: Vec<VolumeItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeItem {
+	#[serde(default, alias = "name")]
+	volume_name: String,
+	#[serde(default)]
+	chapter_items: Vec<ChapterItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChapterItem {
+	#[serde(alias = "id")]
+	chapter_id: serde_json::Value,
+	#[serde(alias = "name")]
+	chapter_name: String,
+	#[serde(default)]
+	is_vip: u8,
+	#[serde(default)]
+	is_auth: u8,
+	/// When the chapter becomes free, in milliseconds since the epoch, for
+	/// chapters released to paying readers first.
+	#[serde(default)]
+	free_time: Option<u64>,
+}
+
+impl ChapterItem {
+	/// Returns until when the chapter is locked, if it has to be paid for
+	/// and was not.
+	fn lock(&self) -> Option<Lock> {
+		if self.is_vip == 0 || self.is_auth != 0 {
+			return None;
+		}
+
+		Some(match self.free_time {
+			Some(millis) if millis > 0 => Lock::Until(UNIX_EPOCH + Duration::from_millis(millis)),
+			_ => Lock::Paid,
+		})
+	}
+}
+
+/// Decodes a string literal captured out of the json embedded in a page.
+fn json_string(raw: &str) -> String {
+	serde_json::from_str(&format!("\"{}\"", raw)).unwrap_or_else(|_| raw.to_string())
+}
+
+/// Ids are numbers too large for some clients, so they come as strings too.
+fn id(value: &serde_json::Value) -> String {
+	match value {
+		serde_json::Value::String(id) => id.clone(),
+		id => id.to_string(),
+	}
+}
+
+/// Returns the book id and, for chapters, the chapter id of `url`.
+fn ids(url: &Url) -> Option<(String, Option<String>)> {
+	let ids = BOOK_URL_RE.captures(url.path())?;
+	Some((
+		ids.get(1).unwrap().as_str().to_string(),
+		ids.get(2).map(|chapter| chapter.as_str().to_string()),
+	))
+}
+
+fn invalid_url(url: &Url) -> RanobeError {
+	RanobeError::Invalid(format!("{} is not a Webnovel book or chapter.", url))
+}
+
+/// Returns the csrf token handed out with the cookies of the home page.
+async fn csrf_token() -> Result<String, RanobeError> {
+	if let Some(token) = CSRF_TOKEN.lock().unwrap().clone() {
+		return Ok(token);
+	}
+
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+	let response = client.get(Url::parse(BASE_URL)?).await?;
+	let token = response
+		.header("set-cookie")
+		.into_iter()
+		.flat_map(|cookies| cookies.iter())
+		.find_map(|cookie| CSRF_RE.captures(cookie.as_str()))
+		.map(|token| token.get(1).unwrap().as_str().to_string())
+		.ok_or_else(|| {
+			RanobeError::network(StatusCode::BadGateway, "Webnovel handed out no csrf token.")
+		})?;
+
+	*CSRF_TOKEN.lock().unwrap() = Some(token.clone());
+	Ok(token)
+}
+
+/// Calls the api at `path` with `query` and returns the data it answered.
+async fn api<T: DeserializeOwned>(path: &str, query: &[(&str, &str)]) -> Result<T, RanobeError> {
+	let client = CLIENT.get_or_init(|| client_init().unwrap());
+	let token = csrf_token().await?;
+
+	let mut url = Url::parse(&format!("{}{}", BASE_URL, path))?;
+	url.query_pairs_mut()
+		.append_pair("_csrfToken", &token)
+		.extend_pairs(query);
+
+	let answer: Answer<T> = client
+		.get(url)
+		.header("cookie", format!("_csrfToken={}", token).as_str())
+		.recv_json()
+		.await?;
+
+	match answer.data {
+		Some(data) if answer.code == 0 => Ok(data),
+		_ => Err(RanobeError::network(
+			StatusCode::BadGateway,
+			format!(
+				"Webnovel answered {}: {}",
+				answer.code,
+				answer.msg.unwrap_or_default()
+			),
+		)),
+	}
+}
+
+/// Parses the books of a listing like the power ranking.
+pub(crate) fn books(body: &str) -> Result<Vec<Novel>, RanobeError> {
+	let base = Url::parse(BASE_URL)?;
+
+	let mut ranobe_list: Vec<Novel> = vec![];
+	for book in BOOK_LINK_RE.captures_iter(body) {
+		let url = base.join(book.get(1).unwrap().as_str())?;
+		// Covers and titles both link to the book
+		if ranobe_list.iter().any(|ranobe| ranobe.url == url) {
+			continue;
+		}
+		ranobe_list.push(Novel::new(to_markdown(book.get(2).unwrap().as_str()), url));
+	}
+
+	Ok(ranobe_list)
+}
+
+#[derive(Debug, Clone)]
+pub struct Webnovel {
+	page: u32,
+}
+
+impl Webnovel {
+	pub fn new() -> Result<Self, RanobeError> {
+		Ok(Self { page: 1 })
+	}
+
+	/// Fetches the volumes of the book `url` belongs to with their
+	/// chapters, first to last, locked ones included.
+	async fn table_of_contents(
+		&self,
+		url: &Url,
+	) -> Result<Vec<(String, Vec<Chapter>)>, RanobeError> {
+		let (book, _) = ids(url).ok_or_else(|| invalid_url(url))?;
+		let novel = Url::parse(&format!("{}/book/{}", BASE_URL, book))?;
+
+		let list: ChapterListData =
+			api("/go/pcm/chapter/get-chapter-list", &[("bookId", &book)]).await?;
+
+		let mut volumes = vec![];
+		let mut index = 0;
+		for volume in list.volume_items {
+			let mut chapters = vec![];
+			for chapter in volume.chapter_items {
+				let lock = chapter.lock();
+				chapters.push(Chapter {
+					title: chapter.chapter_name,
+					url: Url::parse(&format!(
+						"{}/book/{}/{}",
+						BASE_URL,
+						book,
+						id(&chapter.chapter_id)
+					))?,
+					novel: Some(novel.clone()),
+					index: Some(index),
+					published: None,
+					lock,
+				});
+				index += 1;
+			}
+			volumes.push((volume.volume_name, chapters));
+		}
+
+		Ok(volumes)
+	}
+}
+
+#[async_trait]
+impl RanobeScraper for Webnovel {
+	fn name(&self) -> &'static str {
+		NAME
+	}
+	fn capabilities(&self) -> Capabilities {
+		Capabilities {
+			toc: true,
+			covers: true,
+			catalogue: true,
+			ranking: true,
+			..Capabilities::default()
+		}
+	}
+	async fn get_latest(&mut self) -> Result<Vec<Chapter>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!(
+			"{}/stories/novel?orderBy=5&pageIndex={}",
+			BASE_URL, self.page
+		))?;
+		let body = fetch_url(client, url.clone()).await?;
+
+		if let Some(ranobe_list) = script::parse_latest(NAME, &url, &body) {
+			let ranobe_list = ranobe_list?;
+			self.page += 1;
+			return Ok(ranobe_list);
+		}
+
+		// Every book lists the chapter it was last updated with
+		let mut ranobe_list = vec![];
+		let starts = STORY_ITEM_RE
+			.find_iter(&body)
+			.map(|item| item.start())
+			.collect::<Vec<_>>();
+		for (i, &start) in starts.iter().enumerate() {
+			let end = starts.get(i + 1).copied().unwrap_or(body.len());
+			let item = &body[start..end];
+
+			let (book, chapter) =
+				match (BOOK_LINK_RE.captures(item), CHAPTER_LINK_RE.captures(item)) {
+					(Some(book), Some(chapter)) => (book, chapter),
+					_ => continue,
+				};
+			ranobe_list.push(Chapter {
+				title: format!(
+					"{}: {}",
+					strip_tags(book.get(2).unwrap().as_str()),
+					strip_tags(chapter.get(2).unwrap().as_str())
+				),
+				url: url.join(chapter.get(1).unwrap().as_str())?,
+				novel: Some(url.join(book.get(1).unwrap().as_str())?),
+				index: None,
+				published: None,
+				lock: None,
+			});
+		}
+
+		if ranobe_list.is_empty() {
+			return Err(selector_failed(NAME, &url, "latest updates", &body));
+		}
+
+		self.page += 1;
+
+		Ok(ranobe_list)
+	}
+	/// Reads the chapter through the content api, which pages only load
+	/// with scripts.
+	async fn get_text(&self, url: Url) -> Result<String, RanobeError> {
+		let (book, chapter) = match ids(&url) {
+			Some((book, Some(chapter))) => (book, chapter),
+			_ => return Err(invalid_url(&url)),
+		};
+
+		let content: ContentData = api(
+			"/go/pcm/chapter/getContent",
+			&[("bookId", &book), ("chapterId", &chapter)],
+		)
+		.await?;
+		let info = content.chapter_info;
+
+		if info.is_locked() {
+			return Err(RanobeError::Unauthorized(format!(
+				"{} is locked, unlock it on Webnovel to read it.",
+				info.chapter_name
+			)));
+		}
+
+		let text = info
+			.contents
+			.iter()
+			.flat_map(|content| {
+				// Paragraphs are either html or plain lines
+				let paragraphs = PARAGRAPH_RE
+					.captures_iter(&content.content)
+					.map(|paragraph| to_markdown(paragraph.get(1).unwrap().as_str()))
+					.collect::<Vec<_>>();
+				if paragraphs.is_empty() {
+					vec![to_markdown(&content.content)]
+				} else {
+					paragraphs
+				}
+			})
+			.filter(|paragraph| !paragraph.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n");
+
+		if text.trim().is_empty() {
+			return Err(RanobeError::Parse(format!(
+				"Webnovel sent no text for {}.",
+				info.chapter_name
+			)));
+		}
+
+		Ok(highlight_quotes(&text))
+	}
+	async fn get_catalogue(
+		&self,
+		genre: Option<&str>,
+		page: u32,
+	) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = match genre {
+			Some(genre) => format!(
+				"{}/stories/novel-{}?pageIndex={}",
+				BASE_URL,
+				genre.trim().to_lowercase().replace(' ', "-"),
+				page
+			),
+			None => format!("{}/stories/novel?orderBy=1&pageIndex={}", BASE_URL, page),
+		};
+		let body = fetch_url(client, Url::parse(&url)?).await?;
+
+		books(&body)
+	}
+	async fn get_ranking(&self) -> Result<Vec<Novel>, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let url = Url::parse(&format!("{}/ranking/novel/all_time/power_rank", BASE_URL))?;
+		let body = fetch_url(client, url).await?;
+
+		books(&body)
+	}
+	/// Books without named volumes are left unsplit.
+	async fn get_volumes(&self, url: Url) -> Result<Vec<Volume>, RanobeError> {
+		let volumes = self.table_of_contents(&url).await?;
+		if volumes.iter().all(|(title, _)| title.trim().is_empty()) {
+			return Ok(vec![]);
+		}
+
+		Ok(volumes
+			.into_iter()
+			.map(|(title, chapters)| Volume {
+				title: title.trim().to_string(),
+				chapters: chapters
+					.iter()
+					.map(|chapter| canonical_url(&chapter.url))
+					.collect(),
+			})
+			.collect())
+	}
+	/// Fetches the chapters of the book `novel` belongs to, first to last,
+	/// locked ones included.
+	async fn get_chapters(&self, novel: &Novel) -> Result<Vec<Chapter>, RanobeError> {
+		Ok(self
+			.table_of_contents(&novel.url)
+			.await?
+			.into_iter()
+			.flat_map(|(_, chapters)| chapters)
+			.collect())
+	}
+	async fn get_metadata(&self, url: Url) -> Result<Metadata, RanobeError> {
+		let client = CLIENT.get_or_init(|| client_init().unwrap());
+
+		let (book, _) = ids(&url).ok_or_else(|| invalid_url(&url))?;
+		let page = Url::parse(&format!("{}/book/{}", BASE_URL, book))?;
+		let body = fetch_url(client, page.clone()).await?;
+
+		// The book is described by the json the page is rendered from
+		let field = |re: &Regex| {
+			re.captures(&body)
+				.map(|field| field.get(1).unwrap().as_str().to_string())
+		};
+
+		let mut metadata = Metadata::default();
+
+		match field(&BOOK_NAME_RE) {
+			Some(title) => metadata.title = json_string(&title),
+			None => return Err(selector_failed(NAME, &page, "book name", &body)),
+		}
+
+		metadata.author = field(&AUTHOR_RE).map(|author| json_string(&author));
+		metadata.status = field(&STATUS_RE).map(|status| match status.as_str() {
+			"30" | "50" => "Completed".to_string(),
+			_ => "Ongoing".to_string(),
+		});
+		metadata.genres = field(&CATEGORY_RE)
+			.map(|category| json_string(&category))
+			.into_iter()
+			.collect();
+		metadata.description = field(&DESCRIPTION_RE).map(|description| json_string(&description));
+		metadata.rating = field(&RATING_RE).and_then(|rating| rating.parse().ok());
+		metadata.reviews = field(&REVIEWS_RE).and_then(|reviews| reviews.parse().ok());
+		metadata.chapters = field(&CHAPTERS_RE).and_then(|chapters| chapters.parse().ok());
+		metadata.cover =
+			Url::parse(&format!("https://book-pic.webnovel.com/bookcover/{}", book)).ok();
+
+		Ok(metadata)
+	}
+}
@@ -0,0 +1,129 @@
+use std::time::SystemTime;
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use crate::providers::{Listed, Metadata};
+
+const TITLE_WEIGHT: f64 = 0.6;
+const CHAPTERS_WEIGHT: f64 = 0.2;
+const RECENCY_WEIGHT: f64 = 0.2;
+
+/// Days after which a novel counts as half as fresh as one updated today.
+const RECENCY_HALF_LIFE: f64 = 30.0;
+
+/// A search result together with its score.
+#[derive(Debug, Clone)]
+pub struct Ranked<E> {
+	pub ranobe: E,
+	/// Score between 0 and 1, higher is better.
+	pub score: f64,
+}
+
+/// Ranks `items` against `query`, best match first.
+///
+/// The score mixes title similarity with the chapter count and the update
+/// recency from `metadata`, falling back to the update time of the listing.
+/// Items without either only score on their title, items whose title does
+/// not match `query` at all, like ones found under another title, only on
+/// the rest.
+pub fn rank<'a, E, F>(query: &str, items: Vec<E>, metadata: F) -> Vec<Ranked<E>>
+where
+	E: Listed,
+	F: Fn(&E) -> Option<&'a Metadata>,
+{
+	let matcher = SkimMatcherV2::default();
+
+	let matches = items
+		.into_iter()
+		.map(|ranobe| {
+			let title = matcher.fuzzy_match(ranobe.title(), query).unwrap_or(0);
+			(ranobe, title.max(0))
+		})
+		.collect::<Vec<_>>();
+
+	let best_title = matches
+		.iter()
+		.map(|(_, title)| *title)
+		.max()
+		.unwrap_or(1)
+		.max(1);
+	let most_chapters = matches
+		.iter()
+		.filter_map(|(ranobe, _)| metadata(ranobe)?.chapters)
+		.max()
+		.unwrap_or(0);
+
+	let mut ranked = matches
+		.into_iter()
+		.map(|(ranobe, title)| {
+			let title = title as f64 / best_title as f64;
+			let chapters = metadata(&ranobe)
+				.and_then(|metadata| metadata.chapters)
+				.map_or(0.0, |chapters| log_ratio(chapters, most_chapters));
+			let recency = metadata(&ranobe)
+				.and_then(|metadata| metadata.updated)
+				.or(ranobe.updated())
+				.map_or(0.0, recency);
+
+			Ranked {
+				ranobe,
+				score: TITLE_WEIGHT * title + CHAPTERS_WEIGHT * chapters + RECENCY_WEIGHT * recency,
+			}
+		})
+		.collect::<Vec<_>>();
+
+	ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+	ranked
+}
+
+/// Compares chapter counts on a log scale, so 2000 chapters do not bury 200.
+fn log_ratio(chapters: usize, most: usize) -> f64 {
+	if most == 0 {
+		return 0.0;
+	}
+
+	(chapters as f64).ln_1p() / (most as f64).ln_1p()
+}
+
+fn recency(updated: SystemTime) -> f64 {
+	let days = SystemTime::now()
+		.duration_since(updated)
+		.map_or(0.0, |age| age.as_secs_f64() / 86400.0);
+
+	0.5_f64.powf(days / RECENCY_HALF_LIFE)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Novel;
+
+	fn ranobe(title: &str, url: &str) -> Novel {
+		Novel::new(title.to_string(), surf::Url::parse(url).unwrap())
+	}
+
+	#[test]
+	fn metadata_breaks_title_ties() {
+		let popular = Metadata {
+			chapters: Some(1200),
+			updated: Some(SystemTime::now()),
+			..Metadata::default()
+		};
+
+		let ranked = rank(
+			"slime",
+			vec![
+				ranobe("Slime Diaries", "https://a.example/slime"),
+				ranobe("Slime Diaries", "https://b.example/slime"),
+				ranobe("Overlord", "https://a.example/overlord"),
+			],
+			|ranobe| (ranobe.url.host_str() == Some("b.example")).then_some(&popular),
+		);
+
+		assert_eq!(ranked.len(), 3);
+		assert_eq!(ranked[0].ranobe.url.host_str(), Some("b.example"));
+		assert!(ranked[0].score > ranked[1].score);
+		assert_eq!(ranked[2].ranobe.title, "Overlord");
+	}
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::crypt;
+use crate::utils::data_dir;
+
+/// A daily nudge to continue reading a novel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+	pub novel: String,
+	/// Local time of day the reminder is due at.
+	pub at: NaiveTime,
+	pub last_sent: Option<DateTime<Local>>,
+}
+
+impl Reminder {
+	/// Returns whether the reminder is due at `now`, it is due once a day
+	/// from its time of day on.
+	pub fn is_due(&self, now: DateTime<Local>) -> bool {
+		let today = match Local
+			.from_local_datetime(&now.date_naive().and_time(self.at))
+			.earliest()
+		{
+			Some(today) => today,
+			None => return false,
+		};
+
+		now >= today && self.last_sent.is_none_or(|sent| sent < today)
+	}
+}
+
+/// Parses a time of day given as `HH:MM`.
+pub fn parse_time(time: &str) -> Option<NaiveTime> {
+	NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+/// Reading reminders, at most one per novel.
+pub struct Reminders {
+	path: PathBuf,
+	reminders: Vec<Reminder>,
+}
+
+impl Reminders {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("reminders.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let reminders = match crypt::read_to_string(&path) {
+			Ok(reminders) => serde_json::from_str(&reminders)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, reminders })
+	}
+
+	pub fn reminders(&self) -> &[Reminder] {
+		&self.reminders
+	}
+
+	/// Reminds to continue `novel` every day at `at`, replacing its previous
+	/// reminder.
+	pub fn set(&mut self, novel: &str, at: NaiveTime) -> Result<()> {
+		self.reminders.retain(|reminder| reminder.novel != novel);
+		self.reminders.push(Reminder {
+			novel: novel.to_string(),
+			at,
+			last_sent: None,
+		});
+
+		self.save()
+	}
+
+	/// Removes the reminder of `novel`, returns whether there was one.
+	pub fn remove(&mut self, novel: &str) -> Result<bool> {
+		let len = self.reminders.len();
+		self.reminders.retain(|reminder| reminder.novel != novel);
+		self.save()?;
+
+		Ok(self.reminders.len() != len)
+	}
+
+	/// Returns the reminders due at `now` and marks them as sent.
+	pub fn take_due(&mut self, now: DateTime<Local>) -> Result<Vec<Reminder>> {
+		let mut due = vec![];
+		for reminder in self.reminders.iter_mut().filter(|r| r.is_due(now)) {
+			reminder.last_sent = Some(now);
+			due.push(reminder.clone());
+		}
+
+		if !due.is_empty() {
+			self.save()?;
+		}
+
+		Ok(due)
+	}
+
+	fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.reminders)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn due_once_a_day() {
+		let at = |h, m| Local.with_ymd_and_hms(2023, 2, 10, h, m, 0).unwrap();
+		let mut reminder = Reminder {
+			novel: "Novel".to_string(),
+			at: parse_time("21:00").unwrap(),
+			last_sent: None,
+		};
+
+		assert!(!reminder.is_due(at(20, 59)));
+		assert!(reminder.is_due(at(21, 30)));
+
+		reminder.last_sent = Some(at(21, 30));
+		assert!(!reminder.is_due(at(23, 0)));
+		assert!(reminder.is_due(at(23, 0) + chrono::Duration::days(1)));
+	}
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::crypt;
+use crate::utils::data_dir;
+
+/// How many queries are remembered per context.
+const SEARCHES_SIZE: usize = 20;
+
+/// Recent search queries, kept apart per context like the novel search or
+/// the chapter filter.
+pub struct Searches {
+	path: PathBuf,
+	queries: HashMap<String, Vec<String>>,
+}
+
+impl Searches {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("searches.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let queries = match crypt::read_to_string(&path) {
+			Ok(queries) => serde_json::from_str(&queries)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, queries })
+	}
+
+	/// Returns the queries of `context`, most recent first.
+	pub fn queries(&self, context: &str) -> &[String] {
+		self.queries.get(context).map_or(&[], Vec::as_slice)
+	}
+
+	/// Records `query` as the most recent query of `context`.
+	pub fn record(&mut self, context: &str, query: &str) -> Result<()> {
+		let query = query.trim();
+		if query.is_empty() {
+			return Ok(());
+		}
+
+		let queries = self.queries.entry(context.to_string()).or_default();
+		queries.retain(|existing| existing != query);
+		queries.insert(0, query.to_string());
+		queries.truncate(SEARCHES_SIZE);
+
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.queries)?)
+	}
+}
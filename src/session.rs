@@ -0,0 +1,65 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crypt;
+use crate::providers::Chapter;
+use crate::utils::data_dir;
+
+/// Where the interactive browsing was, saved while browsing so it can be
+/// restored after a crash or a closed terminal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+	/// The mode browsed, like `latest`.
+	pub mode: String,
+	/// The list as it was loaded.
+	pub list: Vec<Chapter>,
+	pub search_term: String,
+	/// Title of the highlighted entry.
+	pub selected: Option<String>,
+	/// The active facet filter.
+	pub filter: Option<String>,
+	/// Pages of the provider the lists browsed started at, the one of
+	/// `list` last.
+	#[serde(default)]
+	pub pages: Vec<u32>,
+	/// Page of the provider after the ones `list` was loaded from.
+	#[serde(default)]
+	pub next_page: Option<u32>,
+	pub saved_at: SystemTime,
+}
+
+impl Session {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("session.json")
+	}
+
+	/// Loads the session at `path`, if one was left behind.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+		match crypt::read_to_string(path) {
+			Ok(session) => Ok(Some(serde_json::from_str(&session)?)),
+			Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+			Err(err) => Err(err),
+		}
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+		let path = path.as_ref();
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(path, serde_json::to_string(self)?)
+	}
+
+	/// Removes the session at `path` once browsing ended normally.
+	pub fn clear<P: AsRef<Path>>(path: P) -> Result<()> {
+		match fs::remove_file(path) {
+			Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+			_ => Ok(()),
+		}
+	}
+}
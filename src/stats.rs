@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{data_dir, write_atomic};
+
+/// What was read on a day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Day {
+	pub chapters: u32,
+	pub seconds: u64,
+}
+
+/// A daily reading goal, in chapters, minutes or both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goal {
+	pub chapters: Option<u32>,
+	pub minutes: Option<u32>,
+}
+
+impl Goal {
+	pub fn is_set(&self) -> bool {
+		self.chapters.is_some() || self.minutes.is_some()
+	}
+
+	/// Whether `day` reaches every part of the goal.
+	pub fn is_met(&self, day: &Day) -> bool {
+		self.is_set()
+			&& self
+				.chapters
+				.is_none_or(|chapters| day.chapters >= chapters)
+			&& self
+				.minutes
+				.is_none_or(|minutes| day.seconds >= u64::from(minutes) * 60)
+	}
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Data {
+	goal: Goal,
+	days: BTreeMap<NaiveDate, Day>,
+}
+
+/// Chapters and time read per day, and the daily goal.
+pub struct Stats {
+	path: PathBuf,
+	data: Data,
+}
+
+impl Stats {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("stats.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let data = match fs::read_to_string(&path) {
+			Ok(data) => serde_json::from_str(&data)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => Data::default(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, data })
+	}
+
+	pub fn goal(&self) -> Goal {
+		self.data.goal
+	}
+
+	pub fn set_goal(&mut self, goal: Goal) -> Result<()> {
+		self.data.goal = goal;
+		self.save()
+	}
+
+	pub fn day(&self, date: NaiveDate) -> Day {
+		self.data.days.get(&date).copied().unwrap_or_default()
+	}
+
+	/// Records a chapter read on `date` for `spent`.
+	pub fn record(&mut self, date: NaiveDate, spent: Duration) -> Result<()> {
+		let day = self.data.days.entry(date).or_default();
+		day.chapters += 1;
+		day.seconds += spent.as_secs();
+		self.save()
+	}
+
+	/// Returns the number of days in a row the goal was met, up to `today`.
+	///
+	/// A streak is still running if only today's goal is not met yet.
+	pub fn streak(&self, today: NaiveDate) -> usize {
+		let goal = self.data.goal;
+		let mut date = today;
+		if !goal.is_met(&self.day(today)) {
+			date = match today.pred_opt() {
+				Some(yesterday) => yesterday,
+				None => return 0,
+			};
+		}
+
+		let mut streak = 0;
+		while goal.is_met(&self.day(date)) {
+			streak += 1;
+			date = match date.pred_opt() {
+				Some(date) => date,
+				None => break,
+			};
+		}
+		streak
+	}
+
+	/// Returns the longest number of days in a row the goal was met.
+	pub fn longest_streak(&self) -> usize {
+		let goal = self.data.goal;
+		let mut longest = 0;
+		let mut current = 0;
+		let mut last: Option<NaiveDate> = None;
+
+		let met = self
+			.data
+			.days
+			.iter()
+			.filter(|(_, day)| goal.is_met(day))
+			.map(|(date, _)| date);
+		for date in met {
+			current = match last.and_then(|last| last.succ_opt()) {
+				Some(next) if next == *date => current + 1,
+				_ => 1,
+			};
+			longest = longest.max(current);
+			last = Some(*date);
+		}
+		longest
+	}
+
+	/// Returns the share of the `days` days up to `today` the goal was met
+	/// on, counting only days since the first recorded one.
+	pub fn completion_rate(&self, today: NaiveDate, days: u32) -> Option<f32> {
+		let first = *self.data.days.keys().next()?;
+		let goal = self.data.goal;
+
+		let mut met = 0;
+		let mut counted = 0;
+		let mut date = today;
+		for _ in 0..days {
+			if date < first {
+				break;
+			}
+			counted += 1;
+			if goal.is_met(&self.day(date)) {
+				met += 1;
+			}
+			date = date.pred_opt()?;
+		}
+
+		(counted > 0).then(|| met as f32 / counted as f32)
+	}
+
+	/// Describes how far `today` is towards the goal, like
+	/// `goal 2/3 ch, 12/30 min`.
+	pub fn progress(&self, today: NaiveDate) -> Option<String> {
+		let goal = self.data.goal;
+		if !goal.is_set() {
+			return None;
+		}
+
+		let day = self.day(today);
+		let mut parts = vec![];
+		if let Some(chapters) = goal.chapters {
+			parts.push(format!("{}/{} ch", day.chapters, chapters));
+		}
+		if let Some(minutes) = goal.minutes {
+			parts.push(format!("{}/{} min", day.seconds / 60, minutes));
+		}
+
+		let done = if goal.is_met(&day) { " ✓" } else { "" };
+		Some(format!("goal {}{}", parts.join(", "), done))
+	}
+
+	fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		write_atomic(&self.path, serde_json::to_string(&self.data)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn tracks_streaks_and_completion() {
+		let date = |d| NaiveDate::from_ymd_opt(2023, 2, d).unwrap();
+		let dir = TestDir::new("stats");
+		let path = dir.join("stats.json");
+		let mut stats = Stats::open(&path).unwrap();
+		stats
+			.set_goal(Goal {
+				chapters: Some(2),
+				minutes: None,
+			})
+			.unwrap();
+
+		for d in [1, 1, 2, 2, 4, 5, 5, 6, 6, 7] {
+			stats.record(date(d), Duration::from_secs(300)).unwrap();
+		}
+
+		// Today, the 7th, is not met yet but the streak of the 5th and 6th runs
+		assert_eq!(stats.streak(date(7)), 2);
+		assert_eq!(stats.longest_streak(), 2);
+		assert_eq!(stats.completion_rate(date(7), 30), Some(4.0 / 7.0));
+		assert_eq!(stats.progress(date(7)), Some("goal 1/2 ch".to_string()));
+	}
+}
@@ -0,0 +1,284 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surf::Url;
+
+use crate::chapter::ChapterNumber;
+use crate::crypt::{self, Text};
+use crate::providers::{canonical_url, Chapter, Listed};
+use crate::utils::data_dir;
+
+/// A chapter known to the store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+	pub novel: String,
+	pub title: String,
+	/// Sha256 of the chapter text, also used as the name of its blob.
+	pub hash: String,
+}
+
+/// Content-addressed storage for chapter text.
+///
+/// Texts are written to `blobs/<sha256>` and `index.json` maps every chapter
+/// url to its blob, so the same text mirrored by several providers is only
+/// stored once and can be verified when it is read back. Chapters are keyed
+/// by their canonical url, so the same chapter is never stored twice.
+///
+/// Every file is written atomically and a pending chapter is recorded in
+/// `journal.json` until both its blob and the index are on disk, so an
+/// interrupted write is either finished or dropped the next time the store
+/// is opened. With an encrypted library every file is written through
+/// `crypt`, blobs are still named after the hash of the plain text.
+pub struct ChapterStore {
+	root: PathBuf,
+	index: HashMap<Url, Entry>,
+}
+
+/// Returns the hex encoded sha256 of `text`.
+pub fn hash(text: &str) -> String {
+	Sha256::digest(text.as_bytes())
+		.iter()
+		.map(|byte| format!("{:02x}", byte))
+		.collect()
+}
+
+impl ChapterStore {
+	/// Returns the directory the store lives in by default.
+	pub fn default_root() -> PathBuf {
+		data_dir().join("chapters")
+	}
+
+	/// Opens the store in `root`, creating it if needed.
+	pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+		let root = root.as_ref().to_path_buf();
+		fs::create_dir_all(root.join("blobs"))?;
+
+		let index: HashMap<Url, Entry> = match crypt::read_to_string(root.join("index.json")) {
+			Ok(index) => serde_json::from_str(&index)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(err) => return Err(err),
+		};
+		// Chapters stored before urls were canonicalized
+		let index = index
+			.into_iter()
+			.map(|(url, entry)| (canonical_url(&url), entry))
+			.collect();
+
+		let mut store = Self { root, index };
+		store.recover()?;
+
+		Ok(store)
+	}
+
+	/// Replays the chapters left in the journal by an interrupted `put`.
+	fn recover(&mut self) -> Result<()> {
+		let journal: Vec<(Url, Entry)> = match crypt::read_to_string(self.journal_path()) {
+			Ok(journal) => serde_json::from_str(&journal).unwrap_or_default(),
+			Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+			Err(err) => return Err(err),
+		};
+
+		for (url, entry) in journal {
+			// Only keep chapters whose blob made it to disk intact
+			let blob = crypt::read_to_string(self.blob_path(&entry.hash));
+			if blob.is_ok_and(|text| hash(&text) == entry.hash) {
+				self.index.insert(canonical_url(&url), entry);
+			}
+		}
+
+		self.save_index()?;
+		fs::remove_file(self.journal_path())
+	}
+
+	fn journal_path(&self) -> PathBuf {
+		self.root.join("journal.json")
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = (&Url, &Entry)> {
+		self.index.iter()
+	}
+
+	pub fn entry(&self, url: &Url) -> Option<&Entry> {
+		self.index.get(&canonical_url(url))
+	}
+
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	/// Returns the blobs no chapter of the index refers to, except those
+	/// in `keep`, like the blobs of trashed chapters.
+	pub fn unreferenced_blobs(&self, keep: &HashSet<&str>) -> Result<Vec<PathBuf>> {
+		let referenced = self
+			.index
+			.values()
+			.map(|entry| entry.hash.as_str())
+			.collect::<HashSet<_>>();
+
+		let mut blobs = vec![];
+		for file in fs::read_dir(self.root.join("blobs"))? {
+			let path = file?.path();
+			let hash = path.file_name().and_then(|name| name.to_str());
+			if hash.is_some_and(|hash| !referenced.contains(hash) && !keep.contains(hash)) {
+				blobs.push(path);
+			}
+		}
+
+		Ok(blobs)
+	}
+
+	fn blob_path(&self, hash: &str) -> PathBuf {
+		self.root.join("blobs").join(hash)
+	}
+
+	/// Stores `text` as the chapter at `url` and returns its hash.
+	pub fn put(&mut self, url: &Url, novel: &str, title: &str, text: &str) -> Result<String> {
+		let url = &canonical_url(url);
+		let hash = hash(text);
+		let entry = Entry {
+			novel: novel.to_string(),
+			title: title.to_string(),
+			hash: hash.clone(),
+		};
+
+		crypt::write(
+			self.journal_path(),
+			serde_json::to_string(&[(url, &entry)])?,
+		)?;
+
+		// Rewrite the blob if it went missing or got corrupted
+		let blob = self.blob_path(&hash);
+		if crypt::read_to_string(&blob).map_or(true, |stored| stored != text) {
+			crypt::write(&blob, text)?;
+		}
+
+		self.index.insert(url.clone(), entry);
+		self.save_index()?;
+
+		// The chapter is safely stored, a stale journal is replayed harmlessly
+		let _ = fs::remove_file(self.journal_path());
+
+		Ok(hash)
+	}
+
+	/// Reads the chapter at `url` back from the store.
+	///
+	/// Returns `None` if the chapter was never stored and an error of kind
+	/// `InvalidData` if the blob does not match its hash anymore.
+	pub fn get(&self, url: &Url) -> Result<Option<String>> {
+		Ok(self.read(url)?.map(Text::into_string))
+	}
+
+	/// Reads the chapter at `url` back like `get`, mapping large chapters
+	/// like whole volumes instead of copying them into memory.
+	pub fn read(&self, url: &Url) -> Result<Option<Text>> {
+		let entry = match self.index.get(&canonical_url(url)) {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+
+		let text = crypt::read_text(self.blob_path(&entry.hash))?;
+		if hash(&text) != entry.hash {
+			return Err(Error::new(
+				ErrorKind::InvalidData,
+				format!("chapter {} is corrupted", url),
+			));
+		}
+
+		Ok(Some(text))
+	}
+
+	/// Returns the chapters of `novel` ordered by their chapter number.
+	pub fn chapters(&self, novel: &str) -> Vec<Chapter> {
+		let mut chapters = self
+			.index
+			.iter()
+			.filter(|(_, entry)| entry.novel == novel)
+			.map(|(url, entry)| Chapter::new(entry.title.clone(), url.clone()))
+			.collect::<Vec<_>>();
+		chapters.sort_by_cached_key(|chapter| {
+			(ChapterNumber::parse(&chapter.title), chapter.title.clone())
+		});
+
+		chapters
+	}
+
+	/// Removes every chapter of `novel` from the index and returns them.
+	///
+	/// Blobs are left on disk so the chapters can be restored with `restore`.
+	pub fn remove_novel(&mut self, novel: &str) -> Result<Vec<(Url, Entry)>> {
+		let urls = self
+			.index
+			.iter()
+			.filter(|(_, entry)| entry.novel == novel)
+			.map(|(url, _)| url.clone())
+			.collect::<Vec<_>>();
+
+		let removed = urls
+			.into_iter()
+			.filter_map(|url| {
+				let entry = self.index.remove(&url)?;
+				Some((url, entry))
+			})
+			.collect();
+		self.save_index()?;
+
+		Ok(removed)
+	}
+
+	/// Adds chapters removed by `remove_novel` back to the index.
+	pub fn restore(&mut self, chapters: Vec<(Url, Entry)>) -> Result<()> {
+		self.index.extend(chapters);
+		self.save_index()
+	}
+
+	/// Returns the urls of the chapters whose blob is missing or does not
+	/// match its hash anymore, optionally only for chapters of `novel`.
+	pub fn corrupted(&self, novel: Option<&str>) -> Vec<Url> {
+		self.index
+			.iter()
+			.filter(|(_, entry)| novel.is_none_or(|novel| entry.novel == novel))
+			.filter(|(url, _)| self.get(url).is_err())
+			.map(|(url, _)| url.clone())
+			.collect()
+	}
+
+	fn save_index(&self) -> Result<()> {
+		crypt::write(
+			self.root.join("index.json"),
+			serde_json::to_string(&self.index)?,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn mirrored_chapters_share_a_blob() {
+		let dir = TestDir::new("store");
+		let root = dir.join("chapters");
+		let mut store = ChapterStore::open(&root).unwrap();
+
+		let first = Url::parse("https://a.example/novel/chapter-1").unwrap();
+		let second = Url::parse("https://b.example/novel/chapter-1").unwrap();
+		store.put(&first, "Novel", "Chapter 1", "text").unwrap();
+		store.put(&second, "Novel", "Chapter 1", "text").unwrap();
+
+		assert_eq!(fs::read_dir(root.join("blobs")).unwrap().count(), 1);
+		assert_eq!(store.get(&second).unwrap().as_deref(), Some("text"));
+
+		fs::write(store.blob_path(&hash("text")), "garbage").unwrap();
+		assert!(store.get(&first).is_err());
+		assert_eq!(store.corrupted(Some("Novel")).len(), 2);
+
+		store.put(&first, "Novel", "Chapter 1", "text").unwrap();
+		assert!(store.corrupted(None).is_empty());
+	}
+}
@@ -0,0 +1,106 @@
+/// Most suggestions offered for a mistyped name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the number of single character edits turning `a` into `b`.
+fn distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut previous = (0..=b.len()).collect::<Vec<_>>();
+	for (i, a) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(a != *b);
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+/// Returns the candidates `input` was likely meant to be, closest first.
+///
+/// Candidates match when they are a few typos away from `input` or contain
+/// it, ignoring case, so a misspelled or partial title finds the novel.
+pub fn similar<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+	let input = input.trim().to_lowercase();
+	if input.is_empty() {
+		return vec![];
+	}
+	let typos = (input.chars().count() / 3).max(1);
+
+	let mut similar = candidates
+		.into_iter()
+		.filter_map(|candidate| {
+			let lowercase = candidate.to_lowercase();
+			if lowercase == input {
+				return None;
+			}
+			let distance = distance(&input, &lowercase);
+			if distance <= typos {
+				Some((distance, candidate))
+			} else if input.chars().count() >= 3 && lowercase.contains(&input) {
+				Some((typos + 1, candidate))
+			} else {
+				None
+			}
+		})
+		.collect::<Vec<_>>();
+	similar.sort_by_key(|(distance, _)| *distance);
+
+	let mut seen = vec![];
+	similar
+		.into_iter()
+		.map(|(_, candidate)| candidate)
+		.filter(|candidate| {
+			let new = !seen.contains(candidate);
+			seen.push(*candidate);
+			new
+		})
+		.take(MAX_SUGGESTIONS)
+		.collect()
+}
+
+/// Appends a "did you mean" question offering `suggestions` to `message`,
+/// leaving it as is without any.
+pub fn did_you_mean(message: &str, suggestions: &[&str]) -> String {
+	match suggestions {
+		[] => message.to_string(),
+		[suggestion] => format!("{} Did you mean {}?", message, suggestion),
+		[suggestions @ .., last] => format!(
+			"{} Did you mean {} or {}?",
+			message,
+			suggestions.join(", "),
+			last
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn suggests_close_names() {
+		let providers = ["readlightnovel", "royalroad", "rr", "scribblehub", "ao3"];
+		assert_eq!(similar("royalraod", providers), vec!["royalroad"]);
+		assert_eq!(similar("RR", providers), Vec::<&str>::new());
+		assert_eq!(similar("r", providers), vec!["rr"]);
+		assert_eq!(similar("scribble", providers), vec!["scribblehub"]);
+		assert!(similar("kakuyomu", providers).is_empty());
+
+		let titles = ["Overlord", "The Overlord's Return", "Lord of Mysteries"];
+		assert_eq!(
+			similar("overlor", titles),
+			vec!["Overlord", "The Overlord's Return"]
+		);
+
+		assert_eq!(did_you_mean("Unknown.", &[]), "Unknown.");
+		assert_eq!(
+			did_you_mean("Unknown.", &["rr"]),
+			"Unknown. Did you mean rr?"
+		);
+		assert_eq!(
+			did_you_mean("Unknown.", &["a", "b", "c"]),
+			"Unknown. Did you mean a, b or c?"
+		);
+	}
+}
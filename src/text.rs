@@ -0,0 +1,356 @@
+use console::{measure_text_width, pad_str, Alignment};
+
+/// Characters that end a sentence.
+const TERMINATORS: [char; 7] = ['.', '!', '?', '…', '。', '！', '？'];
+
+/// Characters that may follow a terminator and still belong to the sentence.
+const CLOSERS: [char; 8] = ['"', '\'', '”', '’', '」', '』', ')', ']'];
+
+/// Chapters shorter than this, in characters, are most likely broken.
+const MIN_CHAPTER_LENGTH: usize = 200;
+
+/// Vowels of the latin script, around which words are split into syllables.
+const VOWELS: &str = "aeiouyàáâãäåæèéêëìíîïòóôõöøùúûüý";
+
+/// Consonants spelling a single sound, never split by a hyphen.
+const DIGRAPHS: [[char; 2]; 8] = [
+	['c', 'h'],
+	['c', 'k'],
+	['g', 'h'],
+	['p', 'h'],
+	['q', 'u'],
+	['s', 'h'],
+	['t', 'h'],
+	['w', 'h'],
+];
+
+/// Fewest letters kept on either side of a hyphen.
+const MIN_HYPHENATED: usize = 2;
+
+/// Phrases of the pages sites serve instead of a chapter to scrapers.
+const PLACEHOLDERS: [&str; 6] = [
+	"please enable javascript",
+	"checking your browser",
+	"enable cookies",
+	"verify you are human",
+	"content is loading",
+	"read this chapter at",
+];
+
+/// Why an extracted chapter is not worth opening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+	Empty,
+	/// The chapter only has this many characters.
+	TooShort(usize),
+	/// The chapter is a placeholder containing this phrase.
+	Placeholder(&'static str),
+}
+
+impl std::fmt::Display for Problem {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Problem::Empty => write!(f, "the chapter is empty"),
+			Problem::TooShort(length) => {
+				write!(f, "the chapter only has {} characters", length)
+			}
+			Problem::Placeholder(phrase) => {
+				write!(f, "the site served a placeholder saying \"{}\"", phrase)
+			}
+		}
+	}
+}
+
+/// Checks that extraction produced an actual chapter.
+pub fn validate(text: &str) -> Result<(), Problem> {
+	let length = paragraphs(text).map(|p| p.chars().count()).sum::<usize>();
+	if length == 0 {
+		return Err(Problem::Empty);
+	}
+
+	// Placeholders are short, a long chapter merely mentioning one is fine
+	let lower = text.to_lowercase();
+	if length < MIN_CHAPTER_LENGTH * 5 {
+		if let Some(phrase) = PLACEHOLDERS.iter().find(|phrase| lower.contains(*phrase)) {
+			return Err(Problem::Placeholder(phrase));
+		}
+	}
+
+	if length < MIN_CHAPTER_LENGTH {
+		return Err(Problem::TooShort(length));
+	}
+
+	Ok(())
+}
+
+/// A sentence of a chapter with an id that stays the same as long as the
+/// chapter text does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk<'a> {
+	/// `p<paragraph>-s<sentence>`, both counted from zero.
+	pub id: String,
+	pub paragraph: usize,
+	pub sentence: usize,
+	pub text: &'a str,
+}
+
+/// Splits a chapter into its non-empty paragraphs.
+pub fn paragraphs(text: &str) -> impl Iterator<Item = &str> {
+	text.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// Splits a paragraph into sentences, keeping their punctuation.
+pub fn sentences(paragraph: &str) -> Vec<&str> {
+	let mut sentences = vec![];
+	let mut start = 0;
+	let mut chars = paragraph.char_indices().peekable();
+
+	while let Some((_, c)) = chars.next() {
+		if !TERMINATORS.contains(&c) {
+			continue;
+		}
+
+		// Keep runs like `?!`, `...` and closing quotes with the sentence
+		while let Some(&(_, next)) = chars.peek() {
+			if TERMINATORS.contains(&next) || CLOSERS.contains(&next) {
+				chars.next();
+			} else {
+				break;
+			}
+		}
+
+		let end = chars.peek().map_or(paragraph.len(), |&(i, _)| i);
+		let at_boundary = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+		// Dialogue tags like `"Wait!" he said.` continue the sentence
+		let continues = paragraph[end..]
+			.trim_start()
+			.chars()
+			.next()
+			.is_some_and(char::is_lowercase);
+		if (at_boundary || !c.is_ascii()) && !continues {
+			let sentence = paragraph[start..end].trim();
+			if !sentence.is_empty() {
+				sentences.push(sentence);
+			}
+			start = end;
+		}
+	}
+
+	let rest = paragraph[start..].trim();
+	if !rest.is_empty() {
+		sentences.push(rest);
+	}
+
+	sentences
+}
+
+/// Splits a chapter into sentence chunks, for read-aloud and anchors.
+pub fn chunks(text: &str) -> Vec<Chunk<'_>> {
+	paragraphs(text)
+		.enumerate()
+		.flat_map(|(paragraph, text)| {
+			sentences(text)
+				.into_iter()
+				.enumerate()
+				.map(move |(sentence, text)| Chunk {
+					id: format!("p{}-s{}", paragraph, sentence),
+					paragraph,
+					sentence,
+					text,
+				})
+		})
+		.collect()
+}
+
+/// Returns whether `c` is a vowel of the latin script.
+fn is_vowel(c: char) -> bool {
+	c.to_lowercase().all(|c| VOWELS.contains(c))
+}
+
+/// Returns the byte offsets `word` can be hyphenated at by rough English
+/// syllable rules: before a consonant followed by a vowel, or between two
+/// consonants standing between vowels, keeping digraphs like `th` whole.
+///
+/// There is no dictionary nor other language behind them, so some breaks
+/// are off. Only words of latin letters are split, punctuation around them
+/// aside, leaving numbers, urls and scripts breaking anywhere like
+/// Japanese alone.
+fn syllable_breaks(word: &str) -> Vec<usize> {
+	let start = word.len()
+		- word
+			.trim_start_matches(|c: char| !c.is_alphanumeric())
+			.len();
+	let core = word[start..].trim_end_matches(|c: char| !c.is_alphanumeric());
+	let letters = core.char_indices().collect::<Vec<_>>();
+	let latin = |c: char| c.is_ascii_alphabetic() || ('\u{c0}'..='\u{24f}').contains(&c);
+	if !letters.iter().all(|&(_, c)| latin(c)) {
+		return vec![];
+	}
+
+	let vowel = |i: usize| letters.get(i).is_some_and(|&(_, c)| is_vowel(c));
+	let lower = |i: usize| letters[i].1.to_ascii_lowercase();
+	(MIN_HYPHENATED..=letters.len().saturating_sub(MIN_HYPHENATED))
+		.filter(|&i| !vowel(i) && vowel(i + 1))
+		.filter(|&i| {
+			vowel(i - 1) || (vowel(i - 2) && !DIGRAPHS.contains(&[lower(i - 1), lower(i)]))
+		})
+		.map(|i| start + letters[i].0)
+		.collect()
+}
+
+/// Wraps a paragraph into lines at most `width` columns wide, breaking
+/// words that do not fit on a line of their own.
+///
+/// With `hyphenate`, words that do not fit at the end of a line are split
+/// at a syllable break instead of moved whole to the next one when they
+/// have one.
+pub fn wrap(paragraph: &str, width: usize, hyphenate: bool) -> Vec<String> {
+	let width = width.max(1);
+	let mut lines = vec![];
+	let mut line = String::new();
+
+	for mut word in paragraph.split_whitespace() {
+		loop {
+			let separator = usize::from(!line.is_empty());
+			let room = width.saturating_sub(measure_text_width(&line) + separator);
+			if measure_text_width(word) <= room {
+				if !line.is_empty() {
+					line.push(' ');
+				}
+				line.push_str(word);
+				break;
+			}
+
+			let points = if hyphenate {
+				syllable_breaks(word)
+			} else {
+				vec![]
+			};
+			if let Some(&point) = points
+				.iter()
+				.rev()
+				.find(|&&point| measure_text_width(&word[..point]) < room)
+			{
+				if !line.is_empty() {
+					line.push(' ');
+				}
+				line.push_str(&word[..point]);
+				line.push('-');
+				lines.push(std::mem::take(&mut line));
+				word = &word[point..];
+				continue;
+			}
+
+			if !line.is_empty() {
+				lines.push(std::mem::take(&mut line));
+				continue;
+			}
+			for c in word.chars() {
+				if measure_text_width(&line) + measure_text_width(c.encode_utf8(&mut [0; 4]))
+					> width
+				{
+					lines.push(std::mem::take(&mut line));
+				}
+				line.push(c);
+			}
+			break;
+		}
+	}
+	if !line.is_empty() {
+		lines.push(line);
+	}
+
+	lines
+}
+
+/// Lays out two chapters side by side in `width` columns, starting every
+/// pair of paragraphs with the same index on the same row, so scrolling
+/// keeps them in sync.
+pub fn side_by_side(left: &str, right: &str, width: usize, hyphenate: bool) -> String {
+	let column = width.saturating_sub(3) / 2;
+	let left = paragraphs(left).collect::<Vec<_>>();
+	let right = paragraphs(right).collect::<Vec<_>>();
+
+	let mut rows = vec![];
+	for i in 0..left.len().max(right.len()) {
+		let left = left.get(i).map_or(vec![], |p| wrap(p, column, hyphenate));
+		let right = right.get(i).map_or(vec![], |p| wrap(p, column, hyphenate));
+
+		if i > 0 {
+			rows.push(format!("{} │", " ".repeat(column)));
+		}
+		for k in 0..left.len().max(right.len()) {
+			let l = left.get(k).map_or("", String::as_str);
+			let r = right.get(k).map_or("", String::as_str);
+			let row = format!("{} │ {}", pad_str(l, column, Alignment::Left, None), r);
+			rows.push(row.trim_end().to_string());
+		}
+	}
+
+	rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn chunks_sentences_with_ids() {
+		let text = "\"Wait!\" he said. It was 3.5 meters tall...\n\n誰だ？そうか。";
+		let chunks = chunks(text);
+
+		let texts = chunks.iter().map(|chunk| chunk.text).collect::<Vec<_>>();
+		assert_eq!(
+			texts,
+			vec![
+				"\"Wait!\" he said.",
+				"It was 3.5 meters tall...",
+				"誰だ？",
+				"そうか。"
+			]
+		);
+		assert_eq!(chunks[2].id, "p1-s0");
+	}
+
+	#[test]
+	fn validates_chapters() {
+		assert_eq!(validate(" \n\n "), Err(Problem::Empty));
+		assert_eq!(validate("Chapter 1"), Err(Problem::TooShort(9)));
+		assert_eq!(
+			validate("Checking your browser before accessing the site."),
+			Err(Problem::Placeholder("checking your browser"))
+		);
+		assert_eq!(validate(&"He walked on. ".repeat(20)), Ok(()));
+	}
+
+	#[test]
+	fn lays_out_side_by_side() {
+		let left = "One two three.\n\nFour.";
+		let right = "一二三四五六七八\n\n五";
+
+		assert_eq!(wrap("One two three.", 7, false), vec!["One two", "three."]);
+		assert_eq!(
+			side_by_side(left, right, 17, false),
+			"One two │ 一二三\nthree.  │ 四五六\n        │ 七八\n        │\nFour.   │ 五"
+		);
+	}
+
+	#[test]
+	fn hyphenates_words() {
+		assert_eq!(syllable_breaks("chapter"), vec![4]);
+		assert_eq!(syllable_breaks("\"wonderful,\""), vec![4, 7]);
+		assert!(syllable_breaks("other").is_empty());
+		assert!(syllable_breaks("3.5").is_empty());
+		assert!(syllable_breaks("誰だろう").is_empty());
+
+		let paragraph = "The wonderful chapter.";
+		assert_eq!(
+			wrap(paragraph, 10, false),
+			vec!["The", "wonderful", "chapter."]
+		);
+		assert_eq!(
+			wrap(paragraph, 10, true),
+			vec!["The won-", "derful", "chapter."]
+		);
+	}
+}
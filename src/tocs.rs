@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::crypt;
+use crate::providers::Chapter;
+use crate::utils::data_dir;
+
+/// How long a table of contents is used before it is fetched again.
+pub const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A table of contents as it was fetched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Toc {
+	chapters: Vec<Chapter>,
+	fetched_at: SystemTime,
+}
+
+/// Returns `url` without its last path segment, the part the urls of the
+/// chapters of a novel share.
+fn parent(url: &Url) -> Option<Url> {
+	let mut parent = url.clone();
+	parent.path_segments_mut().ok()?.pop();
+	parent.set_query(None);
+	parent.set_fragment(None);
+	Some(parent)
+}
+
+/// The tables of contents fetched lately, by the url of their novel, so
+/// opening a novel again does not fetch thousands of chapters every time.
+pub struct Tocs {
+	path: PathBuf,
+	tocs: HashMap<String, Toc>,
+}
+
+impl Tocs {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("tocs.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let tocs = match crypt::read_to_string(&path) {
+			Ok(tocs) => serde_json::from_str(&tocs)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, tocs })
+	}
+
+	/// Returns the chapters of the novel at `url` if they were fetched less
+	/// than `TTL` before `now`.
+	pub fn get(&self, url: &Url, now: SystemTime) -> Option<&[Chapter]> {
+		let toc = self.tocs.get(url.as_str())?;
+		let age = now.duration_since(toc.fetched_at).unwrap_or_default();
+
+		(age < TTL).then_some(&toc.chapters[..])
+	}
+
+	/// Keeps the `chapters` of the novel at `url` fetched at `now`.
+	pub fn set(&mut self, url: &Url, chapters: Vec<Chapter>, now: SystemTime) -> Result<()> {
+		self.tocs.insert(
+			url.to_string(),
+			Toc {
+				chapters,
+				fetched_at: now,
+			},
+		);
+		self.save(now)
+	}
+
+	/// Forgets the tables of contents missing a chapter of `latest`, the
+	/// latest updates, and returns how many.
+	///
+	/// A chapter belongs to a novel when its url is next to the urls of the
+	/// chapters of the novel, as sites put them under the same path.
+	pub fn invalidate(&mut self, latest: &[Chapter], now: SystemTime) -> Result<usize> {
+		let before = self.tocs.len();
+		self.tocs.retain(|_, toc| {
+			!latest.iter().any(|update| {
+				let novel = parent(&update.url);
+				novel.is_some()
+					&& toc.chapters.iter().all(|chapter| chapter.url != update.url)
+					&& toc
+						.chapters
+						.iter()
+						.any(|chapter| parent(&chapter.url) == novel)
+			})
+		});
+
+		let invalidated = before - self.tocs.len();
+		if invalidated > 0 {
+			self.save(now)?;
+		}
+		Ok(invalidated)
+	}
+
+	/// Saves the tables of contents, dropping the expired ones.
+	fn save(&mut self, now: SystemTime) -> Result<()> {
+		self.tocs.retain(|_, toc| {
+			now.duration_since(toc.fetched_at)
+				.is_ok_and(|age| age < TTL)
+		});
+
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.tocs)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Listed;
+	use crate::utils::TestDir;
+
+	#[test]
+	fn invalidates_on_new_chapters() {
+		let dir = TestDir::new("tocs");
+		let path = dir.join("tocs.json");
+		let chapter = |novel: &str, n: u32| {
+			Chapter::new(
+				format!("Chapter {}", n),
+				Url::parse(&format!("https://example.com/{}/chapter-{}", novel, n)).unwrap(),
+			)
+		};
+		let novel = Url::parse("https://example.com/overlord").unwrap();
+		let now = SystemTime::now();
+
+		let mut tocs = Tocs::open(&path).unwrap();
+		tocs.set(
+			&novel,
+			vec![chapter("overlord", 1), chapter("overlord", 2)],
+			now,
+		)
+		.unwrap();
+		assert_eq!(tocs.get(&novel, now).unwrap().len(), 2);
+		assert!(tocs.get(&novel, now + TTL).is_none());
+
+		// Known chapters and chapters of other novels change nothing
+		let latest = [chapter("overlord", 2), chapter("overgeared", 3)];
+		assert_eq!(tocs.invalidate(&latest, now).unwrap(), 0);
+		assert!(Tocs::open(&path).unwrap().get(&novel, now).is_some());
+
+		assert_eq!(tocs.invalidate(&[chapter("overlord", 3)], now).unwrap(), 1);
+		assert!(tocs.get(&novel, now).is_none());
+	}
+}
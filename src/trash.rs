@@ -0,0 +1,96 @@
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use surf::Url;
+
+use crate::crypt;
+use crate::library::Novel;
+use crate::store::Entry;
+use crate::utils::data_dir;
+
+/// How long deleted novels can be restored.
+pub const TRASH_DURATION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// A novel deleted from the library together with its chapters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trashed {
+	pub novel: Novel,
+	pub chapters: Vec<(Url, Entry)>,
+	pub deleted_at: SystemTime,
+}
+
+/// Novels deleted from the library that can still be restored.
+///
+/// Only the index entries of the chapters are kept here, their blobs stay in
+/// the chapter store until the novel expires from the trash.
+pub struct Trash {
+	path: PathBuf,
+	novels: Vec<Trashed>,
+}
+
+impl Trash {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("trash.json")
+	}
+
+	/// Opens the trash, dropping novels deleted longer than
+	/// `TRASH_DURATION` ago.
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let mut novels: Vec<Trashed> = match crypt::read_to_string(&path) {
+			Ok(novels) => serde_json::from_str(&novels)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+		novels.retain(|trashed| {
+			trashed
+				.deleted_at
+				.elapsed()
+				.is_ok_and(|elapsed| elapsed < TRASH_DURATION)
+		});
+
+		Ok(Self { path, novels })
+	}
+
+	pub fn novels(&self) -> &[Trashed] {
+		&self.novels
+	}
+
+	pub fn push(&mut self, novel: Novel, chapters: Vec<(Url, Entry)>) -> Result<()> {
+		self.novels.push(Trashed {
+			novel,
+			chapters,
+			deleted_at: SystemTime::now(),
+		});
+
+		self.save()
+	}
+
+	/// Takes the most recently deleted novel titled `title` out of the trash.
+	pub fn take(&mut self, title: &str) -> Result<Option<Trashed>> {
+		let trashed = match self
+			.novels
+			.iter()
+			.rposition(|trashed| trashed.novel.title == title)
+		{
+			Some(index) => self.novels.remove(index),
+			None => return Ok(None),
+		};
+
+		self.save()?;
+
+		Ok(Some(trashed))
+	}
+
+	/// Writes the trash back without the novels that expired.
+	pub fn purge(&self) -> Result<()> {
+		self.save()
+	}
+
+	fn save(&self) -> Result<()> {
+		crypt::write(&self.path, serde_json::to_string(&self.novels)?)
+	}
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers::{title_key, Novel};
+use crate::utils::{data_dir, write_atomic};
+
+/// How a novel moved in the ranking since the last check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Movement {
+	New,
+	Up(usize),
+	Down(usize),
+	Same,
+}
+
+/// A novel of the aggregated ranking.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trend {
+	pub ranobe: Novel,
+	/// Providers ranking the novel.
+	pub providers: Vec<&'static str>,
+	pub movement: Movement,
+}
+
+/// Merges the rankings of several providers into one.
+///
+/// Novels ranked by several providers are merged by title and ordered by
+/// the sum of the reciprocals of their ranks, so a novel near the top of
+/// one list and one ranked by many lists both rise.
+pub fn aggregate(rankings: &[(&'static str, Vec<Novel>)]) -> Vec<Trend> {
+	let mut scores: Vec<(String, f64, Trend)> = vec![];
+
+	for (provider, ranking) in rankings {
+		for (rank, ranobe) in ranking.iter().enumerate() {
+			let key = title_key(&ranobe.title);
+			let score = 1.0 / (rank + 1) as f64;
+
+			match scores.iter_mut().find(|(k, _, _)| *k == key) {
+				Some((_, total, trend)) => {
+					*total += score;
+					if !trend.providers.contains(provider) {
+						trend.providers.push(provider);
+					}
+				}
+				None => scores.push((
+					key,
+					score,
+					Trend {
+						ranobe: ranobe.clone(),
+						providers: vec![provider],
+						movement: Movement::New,
+					},
+				)),
+			}
+		}
+	}
+
+	scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+	scores.into_iter().map(|(_, _, trend)| trend).collect()
+}
+
+/// The ranking of the last check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+	/// Deduplication keys to their position, counted from zero.
+	pub ranks: HashMap<String, usize>,
+	pub checked_at: Option<SystemTime>,
+}
+
+impl Snapshot {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("trending.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		match fs::read_to_string(path) {
+			Ok(snapshot) => Ok(serde_json::from_str(&snapshot)?),
+			Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+			Err(err) => Err(err),
+		}
+	}
+
+	/// Sets the movement of every trend compared to this snapshot.
+	pub fn compare(&self, trends: &mut [Trend]) {
+		for (rank, trend) in trends.iter_mut().enumerate() {
+			trend.movement = match self.ranks.get(&title_key(&trend.ranobe.title)) {
+				None => Movement::New,
+				Some(&last) if last > rank => Movement::Up(last - rank),
+				Some(&last) if last < rank => Movement::Down(rank - last),
+				Some(_) => Movement::Same,
+			};
+		}
+	}
+
+	/// Saves `trends` as the ranking of the last check.
+	pub fn save<P: AsRef<Path>>(path: P, trends: &[Trend]) -> Result<()> {
+		let snapshot = Self {
+			ranks: trends
+				.iter()
+				.enumerate()
+				.map(|(rank, trend)| (title_key(&trend.ranobe.title), rank))
+				.collect(),
+			checked_at: Some(SystemTime::now()),
+		};
+
+		write_atomic(path, serde_json::to_string(&snapshot)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Listed;
+
+	fn ranking(titles: &[&str]) -> Vec<Novel> {
+		titles
+			.iter()
+			.map(|title| {
+				Novel::new(
+					title.to_string(),
+					surf::Url::parse("https://example.com").unwrap(),
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn aggregates_and_compares() {
+		let mut trends = aggregate(&[
+			(
+				"a",
+				ranking(&["Solo Leveling", "Overlord", "Mushoku Tensei"]),
+			),
+			("b", ranking(&["Mushoku Tensei", "solo leveling"])),
+		]);
+
+		let titles = trends
+			.iter()
+			.map(|trend| trend.ranobe.title.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(titles, vec!["Solo Leveling", "Mushoku Tensei", "Overlord"]);
+		assert_eq!(trends[0].providers, vec!["a", "b"]);
+
+		let snapshot = Snapshot {
+			ranks: [("overlord".to_string(), 0), ("sololeveling".to_string(), 0)]
+				.into_iter()
+				.collect(),
+			checked_at: None,
+		};
+		snapshot.compare(&mut trends);
+		let movements = trends
+			.iter()
+			.map(|trend| trend.movement)
+			.collect::<Vec<_>>();
+		assert_eq!(
+			movements,
+			vec![Movement::Same, Movement::New, Movement::Down(2)]
+		);
+	}
+}
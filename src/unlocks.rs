@@ -0,0 +1,123 @@
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crypt;
+use crate::providers::Chapter;
+use crate::utils::data_dir;
+
+/// A locked chapter to be notified about once it becomes free.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Unlock {
+	pub novel: String,
+	/// Provider the chapter is read from.
+	pub provider: String,
+	pub chapter: Chapter,
+	/// When the chapter becomes free.
+	pub at: SystemTime,
+}
+
+/// The locked chapters waited for.
+pub struct Unlocks {
+	path: PathBuf,
+	unlocks: Vec<Unlock>,
+}
+
+impl Unlocks {
+	pub fn default_path() -> PathBuf {
+		data_dir().join("unlocks.json")
+	}
+
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
+
+		let unlocks = match crypt::read_to_string(&path) {
+			Ok(unlocks) => serde_json::from_str(&unlocks)?,
+			Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+			Err(err) => return Err(err),
+		};
+
+		Ok(Self { path, unlocks })
+	}
+
+	pub fn unlocks(&self) -> &[Unlock] {
+		&self.unlocks
+	}
+
+	/// Waits for `unlock`, replacing the previous wait for its chapter.
+	pub fn add(&mut self, unlock: Unlock) -> Result<()> {
+		self.unlocks
+			.retain(|known| known.chapter.url != unlock.chapter.url);
+		self.unlocks.push(unlock);
+
+		self.save()
+	}
+
+	/// Returns the chapters free at `now` and stops waiting for them.
+	pub fn take_due(&mut self, now: SystemTime) -> Result<Vec<Unlock>> {
+		let (due, waiting) = self
+			.unlocks
+			.drain(..)
+			.partition::<Vec<_>, _>(|unlock| unlock.at <= now);
+		self.unlocks = waiting;
+
+		if !due.is_empty() {
+			self.save()?;
+		}
+
+		Ok(due)
+	}
+
+	fn save(&self) -> Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		crypt::write(&self.path, serde_json::to_string(&self.unlocks)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::providers::Listed;
+	use crate::utils::TestDir;
+	use std::time::Duration;
+	use surf::Url;
+
+	#[test]
+	fn takes_the_chapters_that_became_free() {
+		let dir = TestDir::new("unlocks");
+		let path = dir.join("unlocks.json");
+		let now = SystemTime::now();
+		let unlock = |n: u32, at| Unlock {
+			novel: "Novel".to_string(),
+			provider: "webnovel".to_string(),
+			chapter: Chapter::new(
+				format!("Chapter {}", n),
+				Url::parse(&format!("https://example.com/chapter-{}", n)).unwrap(),
+			),
+			at,
+		};
+
+		let mut unlocks = Unlocks::open(&path).unwrap();
+		unlocks.add(unlock(1, now)).unwrap();
+		unlocks
+			.add(unlock(2, now + Duration::from_secs(60)))
+			.unwrap();
+		// Waiting again for a chapter replaces the previous wait
+		unlocks
+			.add(unlock(2, now + Duration::from_secs(3600)))
+			.unwrap();
+		assert_eq!(unlocks.unlocks().len(), 2);
+
+		let due = unlocks.take_due(now).unwrap();
+		assert_eq!(due.len(), 1);
+		assert_eq!(due[0].chapter.title, "Chapter 1");
+		assert!(unlocks.take_due(now).unwrap().is_empty());
+		assert_eq!(Unlocks::open(&path).unwrap().unlocks().len(), 1);
+	}
+}
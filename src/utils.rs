@@ -1,10 +1,23 @@
-use std::io::Result;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::config::Navigation;
+
+/// Whether output is kept plain for screen readers.
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
 lazy_static! {
+	/// Name of the library in use, `None` for the default one.
+	static ref LIBRARY: RwLock<Option<String>> = RwLock::new(None);
+	/// How the lists are moved through, set from the configuration.
+	static ref NAVIGATION: RwLock<Navigation> = RwLock::new(Navigation::default());
 	static ref STRING_RE: Regex =
 		Regex::new(r#"(“|"|&quot;|&ldquo;)(.+?)(”|"|&quot;|&rdquo;)"#).unwrap();
 }
@@ -13,31 +26,278 @@ pub fn italicize(text: &String) -> String {
 	STRING_RE.replace_all(text, " _${1}${2}${3}_ ").to_string()
 }
 
-pub fn open_glow(text: String, wrap: u16) -> Result<ExitStatus> {
-	let termsize::Size { rows: _, cols } = termsize::get().unwrap();
+/// Turns the plain output for screen readers on or off.
+pub fn set_plain(plain: bool) {
+	PLAIN.store(plain, Ordering::Relaxed);
+}
 
-	let cols = std::cmp::min(cols, wrap);
+/// Whether to print plain lines instead of redrawing the screen, so braille
+/// displays and screen readers can follow.
+pub fn plain() -> bool {
+	PLAIN.load(Ordering::Relaxed)
+}
 
-	let echo = Command::new("echo")
-		.arg(text)
-		.stdout(Stdio::piped())
+/// Moves through the lists as `navigation` says from now on.
+pub fn set_navigation(navigation: Navigation) {
+	*NAVIGATION.write().unwrap() = navigation;
+}
+
+pub fn navigation() -> Navigation {
+	*NAVIGATION.read().unwrap()
+}
+
+/// Switches to the library called `name`, which keeps its own database and
+/// chapters, or back to the default library with `None`.
+///
+/// Names are limited to letters, digits, `-` and `_` as they name a
+/// directory.
+pub fn set_library(name: Option<&str>) -> Result<()> {
+	let valid = name.is_none_or(|name| {
+		!name.is_empty()
+			&& name
+				.chars()
+				.all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+	});
+	if !valid {
+		return Err(Error::new(
+			ErrorKind::InvalidInput,
+			format!(
+				"{} is not a library name, use letters, digits, - and _",
+				name.unwrap_or_default()
+			),
+		));
+	}
+
+	*LIBRARY.write().unwrap() = name.map(str::to_string);
+	Ok(())
+}
+
+/// Returns the name of the library in use, `None` for the default one.
+pub fn library() -> Option<String> {
+	LIBRARY.read().unwrap().clone()
+}
+
+/// Returns the directory the default library is kept in, the others are
+/// kept in its `libraries` directory.
+fn root_data_dir() -> PathBuf {
+	dirs::data_dir()
+		.unwrap_or_else(std::env::temp_dir)
+		.join("ranobe")
+}
+
+/// Returns the names of the libraries besides the default one.
+pub fn libraries() -> Result<Vec<String>> {
+	let mut names = match fs::read_dir(root_data_dir().join("libraries")) {
+		Ok(entries) => entries
+			.filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+			.collect::<Vec<_>>(),
+		Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+		Err(err) => return Err(err),
+	};
+	names.sort_unstable();
+	Ok(names)
+}
+
+/// Returns the directory ranobe keeps the data of the library in use in.
+pub fn data_dir() -> PathBuf {
+	match library() {
+		Some(name) => root_data_dir().join("libraries").join(name),
+		None => root_data_dir(),
+	}
+}
+
+/// A temporary directory of its own for a test, so tests running at once
+/// never share files, removed with what it holds once dropped.
+#[cfg(test)]
+pub struct TestDir(PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+	pub fn new(name: &str) -> Self {
+		static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let path = std::env::temp_dir().join(format!(
+			"ranobe-{}-{}-{}",
+			name,
+			std::process::id(),
+			COUNT.fetch_add(1, Ordering::Relaxed)
+		));
+		fs::create_dir_all(&path).unwrap();
+		Self(path)
+	}
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TestDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.0
+	}
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TestDir {
+	fn as_ref(&self) -> &Path {
+		&self.0
+	}
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+/// Returns the directory ranobe keeps disposable files in.
+pub fn cache_dir() -> PathBuf {
+	dirs::cache_dir()
+		.unwrap_or_else(std::env::temp_dir)
+		.join("ranobe")
+}
+
+/// Writes `contents` to `path` through a temporary file and a rename, so a
+/// crash never leaves a half-written file behind.
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+	let path = path.as_ref();
+	let tmp = path.with_extension("tmp");
+
+	let mut file = File::create(&tmp)?;
+	file.write_all(contents.as_ref())?;
+	file.sync_all()?;
+
+	fs::rename(tmp, path)
+}
+
+/// Reads the text on the clipboard through the first clipboard tool that
+/// is installed.
+pub fn read_clipboard() -> Result<String> {
+	let tools: [&[&str]; 4] = [
+		&["wl-paste", "--no-newline"],
+		&["xclip", "-selection", "clipboard", "-o"],
+		&["xsel", "--clipboard", "--output"],
+		&["pbpaste"],
+	];
+
+	for tool in tools {
+		if let Ok(output) = Command::new(tool[0]).args(&tool[1..]).output() {
+			if output.status.success() {
+				return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+			}
+		}
+	}
+
+	Err(std::io::Error::new(
+		std::io::ErrorKind::NotFound,
+		"no clipboard tool found, install wl-paste, xclip, xsel or pbpaste",
+	))
+}
+
+/// Puts `text` on the clipboard through the first clipboard tool that is
+/// installed.
+pub fn write_clipboard(text: &str) -> Result<()> {
+	let tools: [&[&str]; 4] = [
+		&["wl-copy"],
+		&["xclip", "-selection", "clipboard", "-i"],
+		&["xsel", "--clipboard", "--input"],
+		&["pbcopy"],
+	];
+
+	for tool in tools {
+		if let Ok(mut child) = Command::new(tool[0])
+			.args(&tool[1..])
+			.stdin(Stdio::piped())
+			.spawn()
+		{
+			child.stdin.take().unwrap().write_all(text.as_bytes())?;
+			if child.wait()?.success() {
+				return Ok(());
+			}
+		}
+	}
+
+	Err(std::io::Error::new(
+		std::io::ErrorKind::NotFound,
+		"no clipboard tool found, install wl-copy, xclip, xsel or pbcopy",
+	))
+}
+
+/// Opens `url` in the default browser.
+pub fn open_browser(url: &str) -> Result<ExitStatus> {
+	let opener = if cfg!(target_os = "macos") {
+		"open"
+	} else {
+		"xdg-open"
+	};
+	Command::new(opener).arg(url).status()
+}
+
+/// Sends a desktop notification through `notify-send`.
+pub fn notify(summary: &str, body: &str) -> Result<ExitStatus> {
+	Command::new("notify-send")
+		.arg("-a")
+		.arg("ranobe")
+		.arg(summary)
+		.arg(body)
+		.status()
+}
+
+/// Shows text laid out by ranobe itself, like the split view, in `less`.
+pub fn open_pager(text: &str) -> Result<ExitStatus> {
+	if plain() {
+		println!("{}", text);
+		return Ok(ExitStatus::default());
+	}
+
+	let mut less = Command::new("less")
+		.arg("-R")
+		.arg("-S")
+		.stdin(Stdio::piped())
 		.spawn()?;
+	less.stdin.take().unwrap().write_all(text.as_bytes())?;
+	less.wait()
+}
 
-	let sorf_wrap = Command::new("fold")
+/// Shows `text` in glow, wrapped at `wrap` columns at most.
+///
+/// The text is written to the input of fold rather than passed as an
+/// argument, which the system caps far below the size of a whole volume.
+pub fn open_glow(text: &str, wrap: u16, style: Option<&str>) -> Result<ExitStatus> {
+	let termsize::Size { rows: _, cols } = termsize::get().unwrap();
+
+	let cols = std::cmp::min(cols, wrap);
+
+	let mut sorf_wrap = Command::new("fold")
 		.arg("-s")
 		.arg("-w")
 		.arg(cols.to_string())
-		.stdin(Stdio::from(echo.stdout.unwrap()))
+		.stdin(Stdio::piped())
 		.stdout(Stdio::piped())
 		.spawn()?;
+	let mut input = sorf_wrap.stdin.take().unwrap();
 
-	Command::new("glow")
-		.arg("-p")
+	let mut glow = Command::new("glow");
+	if plain() {
+		// Printed as plain text without a pager
+		glow.arg("-s").arg("notty");
+	} else {
+		if let Some(style) = style {
+			glow.arg("-s").arg(style);
+		}
+		glow.arg("-p");
+	}
+
+	let mut glow = glow
 		.arg("-w")
 		.arg((cols + 1).to_string())
 		.stdin(Stdio::from(sorf_wrap.stdout.unwrap()))
-		.spawn()?
-		.wait()
+		.spawn()?;
+
+	// Like echo, end the text with a newline
+	input.write_all(text.as_bytes())?;
+	input.write_all(b"\n")?;
+	drop(input);
+	glow.wait()
 
 	// Command::new("mdless")
 	// 	.arg("--columns")